@@ -0,0 +1,224 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    clues::ClueKind, journal::DeductionLog, puzzle::CellLocIndex, DisplayCluebox, GameState,
+    PuzzleClueComponent, PuzzleOutcome, UpdateCellIndex, NO_PICK,
+};
+
+// How much each clue contributed over a solve, so the generator's clue mix can eventually be tuned
+// toward the kinds that actually get used.
+#[derive(Debug, Default, Component, Reflect)]
+pub struct ClueUsage {
+    pub deductions: usize,
+    // The candidate the clue's most recent deduction changed. Its move is looked up in the
+    // deduction log, since the deduction is only logged once it's been applied.
+    pub last_deduction: Option<CellLocIndex>,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ClueDeductionApplied {
+    pub clue: Entity,
+    pub update: UpdateCellIndex,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ClueSummaryPanel;
+
+const SUMMARY_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+
+fn add_clue_usage(ev: Trigger<OnAdd, PuzzleClueComponent>, mut commands: Commands) {
+    commands.entity(ev.entity()).insert(ClueUsage::default());
+}
+
+fn record_clue_usage(
+    mut ev_rx: EventReader<ClueDeductionApplied>,
+    mut q_usage: Query<&mut ClueUsage>,
+) {
+    for ev in ev_rx.read() {
+        let Ok(mut usage) = q_usage.get_mut(ev.clue) else {
+            continue;
+        };
+        usage.deductions += 1;
+        usage.last_deduction = Some(ev.update.index);
+    }
+}
+
+// The move, numbered from 1 like the exported log, of the last clue deduction that changed `index`.
+fn move_deducing(log: &DeductionLog, index: CellLocIndex) -> Option<usize> {
+    log.entries
+        .iter()
+        .rposition(|action| {
+            std::iter::once(&action.update)
+                .chain(&action.joined)
+                .any(|update| update.explanation.is_some() && update.index == index)
+        })
+        .map(|entry| entry + 1)
+}
+
+// One line per clue in cluebox order, then the totals for each kind of clue.
+fn summarize<'a>(
+    clues: impl IntoIterator<Item = (ClueKind, &'a ClueUsage)>,
+    log: &DeductionLog,
+) -> String {
+    let mut lines = vec!["Clue usefulness".to_string()];
+    let mut by_kind = HashMap::<ClueKind, (usize, usize)>::new();
+    for (nr, (kind, usage)) in clues.into_iter().enumerate() {
+        let (n_clues, n_deductions) = by_kind.entry(kind).or_default();
+        *n_clues += 1;
+        *n_deductions += usage.deductions;
+        let last_used = usage
+            .last_deduction
+            .and_then(|index| move_deducing(log, index));
+        let plural = if usage.deductions == 1 { "" } else { "s" };
+        lines.push(match last_used {
+            Some(last) => format!(
+                "Clue {} ({kind:?}): {} deduction{plural}, exhausted after move {last}",
+                nr + 1,
+                usage.deductions,
+            ),
+            None => format!("Clue {} ({kind:?}): never used", nr + 1),
+        });
+    }
+    let mut by_kind = by_kind.into_iter().collect::<Vec<_>>();
+    by_kind.sort_by_key(|&(kind, _)| format!("{kind:?}"));
+    for (kind, (n_clues, n_deductions)) in by_kind {
+        let deductions_plural = if n_deductions == 1 { "" } else { "s" };
+        let clues_plural = if n_clues == 1 { "" } else { "s" };
+        lines.push(format!(
+            "{kind:?}: {n_deductions} deduction{deductions_plural} from {n_clues} \
+             clue{clues_plural}"
+        ));
+    }
+    lines.join("\n")
+}
+
+fn show_clue_summary(
+    mut commands: Commands,
+    q_cluebox: Single<&Children, With<DisplayCluebox>>,
+    q_clues: Query<(&PuzzleClueComponent, &ClueUsage)>,
+    log: Res<DeductionLog>,
+) {
+    let clues = q_cluebox
+        .iter()
+        .filter_map(|&entity| q_clues.get(entity).ok())
+        .map(|(PuzzleClueComponent { clue, .. }, usage)| (clue.kind(), usage));
+    let summary = summarize(clues, &log);
+    commands
+        .spawn((
+            ClueSummaryPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Px(10.),
+                max_height: Val::Percent(90.),
+                padding: UiRect::all(Val::Px(10.)),
+                overflow: Overflow::clip_y(),
+                ..Default::default()
+            },
+            BackgroundColor(SUMMARY_BACKGROUND_COLOR),
+        ))
+        .with_child((
+            Text::new(summary),
+            TextFont {
+                font_size: 14.,
+                ..Default::default()
+            },
+            NO_PICK,
+        ));
+}
+
+fn clue_summary_clicked(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_panel: Query<(), With<ClueSummaryPanel>>,
+) {
+    if q_panel.contains(ev.entity()) {
+        commands.entity(ev.entity()).despawn_recursive();
+    }
+}
+
+fn despawn_clue_summary(mut commands: Commands, q_panel: Query<Entity, With<ClueSummaryPanel>>) {
+    for entity in &q_panel {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct ClueAnalyticsPlugin;
+
+impl Plugin for ClueAnalyticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ClueDeductionApplied>()
+            .register_type::<ClueSummaryPanel>()
+            .register_type::<ClueUsage>()
+            .add_observer(add_clue_usage)
+            .add_observer(clue_summary_clicked)
+            .add_systems(
+                Update,
+                record_clue_usage.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(PuzzleOutcome::Solved), show_clue_summary)
+            .add_systems(OnExit(PuzzleOutcome::Solved), despawn_clue_summary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clues::{DynPuzzleClue, SavedClue},
+        puzzle::{loc, test_puzzle},
+        undo::Action,
+    };
+
+    fn logged(update: UpdateCellIndex) -> Action {
+        Action {
+            update,
+            update_count: 1,
+            inferred_count: 0,
+            joined: vec![],
+        }
+    }
+
+    #[test]
+    fn summaries_use_the_move_each_clue_last_deduced() {
+        let puzzle = test_puzzle(2, 5);
+        let clue = SavedClue::from_parts(ClueKind::EdgeColumn, &[loc(0, 0)], false).unwrap();
+        let deduced = DynPuzzleClue::from(clue).advance_puzzle(&puzzle).unwrap();
+        let by_hand = puzzle.answer_at(loc(1, 3)).decay_to_ind().as_clear();
+        // Clearing the same candidate by hand afterwards isn't the clue's doing.
+        let log = DeductionLog {
+            entries: vec![
+                logged(by_hand),
+                logged(deduced.clone()),
+                logged(UpdateCellIndex::manual(deduced.index, deduced.op)),
+            ],
+        };
+        let used = ClueUsage {
+            deductions: 1,
+            last_deduction: Some(deduced.index),
+        };
+        let unused = ClueUsage::default();
+        let summary = summarize(
+            [
+                (ClueKind::EdgeColumn, &used),
+                (ClueKind::SameColumn, &unused),
+                (ClueKind::SameColumn, &unused),
+            ],
+            &log,
+        );
+        assert_eq!(summary.lines().collect::<Vec<_>>(), vec![
+            "Clue usefulness",
+            "Clue 1 (EdgeColumn): 1 deduction, exhausted after move 2",
+            "Clue 2 (SameColumn): never used",
+            "Clue 3 (SameColumn): never used",
+            "EdgeColumn: 1 deduction from 1 clue",
+            "SameColumn: 0 deductions from 2 clues",
+        ]);
+    }
+}
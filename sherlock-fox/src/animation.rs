@@ -10,8 +10,11 @@ use bevy::{
 };
 use petgraph::graph::NodeIndex;
 
+use crate::settings::{AnimationSettings, MotionKind};
+
 pub trait SavedAnimationNode {
     type AnimatedFrom: Component;
+    const MOTION: MotionKind = MotionKind::Other;
     fn node_mut(&mut self) -> &mut Option<NodeIndex>;
 }
 
@@ -32,47 +35,415 @@ type AnimD<'r, C> = (
 type ReaderD<'r> = (&'r mut AnimationPlayer, &'r AnimationGraphHandle);
 type Clips = Assets<AnimationClip>;
 type Graphs = Assets<AnimationGraph>;
-type CB<C> = Box<dyn FnOnce(Query<AnimD<C>>, Query<ReaderD>, ResMut<Clips>, ResMut<Graphs>) + Send>;
+type CB<C> = Box<
+    dyn FnOnce(
+            Commands,
+            Query<AnimD<C>>,
+            Query<ReaderD>,
+            ResMut<Clips>,
+            ResMut<Graphs>,
+            &AnimationSettings,
+        ) + Send,
+>;
+
+// How a started clip plays. A bare `RepeatAnimation` converts into this for the common case.
+#[derive(Debug, Clone, Copy)]
+pub struct Playback {
+    pub repeat: RepeatAnimation,
+    pub speed: f32,
+    // The tag to send with `AnimationFinished` once the clip stops, if anything is waiting on it.
+    // Clips repeating forever never stop.
+    pub finish_tag: Option<&'static str>,
+}
+
+impl Playback {
+    pub fn new(repeat: RepeatAnimation) -> Self {
+        Playback {
+            repeat,
+            speed: 1.,
+            finish_tag: None,
+        }
+    }
+
+    pub fn with_speed(self, speed: f32) -> Self {
+        Playback { speed, ..self }
+    }
+
+    pub fn notify_finished(self, tag: &'static str) -> Self {
+        Playback {
+            finish_tag: Some(tag),
+            ..self
+        }
+    }
+}
+
+impl From<RepeatAnimation> for Playback {
+    fn from(repeat: RepeatAnimation) -> Self {
+        Playback::new(repeat)
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    // Which step of a sequence finished, for entities that run more than one.
+    pub tag: &'static str,
+}
+
+// Left on an animated entity whose clip was started with `Playback::notify_finished`. Starting
+// another clip on the entity replaces or removes it, so an interrupted clip never reports.
+#[derive(Debug, Component, Reflect)]
+struct AwaitingFinish {
+    player: Entity,
+    node: NodeIndex,
+    tag: &'static str,
+}
 
 impl<C: SavedAnimationNode + Component> AnimatorPlugin<C> {
     pub fn start_animation<F>(
         commands: &mut Commands,
         entity: Entity,
-        repeat: RepeatAnimation,
+        playback: impl Into<Playback>,
         build_clip: F,
     ) where
         F: FnOnce(&C::AnimatedFrom, AnimationTargetId) -> AnimationClip + Send + 'static,
     {
+        let playback = playback.into();
         let cb: CB<C> = Box::new(
-            move |mut q_animation, mut q_reader, mut animation_clips, mut animation_graphs| {
-                let Some((target, mut saved, anim_from, mut player, graph)) = (try {
-                    let (target, saved, anim_from) = q_animation.get_mut(entity).ok()?;
-                    let (player, graph_handle) = q_reader.get_mut(target.player).ok()?;
-                    let graph = animation_graphs.get_mut(graph_handle.id())?;
-                    (target, saved, anim_from, player, graph)
-                }) else {
-                    warn!("couldn't start a readied animation");
+            move |mut commands,
+                  mut q_animation,
+                  mut q_reader,
+                  mut animation_clips,
+                  mut animation_graphs,
+                  settings| {
+                let Ok((target, mut saved, anim_from)) = q_animation.get_mut(entity) else {
+                    warn!("couldn't start a readied animation on {entity}");
+                    return;
+                };
+                // The player can be despawned out from under its targets, e.g. when the board is
+                // torn down; any node saved from its graph is meaningless now.
+                let Ok((mut player, graph_handle)) = q_reader.get_mut(target.player) else {
+                    warn!(
+                        "{entity} can't animate with despawned player {}",
+                        target.player
+                    );
+                    *saved.node_mut() = None;
+                    return;
+                };
+                let Some(graph) = animation_graphs.get_mut(graph_handle.id()) else {
+                    warn!("{entity}'s animation player has no graph");
+                    *saved.node_mut() = None;
                     return;
                 };
                 let clip = build_clip(anim_from, target.id);
+                let duration = clip.duration();
                 if let &mut Some(prev_node) = saved.node_mut() {
                     graph.remove_edge(graph.root, prev_node);
                 }
                 let clip_handle = animation_clips.add(clip);
                 let node_index = graph.add_clip(clip_handle, 1., graph.root);
-                player.play(node_index).set_repeat(repeat);
+                let active = player
+                    .play(node_index)
+                    .set_repeat(playback.repeat)
+                    .set_speed(playback.speed * settings.speed);
+                if !settings.plays(C::MOTION) {
+                    match playback.repeat {
+                        // A loop has no end to jump to, so it holds where it starts instead.
+                        RepeatAnimation::Forever => {
+                            active.set_speed(0.);
+                        }
+                        _ => {
+                            active.set_repeat(RepeatAnimation::Never).seek_to(duration);
+                        }
+                    }
+                }
                 *saved.node_mut() = Some(node_index);
+                let mut entity_commands = commands.entity(entity);
+                if let Some(tag) = playback.finish_tag {
+                    entity_commands.insert(AwaitingFinish {
+                        player: target.player,
+                        node: node_index,
+                        tag,
+                    });
+                } else {
+                    entity_commands.remove::<AwaitingFinish>();
+                }
             },
         );
         commands.run_system_cached_with(
             move |In(callback): In<CB<C>>,
+                  commands: Commands,
                   q_animation: Query<AnimD<C>>,
                   q_reader: Query<ReaderD>,
                   animation_clips: ResMut<Clips>,
-                  animation_graphs: ResMut<Graphs>| {
-                callback(q_animation, q_reader, animation_clips, animation_graphs);
+                  animation_graphs: ResMut<Graphs>,
+                  settings: Option<Res<AnimationSettings>>| {
+                callback(
+                    commands,
+                    q_animation,
+                    q_reader,
+                    animation_clips,
+                    animation_graphs,
+                    &settings.map(|s| s.clone()).unwrap_or_default(),
+                );
             },
             cb,
         );
     }
 }
+
+// Targets whose player is gone would otherwise keep warning every time they're animated.
+fn remove_orphaned_targets(
+    mut commands: Commands,
+    q_target: Query<(Entity, &AnimationTarget)>,
+    q_player: Query<(), With<AnimationPlayer>>,
+) {
+    for (entity, target) in &q_target {
+        if !q_player.contains(target.player) {
+            debug!(
+                "removing {entity}'s target for despawned player {}",
+                target.player
+            );
+            commands.entity(entity).remove::<AnimationTarget>();
+        }
+    }
+}
+
+fn report_finished_animations(
+    mut commands: Commands,
+    q_awaiting: Query<(Entity, &AwaitingFinish)>,
+    q_player: Query<&AnimationPlayer>,
+    mut finished_tx: EventWriter<AnimationFinished>,
+) {
+    for (entity, awaiting) in &q_awaiting {
+        let active = q_player
+            .get(awaiting.player)
+            .ok()
+            .and_then(|player| player.animation(awaiting.node));
+        match active {
+            Some(active) if !active.is_finished() => continue,
+            Some(_) => {
+                finished_tx.send(AnimationFinished {
+                    entity,
+                    tag: awaiting.tag,
+                });
+            }
+            // The player or its clip went away without finishing.
+            None => (),
+        }
+        commands.entity(entity).remove::<AwaitingFinish>();
+    }
+}
+
+pub struct AnimationTrackingPlugin;
+
+impl Plugin for AnimationTrackingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnimationFinished>()
+            .register_type::<AwaitingFinish>()
+            .add_systems(
+                PostUpdate,
+                (
+                    report_finished_animations.after(bevy::app::Animation),
+                    remove_orphaned_targets,
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::animation::animated_field;
+
+    use super::*;
+
+    #[derive(Debug, Default, Component)]
+    struct TestEdge(Option<NodeIndex>);
+
+    impl SavedAnimationNode for TestEdge {
+        type AnimatedFrom = Transform;
+
+        fn node_mut(&mut self) -> &mut Option<NodeIndex> {
+            &mut self.0
+        }
+    }
+
+    // A board with one animated cell, and its player spawned separately the way rows spawn theirs.
+    fn board() -> (App, Entity, Entity) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(AnimationTrackingPlugin)
+            .init_resource::<Clips>()
+            .init_resource::<Graphs>()
+            .add_systems(
+                PostUpdate,
+                bevy::animation::advance_animations.in_set(bevy::app::Animation),
+            );
+        let graph = app
+            .world_mut()
+            .resource_mut::<Graphs>()
+            .add(AnimationGraph::new());
+        let player = app
+            .world_mut()
+            .spawn((AnimationPlayer::default(), AnimationGraphHandle(graph)))
+            .id();
+        let cell = app
+            .world_mut()
+            .spawn(
+                (TestEdge::default(), Transform::default(), AnimationTarget {
+                    id: AnimationTargetId::from_name(&Name::new("cell")),
+                    player,
+                }),
+            )
+            .id();
+        (app, player, cell)
+    }
+
+    fn animate(app: &mut App, cell: Entity) {
+        animate_with(app, cell, RepeatAnimation::Never.into());
+    }
+
+    fn animate_with(app: &mut App, cell: Entity, playback: Playback) {
+        let mut commands = app.world_mut().commands();
+        AnimatorPlugin::<TestEdge>::start_animation(&mut commands, cell, playback, |_, _| {
+            AnimationClip::default()
+        });
+        app.world_mut().flush();
+    }
+
+    fn finished(app: &mut App) -> Vec<(Entity, &'static str)> {
+        app.world_mut()
+            .resource_mut::<Events<AnimationFinished>>()
+            .drain()
+            .map(|ev| (ev.entity, ev.tag))
+            .collect()
+    }
+
+    #[test]
+    fn animates_with_a_live_player() {
+        let (mut app, _player, cell) = board();
+        animate(&mut app, cell);
+        assert!(app.world().get::<TestEdge>(cell).unwrap().0.is_some());
+    }
+
+    #[test]
+    fn board_teardown_orphans_no_targets() {
+        let (mut app, player, cell) = board();
+        animate(&mut app, cell);
+        app.world_mut().despawn(player);
+
+        // Animating before the cleanup has run forgets the stale node instead of panicking.
+        animate(&mut app, cell);
+        assert!(app.world().get::<TestEdge>(cell).unwrap().0.is_none());
+
+        app.update();
+        assert!(app.world().get::<AnimationTarget>(cell).is_none());
+        animate(&mut app, cell);
+    }
+
+    #[test]
+    fn plays_at_the_requested_speed() {
+        let (mut app, player, cell) = board();
+        animate_with(
+            &mut app,
+            cell,
+            Playback::new(RepeatAnimation::Never).with_speed(2.),
+        );
+        let node = app.world().get::<TestEdge>(cell).unwrap().0.unwrap();
+        let player = app.world().get::<AnimationPlayer>(player).unwrap();
+        assert_eq!(player.animation(node).unwrap().speed(), 2.);
+    }
+
+    #[test]
+    fn settings_speed_up_or_skip_clips() {
+        let (mut app, player, cell) = board();
+        app.insert_resource(AnimationSettings {
+            speed: 3.,
+            ..Default::default()
+        });
+        let grow = |_: &Transform, target| {
+            let mut clip = AnimationClip::default();
+            clip.add_curve_to_target(
+                target,
+                AnimatableCurve::new(
+                    animated_field!(Transform::scale),
+                    EasingCurve::new(Vec3::ONE, Vec3::splat(2.), EaseFunction::Linear)
+                        .reparametrize_linear(interval(0., 1.).unwrap())
+                        .unwrap(),
+                ),
+            );
+            clip
+        };
+        let start = |app: &mut App, playback: Playback| {
+            let mut commands = app.world_mut().commands();
+            AnimatorPlugin::<TestEdge>::start_animation(&mut commands, cell, playback, grow);
+            app.world_mut().flush();
+            let node = app.world().get::<TestEdge>(cell).unwrap().0.unwrap();
+            let player = app.world().get::<AnimationPlayer>(player).unwrap();
+            let active = player.animation(node).unwrap();
+            (active.speed(), active.seek_time())
+        };
+        let never = Playback::new(RepeatAnimation::Never).with_speed(2.);
+        assert_eq!(start(&mut app, never), (6., 0.));
+
+        // With motion turned off, a clip starts at its end, and a loop stands still.
+        app.insert_resource(AnimationSettings::reduced_motion());
+        assert_eq!(start(&mut app, never), (2., 1.));
+        assert_eq!(start(&mut app, RepeatAnimation::Forever.into()).0, 0.);
+    }
+
+    #[test]
+    fn reports_a_finished_clip() {
+        let (mut app, _player, cell) = board();
+        animate_with(
+            &mut app,
+            cell,
+            Playback::new(RepeatAnimation::Never).notify_finished("test"),
+        );
+        app.update();
+        assert_eq!(finished(&mut app), vec![(cell, "test")]);
+        // Nothing is left waiting to report the same clip again.
+        assert!(app.world().get::<AwaitingFinish>(cell).is_none());
+    }
+
+    #[test]
+    fn a_sequence_reports_each_step() {
+        let (mut app, _player, cell) = board();
+        let step = |tag| Playback::new(RepeatAnimation::Never).notify_finished(tag);
+        animate_with(&mut app, cell, step("enter"));
+        app.update();
+        assert_eq!(finished(&mut app), vec![(cell, "enter")]);
+
+        // What a listener would do on hearing the first step finish.
+        animate_with(&mut app, cell, step("settle"));
+        app.update();
+        assert_eq!(finished(&mut app), vec![(cell, "settle")]);
+    }
+
+    #[test]
+    fn interrupted_clips_never_report() {
+        let (mut app, _player, cell) = board();
+        animate_with(
+            &mut app,
+            cell,
+            Playback::new(RepeatAnimation::Never).notify_finished("test"),
+        );
+        animate(&mut app, cell);
+        app.update();
+        assert_eq!(finished(&mut app), vec![]);
+    }
+
+    #[test]
+    fn repeating_clips_never_report() {
+        let (mut app, _player, cell) = board();
+        animate_with(
+            &mut app,
+            cell,
+            Playback::new(RepeatAnimation::Forever).notify_finished("test"),
+        );
+        app.update();
+        app.update();
+        assert_eq!(finished(&mut app), vec![]);
+        assert!(app.world().get::<AwaitingFinish>(cell).is_some());
+    }
+}
@@ -0,0 +1,196 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    fit::{FitManip, FitWithin, FitWithinBackground, FitWithinBundle},
+    pins::PinnedClue,
+    toast::ShowToast,
+    DisplayClue, DisplayCluebox, ExplanationHilight, HintHilight, DEFAULT_BORDER_COLOR, NO_PICK,
+};
+
+// A clue the player is done with, moved out of the way into a drawer at the end of the cluebox.
+// Middle-clicking a clue archives it or brings it back. Archived clues are still counted, saved,
+// and pointed out by hints and explanations.
+#[derive(Debug, Component, Reflect)]
+pub struct ArchivedClue;
+
+// The drawer stays shut until it's clicked, except while a hint or explanation points into it.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ClueArchive {
+    open: bool,
+    held_open: bool,
+}
+
+impl ClueArchive {
+    pub fn is_open(&self) -> bool {
+        self.open || self.held_open
+    }
+}
+
+// Lives in the cluebox after the clues, and is laid out only while something is archived.
+#[derive(Debug, Component, Reflect)]
+pub struct ArchiveDrawer;
+
+#[derive(Debug, Component, Reflect)]
+struct ArchiveDrawerLabel;
+
+fn spawn_archive_drawer(ev: Trigger<OnAdd, DisplayCluebox>, mut commands: Commands) {
+    commands.entity(ev.entity()).with_children(|parent| {
+        parent
+            .spawn((
+                ArchiveDrawer,
+                FitWithinBundle::new(),
+                FitWithinBackground::new(6)
+                    .colored(DEFAULT_BORDER_COLOR)
+                    .with_interaction(true),
+                Visibility::Hidden,
+            ))
+            .with_child((
+                ArchiveDrawerLabel,
+                Text2d::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..Default::default()
+                },
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ));
+    });
+}
+
+fn toggle_archived_clue(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_clue: Query<(Has<ArchivedClue>, Has<PinnedClue>), With<DisplayClue>>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    if ev.event().button != PointerButton::Middle {
+        return;
+    }
+    let clue = ev.entity();
+    let Ok((archived, pinned)) = q_clue.get(clue) else {
+        return;
+    };
+    if archived {
+        commands.entity(clue).remove::<ArchivedClue>();
+    } else if pinned {
+        toast_tx.send(ShowToast::error("Unpin the clue before archiving it."));
+    } else {
+        commands.entity(clue).insert(ArchivedClue);
+    }
+}
+
+fn archive_drawer_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_drawer: Query<(), With<ArchiveDrawer>>,
+    mut archive: ResMut<ClueArchive>,
+) {
+    if ev.event().button == PointerButton::Primary && q_drawer.contains(ev.entity()) {
+        archive.open = !archive.open;
+    }
+}
+
+fn hold_archive_open(
+    mut archive: ResMut<ClueArchive>,
+    q_archived: Query<(Has<ExplanationHilight>, Has<HintHilight>), With<ArchivedClue>>,
+) {
+    let held_open = q_archived
+        .iter()
+        .any(|(explained, hinted)| explained || hinted);
+    if archive.held_open != held_open {
+        archive.held_open = held_open;
+    }
+}
+
+// Whenever what's archived or whether the drawer is open changes, archived clues are shown or
+// hidden, the drawer relabeled, and the cluebox laid out again.
+fn update_clue_archive(
+    archive: Res<ClueArchive>,
+    (q_added, mut removed): (
+        Query<(), Added<ArchivedClue>>,
+        RemovedComponents<ArchivedClue>,
+    ),
+    q_clues: Query<(Entity, Has<ArchivedClue>), With<DisplayClue>>,
+    q_drawer: Query<Entity, With<ArchiveDrawer>>,
+    mut q_label: Query<&mut Text2d, With<ArchiveDrawerLabel>>,
+    q_cluebox: Query<(Entity, &FitWithin), With<DisplayCluebox>>,
+    mut commands: Commands,
+) {
+    let removed = removed.read().count() > 0;
+    if !archive.is_changed() && q_added.is_empty() && !removed {
+        return;
+    }
+    let mut n_archived = 0;
+    for (entity, archived) in &q_clues {
+        n_archived += usize::from(archived);
+        let visibility = if archived && !archive.is_open() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        commands.entity(entity).insert(visibility);
+    }
+    let drawer_visibility = if n_archived > 0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for entity in &q_drawer {
+        commands.entity(entity).insert(drawer_visibility);
+    }
+    let label = archive_drawer_label(archive.is_open(), n_archived);
+    for mut text in &mut q_label {
+        text.0.clone_from(&label);
+    }
+    for e_fit in &q_cluebox {
+        e_fit.refresh_rect(&mut commands);
+    }
+}
+
+fn archive_drawer_label(open: bool, n_archived: usize) -> String {
+    if open {
+        format!("{n_archived} archived; click to close")
+    } else {
+        format!("{n_archived} archived; click to open")
+    }
+}
+
+pub struct ArchivePlugin;
+
+impl Plugin for ArchivePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClueArchive>()
+            .register_type::<ArchiveDrawer>()
+            .register_type::<ArchiveDrawerLabel>()
+            .register_type::<ArchivedClue>()
+            .register_type::<ClueArchive>()
+            .add_observer(archive_drawer_clicked)
+            .add_observer(spawn_archive_drawer)
+            .add_observer(toggle_archived_clue)
+            .add_systems(Update, (hold_archive_open, update_clue_archive).chain());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pointing_into_the_drawer_holds_it_open() {
+        let mut archive = ClueArchive::default();
+        assert!(!archive.is_open());
+        archive.held_open = true;
+        assert!(archive.is_open());
+        archive.held_open = false;
+        archive.open = true;
+        assert!(archive.is_open());
+        assert_eq!(
+            archive_drawer_label(archive.is_open(), 2),
+            "2 archived; click to close"
+        );
+    }
+}
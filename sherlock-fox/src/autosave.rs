@@ -0,0 +1,179 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    archive::ArchivedClue,
+    notes::PuzzleNotes,
+    puzzle::Puzzle,
+    save::{self, SavedPuzzle, SAVE_EXTENSION},
+    settings::Settings,
+    undo::{PushNewAction, UndoTree, UndoTreeLocation},
+    DisplayCluebox, GameState, PuzzleClueComponent, PuzzleOrigin, PuzzleReveals,
+};
+
+// Checkpoints of the game in progress, so a crash loses at most a few moves. They're numbered in
+// the order they were written, and only the newest few are kept.
+pub const AUTOSAVE_DIR: &str = "autosave";
+const CHECKPOINT_PREFIX: &str = "checkpoint-";
+
+fn checkpoint_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{CHECKPOINT_PREFIX}{seq:06}.{SAVE_EXTENSION}"))
+}
+
+// The sequence numbers of the checkpoints in `dir`, oldest first.
+fn checkpoint_seqs(dir: &Path) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut seqs = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            name.strip_prefix(CHECKPOINT_PREFIX)?
+                .strip_suffix(SAVE_EXTENSION)?
+                .strip_suffix('.')?
+                .parse()
+                .ok()
+        })
+        .collect::<Vec<u64>>();
+    seqs.sort();
+    seqs
+}
+
+pub fn has_checkpoints(dir: &Path) -> bool {
+    !checkpoint_seqs(dir).is_empty()
+}
+
+// Written to a scratch file first so a crash partway through can't leave a truncated checkpoint.
+pub fn write_checkpoint(dir: &Path, saved: &SavedPuzzle, keep: usize) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let seqs = checkpoint_seqs(dir);
+    let seq = seqs.last().map_or(0, |&last| last + 1);
+    let path = checkpoint_path(dir, seq);
+    let scratch = path.with_extension("tmp");
+    saved.write_to(&scratch)?;
+    fs::rename(&scratch, &path)?;
+    let stale = (seqs.len() + 1).saturating_sub(keep.max(1));
+    for &old in &seqs[..stale] {
+        fs::remove_file(checkpoint_path(dir, old))?;
+    }
+    Ok(path)
+}
+
+// The newest checkpoint that still loads; older ones stand in for any that don't.
+pub fn read_latest_checkpoint(dir: &Path) -> io::Result<SavedPuzzle> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no checkpoints");
+    for seq in checkpoint_seqs(dir).into_iter().rev() {
+        let path = checkpoint_path(dir, seq);
+        match SavedPuzzle::read_from(&path) {
+            Ok(saved) => return Ok(saved),
+            Err(e) => {
+                warn!("skipping unreadable checkpoint {path:?}: {e}");
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct Autosave {
+    actions_since_checkpoint: usize,
+}
+
+fn autosave_checkpoint(
+    mut ev_rx: EventReader<PushNewAction>,
+    settings: Res<Settings>,
+    mut autosave: ResMut<Autosave>,
+    q_puzzle: Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+    q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+    q_cluebox: Query<&Children, With<DisplayCluebox>>,
+    q_clues: Query<(&PuzzleClueComponent, Has<ArchivedClue>)>,
+) {
+    autosave.actions_since_checkpoint += ev_rx.read().count();
+    if settings.autosave_interval == 0
+        || autosave.actions_since_checkpoint < settings.autosave_interval
+    {
+        return;
+    }
+    let Some(saved) = save::capture_game(&q_puzzle, &q_tree, &q_cluebox, &q_clues) else {
+        return;
+    };
+    autosave.actions_since_checkpoint = 0;
+    let dir = Path::new(AUTOSAVE_DIR);
+    match write_checkpoint(dir, &saved, settings.autosave_checkpoints) {
+        Ok(path) => debug!("autosaved to {path:?}"),
+        Err(e) => warn!("couldn't autosave to {dir:?}: {e}"),
+    }
+}
+
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autosave>()
+            .register_type::<Autosave>()
+            // After the undo tree has taken in the new action.
+            .add_systems(
+                PostUpdate,
+                autosave_checkpoint.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{puzzle::test_puzzle, save::PuzzleMetadata};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sherlock-fox-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn saved(notes: &str) -> SavedPuzzle {
+        SavedPuzzle {
+            metadata: PuzzleMetadata::default(),
+            puzzle: test_puzzle(2, 4),
+            reveals: vec![],
+            clues: vec![],
+            archived: vec![],
+            notes: notes.to_string(),
+            undo: None,
+        }
+    }
+
+    #[test]
+    fn only_the_newest_checkpoints_are_kept() {
+        let dir = scratch_dir("rotate");
+        assert!(!has_checkpoints(&dir));
+        for nr in 0..5 {
+            write_checkpoint(&dir, &saved(&nr.to_string()), 2).unwrap();
+        }
+        assert_eq!(checkpoint_seqs(&dir), vec![3, 4]);
+        assert_eq!(read_latest_checkpoint(&dir).unwrap().notes, "4");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_checkpoints_fall_back_to_older_ones() {
+        let dir = scratch_dir("fallback");
+        write_checkpoint(&dir, &saved("good"), 3).unwrap();
+        let bad = write_checkpoint(&dir, &saved("bad"), 3).unwrap();
+        fs::write(&bad, "(metadata: (").unwrap();
+        assert_eq!(read_latest_checkpoint(&dir).unwrap().notes, "good");
+
+        fs::write(checkpoint_path(&dir, 0), "").unwrap();
+        assert!(read_latest_checkpoint(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
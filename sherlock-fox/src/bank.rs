@@ -0,0 +1,193 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{path::PathBuf, str::FromStr};
+
+use bevy::app::AppExit;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    clues::ClueKind,
+    generate::{self, GeneratorConfig},
+    save::{PuzzleMetadata, SavedPuzzle, SAVE_EXTENSION},
+    solver::{DeductionTier, Difficulty},
+};
+
+const USAGE: &str = "usage: sherlock-fox generate-bank <output-dir> [--count N] [--rows N] \
+                     [--cols N] [--max-clues N] [--reveals N] [--min-depth N] [--tier TIER] \
+                     [--difficulty DIFFICULTY] [--seed N] [--generator-version N] [--clue-mix \
+                     MIX] [--edge-clue-weight N]
+
+--rows and --cols are each from 4 to 8.
+
+--reveals is how many cells start out revealed, from 0 to 3; from --generator-version 9 it \
+                     defaults to fewer for harder tiers, and before that to 1.
+
+TIER is one of single, inference, multi, or hypothesis; only puzzles whose hardest required \
+                     deduction is exactly that tier are kept.
+
+DIFFICULTY is one of easy, medium, hard, or fiendish; only puzzles scored at exactly that \
+                     difficulty are kept.
+
+MIX is one of balanced, easy, or hard, and sets how often each kind of clue is rolled; \
+                     --edge-clue-weight then overrides the weight for edge-column clues with a \
+                     whole number, which is also what version 7 weighs them by.
+
+Seeds only reproduce puzzles made with the same --generator-version, which defaults to the latest, \
+                     and the same --clue-mix, --edge-clue-weight and --reveals.";
+
+#[derive(Debug)]
+struct BankArgs {
+    output: PathBuf,
+    count: usize,
+    config: GeneratorConfig,
+    min_depth: usize,
+    tier: Option<DeductionTier>,
+    difficulty: Option<Difficulty>,
+    seed: Option<u64>,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag}: couldn't parse {value:?}"))
+}
+
+fn parse_args(args: &[String]) -> Result<BankArgs, String> {
+    let mut output = None;
+    let mut parsed = BankArgs {
+        output: PathBuf::new(),
+        count: 10,
+        config: GeneratorConfig::default(),
+        min_depth: 0,
+        tier: None,
+        difficulty: None,
+        seed: None,
+    };
+    let mut reveals = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--count" => parsed.count = parse_value(arg, args.next())?,
+            "--rows" => parsed.config.n_rows = parse_value(arg, args.next())?,
+            "--cols" => parsed.config.n_cols = parse_value(arg, args.next())?,
+            "--max-clues" => parsed.config.max_clues = parse_value(arg, args.next())?,
+            "--reveals" => reveals = Some(parse_value(arg, args.next())?),
+            "--min-depth" => parsed.min_depth = parse_value(arg, args.next())?,
+            "--tier" => {
+                let tier = parse_value(arg, args.next())?;
+                parsed.config.max_tier = tier;
+                parsed.tier = Some(tier);
+            }
+            "--difficulty" => parsed.difficulty = Some(parse_value(arg, args.next())?),
+            "--seed" => parsed.seed = Some(parse_value(arg, args.next())?),
+            "--generator-version" => parsed.config.version = parse_value(arg, args.next())?,
+            "--clue-mix" => parsed.config.set_clue_mix(parse_value(arg, args.next())?),
+            "--edge-clue-weight" => {
+                let weight = parse_value(arg, args.next())?;
+                parsed.config.edge_clue_weight = weight;
+                parsed
+                    .config
+                    .clue_weights
+                    .insert(ClueKind::EdgeColumn, weight.into());
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            path if output.is_none() => output = Some(PathBuf::from(path)),
+            extra => return Err(format!("unexpected argument {extra:?}")),
+        }
+    }
+    parsed.output = output.ok_or("missing output directory")?;
+    parsed.config.n_reveals = reveals.unwrap_or_else(|| {
+        generate::default_reveals(parsed.config.version, parsed.config.max_tier)
+    });
+    parsed.config.check_size()?;
+    parsed.config.check_reveals()?;
+    Ok(parsed)
+}
+
+pub fn generate_bank(args: &[String]) -> AppExit {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            return AppExit::error();
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&args.output) {
+        eprintln!("couldn't create {:?}: {e}", args.output);
+        return AppExit::error();
+    }
+
+    let mut bank_rng = match args.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_os_rng(),
+    };
+    let max_attempts = args.count.saturating_mul(50).max(50);
+    let mut written = 0;
+    for _ in 0..max_attempts {
+        if written == args.count {
+            break;
+        }
+        // Every puzzle gets its own seed so any single one can be regenerated later.
+        let seed = bank_rng.random();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let Some(generated) = generate::generate_puzzle(&mut rng, &args.config) else {
+            continue;
+        };
+        if generated.report.rounds < args.min_depth {
+            continue;
+        }
+        if args
+            .tier
+            .is_some_and(|tier| generated.report.max_tier != tier)
+        {
+            continue;
+        }
+        let difficulty = generated.report.difficulty();
+        if args.difficulty.is_some_and(|wanted| difficulty != wanted) {
+            continue;
+        }
+        let saved = SavedPuzzle {
+            metadata: PuzzleMetadata::generated(seed, &args.config, &generated),
+            puzzle: generated.puzzle,
+            reveals: generated.reveals,
+            clues: generated.clues.iter().map(|c| c.to_saved()).collect(),
+            archived: vec![],
+            notes: String::new(),
+            undo: None,
+        };
+        written += 1;
+        let path = args
+            .output
+            .join(format!("puzzle-{written:04}.{SAVE_EXTENSION}"));
+        if let Err(e) = saved.write_to(&path) {
+            eprintln!("couldn't write {path:?}: {e}");
+            return AppExit::error();
+        }
+        println!(
+            "{}: seed {seed} with {} clues and {} reveals ({} needed), deduction depth {} at \
+             {:?}, {:?} (score {})",
+            path.display(),
+            saved.metadata.clue_count,
+            saved.metadata.reveal_count,
+            generated.needed_reveals,
+            saved.metadata.deduction_depth,
+            saved.metadata.deduction_tier,
+            difficulty,
+            generated.report.difficulty_score(),
+        );
+    }
+
+    if written < args.count {
+        eprintln!(
+            "only generated {written} of {} puzzles; try more --max-clues, a lower --min-depth, \
+             or another --tier or --difficulty",
+            args.count
+        );
+        return AppExit::error();
+    }
+    AppExit::Success
+}
@@ -0,0 +1,242 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{prelude::*, utils::HashMap, window::PrimaryWindow};
+use petgraph::Direction;
+
+use crate::{
+    fit::{FitDisabled, FitHover},
+    puzzle::Puzzle,
+    speedrun::Speedrun,
+    undo::{UndoTree, UndoTreeLocation},
+    DisplayTopButton, TopButtonAction, NO_PICK,
+};
+
+// Which top buttons can't be used right now, and why. The buttons, the keyboard shortcuts and the
+// outcome overlay all check this, so a disabled action is blocked however it's pressed.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TopButtonState {
+    disabled: HashMap<TopButtonAction, &'static str>,
+}
+
+impl TopButtonState {
+    pub fn compute(puzzle: &Puzzle, tree: &UndoTree, tree_loc: &UndoTreeLocation) -> Self {
+        use TopButtonAction as B;
+        let mut disabled = HashMap::new();
+        if tree_loc.current == tree.start {
+            disabled.insert(B::Undo, "There's nothing to undo.");
+            disabled.insert(B::Restart, "The puzzle is already at the start.");
+        }
+        if tree.redo_target(tree_loc.current).is_none() {
+            let branches = tree
+                .tree
+                .edges_directed(tree_loc.current, Direction::Incoming)
+                .count();
+            let reason = if branches > 1 {
+                "The history branches here; pick a branch from History."
+            } else {
+                "There's nothing to redo."
+            };
+            disabled.insert(B::Redo, reason);
+        }
+        if puzzle.is_solved() {
+            disabled.insert(B::Clue, "The puzzle is already solved.");
+            disabled.insert(B::Hint, "The puzzle is already solved.");
+        }
+        TopButtonState { disabled }
+    }
+
+    // Keeps whichever reason was given first.
+    pub fn disable(&mut self, action: TopButtonAction, reason: &'static str) {
+        self.disabled.entry(action).or_insert(reason);
+    }
+
+    pub fn is_enabled(&self, action: TopButtonAction) -> bool {
+        !self.disabled.contains_key(&action)
+    }
+
+    pub fn reason(&self, action: TopButtonAction) -> Option<&'static str> {
+        self.disabled.get(&action).copied()
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct TopButtonTooltip;
+
+const DISABLED_TEXT_ALPHA: f32 = 0.35;
+const TOOLTIP_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+
+fn update_top_button_state(
+    mut state: ResMut<TopButtonState>,
+    q_puzzle: Query<Ref<Puzzle>>,
+    q_tree: Query<(Ref<UndoTree>, Ref<UndoTreeLocation>)>,
+    speedrun: Res<Speedrun>,
+) {
+    let (Ok(puzzle), Ok((tree, tree_loc))) = (q_puzzle.get_single(), q_tree.get_single()) else {
+        return;
+    };
+    if !puzzle.is_changed()
+        && !tree.is_changed()
+        && !tree_loc.is_changed()
+        && !speedrun.is_changed()
+    {
+        return;
+    }
+    let mut new_state = TopButtonState::compute(&puzzle, &tree, &tree_loc);
+    if speedrun.is_active() {
+        let reason = "Hints are off in speedrun mode.";
+        new_state.disable(TopButtonAction::Clue, reason);
+        new_state.disable(TopButtonAction::Hint, reason);
+    }
+    if new_state.disabled != state.disabled {
+        *state = new_state;
+    }
+}
+
+fn show_top_button_state(
+    mut commands: Commands,
+    state: Res<TopButtonState>,
+    q_button: Query<(Entity, Ref<DisplayTopButton>, &Children)>,
+    mut q_text: Query<&mut TextColor, With<Text2d>>,
+) {
+    for (entity, button, children) in &q_button {
+        if !state.is_changed() && !button.is_added() {
+            continue;
+        }
+        let enabled = state.is_enabled(button.0);
+        if enabled {
+            commands.entity(entity).remove::<FitDisabled>();
+        } else {
+            commands.entity(entity).insert(FitDisabled);
+        }
+        let alpha = if enabled { 1. } else { DISABLED_TEXT_ALPHA };
+        let mut texts = q_text.iter_many_mut(children);
+        while let Some(mut color) = texts.fetch_next() {
+            color.0.set_alpha(alpha);
+        }
+    }
+}
+
+fn show_top_button_tooltip(
+    ev: Trigger<OnAdd, FitHover>,
+    mut commands: Commands,
+    state: Res<TopButtonState>,
+    q_button: Query<&DisplayTopButton>,
+    window: Single<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    let (Some(reason), Some(cursor)) = (state.reason(button.0), window.cursor_position()) else {
+        return;
+    };
+    commands.spawn((
+        TopButtonTooltip,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(cursor.x + 12.),
+            top: Val::Px(cursor.y + 16.),
+            padding: UiRect::axes(Val::Px(6.), Val::Px(3.)),
+            ..Default::default()
+        },
+        BackgroundColor(TOOLTIP_BACKGROUND_COLOR),
+        Text::new(reason),
+        TextFont {
+            font_size: 14.,
+            ..Default::default()
+        },
+        NO_PICK,
+    ));
+}
+
+fn hide_top_button_tooltip(
+    ev: Trigger<OnRemove, FitHover>,
+    mut commands: Commands,
+    q_button: Query<(), With<DisplayTopButton>>,
+    q_tooltip: Query<Entity, With<TopButtonTooltip>>,
+) {
+    if !q_button.contains(ev.entity()) {
+        return;
+    }
+    for entity in &q_tooltip {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct TopButtonStatePlugin;
+
+impl Plugin for TopButtonStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TopButtonState>()
+            .register_type::<TopButtonState>()
+            .register_type::<TopButtonTooltip>()
+            .add_observer(show_top_button_tooltip)
+            .add_observer(hide_top_button_tooltip)
+            .add_systems(
+                Update,
+                (update_top_button_state, show_top_button_state).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+
+    use super::*;
+    use crate::{
+        puzzle::{test_puzzle, CellLoc, LCol, LRow},
+        undo::Action,
+    };
+
+    fn play(puzzle: &mut Puzzle, col: isize) -> Action {
+        let loc = CellLoc {
+            row: LRow(0),
+            col: LCol(col),
+        };
+        let update = puzzle.answer_at(loc).decay_to_ind().as_solo();
+        let (update_count, inferred_count) = puzzle
+            .apply_update(&update, &mut HashSet::new())
+            .unwrap()
+            .unwrap();
+        Action {
+            update,
+            update_count,
+            inferred_count,
+            joined: vec![],
+        }
+    }
+
+    #[test]
+    fn buttons_follow_the_history() {
+        use TopButtonAction as B;
+        let mut puzzle = test_puzzle(2, 4);
+        let (mut tree, mut location) = UndoTree::new(puzzle.clone());
+        let state = TopButtonState::compute(&puzzle, &tree, &location);
+        assert!(!state.is_enabled(B::Undo) && !state.is_enabled(B::Redo));
+        assert_eq!(state.reason(B::Redo), Some("There's nothing to redo."));
+        assert!(state.is_enabled(B::Clue) && state.is_enabled(B::Save));
+
+        let action = play(&mut puzzle, 0);
+        tree.push(&mut location, puzzle.clone(), action);
+        let state = TopButtonState::compute(&puzzle, &tree, &location);
+        assert!(state.is_enabled(B::Undo) && !state.is_enabled(B::Redo));
+
+        // Two moves from the start leave redo with no single way forward.
+        let first = location.current;
+        location.current = tree.root;
+        let mut branch = tree.tree[tree.root].clone();
+        let action = play(&mut branch, 1);
+        tree.push(&mut location, branch, action);
+        location.current = tree.root;
+        let state = TopButtonState::compute(&puzzle, &tree, &location);
+        assert_eq!(
+            state.reason(B::Redo),
+            Some("The history branches here; pick a branch from History.")
+        );
+        location.current = first;
+        assert!(TopButtonState::compute(&puzzle, &tree, &location).is_enabled(B::Undo));
+    }
+}
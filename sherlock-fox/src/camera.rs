@@ -0,0 +1,100 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{
+    core_pipeline::tonemapping::Tonemapping,
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    ui::IsDefaultUiCamera,
+    window::PrimaryWindow,
+};
+
+// The board is always laid out in this many world units, whatever the window's size; the window
+// shows it as large as fits at this aspect ratio, with bars filling the rest.
+pub const BOARD_SIZE: Vec2 = Vec2::new(1280., 720.);
+
+#[derive(Reflect, Debug, Component)]
+pub struct BoardCamera;
+
+fn spawn_cameras(mut commands: Commands) {
+    commands.spawn((BoardCamera, Camera2d, OrthographicProjection {
+        scaling_mode: ScalingMode::Fixed {
+            width: BOARD_SIZE.x,
+            height: BOARD_SIZE.y,
+        },
+        ..OrthographicProjection::default_2d()
+    }));
+    // UI stays in window space over the whole window, bars included. It's a 3d camera so that it
+    // draws none of the board's sprites, and so that sprite picking, which goes through the first
+    // orthographic camera it finds, only ever finds the board's.
+    commands.spawn((
+        IsDefaultUiCamera,
+        Camera3d::default(),
+        Camera {
+            order: 1,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        Tonemapping::None,
+    ));
+}
+
+// The largest centered viewport with the board's aspect ratio, in physical pixels.
+fn letterbox(window_size: UVec2) -> (UVec2, UVec2) {
+    let window_size = window_size.as_vec2();
+    let scale = (window_size / BOARD_SIZE).min_element();
+    let size = (BOARD_SIZE * scale).floor().max(Vec2::ONE);
+    let position = ((window_size - size) / 2.).floor().max(Vec2::ZERO);
+    (position.as_uvec2(), size.as_uvec2())
+}
+
+fn letterbox_board_camera(
+    window: Single<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut camera: Single<&mut Camera, With<BoardCamera>>,
+) {
+    let (physical_position, physical_size) = letterbox(window.physical_size());
+    let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+        viewport.physical_position == physical_position && viewport.physical_size == physical_size
+    });
+    if !unchanged {
+        camera.viewport = Some(Viewport {
+            physical_position,
+            physical_size,
+            ..Default::default()
+        });
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<BoardCamera>()
+            .add_systems(Startup, spawn_cameras)
+            .add_systems(PreUpdate, letterbox_board_camera);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letterboxes_to_the_board_aspect() {
+        // Too wide: bars on the sides.
+        assert_eq!(
+            letterbox(UVec2::new(1600, 720)),
+            (UVec2::new(160, 0), UVec2::new(1280, 720))
+        );
+        // Too tall: bars above and below.
+        assert_eq!(
+            letterbox(UVec2::new(640, 720)),
+            (UVec2::new(0, 180), UVec2::new(640, 360))
+        );
+        assert_eq!(
+            letterbox(UVec2::new(2560, 1440)),
+            (UVec2::ZERO, UVec2::new(2560, 1440))
+        );
+    }
+}
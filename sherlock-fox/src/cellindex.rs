@@ -0,0 +1,166 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    puzzle::{CellLoc, CellLocIndex},
+    DisplayCell, DisplayCellButton,
+};
+
+// Where each cell's entities are, kept up to date as they spawn and despawn so that refreshing a
+// cell's display doesn't walk every button on the board to find its own.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct CellEntities {
+    #[reflect(ignore)]
+    backgrounds: HashMap<CellLoc, Entity>,
+    // Kept sorted by entity, the order buttons are animated in.
+    #[reflect(ignore)]
+    buttons: HashMap<CellLoc, Vec<(Entity, CellLocIndex)>>,
+}
+
+impl CellEntities {
+    pub fn background(&self, loc: CellLoc) -> Option<Entity> {
+        self.backgrounds.get(&loc).copied()
+    }
+
+    pub fn buttons(&self, loc: CellLoc) -> &[(Entity, CellLocIndex)] {
+        self.buttons.get(&loc).map_or(&[], Vec::as_slice)
+    }
+
+    // The buttons for one candidate: the board's own, and the magnifier's copy while it's open.
+    pub fn buttons_showing(&self, index: CellLocIndex) -> impl Iterator<Item = Entity> + use<'_> {
+        self.buttons(index.loc)
+            .iter()
+            .filter(move |&&(_, i)| i == index)
+            .map(|&(entity, _)| entity)
+    }
+}
+
+fn index_cell(
+    ev: Trigger<OnAdd, DisplayCell>,
+    q_cell: Query<&DisplayCell, Without<DisplayCellButton>>,
+    mut index: ResMut<CellEntities>,
+) {
+    if let Ok(cell) = q_cell.get(ev.entity()) {
+        index.backgrounds.insert(cell.loc, ev.entity());
+    }
+}
+
+fn unindex_cell(
+    ev: Trigger<OnRemove, DisplayCell>,
+    q_cell: Query<&DisplayCell>,
+    mut index: ResMut<CellEntities>,
+) {
+    let Ok(cell) = q_cell.get(ev.entity()) else {
+        return;
+    };
+    if index.backgrounds.get(&cell.loc) == Some(&ev.entity()) {
+        index.backgrounds.remove(&cell.loc);
+    }
+}
+
+fn index_button(
+    ev: Trigger<OnAdd, DisplayCellButton>,
+    q_button: Query<&DisplayCellButton, Without<DisplayCell>>,
+    mut index: ResMut<CellEntities>,
+) {
+    let Ok(&DisplayCellButton { index: cell_index }) = q_button.get(ev.entity()) else {
+        return;
+    };
+    let buttons = index.buttons.entry(cell_index.loc).or_default();
+    let pos = buttons.partition_point(|&(e, _)| e < ev.entity());
+    buttons.insert(pos, (ev.entity(), cell_index));
+}
+
+fn unindex_button(
+    ev: Trigger<OnRemove, DisplayCellButton>,
+    q_button: Query<&DisplayCellButton>,
+    mut index: ResMut<CellEntities>,
+) {
+    let Ok(&DisplayCellButton { index: cell_index }) = q_button.get(ev.entity()) else {
+        return;
+    };
+    let Some(buttons) = index.buttons.get_mut(&cell_index.loc) else {
+        return;
+    };
+    buttons.retain(|&(e, _)| e != ev.entity());
+    if buttons.is_empty() {
+        index.buttons.remove(&cell_index.loc);
+    }
+}
+
+pub struct CellIndexPlugin;
+
+impl Plugin for CellIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CellEntities>()
+            .register_type::<CellEntities>()
+            .add_observer(index_button)
+            .add_observer(index_cell)
+            .add_observer(unindex_button)
+            .add_observer(unindex_cell);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, LInd};
+
+    const SIZE: usize = 10;
+
+    // A 10x10 board, each cell with a button per candidate.
+    fn board() -> (App, Vec<Entity>) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(CellIndexPlugin);
+        let mut cells = vec![];
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let loc = loc(row, col as isize);
+                let cell = app.world_mut().spawn(DisplayCell { loc }).id();
+                for index in 0..SIZE {
+                    let index = CellLocIndex {
+                        loc,
+                        index: LInd(index),
+                    };
+                    app.world_mut()
+                        .spawn(DisplayCellButton { index })
+                        .set_parent(cell);
+                }
+                cells.push(cell);
+            }
+        }
+        (app, cells)
+    }
+
+    #[test]
+    fn indexes_every_cell_of_a_board() {
+        let (app, cells) = board();
+        let index = app.world().resource::<CellEntities>();
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let loc = loc(row, col as isize);
+                assert_eq!(index.background(loc), Some(cells[row * SIZE + col]));
+                let buttons = index.buttons(loc);
+                assert_eq!(buttons.len(), SIZE);
+                assert!(buttons.is_sorted_by_key(|&(e, _)| e));
+                assert!(buttons.iter().all(|(_, i)| i.loc == loc));
+            }
+        }
+    }
+
+    #[test]
+    fn despawned_cells_leave_the_index() {
+        let (mut app, cells) = board();
+        for &cell in &cells[..SIZE] {
+            app.world_mut().entity_mut(cell).despawn_recursive();
+        }
+        let index = app.world().resource::<CellEntities>();
+        assert_eq!(index.background(loc(0, 3)), None);
+        assert!(index.buttons(loc(0, 3)).is_empty());
+        assert_eq!(index.buttons(loc(1, 3)).len(), SIZE);
+    }
+}
@@ -0,0 +1,472 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{input::mouse::MouseWheel, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    camera::BoardCamera,
+    clues::DynPuzzleClue,
+    cursor_to_world,
+    fit::{wheel_steps, FitManip, FitWithin, FitWithinBackground, FitWithinBundle},
+    modal::{Modal, ModalStack},
+    puzzle::Puzzle,
+    settings::Settings,
+    zoom::ZOOM_MODIFIERS,
+    DisplayClue, DisplayCluebox, DisplayPuzzle, ExplanationHilight, GameState, HintHilight,
+    PuzzleClueComponent, DEFAULT_BORDER_COLOR, NO_PICK,
+};
+
+// Height of the strip between the matrix and the cluebox, which stays when the cluebox collapses.
+pub const CLUEBOX_HANDLE_HEIGHT: f32 = 24.;
+
+// Collapsing the cluebox gives its height to the matrix. An explanation needs its clue on screen,
+// so it holds the cluebox open for as long as it's shown.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ClueboxFold {
+    collapsed: bool,
+    held_open: bool,
+}
+
+impl ClueboxFold {
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed && !self.held_open
+    }
+
+    pub fn toggle_collapsed(&mut self) {
+        self.collapsed = !self.collapsed;
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+pub struct ClueboxHandle;
+
+// Which row of clues is at the top of the cluebox, once there are more than fit in view.
+#[derive(Debug, Default, Component, Reflect)]
+pub struct ClueboxScroll {
+    first_row: usize,
+    // A clue to bring into view the next time the cluebox is laid out.
+    reveal: Option<Entity>,
+}
+
+impl ClueboxScroll {
+    pub fn scroll_by(&mut self, rows: isize) {
+        self.first_row = self.first_row.saturating_add_signed(rows);
+    }
+
+    pub fn revealing(&self) -> Option<Entity> {
+        self.reveal
+    }
+
+    // Keeps the top row within the `n_rows` there are, `in_view` of which are shown at once, and
+    // scrolls just far enough to show the revealed clue's row. Returns the top row.
+    pub fn settle(&mut self, n_rows: usize, in_view: usize, revealed_row: Option<usize>) -> usize {
+        let mut first_row = self.first_row.min(n_rows.saturating_sub(in_view));
+        match revealed_row {
+            Some(row) if row < first_row => first_row = row,
+            Some(row) if row >= first_row + in_view => first_row = row + 1 - in_view,
+            _ => (),
+        }
+        self.first_row = first_row;
+        self.reveal = None;
+        first_row
+    }
+}
+
+// A clue with nothing left to give, which is greyed out, or left out of the cluebox altogether if
+// `Settings::collapse_spent_clues` is on.
+#[derive(Debug, Component, Reflect)]
+pub struct SpentClue;
+
+#[derive(Debug, Component, Reflect)]
+struct SpentClueShade;
+
+const SPENT_CLUE_SHADE_COLOR: Color = Color::hsla(0., 0., 0.15, 0.7);
+
+// A clue that can't add anything right now may still once more candidates are ruled out, so it's
+// only spent once every item it names is placed as well.
+fn clue_is_spent(clue: &DynPuzzleClue, puzzle: &Puzzle) -> bool {
+    clue.locs().into_iter().all(|loc| {
+        let answer = puzzle.answer_at(loc).decay_to_ind();
+        puzzle.cell_selection(loc).is_solo(answer.index)
+    }) && clue.advance_puzzle(puzzle).is_none()
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ClueboxHandleLabel;
+
+fn spawn_cluebox_handle(ev: Trigger<OnAdd, DisplayPuzzle>, mut commands: Commands) {
+    commands.entity(ev.entity()).with_children(|parent| {
+        parent
+            .spawn((
+                ClueboxHandle,
+                FitWithinBundle::new(),
+                FitWithinBackground::new(6)
+                    .colored(DEFAULT_BORDER_COLOR)
+                    .with_interaction(true),
+            ))
+            .with_child((
+                ClueboxHandleLabel,
+                Text2d::default(),
+                TextFont {
+                    font_size: 14.,
+                    ..Default::default()
+                },
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ));
+    });
+}
+
+fn add_cluebox_scroll(ev: Trigger<OnAdd, DisplayCluebox>, mut commands: Commands) {
+    commands
+        .entity(ev.entity())
+        .insert(ClueboxScroll::default());
+}
+
+fn cluebox_handle_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_handle: Query<(), With<ClueboxHandle>>,
+    mut fold: ResMut<ClueboxFold>,
+) {
+    if q_handle.contains(ev.entity()) {
+        fold.toggle_collapsed();
+    }
+}
+
+fn hold_cluebox_open(modals: Res<ModalStack>, mut fold: ResMut<ClueboxFold>) {
+    let held_open = modals.is_open(Modal::Explanation);
+    if fold.held_open != held_open {
+        fold.held_open = held_open;
+    }
+}
+
+// Relaying out the puzzle moves the matrix into the height the cluebox gave up, or back out of it.
+fn apply_cluebox_fold(
+    fold: Res<ClueboxFold>,
+    mut commands: Commands,
+    q_puzzle: Query<(Entity, &FitWithin), With<DisplayPuzzle>>,
+    q_cluebox: Query<Entity, With<DisplayCluebox>>,
+) {
+    if !fold.is_changed() {
+        return;
+    }
+    for e_fit in &q_puzzle {
+        e_fit.refresh_rect(&mut commands);
+    }
+    let visibility = if fold.is_collapsed() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+    for entity in &q_cluebox {
+        commands.entity(entity).insert(visibility);
+    }
+}
+
+fn update_cluebox_handle_label(
+    fold: Res<ClueboxFold>,
+    q_cluebox: Query<Ref<Children>, With<DisplayCluebox>>,
+    q_clues: Query<(), With<DisplayClue>>,
+    mut q_label: Query<&mut Text2d, With<ClueboxHandleLabel>>,
+) {
+    let Ok(children) = q_cluebox.get_single() else {
+        return;
+    };
+    if !fold.is_changed() && !children.is_changed() {
+        return;
+    }
+    let n_clues = children.iter().filter(|&&e| q_clues.contains(e)).count();
+    let label = cluebox_handle_label(fold.is_collapsed(), n_clues);
+    for mut text in &mut q_label {
+        text.0.clone_from(&label);
+    }
+}
+
+// Whether a clue is spent only changes with the puzzle, and a new clue may arrive spent already.
+// The settings decide whether spent clues are left out of the cluebox.
+fn spent_clues_may_change(
+    q_puzzle: Query<(), Changed<Puzzle>>,
+    q_new_clue: Query<(), Added<PuzzleClueComponent>>,
+    settings: Res<Settings>,
+) -> bool {
+    !q_puzzle.is_empty() || !q_new_clue.is_empty() || settings.is_changed()
+}
+
+// Undoing can bring a spent clue back, so this follows the puzzle both ways.
+fn track_spent_clues(
+    puzzle: Single<&Puzzle>,
+    settings: Res<Settings>,
+    q_clues: Query<(
+        Entity,
+        &PuzzleClueComponent,
+        Option<&FitWithin>,
+        Has<SpentClue>,
+    )>,
+    (q_children, q_shade): (Query<&Children>, Query<(), With<SpentClueShade>>),
+    q_cluebox: Query<(Entity, &FitWithin), With<DisplayCluebox>>,
+    mut commands: Commands,
+) {
+    let mut relayout = settings.is_changed();
+    for (entity, clue, fit, spent) in &q_clues {
+        let now_spent = clue_is_spent(&clue.clue, &puzzle);
+        if now_spent == spent {
+            continue;
+        }
+        relayout = true;
+        if now_spent {
+            let size = fit.map(|fit| fit.rect().size()).unwrap_or_default();
+            commands.entity(entity).insert(SpentClue).with_child((
+                SpentClueShade,
+                Sprite::from_color(SPENT_CLUE_SHADE_COLOR, size),
+                Transform::from_xyz(0., 0., 5.),
+                NO_PICK,
+            ));
+        } else {
+            commands.entity(entity).remove::<SpentClue>();
+            for &child in q_children.get(entity).into_iter().flatten() {
+                if q_shade.contains(child) {
+                    commands.entity(child).despawn_recursive();
+                }
+            }
+        }
+    }
+    if relayout {
+        for e_fit in &q_cluebox {
+            e_fit.refresh_rect(&mut commands);
+        }
+    }
+}
+
+fn size_spent_clue_shade(
+    ev: Trigger<OnInsert, FitWithin>,
+    q_clue: Query<(&FitWithin, &Children), With<SpentClue>>,
+    mut q_shade: Query<&mut Sprite, With<SpentClueShade>>,
+) {
+    let Ok((fit, children)) = q_clue.get(ev.entity()) else {
+        return;
+    };
+    for &child in children {
+        if let Ok(mut sprite) = q_shade.get_mut(child) {
+            sprite.custom_size = Some(fit.rect().size());
+        }
+    }
+}
+
+fn scroll_cluebox(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel_rx: EventReader<MouseWheel>,
+    mut pixels: Local<f32>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    q_camera: Single<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    mut q_cluebox: Query<(Entity, &FitWithin, &GlobalTransform, &mut ClueboxScroll)>,
+    mut commands: Commands,
+) {
+    // The wheel zooms instead while a zoom modifier is held.
+    if keys.any_pressed(ZOOM_MODIFIERS) {
+        wheel_rx.clear();
+        return;
+    }
+    let (camera, camera_transform) = *q_camera;
+    let Some(cursor) = window
+        .cursor_position()
+        .and_then(|cursor| cursor_to_world(camera, camera_transform, cursor))
+    else {
+        wheel_rx.clear();
+        return;
+    };
+    for wheel in wheel_rx.read() {
+        let steps = wheel_steps(wheel, &mut pixels);
+        if steps == 0 {
+            continue;
+        }
+        for (entity, fit, transform, mut scroll) in &mut q_cluebox {
+            let local = transform
+                .affine()
+                .inverse()
+                .transform_point3(cursor.extend(0.));
+            if !Rect::from_center_size(Vec2::ZERO, fit.rect().size()).contains(local.truncate()) {
+                continue;
+            }
+            // Rolling the wheel up goes back toward the first clues.
+            scroll.scroll_by(-steps);
+            (entity, fit).refresh_rect(&mut commands);
+        }
+    }
+}
+
+type NewlyHighlightedClueF = (
+    With<DisplayClue>,
+    Or<(Added<ExplanationHilight>, Added<HintHilight>)>,
+);
+
+// An explanation or hint needs its clue on screen, wherever the cluebox was scrolled to.
+fn reveal_highlighted_clue(
+    q_highlighted: Query<Entity, NewlyHighlightedClueF>,
+    mut q_cluebox: Query<(Entity, &FitWithin, &mut ClueboxScroll)>,
+    mut commands: Commands,
+) {
+    let Some(clue) = q_highlighted.iter().next() else {
+        return;
+    };
+    for (entity, fit, mut scroll) in &mut q_cluebox {
+        scroll.reveal = Some(clue);
+        (entity, fit).refresh_rect(&mut commands);
+    }
+}
+
+fn cluebox_handle_label(collapsed: bool, n_clues: usize) -> String {
+    let plural = if n_clues == 1 { "" } else { "s" };
+    if collapsed {
+        format!("{n_clues} clue{plural} hidden; click to show")
+    } else {
+        format!("{n_clues} clue{plural}; click to hide")
+    }
+}
+
+pub struct ClueboxPlugin;
+
+impl Plugin for ClueboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClueboxFold>()
+            .register_type::<ClueboxFold>()
+            .register_type::<ClueboxHandle>()
+            .register_type::<ClueboxHandleLabel>()
+            .register_type::<ClueboxScroll>()
+            .register_type::<SpentClue>()
+            .register_type::<SpentClueShade>()
+            .add_observer(add_cluebox_scroll)
+            .add_observer(cluebox_handle_clicked)
+            .add_observer(size_spent_clue_shade)
+            .add_observer(spawn_cluebox_handle)
+            .add_systems(
+                Update,
+                (
+                    hold_cluebox_open,
+                    (apply_cluebox_fold, update_cluebox_handle_label),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    track_spent_clues.run_if(spent_clues_may_change),
+                    scroll_cluebox,
+                    reveal_highlighted_clue,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explanations_hold_the_cluebox_open() {
+        let mut fold = ClueboxFold::default();
+        assert!(!fold.is_collapsed());
+        fold.collapsed = true;
+        assert!(fold.is_collapsed());
+        fold.held_open = true;
+        assert!(!fold.is_collapsed());
+        fold.held_open = false;
+        assert!(fold.is_collapsed());
+        assert_eq!(
+            cluebox_handle_label(true, 1),
+            "1 clue hidden; click to show"
+        );
+    }
+
+    #[test]
+    fn scrolling_stays_within_the_rows_and_follows_reveals() {
+        let mut scroll = ClueboxScroll::default();
+        scroll.scroll_by(-3);
+        assert_eq!(scroll.settle(5, 2, None), 0);
+        scroll.scroll_by(10);
+        assert_eq!(scroll.settle(5, 2, None), 3);
+        // Revealing a row already in view leaves the scroll where it is.
+        assert_eq!(scroll.settle(5, 2, Some(4)), 3);
+        assert_eq!(scroll.settle(5, 2, Some(1)), 1);
+        scroll.first_row = 0;
+        assert_eq!(scroll.settle(5, 2, Some(3)), 2);
+        // Fewer rows than fit don't scroll at all.
+        assert_eq!(scroll.settle(1, 2, None), 0);
+    }
+
+    #[test]
+    fn clues_are_spent_once_their_items_are_placed() {
+        use bevy::utils::HashSet;
+
+        use crate::{
+            clues::{ClueKind, SavedClue},
+            puzzle::{loc, test_puzzle},
+        };
+        let mut puzzle = test_puzzle(3, 3);
+        let saved = SavedClue::from_parts(ClueKind::SameColumn, &[loc(0, 1), loc(1, 1)], false);
+        let clue = DynPuzzleClue::from(saved.unwrap());
+        assert!(!clue_is_spent(&clue, &puzzle));
+        let place = |puzzle: &mut Puzzle, loc| {
+            let update = puzzle.answer_at(loc).decay_to_ind().as_solo();
+            puzzle.apply_update(&update, &mut HashSet::new()).unwrap();
+        };
+        place(&mut puzzle, loc(0, 1));
+        assert!(!clue_is_spent(&clue, &puzzle));
+        place(&mut puzzle, loc(1, 1));
+        assert!(clue_is_spent(&clue, &puzzle));
+    }
+
+    #[test]
+    fn spent_shades_cover_their_clue() {
+        use bevy::utils::HashSet;
+
+        use crate::{
+            clues::{ClueKind, SavedClue},
+            puzzle::{loc, test_puzzle},
+        };
+        let mut puzzle = test_puzzle(3, 3);
+        for at in [loc(0, 1), loc(1, 1)] {
+            let update = puzzle.answer_at(at).decay_to_ind().as_solo();
+            puzzle.apply_update(&update, &mut HashSet::new()).unwrap();
+        }
+        let saved = SavedClue::from_parts(ClueKind::SameColumn, &[loc(0, 1), loc(1, 1)], false);
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Settings>()
+            .add_observer(size_spent_clue_shade)
+            .add_systems(Update, track_spent_clues.run_if(spent_clues_may_change));
+        let puzzle = app.world_mut().spawn(puzzle).id();
+        let clue = app
+            .world_mut()
+            .spawn((
+                PuzzleClueComponent::new(DynPuzzleClue::from(saved.unwrap())),
+                FitWithin::new(Rect::new(0., 0., 50., 60.)),
+            ))
+            .id();
+        let shade_size = |app: &App| {
+            let children = app.world().get::<Children>(clue).unwrap();
+            assert_eq!(children.len(), 1);
+            app.world().get::<Sprite>(children[0]).unwrap().custom_size
+        };
+        app.update();
+        assert!(app.world().entity(clue).contains::<SpentClue>());
+        assert_eq!(shade_size(&app), Some(Vec2::new(50., 60.)));
+        app.world_mut()
+            .entity_mut(clue)
+            .insert(FitWithin::new(Rect::new(0., 0., 80., 90.)));
+        assert_eq!(shade_size(&app), Some(Vec2::new(80., 90.)));
+
+        // Nothing is checked again until the puzzle changes.
+        app.world_mut().entity_mut(clue).remove::<SpentClue>();
+        app.update();
+        assert!(!app.world().entity(clue).contains::<SpentClue>());
+        app.world_mut()
+            .get_mut::<Puzzle>(puzzle)
+            .unwrap()
+            .set_changed();
+        app.update();
+        assert!(app.world().entity(clue).contains::<SpentClue>());
+    }
+}
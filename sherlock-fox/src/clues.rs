@@ -2,20 +2,26 @@
 //
 // SPDX-License-Identifier: EUPL-1.2
 
+use std::f32::consts::FRAC_PI_4;
+
 use bevy::{prelude::*, utils::HashMap};
 use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 use typemap::ShareCloneMap;
 
 use crate::{
-    puzzle::{
-        CellLoc, CellLocAnswer, CellLocIndex, LAns, LCol, LColspan, LRow, Puzzle, RowAnswer,
-        RowIndexed,
+    puzzle::{CellLoc, CellLocIndex, LCol, LRow, Puzzle, RowAnswer},
+    resolver::{
+        IfThen, ImplicationResolver, Loc1, Loc2, Loc2Mirrored, Loc3, Loc3Mirrored, SelectionProxy,
     },
     UpdateCellIndex, NO_PICK,
 };
 
 pub type PuzzleAdvance = Option<UpdateCellIndex>;
 
+const NOT_IN_SAME_COLUMN_STRIKE_COLOR: Color = Color::hsla(0., 0.8, 0.5, 0.9);
+const EDGE_COLUMN_MARK_COLOR: Color = Color::hsla(0., 0., 0.9, 0.9);
+
 #[repr(transparent)]
 struct StoredItem<T>(T);
 
@@ -76,6 +82,33 @@ impl ClueExplanation {
             ),
         })
     }
+
+    // The candidates the reasoning mentions, in the order it mentions them.
+    pub fn cells(&self) -> Vec<CellLocIndex> {
+        self.resolved()
+            .filter_map(|c| match c {
+                ClueExplanationResolvedChunk::Accessed(_, cell_display) => {
+                    cell_display.loc_index().copied()
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        use ClueExplanationResolvedChunk as ResCh;
+        self.resolved()
+            .map(|c| match c {
+                ResCh::Text(s) => s.to_string(),
+                ResCh::Accessed(_name, cell_display) => match cell_display.loc_index() {
+                    Some(index) => index.to_string(),
+                    None => cell_display.as_cell_display_string(),
+                },
+                ResCh::Eval(_expr, result) => result,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
 macro_rules! impl_clue_explanation {
@@ -96,7 +129,17 @@ macro_rules! impl_clue_explanation {
 }
 
 impl_clue_explanation! {
-    Loc2, Loc2Mirrored, Loc3,
+    Loc1, Loc2, Loc2Mirrored, Loc3, Loc3Mirrored,
+}
+
+// Explanations made only of text, for updates that don't come from a clue's cells.
+impl From<&'static [ClueExplanationChunk]> for ClueExplanation {
+    fn from(chunks: &'static [ClueExplanationChunk]) -> Self {
+        ClueExplanation {
+            chunks,
+            payload: ClueExplanationPayload::default(),
+        }
+    }
 }
 
 // impl From<(&Loc2, &'static [ClueExplanationChunk])> for ClueExplanation {
@@ -153,8 +196,105 @@ pub enum ClueExplanationResolvedChunk<'d> {
     Eval(&'static str, String),
 }
 
+#[derive(
+    Debug, Reflect, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum ClueKind {
+    SameColumn,
+    AdjacentColumn,
+    DifferentColumns,
+    LeftOf,
+    BetweenColumns,
+    EdgeColumn,
+}
+
+impl ClueKind {
+    pub const ALL: [ClueKind; 6] = [
+        ClueKind::SameColumn,
+        ClueKind::AdjacentColumn,
+        ClueKind::DifferentColumns,
+        ClueKind::LeftOf,
+        ClueKind::BetweenColumns,
+        ClueKind::EdgeColumn,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SavedClue {
+    SameColumn(SameColumnClue),
+    AdjacentColumn(AdjacentColumnClue),
+    DifferentColumns(NotInSameColumnClue),
+    LeftOf(LeftOfClue),
+    BetweenColumns(BetweenColumnsClue),
+    EdgeColumn(EdgeColumnClue),
+}
+
+impl From<SavedClue> for DynPuzzleClue {
+    fn from(value: SavedClue) -> Self {
+        match value {
+            SavedClue::SameColumn(c) => c.into(),
+            SavedClue::AdjacentColumn(c) => c.into(),
+            SavedClue::DifferentColumns(c) => c.into(),
+            SavedClue::LeftOf(c) => c.into(),
+            SavedClue::BetweenColumns(c) => c.into(),
+            SavedClue::EdgeColumn(c) => c.into(),
+        }
+    }
+}
+
+impl SavedClue {
+    // What a share code keeps of a clue: its kind, the cells it names in order, and whether it's
+    // drawn flipped, which only between-columns clues can be.
+    pub fn to_parts(&self) -> (ClueKind, Vec<CellLoc>, bool) {
+        let flipped = matches!(self, SavedClue::BetweenColumns(c) if c.flip_on_display);
+        let clue = DynPuzzleClue::from(self.clone());
+        (clue.kind(), clue.locs(), flipped)
+    }
+
+    pub fn from_parts(kind: ClueKind, locs: &[CellLoc], flipped: bool) -> Option<Self> {
+        let clue = match (kind, locs) {
+            (ClueKind::SameColumn, &[loc, loc2]) => SavedClue::SameColumn(SameColumnClue {
+                loc,
+                row2: loc2.row,
+                row3: None,
+            }),
+            (ClueKind::SameColumn, &[loc, loc2, loc3]) => SavedClue::SameColumn(SameColumnClue {
+                loc,
+                row2: loc2.row,
+                row3: Some(loc3.row),
+            }),
+            (ClueKind::AdjacentColumn, &[loc1, loc2]) => {
+                SavedClue::AdjacentColumn(AdjacentColumnClue { loc1, loc2 })
+            }
+            (ClueKind::DifferentColumns, &[loc1, loc2]) => {
+                SavedClue::DifferentColumns(NotInSameColumnClue { loc1, loc2 })
+            }
+            (ClueKind::LeftOf, &[left, right]) => SavedClue::LeftOf(LeftOfClue { left, right }),
+            (ClueKind::BetweenColumns, &[loc1, loc2, loc3]) => {
+                SavedClue::BetweenColumns(BetweenColumnsClue {
+                    loc1,
+                    loc2,
+                    loc3,
+                    flip_on_display: flipped,
+                })
+            }
+            (ClueKind::EdgeColumn, &[loc]) => SavedClue::EdgeColumn(EdgeColumnClue { loc }),
+            _ => return None,
+        };
+        Some(clue)
+    }
+}
+
 pub trait PuzzleClue: std::fmt::Debug {
-    fn advance_puzzle(&self, puzzle: &Puzzle) -> PuzzleAdvance;
+    fn kind(&self) -> ClueKind;
+    fn to_saved(&self) -> SavedClue;
+    fn locs(&self) -> Vec<CellLoc>;
+    fn satisfied_by(&self, puzzle: &Puzzle) -> bool;
+    fn advance_puzzle(&self, puzzle: &Puzzle) -> PuzzleAdvance {
+        self.advance_puzzle_counted(puzzle, &mut 0)
+    }
+    // Adds how many column shifts its resolver tried to `shifts_tested`, for the clue scan stats.
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, shifts_tested: &mut usize) -> PuzzleAdvance;
     fn spawn_into(
         &self,
         parent: &mut ChildBuilder,
@@ -163,7 +303,7 @@ pub trait PuzzleClue: std::fmt::Debug {
     );
 }
 
-#[derive(Reflect, Asset, Debug)]
+#[derive(Reflect, Debug)]
 #[reflect(from_reflect = false)]
 pub struct DynPuzzleClue(#[reflect(ignore)] Box<(dyn PuzzleClue + Sync + Send + 'static)>);
 
@@ -191,14 +331,61 @@ impl DynPuzzleClue {
     fn new(clue: impl PuzzleClue + Sync + Send + 'static) -> Self {
         DynPuzzleClue(Box::new(clue))
     }
+
+    // The items a clue talks about, independent of the order it names them in.
+    fn items(&self, puzzle: &Puzzle) -> Vec<RowAnswer> {
+        let mut items = self
+            .locs()
+            .into_iter()
+            .map(|loc| puzzle.answer_at(loc).decay_column())
+            .collect::<Vec<_>>();
+        items.sort();
+        items
+    }
+
+    pub fn validate<'c>(
+        &self,
+        puzzle: &Puzzle,
+        existing: impl IntoIterator<Item = &'c DynPuzzleClue>,
+    ) -> Result<(), ClueValidationError> {
+        if let Some(&loc) = self.locs().iter().find(|&&l| !puzzle.contains_loc(l)) {
+            return Err(ClueValidationError::OutOfBounds(loc));
+        }
+        if !self.satisfied_by(puzzle) {
+            return Err(ClueValidationError::NotSatisfied);
+        }
+        let items = self.items(puzzle);
+        for (nr, other) in existing.into_iter().enumerate() {
+            if other.kind() == self.kind() && other.items(puzzle) == items {
+                return Err(ClueValidationError::Duplicate(nr));
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Component, Default, Reflect)]
-pub struct PuzzleClues {
-    pub clues: Vec<Handle<DynPuzzleClue>>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClueValidationError {
+    OutOfBounds(CellLoc),
+    NotSatisfied,
+    Duplicate(usize),
 }
 
-#[derive(Debug, Component, Clone, Reflect)]
+impl std::fmt::Display for ClueValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClueValidationError::OutOfBounds(loc) => write!(
+                f,
+                "refers to row {} column {}, which is off the board",
+                loc.row.0, loc.col.0
+            ),
+            ClueValidationError::NotSatisfied => write!(f, "is false for this puzzle's answer"),
+            ClueValidationError::Duplicate(nr) => write!(f, "duplicates clue #{}", nr + 1),
+        }
+    }
+}
+
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
 pub struct SameColumnClue {
     loc: CellLoc,
     row2: LRow,
@@ -239,277 +426,6 @@ impl SameColumnClue {
     }
 }
 
-struct ImplicationResolver<'p, IT> {
-    puzzle: &'p Puzzle,
-    cells: Vec<CellLocIndex>,
-    actions: Vec<IT>,
-}
-
-impl<'p, IT: std::fmt::Debug> std::fmt::Debug for ImplicationResolver<'p, IT> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("ImplicationResolver")
-            .field("puzzle", &(self.puzzle as *const Puzzle as usize))
-            .field("cells", &self.cells)
-            .field("actions", &self.actions)
-            .finish()
-    }
-}
-
-#[derive(Clone, Reflect, Debug)]
-struct SelectionProxy {
-    index_: CellLocIndex,
-    is_enabled: bool,
-    is_solo: bool,
-    is_void: bool,
-}
-
-impl SelectionProxy {
-    fn from_puzzle_and_index(puzzle: &Puzzle, index: CellLocIndex) -> Self {
-        let sel = puzzle.cell_selection(index.loc);
-        let is_enabled = sel.is_enabled(index.index);
-        let is_solo = sel.is_solo(index.index);
-        let is_void = sel.is_void();
-        SelectionProxy {
-            index_: index,
-            is_enabled,
-            is_solo,
-            is_void,
-        }
-    }
-
-    fn is_enabled_not_solo(&self) -> bool {
-        self.is_enabled && !self.is_solo
-    }
-}
-
-impl std::ops::Deref for SelectionProxy {
-    type Target = CellLocIndex;
-
-    fn deref(&self) -> &Self::Target {
-        &self.index_
-    }
-}
-
-impl CellDisplay for SelectionProxy {
-    fn as_cell_display_string(&self) -> String {
-        format!(
-            "Cell[{:?} {:?} {:?}]",
-            self.loc.row, self.loc.col, self.index
-        )
-    }
-
-    fn spawn_into(&self, puzzle: &Puzzle, parent: &mut ChildBuilder) {
-        let (mut image_node, color) = puzzle.cell_index_display(self.index_);
-        // let button_size = Vec2::new(32., 32.);
-        // sprite.custom_size = Some(button_size - Vec2::new(5., 5.));
-        image_node.color = Color::hsla(0., 0., 1., 1.);
-        parent
-            .spawn((
-                Node {
-                    width: Val::Px(42.),
-                    height: Val::Px(42.),
-                    margin: UiRect::horizontal(Val::Px(5.)),
-                    padding: UiRect::all(Val::Px(5.)),
-                    ..Default::default()
-                },
-                BackgroundColor(color),
-                NO_PICK,
-            ))
-            .with_child((Node::default(), image_node, NO_PICK));
-    }
-
-    fn loc_index(&self) -> Option<&CellLocIndex> {
-        Some(&self.index_)
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Loc2 {
-    loc1: SelectionProxy,
-    loc2: SelectionProxy,
-}
-
-#[derive(Debug, Clone)]
-struct Loc2Mirrored {
-    loc1: SelectionProxy,
-    loc2: SelectionProxy,
-    loc2_p: SelectionProxy,
-}
-
-impl Loc2Mirrored {
-    pub fn colspan(&self) -> usize {
-        self.loc1.loc.columns_between(&self.loc2.loc)
-    }
-}
-
-#[derive(Debug, Clone)]
-struct Loc3 {
-    loc1: SelectionProxy,
-    loc2: SelectionProxy,
-    loc3: SelectionProxy,
-}
-
-#[derive(Debug, Clone)]
-struct Loc3Mirrored {
-    loc1: SelectionProxy,
-    loc2: SelectionProxy,
-    loc2_p: SelectionProxy,
-    loc3: SelectionProxy,
-    loc3_p: SelectionProxy,
-}
-
-impl Loc3Mirrored {
-    fn as_3s(&self) -> (Loc3, Loc3) {
-        (
-            Loc3 {
-                loc1: self.loc1.clone(),
-                loc2: self.loc2.clone(),
-                loc3: self.loc3.clone(),
-            },
-            (Loc3 {
-                loc1: self.loc1.clone(),
-                loc2: self.loc2_p.clone(),
-                loc3: self.loc3_p.clone(),
-            }),
-        )
-    }
-
-    fn both_3s(&self, predicate: fn(&Loc3) -> bool) -> bool {
-        let my_3s = self.as_3s();
-        predicate(&my_3s.0) && predicate(&my_3s.1)
-    }
-
-    fn eval_as_3s<R>(&self, eval: fn(&Loc3) -> Option<R>) -> Option<R> {
-        let my_3s = self.as_3s();
-        eval(&my_3s.0).or_else(|| eval(&my_3s.1))
-    }
-}
-
-type IfThen<L, R> = fn(&L) -> Option<R>;
-
-impl<'p> ImplicationResolver<'p, ()> {
-    fn new_unit(puzzle: &'p Puzzle) -> Self {
-        ImplicationResolver {
-            puzzle,
-            cells: Vec::default(),
-            actions: Vec::default(),
-        }
-    }
-}
-
-impl<'p, IT> ImplicationResolver<'p, IT> {
-    fn add_answer(&mut self, loc: CellLoc) {
-        self.cells.push(self.puzzle.answer_at(loc).decay_to_ind());
-    }
-
-    fn colspan(&self) -> LColspan {
-        use itertools::Itertools;
-        self.cells.iter().map(|i| i.loc.col).minmax().into()
-    }
-
-    fn iter_all_cols<IT2>(&self) -> impl Iterator<Item = ImplicationResolver<IT2>> {
-        let colspan = self.colspan();
-        self.puzzle.iter_col_shift(colspan).map(move |shift| {
-            let cells = self.cells.iter().map(|&c| c.shift_column(shift)).collect();
-            ImplicationResolver {
-                cells,
-                actions: Vec::default(),
-                puzzle: self.puzzle,
-            }
-        })
-    }
-
-    fn if_then(&mut self, if_then_fn: IT) -> &mut Self {
-        self.actions.push(if_then_fn);
-        self
-    }
-}
-
-impl<'p, R> ImplicationResolver<'p, IfThen<Loc2, R>> {
-    fn iter_perm_2s(&self) -> impl Iterator<Item = R> + use<'_, R> {
-        use itertools::Itertools;
-        let proxies = self
-            .cells
-            .iter()
-            .map(|&c| SelectionProxy::from_puzzle_and_index(self.puzzle, c))
-            .collect::<Vec<_>>();
-        let actions_iter = self.actions.iter();
-        proxies
-            .into_iter()
-            .permutations(2)
-            .flat_map(move |mut sels| {
-                let (Some(loc2), Some(loc1)) = (sels.pop(), sels.pop()) else {
-                    unreachable!()
-                };
-                let loc = Loc2 { loc1, loc2 };
-                // info!("incoming perm 2:\n  loc={loc:?}");
-                actions_iter.clone().filter_map(move |a| (a)(&loc))
-            })
-    }
-}
-
-impl<'p, R> ImplicationResolver<'p, IfThen<Loc2Mirrored, R>> {
-    fn iter_reflected_2s(&self) -> impl Iterator<Item = R> + use<'_, R> {
-        use itertools::Itertools;
-        let proxy = |c| SelectionProxy::from_puzzle_and_index(self.puzzle, c);
-        let proxies = self
-            .cells
-            .iter()
-            .map(|c| proxy(*c))
-            .permutations(2)
-            .map(move |mut locs| {
-                let (Some(loc2), Some(loc1)) = (locs.pop(), locs.pop()) else {
-                    unreachable!()
-                };
-                let loc2_p = proxy(loc2.reflect_loc_about(&loc1));
-                Loc2Mirrored { loc1, loc2, loc2_p }
-            })
-            // .inspect(|l| info!("incoming reflected 2:\n  loc={l:?}",))
-            .filter(|l| !l.loc1.is_void)
-            .collect::<Vec<_>>();
-        let actions_iter = self.actions.iter();
-        proxies
-            .into_iter()
-            .flat_map(move |loc| actions_iter.clone().filter_map(move |a| (a)(&loc)))
-    }
-}
-
-impl<'p, R> ImplicationResolver<'p, IfThen<Loc3Mirrored, R>> {
-    fn iter_reflected_3s(&self) -> impl Iterator<Item = R> + use<'_, R> {
-        use itertools::Itertools;
-        let proxy = |c| SelectionProxy::from_puzzle_and_index(self.puzzle, c);
-        let proxies = self
-            .cells
-            .iter()
-            .map(|c| proxy(*c))
-            // TODO: permutations is too many.. we don't need [1, 2, 3] and [1, 3, 2]
-            .permutations(3)
-            .map(move |mut locs| {
-                let (Some(loc3), Some(loc2), Some(loc1)) = (locs.pop(), locs.pop(), locs.pop())
-                else {
-                    unreachable!()
-                };
-                let loc2_p = proxy(loc2.reflect_loc_about(&loc1));
-                let loc3_p = proxy(loc3.reflect_loc_about(&loc1));
-                Loc3Mirrored {
-                    loc1,
-                    loc2,
-                    loc2_p,
-                    loc3,
-                    loc3_p,
-                }
-            })
-            // .inspect(|l| info!("incoming reflected 3:\n  loc={l:#?}",))
-            .filter(|l| !l.loc1.is_void)
-            .collect::<Vec<_>>();
-
-        let actions_iter = self.actions.iter();
-        proxies
-            .into_iter()
-            .flat_map(move |loc| actions_iter.clone().filter_map(move |a| (a)(&loc)))
-    }
-}
-
 // fn f() {
 //     ClueExplanationChunk::Accessor(|p| &p.lookup::<Loc2>().loc1);
 // }
@@ -567,21 +483,38 @@ static SAME_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
 ];
 
 impl PuzzleClue for SameColumnClue {
-    fn advance_puzzle(&self, puzzle: &Puzzle) -> PuzzleAdvance {
+    fn kind(&self) -> ClueKind {
+        ClueKind::SameColumn
+    }
+
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::SameColumn(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        [Some(self.loc), Some(self.loc2()), self.loc3()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    fn satisfied_by(&self, _puzzle: &Puzzle) -> bool {
+        // Every loc shares a column by construction, so the clue only lies if it names one row twice.
+        let rows = self.locs().into_iter().map(|l| l.row).collect::<Vec<_>>();
+        rows.iter()
+            .enumerate()
+            .all(|(i, r)| !rows[i + 1..].contains(r))
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, shifts_tested: &mut usize) -> PuzzleAdvance {
         let mut resolver = ImplicationResolver::new_unit(puzzle);
         resolver.add_answer(self.loc);
         resolver.add_answer(self.loc2());
         if let Some(loc3) = self.loc3() {
             resolver.add_answer(loc3);
         }
-        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>() {
+        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>(shifts_tested) {
             sub_resolver
-                .if_then(|Loc2 { loc1: l1, loc2: l2 }| {
-                    if !l1.is_enabled && l2.is_solo {
-                        panic!("contradiction at {l1:?} {l2:?}");
-                    }
-                    None
-                })
                 .if_then(|l: &Loc2| {
                     if l.loc1.is_enabled_not_solo() && l.loc2.is_solo {
                         Some(l.loc1.as_solo().with_explanation((l, SAME_COLUMN_SOLO)))
@@ -590,7 +523,9 @@ impl PuzzleClue for SameColumnClue {
                     }
                 })
                 .if_then(|l: &Loc2| {
-                    if l.loc1.is_enabled_not_solo() && !l.loc2.is_enabled {
+                    // This also clears a solo whose partner was ruled out, leaving the contradiction
+                    // visible as an empty cell.
+                    if l.loc1.is_enabled && !l.loc2.is_enabled {
                         Some(l.loc1.as_clear().with_explanation((l, SAME_COLUMN_CLEAR)))
                     } else {
                         None
@@ -659,7 +594,7 @@ impl PuzzleClue for SameColumnClue {
     }
 }
 
-#[derive(Debug, Component, Clone, Reflect)]
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
 pub struct AdjacentColumnClue {
     loc1: CellLoc,
     loc2: CellLoc,
@@ -700,12 +635,29 @@ static ADJACENT_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
 ];
 
 impl PuzzleClue for AdjacentColumnClue {
-    fn advance_puzzle(&self, puzzle: &Puzzle) -> PuzzleAdvance {
+    fn kind(&self) -> ClueKind {
+        ClueKind::AdjacentColumn
+    }
+
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::AdjacentColumn(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        vec![self.loc1, self.loc2]
+    }
+
+    fn satisfied_by(&self, _puzzle: &Puzzle) -> bool {
+        // The displayed distance is meaningless for two items in the same column.
+        self.loc1.col != self.loc2.col
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, shifts_tested: &mut usize) -> PuzzleAdvance {
         let mut resolver = ImplicationResolver::new_unit(puzzle);
         resolver.add_answer(self.loc1);
         resolver.add_answer(self.loc2);
         // info!("adjacent resolver: {resolver:#?}");
-        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>() {
+        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>(shifts_tested) {
             // info!("adjacent sub resolver: {sub_resolver:#?}");
             sub_resolver
                 // .if_then(
@@ -787,123 +739,734 @@ impl PuzzleClue for AdjacentColumnClue {
     }
 }
 
-// #[derive(Debug, Component, Clone, Reflect)]
-// pub struct BetweenColumnsClue {
-//     loc1: CellLoc,
-//     loc2: CellLoc,
-//     loc3: CellLoc,
-//     flip_on_display: bool,
-// }
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
+pub struct NotInSameColumnClue {
+    loc1: CellLoc,
+    loc2: CellLoc,
+}
 
-// impl BetweenColumnsClue {
-//     pub fn new_random<R: Rng>(rng: &mut R, puzzle: &Puzzle) -> Option<Self> {
-//         let n_rows = puzzle.rows.len();
-//         let mut columns: [usize; 3] = rand::seq::index::sample_array(rng, puzzle.max_column)?;
-//         columns.sort();
-//         let [col1, col2, col3] = columns;
-//         Some(BetweenColumnsClue {
-//             loc1: CellLoc {
-//                 row_nr: rng.random_range(0..n_rows),
-//                 cell_nr: col1 as isize,
-//             },
-//             loc2: CellLoc {
-//                 row_nr: rng.random_range(0..n_rows),
-//                 cell_nr: col2 as isize,
-//             },
-//             loc3: CellLoc {
-//                 row_nr: rng.random_range(0..n_rows),
-//                 cell_nr: col3 as isize,
-//             },
-//             flip_on_display: rng.random(),
-//         })
-//     }
-// }
+impl NotInSameColumnClue {
+    pub fn new_random<R: Rng>(rng: &mut R, puzzle: &Puzzle) -> Option<Self> {
+        let rows = puzzle.shuffled_rows(rng);
+        let cols = puzzle.shuffled_cols(rng);
+        Some(NotInSameColumnClue {
+            loc1: CellLoc {
+                row: *rows.first()?,
+                col: *cols.first()?,
+            },
+            loc2: CellLoc {
+                row: *rows.get(1)?,
+                col: *cols.get(1)?,
+            },
+        })
+    }
+}
 
-// static BETWEEN_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
-//     Loc3:
-//     %{loc1}, "must be impossible because it requires", %{loc2}, "and", %{loc3},
-// ];
+static NOT_IN_SAME_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
+    Loc2:
+    %{loc1}, "must be impossible, because in the same column",
+    %{loc2}, "is selected.",
+];
 
-// impl PuzzleClue for BetweenColumnsClue {
-//     fn advance_puzzle(&self, puzzle: &Puzzle) -> PuzzleAdvance {
-//         let mut resolver = ImplicationResolver::new_unit(puzzle);
-//         resolver.add_answer(self.loc1);
-//         resolver.add_answer(self.loc2);
-//         resolver.add_answer(self.loc3);
-//         // info!("between resolver: {resolver:?}");
-//         for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>() {
-//             // info!("between sub resolver: {sub_resolver:?}");
-//             sub_resolver.if_then(|l: &Loc3Mirrored| {
-//                 if !l.loc1.is_enabled_not_solo() {
-//                     return None;
-//                 }
-//                 l.eval_as_3s(|sl| {
-//                     if !sl.loc2.is_enabled || !sl.loc3.is_enabled {
-//                         Some(
-//                             sl.loc1
-//                                 .as_clear()
-//                                 .with_explanation((sl, BETWEEN_COLUMN_CLEAR)),
-//                         )
-//                     } else {
-//                         None
-//                     }
-//                 })
-//             });
-//             for ev in sub_resolver.iter_reflected_3s() {
-//                 return Some(ev);
-//             }
-//         }
-//         None
-//     }
+impl PuzzleClue for NotInSameColumnClue {
+    fn kind(&self) -> ClueKind {
+        ClueKind::DifferentColumns
+    }
 
-//     fn spawn_into(
-//         &self,
-//         parent: &mut ChildBuilder,
-//         puzzle: &Puzzle,
-//         cells: &mut HashMap<CellLoc, Entity>,
-//     ) {
-//         let sprite_size = Vec2::new(32., 32.);
-//         let size_sprite = |mut sprite: Sprite| {
-//             sprite.custom_size = Some(sprite_size);
-//             sprite
-//         };
-//         let (loc1, loc3) = if self.flip_on_display {
-//             (self.loc3, self.loc1)
-//         } else {
-//             (self.loc1, self.loc3)
-//         };
-//         let (sprite1, color1) = puzzle.cell_answer_display(loc1);
-//         parent
-//             .spawn((
-//                 Sprite::from_color(color1, sprite_size),
-//                 Transform::from_xyz(-32., 0., 0.),
-//             ))
-//             .with_child((
-//                 size_sprite(sprite1),
-//                 Transform::from_xyz(0., 0., 1.),
-//                 NO_PICK,
-//             ));
-//         let (sprite2, color2) = puzzle.cell_answer_display(self.loc2);
-//         parent
-//             .spawn((
-//                 Sprite::from_color(color2, sprite_size),
-//                 Transform::from_xyz(0., 0., -1.),
-//             ))
-//             .with_child((
-//                 size_sprite(sprite2),
-//                 Transform::from_xyz(0., 0., 1.),
-//                 NO_PICK,
-//             ));
-//         let (sprite3, color3) = puzzle.cell_answer_display(loc3);
-//         parent
-//             .spawn((
-//                 Sprite::from_color(color3, sprite_size),
-//                 Transform::from_xyz(32., 0., 0.),
-//             ))
-//             .with_child((
-//                 size_sprite(sprite3),
-//                 Transform::from_xyz(0., 0., 1.),
-//                 NO_PICK,
-//             ));
-//     }
-// }
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::DifferentColumns(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        vec![self.loc1, self.loc2]
+    }
+
+    fn satisfied_by(&self, _puzzle: &Puzzle) -> bool {
+        // Two items from one row are never in the same column anyway, so that says nothing.
+        self.loc1.col != self.loc2.col && self.loc1.row != self.loc2.row
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, shifts_tested: &mut usize) -> PuzzleAdvance {
+        // The clue only ever talks about the two items sharing a column, so both are placed in the
+        // first one's column rather than where the answer has them.
+        let mut resolver = ImplicationResolver::new_unit(puzzle);
+        resolver.add_answer(self.loc1);
+        resolver.add_answer_in_column(self.loc2, self.loc1.col);
+        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>(shifts_tested) {
+            sub_resolver.if_then(|l: &Loc2| {
+                if l.loc1.is_enabled && l.loc2.is_solo {
+                    Some(
+                        l.loc1
+                            .as_clear()
+                            .with_explanation((l, NOT_IN_SAME_COLUMN_CLEAR)),
+                    )
+                } else {
+                    None
+                }
+            });
+            if let Some(ev) = sub_resolver.iter_perm_2s().next() {
+                return Some(ev);
+            }
+        }
+        None
+    }
+
+    fn spawn_into(
+        &self,
+        parent: &mut ChildBuilder,
+        puzzle: &Puzzle,
+        cells: &mut HashMap<RowAnswer, Entity>,
+    ) {
+        let sprite_size = Vec2::new(32., 32.);
+        let size_sprite = |mut sprite: Sprite| {
+            sprite.custom_size = Some(sprite_size);
+            sprite
+        };
+        let (sprite1, color1) = puzzle.cell_answer_display(self.loc1);
+        let id1 = parent
+            .spawn((
+                Sprite::from_color(color1, sprite_size),
+                Transform::from_xyz(0., -16., 0.),
+            ))
+            .with_child((
+                size_sprite(sprite1),
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ))
+            .id();
+        cells.insert(puzzle.answer_at(self.loc1).decay_column(), id1);
+        let (sprite2, color2) = puzzle.cell_answer_display(self.loc2);
+        let id2 = parent
+            .spawn((
+                Sprite::from_color(color2, sprite_size),
+                Transform::from_xyz(0., 16., 0.),
+            ))
+            .with_child((
+                size_sprite(sprite2),
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ))
+            .id();
+        cells.insert(puzzle.answer_at(self.loc2).decay_column(), id2);
+        // Drawn like the same column clue, but struck through.
+        parent.spawn((
+            Sprite::from_color(NOT_IN_SAME_COLUMN_STRIKE_COLOR, Vec2::new(6., 80.)),
+            Transform::from_xyz(0., 0., 3.).with_rotation(Quat::from_rotation_z(FRAC_PI_4)),
+            NO_PICK,
+        ));
+    }
+}
+
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
+pub struct LeftOfClue {
+    left: CellLoc,
+    right: CellLoc,
+}
+
+impl LeftOfClue {
+    pub fn new_random<R: Rng>(rng: &mut R, puzzle: &Puzzle) -> Option<Self> {
+        let mut cols = puzzle.shuffled_cols(rng);
+        cols.truncate(2);
+        cols.sort();
+        let &[left_col, right_col] = cols.as_slice() else {
+            return None;
+        };
+        Some(LeftOfClue {
+            left: CellLoc {
+                row: puzzle.random_row(rng),
+                col: left_col,
+            },
+            right: CellLoc {
+                row: puzzle.random_row(rng),
+                col: right_col,
+            },
+        })
+    }
+}
+
+// The candidate for the answer at `loc`, moved to column `col`, for clues that check columns
+// directly instead of through the resolver.
+fn answer_in_column(puzzle: &Puzzle, loc: CellLoc, col: LCol) -> SelectionProxy {
+    let index = puzzle.answer_at(loc).decay_to_ind();
+    SelectionProxy::from_puzzle_and_index(puzzle, CellLocIndex {
+        loc: CellLoc { col, ..index.loc },
+        ..index
+    })
+}
+
+static LEFT_OF_CLEAR_LEFT: &[ClueExplanationChunk] = explanation![
+    Loc2:
+    %{loc1}, "must be impossible, because",
+    %{loc2}, "isn't possible in any column to the right of this one.",
+];
+
+static LEFT_OF_CLEAR_RIGHT: &[ClueExplanationChunk] = explanation![
+    Loc2:
+    %{loc1}, "must be impossible, because",
+    %{loc2}, "isn't possible in any column to the left of this one.",
+];
+
+impl PuzzleClue for LeftOfClue {
+    fn kind(&self) -> ClueKind {
+        ClueKind::LeftOf
+    }
+
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::LeftOf(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        vec![self.left, self.right]
+    }
+
+    fn satisfied_by(&self, _puzzle: &Puzzle) -> bool {
+        self.left.col < self.right.col
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, _shifts_tested: &mut usize) -> PuzzleAdvance {
+        // The distance is unknown, so rather than sliding a fixed arrangement across the board
+        // like the resolver does, each column is checked against every column on its far side.
+        let cols = puzzle.iter_cols().collect::<Vec<_>>();
+        let left_in = |col| answer_in_column(puzzle, self.left, col);
+        let right_in = |col| answer_in_column(puzzle, self.right, col);
+        for &col in &cols {
+            let left = left_in(col);
+            if left.is_enabled && !cols.iter().any(|&c| c > col && right_in(c).is_enabled) {
+                let l = Loc2 {
+                    loc1: left,
+                    loc2: right_in(col),
+                };
+                return Some(l.loc1.as_clear().with_explanation((&l, LEFT_OF_CLEAR_LEFT)));
+            }
+            let right = right_in(col);
+            if right.is_enabled && !cols.iter().any(|&c| c < col && left_in(c).is_enabled) {
+                let l = Loc2 {
+                    loc1: right,
+                    loc2: left_in(col),
+                };
+                return Some(
+                    l.loc1
+                        .as_clear()
+                        .with_explanation((&l, LEFT_OF_CLEAR_RIGHT)),
+                );
+            }
+        }
+        None
+    }
+
+    fn spawn_into(
+        &self,
+        parent: &mut ChildBuilder,
+        puzzle: &Puzzle,
+        cells: &mut HashMap<RowAnswer, Entity>,
+    ) {
+        let sprite_size = Vec2::new(32., 32.);
+        let size_sprite = |mut sprite: Sprite| {
+            sprite.custom_size = Some(sprite_size);
+            sprite
+        };
+        parent.spawn(Text2d::new("<"));
+        let (sprite1, color1) = puzzle.cell_answer_display(self.left);
+        let id1 = parent
+            .spawn((
+                Sprite::from_color(color1, sprite_size),
+                Transform::from_xyz(-25., 0., 0.),
+            ))
+            .with_child((
+                size_sprite(sprite1),
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ))
+            .id();
+        cells.insert(puzzle.answer_at(self.left).decay_column(), id1);
+        let (sprite2, color2) = puzzle.cell_answer_display(self.right);
+        let id2 = parent
+            .spawn((
+                Sprite::from_color(color2, sprite_size),
+                Transform::from_xyz(25., 0., 0.),
+            ))
+            .with_child((
+                size_sprite(sprite2),
+                Transform::from_xyz(0., 0., 1.),
+                NO_PICK,
+            ))
+            .id();
+        cells.insert(puzzle.answer_at(self.right).decay_column(), id2);
+    }
+}
+
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
+pub struct BetweenColumnsClue {
+    loc1: CellLoc,
+    loc2: CellLoc,
+    loc3: CellLoc,
+    flip_on_display: bool,
+}
+
+impl BetweenColumnsClue {
+    pub fn new_random<R: Rng>(rng: &mut R, puzzle: &Puzzle) -> Option<Self> {
+        let cols = puzzle.iter_cols().collect::<Vec<_>>();
+        let start = rng.random_range(0..=cols.len().checked_sub(3)?);
+        let &[col1, col2, col3] = &cols[start..start + 3] else {
+            return None;
+        };
+        Some(BetweenColumnsClue {
+            loc1: CellLoc {
+                row: puzzle.random_row(rng),
+                col: col1,
+            },
+            loc2: CellLoc {
+                row: puzzle.random_row(rng),
+                col: col2,
+            },
+            loc3: CellLoc {
+                row: puzzle.random_row(rng),
+                col: col3,
+            },
+            flip_on_display: rng.random(),
+        })
+    }
+}
+
+static BETWEEN_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
+    Loc3Mirrored:
+    %{loc1}, "must be impossible, because neither",
+    %{loc2}, "and", %{loc3}, "nor",
+    %{loc2_p}, "and", %{loc3_p}, "are both possible.",
+];
+
+impl PuzzleClue for BetweenColumnsClue {
+    fn kind(&self) -> ClueKind {
+        ClueKind::BetweenColumns
+    }
+
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::BetweenColumns(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        vec![self.loc1, self.loc2, self.loc3]
+    }
+
+    fn satisfied_by(&self, _puzzle: &Puzzle) -> bool {
+        // The display shows three neighboring cells, so the answer has to have them that way.
+        self.loc2.col.0 == self.loc1.col.0 + 1 && self.loc3.col.0 == self.loc2.col.0 + 1
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, shifts_tested: &mut usize) -> PuzzleAdvance {
+        let mut resolver = ImplicationResolver::new_unit(puzzle);
+        resolver.add_answer(self.loc1);
+        resolver.add_answer(self.loc2);
+        resolver.add_answer(self.loc3);
+        for mut sub_resolver in resolver.iter_all_cols::<IfThen<_, _>>(shifts_tested) {
+            // The clue reads the same in either direction, so a cell is only ruled out once the
+            // other two can't be placed around it either way.
+            sub_resolver.if_then(|l: &Loc3Mirrored| {
+                if l.loc1.is_enabled_not_solo()
+                    && l.both_3s(|sl| !sl.loc2.is_enabled || !sl.loc3.is_enabled)
+                {
+                    Some(
+                        l.loc1
+                            .as_clear()
+                            .with_explanation((l, BETWEEN_COLUMN_CLEAR)),
+                    )
+                } else {
+                    None
+                }
+            });
+            if let Some(ev) = sub_resolver.iter_reflected_3s().next() {
+                return Some(ev);
+            }
+        }
+        None
+    }
+
+    fn spawn_into(
+        &self,
+        parent: &mut ChildBuilder,
+        puzzle: &Puzzle,
+        cells: &mut HashMap<RowAnswer, Entity>,
+    ) {
+        let sprite_size = Vec2::new(32., 32.);
+        let size_sprite = |mut sprite: Sprite| {
+            sprite.custom_size = Some(sprite_size);
+            sprite
+        };
+        // Which end is which would give away the direction, so it's drawn either way around.
+        let (loc1, loc3) = if self.flip_on_display {
+            (self.loc3, self.loc1)
+        } else {
+            (self.loc1, self.loc3)
+        };
+        for (loc, x) in [(loc1, -32.), (self.loc2, 0.), (loc3, 32.)] {
+            let (sprite, color) = puzzle.cell_answer_display(loc);
+            let id = parent
+                .spawn((
+                    Sprite::from_color(color, sprite_size),
+                    Transform::from_xyz(x, 0., 0.),
+                ))
+                .with_child((
+                    size_sprite(sprite),
+                    Transform::from_xyz(0., 0., 1.),
+                    NO_PICK,
+                ))
+                .id();
+            cells.insert(puzzle.answer_at(loc).decay_column(), id);
+        }
+    }
+}
+
+#[derive(Debug, Component, Clone, Reflect, Serialize, Deserialize)]
+pub struct EdgeColumnClue {
+    loc: CellLoc,
+}
+
+impl EdgeColumnClue {
+    pub fn new_random<R: Rng>(rng: &mut R, puzzle: &Puzzle) -> Option<Self> {
+        let cols = puzzle.iter_cols().collect::<Vec<_>>();
+        // With only two columns every item is at an edge, so the clue would say nothing.
+        if cols.len() < 3 {
+            return None;
+        }
+        let col = if rng.random() {
+            *cols.first()?
+        } else {
+            *cols.last()?
+        };
+        Some(EdgeColumnClue {
+            loc: CellLoc {
+                row: puzzle.random_row(rng),
+                col,
+            },
+        })
+    }
+}
+
+static EDGE_COLUMN_CLEAR: &[ClueExplanationChunk] = explanation![
+    Loc1:
+    %{loc1}, "must be impossible, because it's in the first or last column.",
+];
+
+impl PuzzleClue for EdgeColumnClue {
+    fn kind(&self) -> ClueKind {
+        ClueKind::EdgeColumn
+    }
+
+    fn to_saved(&self) -> SavedClue {
+        SavedClue::EdgeColumn(self.clone())
+    }
+
+    fn locs(&self) -> Vec<CellLoc> {
+        vec![self.loc]
+    }
+
+    fn satisfied_by(&self, puzzle: &Puzzle) -> bool {
+        let cols = puzzle.iter_cols().collect::<Vec<_>>();
+        cols.first() == Some(&self.loc.col) || cols.last() == Some(&self.loc.col)
+    }
+
+    fn advance_puzzle_counted(&self, puzzle: &Puzzle, _shifts_tested: &mut usize) -> PuzzleAdvance {
+        let cols = puzzle.iter_cols().collect::<Vec<_>>();
+        let interior = cols
+            .get(1..cols.len().saturating_sub(1))
+            .unwrap_or_default();
+        interior.iter().find_map(|&col| {
+            let l = Loc1 {
+                loc1: answer_in_column(puzzle, self.loc, col),
+            };
+            l.loc1
+                .is_enabled
+                .then(|| l.loc1.as_clear().with_explanation((&l, EDGE_COLUMN_CLEAR)))
+        })
+    }
+
+    fn spawn_into(
+        &self,
+        parent: &mut ChildBuilder,
+        puzzle: &Puzzle,
+        cells: &mut HashMap<RowAnswer, Entity>,
+    ) {
+        let sprite_size = Vec2::new(32., 32.);
+        let (mut sprite, color) = puzzle.cell_answer_display(self.loc);
+        sprite.custom_size = Some(sprite_size);
+        let id = parent
+            .spawn((
+                Sprite::from_color(color, sprite_size),
+                Transform::from_xyz(0., 0., 0.),
+            ))
+            .with_child((sprite, Transform::from_xyz(0., 0., 1.), NO_PICK))
+            .id();
+        cells.insert(puzzle.answer_at(self.loc).decay_column(), id);
+        // The board's two outer walls, with the item against either one.
+        for x in [-24., 24.] {
+            parent.spawn((
+                Sprite::from_color(EDGE_COLUMN_MARK_COLOR, Vec2::new(4., 40.)),
+                Transform::from_xyz(x, 0., 0.),
+                NO_PICK,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle, LCol, UpdateCellIndexOperation};
+
+    fn answer(puzzle: &Puzzle, loc: CellLoc) -> CellLocIndex {
+        puzzle.answer_at(loc).decay_to_ind()
+    }
+
+    fn assert_advance(advance: PuzzleAdvance, index: CellLocIndex, op: UpdateCellIndexOperation) {
+        let update = advance.expect("the clue should advance the puzzle");
+        assert_eq!((update.index, update.op), (index, op));
+        assert!(update.explanation.is_some());
+    }
+
+    fn same_column_clue() -> SameColumnClue {
+        SameColumnClue {
+            loc: loc(0, 2),
+            row2: LRow(1),
+            row3: None,
+        }
+    }
+
+    #[test]
+    fn same_column_waits_on_a_fresh_puzzle() {
+        let puzzle = test_puzzle(2, 5);
+        assert!(same_column_clue().advance_puzzle(&puzzle).is_none());
+    }
+
+    #[test]
+    fn same_column_solos_alongside_a_solo() {
+        let mut puzzle = test_puzzle(2, 5);
+        let soloed = answer(&puzzle, loc(1, 2));
+        puzzle
+            .cell_selection_mut(soloed.loc)
+            .apply(soloed.index, UpdateCellIndexOperation::Solo);
+        assert_advance(
+            same_column_clue().advance_puzzle(&puzzle),
+            answer(&puzzle, loc(0, 2)),
+            UpdateCellIndexOperation::Solo,
+        );
+    }
+
+    #[test]
+    fn same_column_clears_alongside_a_cleared_candidate() {
+        let mut puzzle = test_puzzle(2, 5);
+        let cleared = answer(&puzzle, loc(1, 2)).shift_column(1);
+        puzzle
+            .cell_selection_mut(cleared.loc)
+            .apply(cleared.index, UpdateCellIndexOperation::Clear);
+        assert_advance(
+            same_column_clue().advance_puzzle(&puzzle),
+            answer(&puzzle, loc(0, 2)).shift_column(1),
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn explanations_list_the_cells_they_mention() {
+        let mut puzzle = test_puzzle(2, 5);
+        let soloed = answer(&puzzle, loc(1, 2));
+        puzzle
+            .cell_selection_mut(soloed.loc)
+            .apply(soloed.index, UpdateCellIndexOperation::Solo);
+        let update = same_column_clue().advance_puzzle(&puzzle).unwrap();
+        let cells = update.explanation.unwrap().cells();
+        assert!(cells.contains(&soloed));
+        assert!(cells.contains(&answer(&puzzle, loc(0, 2))));
+    }
+
+    #[test]
+    fn adjacent_column_waits_on_a_fresh_puzzle() {
+        let puzzle = test_puzzle(2, 5);
+        let clue = AdjacentColumnClue {
+            loc1: loc(0, 1),
+            loc2: loc(1, 2),
+        };
+        assert!(clue.advance_puzzle(&puzzle).is_none());
+    }
+
+    fn left_of_clue() -> LeftOfClue {
+        LeftOfClue {
+            left: loc(0, 1),
+            right: loc(1, 3),
+        }
+    }
+
+    #[test]
+    fn left_of_clears_the_board_edges() {
+        let puzzle = test_puzzle(2, 5);
+        // Nothing is left of the first column, so the right item can't be there.
+        assert_advance(
+            left_of_clue().advance_puzzle(&puzzle),
+            CellLocIndex {
+                loc: loc(1, 0),
+                ..answer(&puzzle, loc(1, 3))
+            },
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn left_of_clears_past_the_rightmost_partner() {
+        let mut puzzle = test_puzzle(2, 5);
+        let clue = left_of_clue();
+        let right = answer(&puzzle, loc(1, 3));
+        for col in [0, 3, 4] {
+            puzzle
+                .cell_selection_mut(loc(1, col))
+                .apply(right.index, UpdateCellIndexOperation::Clear);
+        }
+        let left = answer(&puzzle, loc(0, 1));
+        puzzle
+            .cell_selection_mut(loc(0, 4))
+            .apply(left.index, UpdateCellIndexOperation::Clear);
+        // With the right item at column 2 at the furthest, the left item can't be at 2 or past it.
+        assert_advance(
+            clue.advance_puzzle(&puzzle),
+            CellLocIndex {
+                loc: loc(0, 2),
+                ..left
+            },
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn left_of_waits_once_consistent() {
+        let mut puzzle = test_puzzle(2, 5);
+        let left = answer(&puzzle, loc(0, 1));
+        let right = answer(&puzzle, loc(1, 3));
+        puzzle
+            .cell_selection_mut(loc(0, 4))
+            .apply(left.index, UpdateCellIndexOperation::Clear);
+        puzzle
+            .cell_selection_mut(loc(1, 0))
+            .apply(right.index, UpdateCellIndexOperation::Clear);
+        assert!(left_of_clue().advance_puzzle(&puzzle).is_none());
+    }
+
+    fn between_columns_clue() -> BetweenColumnsClue {
+        BetweenColumnsClue {
+            loc1: loc(0, 1),
+            loc2: loc(1, 2),
+            loc3: loc(2, 3),
+            flip_on_display: false,
+        }
+    }
+
+    fn clear_item(puzzle: &mut Puzzle, item: CellLoc, cols: impl IntoIterator<Item = isize>) {
+        let index = answer(puzzle, item);
+        for col in cols {
+            puzzle
+                .cell_selection_mut(CellLoc {
+                    col: LCol(col),
+                    ..index.loc
+                })
+                .apply(index.index, UpdateCellIndexOperation::Clear);
+        }
+    }
+
+    #[test]
+    fn between_columns_clears_the_middle_from_the_edges() {
+        let puzzle = test_puzzle(3, 5);
+        let advance = between_columns_clue().advance_puzzle(&puzzle);
+        let update = advance.expect("the clue should advance the puzzle");
+        assert_eq!(update.index.index, answer(&puzzle, loc(1, 2)).index);
+        assert!([LCol(0), LCol(4)].contains(&update.index.loc.col));
+        assert_eq!(update.op, UpdateCellIndexOperation::Clear);
+    }
+
+    #[test]
+    fn between_columns_clears_an_end_with_no_room_either_way() {
+        let mut puzzle = test_puzzle(3, 5);
+        clear_item(&mut puzzle, loc(1, 2), [0, 4]);
+        clear_item(&mut puzzle, loc(2, 3), [0, 4]);
+        // The first item at column 2 would need the last item at column 0 or 4.
+        assert_advance(
+            between_columns_clue().advance_puzzle(&puzzle),
+            CellLocIndex {
+                loc: loc(0, 2),
+                ..answer(&puzzle, loc(0, 1))
+            },
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn between_columns_waits_while_either_direction_fits() {
+        let mut puzzle = test_puzzle(3, 5);
+        clear_item(&mut puzzle, loc(1, 2), [0, 4]);
+        clear_item(&mut puzzle, loc(2, 3), [4]);
+        assert!(between_columns_clue().advance_puzzle(&puzzle).is_none());
+    }
+
+    #[test]
+    fn edge_column_clears_the_interior() {
+        let puzzle = test_puzzle(2, 5);
+        let clue = EdgeColumnClue { loc: loc(1, 4) };
+        assert_advance(
+            clue.advance_puzzle(&puzzle),
+            CellLocIndex {
+                loc: loc(1, 1),
+                ..answer(&puzzle, loc(1, 4))
+            },
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn edge_column_waits_once_the_interior_is_clear() {
+        let mut puzzle = test_puzzle(2, 5);
+        let clue = EdgeColumnClue { loc: loc(1, 0) };
+        clear_item(&mut puzzle, loc(1, 0), [1, 2, 3]);
+        assert!(clue.advance_puzzle(&puzzle).is_none());
+    }
+
+    #[test]
+    fn adjacent_column_clears_without_a_neighbor() {
+        let mut puzzle = test_puzzle(2, 5);
+        let clue = AdjacentColumnClue {
+            loc1: loc(0, 1),
+            loc2: loc(1, 2),
+        };
+        // With the neighbor ruled out of column 1, the first item can't be in column 0, which has
+        // no other neighbor.
+        let cleared = answer(&puzzle, loc(1, 2)).shift_column(-1);
+        puzzle
+            .cell_selection_mut(cleared.loc)
+            .apply(cleared.index, UpdateCellIndexOperation::Clear);
+        assert_advance(
+            clue.advance_puzzle(&puzzle),
+            answer(&puzzle, loc(0, 1)).shift_column(-1),
+            UpdateCellIndexOperation::Clear,
+        );
+    }
+
+    #[test]
+    fn not_in_same_column_clears_beside_a_solo() {
+        let mut puzzle = test_puzzle(2, 5);
+        let clue = NotInSameColumnClue {
+            loc1: loc(0, 1),
+            loc2: loc(1, 3),
+        };
+        assert!(clue.advance_puzzle(&puzzle).is_none());
+        let soloed = answer(&puzzle, loc(1, 3));
+        puzzle
+            .cell_selection_mut(soloed.loc)
+            .apply(soloed.index, UpdateCellIndexOperation::Solo);
+        let cleared = answer(&puzzle, loc(0, 1)).shift_column(2);
+        assert_advance(
+            clue.advance_puzzle(&puzzle),
+            cleared,
+            UpdateCellIndexOperation::Clear,
+        );
+        // Once the other item is out of the solo's column there's nothing left to say.
+        puzzle
+            .cell_selection_mut(cleared.loc)
+            .apply(cleared.index, UpdateCellIndexOperation::Clear);
+        assert!(clue.advance_puzzle(&puzzle).is_none());
+    }
+}
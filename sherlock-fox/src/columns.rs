@@ -0,0 +1,274 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use bevy::{
+    animation::{AnimationTarget, AnimationTargetId, RepeatAnimation},
+    math::curve::FunctionCurve,
+    prelude::*,
+    utils::HashSet,
+};
+use petgraph::graph::NodeIndex;
+use uuid::Uuid;
+
+use crate::{
+    animation::{AnimationFinished, AnimatorPlugin, Playback, SavedAnimationNode},
+    fit::FitWithin,
+    puzzle::{LCol, Puzzle},
+    ButtonOpacityAnimation, DisplayCell, DisplayCellButton, DisplayRow, GameState, PuzzleSpawn,
+    COMPLETE_COLUMN_CELL_BORDER_COLOR, NO_PICK,
+};
+
+// The columns whose every cell is down to one candidate, as of the last scan.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct SolvedColumns {
+    #[reflect(ignore)]
+    solved: HashSet<LCol>,
+    // The first scan of a puzzle only records what's already solved, so a restored game doesn't
+    // open with every finished column flashing.
+    scanned: bool,
+}
+
+impl SolvedColumns {
+    // Returns the columns newly solved and those no longer solved, e.g. after an undo.
+    fn update(&mut self, puzzle: &Puzzle) -> (Vec<LCol>, Vec<LCol>) {
+        let now = puzzle
+            .iter_cols()
+            .filter(|&col| puzzle.is_column_complete(col))
+            .collect::<HashSet<_>>();
+        let mut solved = now.difference(&self.solved).copied().collect::<Vec<_>>();
+        let mut unsolved = self.solved.difference(&now).copied().collect::<Vec<_>>();
+        solved.sort();
+        unsolved.sort();
+        self.solved = now;
+        (solved, unsolved)
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ColumnCheck(LCol);
+
+#[derive(Reflect, Debug, Component, Clone, Default)]
+struct ColumnFlashEdge(Option<NodeIndex>);
+
+impl SavedAnimationNode for ColumnFlashEdge {
+    type AnimatedFrom = Sprite;
+
+    fn node_mut(&mut self) -> &mut Option<NodeIndex> {
+        &mut self.0
+    }
+}
+
+const COLUMN_FLASH_COLOR: Color = Color::hsla(45., 1., 0.7, 0.);
+const COLUMN_FLASH_PEAK: f32 = 0.5;
+const COLUMN_FLASH_DURATION: f32 = 0.4;
+// How long after each cell the one below it starts flashing.
+const COLUMN_CASCADE_STEP: f32 = 0.08;
+const COLUMN_FLASH_FINISHED: &str = "column flash";
+// From the corner of the column's top cell to the middle of its checkmark.
+const COLUMN_CHECK_INSET: Vec2 = Vec2::new(14., 12.);
+
+fn scan_solved_columns(
+    puzzle: Single<Ref<Puzzle>>,
+    mut columns: ResMut<SolvedColumns>,
+    q_rows: Query<(&DisplayRow, &FitWithin)>,
+    q_cells: Query<(Entity, &DisplayCell, &FitWithin, &Children)>,
+    q_button: Query<&AnimationTarget, With<DisplayCellButton>>,
+    q_check: Query<(Entity, &ColumnCheck)>,
+    mut commands: Commands,
+) {
+    if !puzzle.is_changed() {
+        return;
+    }
+    let animate = columns.scanned;
+    columns.scanned = true;
+    let (solved, unsolved) = columns.update(&puzzle);
+    for (entity, &ColumnCheck(col)) in &q_check {
+        if unsolved.contains(&col) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    if solved.is_empty() {
+        return;
+    }
+    // Top to bottom on screen, which is the order fit rects go in.
+    let mut rows = q_rows.iter().collect::<Vec<_>>();
+    rows.sort_by(|(_, a), (_, b)| a.rect().min.y.total_cmp(&b.rect().min.y));
+    for col in solved {
+        let cells = rows.iter().filter_map(|(row, _)| {
+            q_cells
+                .iter()
+                .find(|(_, cell, _, _)| cell.loc.row == row.row && cell.loc.col == col)
+        });
+        for (nr, (entity, _, fit, children)) in cells.enumerate() {
+            let size = fit.rect().size();
+            if nr == 0 {
+                spawn_column_check(&mut commands, entity, col, size);
+            }
+            let Some(target) = children.iter().find_map(|&e| q_button.get(e).ok()) else {
+                continue;
+            };
+            if animate {
+                flash_column_cell(&mut commands, entity, target.player, size, nr);
+            }
+        }
+    }
+}
+
+// Until every row is on the board, a column can look solved with only some of its cells.
+fn board_spawned(spawn: Option<Res<PuzzleSpawn>>) -> bool {
+    spawn.is_some_and(|s| s.history_spawned)
+}
+
+fn column_check_translation(cell_size: Vec2) -> Vec3 {
+    (cell_size / 2. - COLUMN_CHECK_INSET).extend(3.)
+}
+
+// Two strokes, so it doesn't depend on the font having a check mark.
+fn spawn_column_check(commands: &mut Commands, cell: Entity, col: LCol, cell_size: Vec2) {
+    let stroke = |length: f32, center: Vec2, angle: f32| {
+        (
+            Sprite::from_color(COMPLETE_COLUMN_CELL_BORDER_COLOR, Vec2::new(length, 3.)),
+            Transform::from_translation(center.extend(0.))
+                .with_rotation(Quat::from_rotation_z(angle)),
+            NO_PICK,
+        )
+    };
+    commands
+        .spawn((
+            ColumnCheck(col),
+            Transform::from_translation(column_check_translation(cell_size)),
+            Visibility::default(),
+            NO_PICK,
+        ))
+        .with_child(stroke(7., Vec2::new(-4.5, -2.5), -FRAC_PI_4))
+        .with_child(stroke(14., Vec2::new(3., 0.), FRAC_PI_4))
+        .set_parent(cell);
+}
+
+fn flash_column_cell(
+    commands: &mut Commands,
+    cell: Entity,
+    player: Entity,
+    cell_size: Vec2,
+    nr: usize,
+) {
+    let flash = commands
+        .spawn((
+            ColumnFlashEdge::default(),
+            Sprite::from_color(COLUMN_FLASH_COLOR, cell_size),
+            Transform::from_xyz(0., 0., 2.),
+            NO_PICK,
+            AnimationTarget {
+                id: AnimationTargetId(Uuid::new_v4()),
+                player,
+            },
+        ))
+        .set_parent(cell)
+        .id();
+    // Clips hold their first value until the curve starts, so the cells below wait their turn
+    // unlit.
+    let start = nr as f32 * COLUMN_CASCADE_STEP;
+    AnimatorPlugin::<ColumnFlashEdge>::start_animation(
+        commands,
+        flash,
+        Playback::new(RepeatAnimation::Never).notify_finished(COLUMN_FLASH_FINISHED),
+        move |_, target| {
+            let mut clip = AnimationClip::default();
+            clip.add_curve_to_target(
+                target,
+                AnimatableCurve::new(
+                    ButtonOpacityAnimation,
+                    FunctionCurve::new(
+                        interval(start, start + COLUMN_FLASH_DURATION).unwrap(),
+                        move |t| {
+                            COLUMN_FLASH_PEAK * (PI * (t - start) / COLUMN_FLASH_DURATION).sin()
+                        },
+                    ),
+                ),
+            );
+            clip
+        },
+    );
+}
+
+fn despawn_finished_column_flashes(
+    mut finished_rx: EventReader<AnimationFinished>,
+    q_flash: Query<(), With<ColumnFlashEdge>>,
+    mut commands: Commands,
+) {
+    for ev in finished_rx.read() {
+        if ev.tag == COLUMN_FLASH_FINISHED && q_flash.contains(ev.entity) {
+            commands.entity(ev.entity).despawn_recursive();
+        }
+    }
+}
+
+// Cells are refit when the window or the cluebox changes size; the check stays in the corner.
+fn place_column_check(
+    ev: Trigger<OnInsert, FitWithin>,
+    q_cell: Query<(&FitWithin, &Children), With<DisplayCell>>,
+    mut q_check: Query<&mut Transform, With<ColumnCheck>>,
+) {
+    let Ok((fit, children)) = q_cell.get(ev.entity()) else {
+        return;
+    };
+    for &child in children {
+        if let Ok(mut transform) = q_check.get_mut(child) {
+            transform.translation = column_check_translation(fit.rect().size());
+        }
+    }
+}
+
+fn reset_solved_columns(mut columns: ResMut<SolvedColumns>) {
+    *columns = SolvedColumns::default();
+}
+
+pub struct ColumnsPlugin;
+
+impl Plugin for ColumnsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SolvedColumns>()
+            .register_type::<ColumnCheck>()
+            .register_type::<ColumnFlashEdge>()
+            .register_type::<SolvedColumns>()
+            .add_observer(place_column_check)
+            .add_systems(OnExit(GameState::Playing), reset_solved_columns)
+            .add_systems(
+                Update,
+                (
+                    scan_solved_columns.run_if(board_spawned),
+                    despawn_finished_column_flashes,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{test_puzzle, CellLoc, LRow, UpdateCellIndexOperation};
+
+    #[test]
+    fn solving_and_unsolving_a_column_is_reported_once() {
+        let mut puzzle = test_puzzle(2, 3);
+        let mut columns = SolvedColumns::default();
+        assert_eq!(columns.update(&puzzle), (vec![], vec![]));
+
+        let before = puzzle.clone();
+        for row in [LRow(0), LRow(1)] {
+            let loc = CellLoc { row, col: LCol(1) };
+            let answer = puzzle.answer_at(loc).index.decay_to_ind();
+            puzzle
+                .cell_selection_mut(loc)
+                .apply(answer, UpdateCellIndexOperation::Solo);
+        }
+        assert_eq!(columns.update(&puzzle), (vec![LCol(1)], vec![]));
+        assert_eq!(columns.update(&puzzle), (vec![], vec![]));
+        assert_eq!(columns.update(&before), (vec![], vec![LCol(1)]));
+    }
+}
@@ -0,0 +1,200 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    fit::FitWithin,
+    puzzle::{CellLoc, CellLocIndex, Puzzle, UpdateCellIndexOperation},
+    settings::Settings,
+    DisplayCell, DisplayCellButton, DragTarget, GameState, UIBorders, UpdateCellIndex, NO_PICK,
+};
+
+// While a drag points at an operation in the radial menu, the cells it would change are outlined
+// before the button is released: its own cell, and more faintly, the cells the first step of
+// inference would go on to change. Inference runs on a copy of the board, so the work grows with
+// its candidates. It's checked before any of it runs: the largest generated board, 8x8, takes
+// about 10us in a debug build, and boards with more candidates than that only outline the cell.
+const CONSEQUENCE_CANDIDATE_LIMIT: usize = 8 * 8 * 8;
+
+const DIRECT_OUTLINE_COLOR: Color = Color::hsla(50., 0.9, 0.7, 0.8);
+const INFERRED_OUTLINE_COLOR: Color = Color::hsla(50., 0.9, 0.7, 0.35);
+
+#[derive(Debug, Default, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+struct ConsequencePreview {
+    // The drag's operation the outlines were worked out for.
+    shown: Option<(CellLocIndex, UpdateCellIndexOperation)>,
+    direct: Option<CellLoc>,
+    inferred: Vec<CellLoc>,
+}
+
+impl ConsequencePreview {
+    fn outline_color(&self, loc: CellLoc) -> Option<Color> {
+        if self.direct == Some(loc) {
+            Some(DIRECT_OUTLINE_COLOR)
+        } else if self.inferred.contains(&loc) {
+            Some(INFERRED_OUTLINE_COLOR)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ConsequenceOutline;
+
+fn spawn_consequence_outline(
+    ev: Trigger<OnAdd, DisplayCell>,
+    borders: Res<UIBorders>,
+    mut commands: Commands,
+) {
+    commands.entity(ev.entity()).with_child((
+        ConsequenceOutline,
+        borders.make_sprite(6, DIRECT_OUTLINE_COLOR),
+        Transform::from_xyz(0., 0., 0.6),
+        Visibility::Hidden,
+        NO_PICK,
+    ));
+}
+
+fn size_consequence_outline(
+    ev: Trigger<OnInsert, FitWithin>,
+    q_cell: Query<(&FitWithin, &Children), With<DisplayCell>>,
+    mut q_outline: Query<&mut Sprite, With<ConsequenceOutline>>,
+) {
+    let Ok((fit, children)) = q_cell.get(ev.entity()) else {
+        return;
+    };
+    for &child in children {
+        if let Ok(mut sprite) = q_outline.get_mut(child) {
+            sprite.custom_size = Some(fit.rect().size());
+        }
+    }
+}
+
+fn within_budget(puzzle: &Puzzle) -> bool {
+    let n_cols = puzzle.max_column().0 as usize + 1;
+    puzzle.n_rows() * n_cols * n_cols <= CONSEQUENCE_CANDIDATE_LIMIT
+}
+
+fn update_consequence_preview(
+    settings: Res<Settings>,
+    puzzle: Single<&Puzzle>,
+    q_drag: Query<(&DragTarget, &DisplayCellButton)>,
+    mut preview: ResMut<ConsequencePreview>,
+) {
+    let Some((drag, button)) = q_drag.iter().next() else {
+        preview.set_if_neq(ConsequencePreview::default());
+        return;
+    };
+    let shown = drag
+        .op
+        .filter(|_| settings.consequence_preview)
+        .map(|op| (button.index, op));
+    if shown == preview.shown {
+        return;
+    }
+    preview.shown = shown;
+    preview.direct = None;
+    preview.inferred.clear();
+    let Some((index, op)) = shown else {
+        return;
+    };
+    preview.direct = Some(index.loc);
+    if within_budget(&puzzle) {
+        preview.inferred = puzzle.first_order_consequences(&UpdateCellIndex::manual(index, op));
+    }
+}
+
+fn show_consequence_outlines(
+    preview: Res<ConsequencePreview>,
+    q_cell: Query<(&DisplayCell, &Children)>,
+    mut q_outline: Query<(&mut Sprite, &mut Visibility), With<ConsequenceOutline>>,
+) {
+    if !preview.is_changed() {
+        return;
+    }
+    for (cell, children) in &q_cell {
+        let color = preview.outline_color(cell.loc);
+        for &child in children {
+            let Ok((mut sprite, mut visibility)) = q_outline.get_mut(child) else {
+                continue;
+            };
+            match color {
+                Some(color) => {
+                    sprite.color = color;
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
+
+fn clear_consequence_preview(mut preview: ResMut<ConsequencePreview>) {
+    *preview = ConsequencePreview::default();
+}
+
+pub struct ConsequencesPlugin;
+
+impl Plugin for ConsequencesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConsequencePreview>()
+            .register_type::<ConsequenceOutline>()
+            .register_type::<ConsequencePreview>()
+            .add_observer(size_consequence_outline)
+            .add_observer(spawn_consequence_outline)
+            .add_systems(OnExit(GameState::Playing), clear_consequence_preview)
+            .add_systems(
+                Update,
+                (update_consequence_preview, show_consequence_outlines)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle};
+
+    fn preview_solo(puzzle: Puzzle) -> ConsequencePreview {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(Settings {
+                consequence_preview: true,
+                ..Default::default()
+            })
+            .init_resource::<ConsequencePreview>()
+            .add_systems(Update, update_consequence_preview);
+        let index = puzzle.answer_at(loc(0, 0)).decay_to_ind();
+        app.world_mut().spawn(puzzle);
+        app.world_mut().spawn((
+            DragTarget {
+                op: Some(UpdateCellIndexOperation::Solo),
+                ..DragTarget::new(Vec2::ZERO)
+            },
+            DisplayCellButton { index },
+        ));
+        app.update();
+        app.world_mut()
+            .remove_resource::<ConsequencePreview>()
+            .unwrap()
+    }
+
+    #[test]
+    fn big_boards_only_preview_the_cell_itself() {
+        let small = preview_solo(test_puzzle(2, 3));
+        assert_eq!(small.direct, Some(loc(0, 0)));
+        assert_eq!(small.inferred, vec![loc(0, 1), loc(0, 2)]);
+
+        let big = test_puzzle(10, 10);
+        assert!(!within_budget(&big));
+        let big = preview_solo(big);
+        assert_eq!(big.direct, Some(loc(0, 0)));
+        assert_eq!(big.inferred, vec![]);
+    }
+}
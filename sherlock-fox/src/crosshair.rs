@@ -0,0 +1,138 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    fit::{FitHover, FitWithin},
+    puzzle::CellLoc,
+    DisplayCell, DisplayCellButton, GameState, NO_PICK,
+};
+
+// Hovering a candidate tints every cell in its row and column, to follow them across a large
+// board. The tint sits on the cells, under their buttons, so it leaves the buttons' own hover
+// scaling alone.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct CrosshairTarget(Option<CellLoc>);
+
+#[derive(Debug, Component, Reflect)]
+struct CrosshairTint;
+
+const CROSSHAIR_COLOR: Color = Color::hsla(200., 0.6, 0.6, 0.18);
+
+fn spawn_crosshair_tint(ev: Trigger<OnAdd, DisplayCell>, mut commands: Commands) {
+    commands.entity(ev.entity()).with_child((
+        CrosshairTint,
+        Sprite::from_color(CROSSHAIR_COLOR, Vec2::ZERO),
+        Transform::from_xyz(0., 0., 0.5),
+        Visibility::Hidden,
+        NO_PICK,
+    ));
+}
+
+fn size_crosshair_tint(
+    ev: Trigger<OnInsert, FitWithin>,
+    q_cell: Query<(&FitWithin, &Children), With<DisplayCell>>,
+    mut q_tint: Query<&mut Sprite, With<CrosshairTint>>,
+) {
+    let Ok((fit, children)) = q_cell.get(ev.entity()) else {
+        return;
+    };
+    for &child in children {
+        if let Ok(mut sprite) = q_tint.get_mut(child) {
+            sprite.custom_size = Some(fit.rect().size());
+        }
+    }
+}
+
+fn crosshair_hover_in(
+    ev: Trigger<OnAdd, FitHover>,
+    q_button: Query<&DisplayCellButton>,
+    mut target: ResMut<CrosshairTarget>,
+) {
+    if let Ok(button) = q_button.get(ev.entity()) {
+        target.0 = Some(button.index.loc);
+    }
+}
+
+// Moving between buttons can add the new hover before removing the old one, so only the cell
+// still targeted is cleared.
+fn crosshair_hover_out(
+    ev: Trigger<OnRemove, FitHover>,
+    q_button: Query<&DisplayCellButton>,
+    mut target: ResMut<CrosshairTarget>,
+) {
+    if let Ok(button) = q_button.get(ev.entity()) {
+        if target.0 == Some(button.index.loc) {
+            target.0 = None;
+        }
+    }
+}
+
+fn in_crosshair(target: Option<CellLoc>, loc: CellLoc) -> bool {
+    target.is_some_and(|t| t.row == loc.row || t.col == loc.col)
+}
+
+fn show_crosshair(
+    target: Res<CrosshairTarget>,
+    q_cell: Query<(&DisplayCell, &Children)>,
+    mut q_tint: Query<&mut Visibility, With<CrosshairTint>>,
+) {
+    if !target.is_changed() {
+        return;
+    }
+    for (cell, children) in &q_cell {
+        let visibility = if in_crosshair(target.0, cell.loc) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        for &child in children {
+            if let Ok(mut tint) = q_tint.get_mut(child) {
+                *tint = visibility;
+            }
+        }
+    }
+}
+
+fn clear_crosshair(mut target: ResMut<CrosshairTarget>) {
+    target.0 = None;
+}
+
+pub struct CrosshairPlugin;
+
+impl Plugin for CrosshairPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CrosshairTarget>()
+            .register_type::<CrosshairTarget>()
+            .register_type::<CrosshairTint>()
+            .add_observer(crosshair_hover_in)
+            .add_observer(crosshair_hover_out)
+            .add_observer(size_crosshair_tint)
+            .add_observer(spawn_crosshair_tint)
+            .add_systems(OnExit(GameState::Playing), clear_crosshair)
+            .add_systems(Update, show_crosshair);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{LCol, LRow};
+
+    #[test]
+    fn crosshair_covers_the_row_and_column() {
+        let loc = |row, col| CellLoc {
+            row: LRow(row),
+            col: LCol(col),
+        };
+        let target = Some(loc(1, 2));
+        assert!(in_crosshair(target, loc(1, 2)));
+        assert!(in_crosshair(target, loc(1, 0)));
+        assert!(in_crosshair(target, loc(3, 2)));
+        assert!(!in_crosshair(target, loc(0, 0)));
+        assert!(!in_crosshair(None, loc(1, 2)));
+    }
+}
@@ -0,0 +1,195 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{
+    prelude::*,
+    utils::hashbrown::{HashMap, HashSet},
+};
+
+use crate::{
+    magnifier::MagnifiedButton,
+    puzzle::{CellLocIndex, Puzzle, RowAnswer},
+    DisplayCellButton, PuzzleClueComponent,
+};
+
+// Which entities show which parts of the puzzle, kept current as buttons and clues come and go so
+// nothing has to scan every button or clue to find the ones that go together.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct CrossRefIndex {
+    // Only the matrix's own buttons; the magnifier's copies come and go with hovering.
+    buttons: HashMap<CellLocIndex, Entity>,
+    clues_by_item: HashMap<RowAnswer, HashSet<Entity>>,
+    items_by_clue: HashMap<Entity, Vec<RowAnswer>>,
+}
+
+impl CrossRefIndex {
+    pub fn button(&self, index: CellLocIndex) -> Option<Entity> {
+        self.buttons.get(&index).copied()
+    }
+
+    // The clues that mention an item, wherever in its row the item turns out to be.
+    pub fn clues_mentioning(&self, item: RowAnswer) -> impl Iterator<Item = Entity> + use<'_> {
+        self.clues_by_item.get(&item).into_iter().flatten().copied()
+    }
+
+    pub fn items_in_clue(&self, clue: Entity) -> &[RowAnswer] {
+        self.items_by_clue
+            .get(&clue)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn forget_clue(&mut self, clue: Entity) {
+        for item in self.items_by_clue.remove(&clue).unwrap_or_default() {
+            if let Some(clues) = self.clues_by_item.get_mut(&item) {
+                clues.remove(&clue);
+                if clues.is_empty() {
+                    self.clues_by_item.remove(&item);
+                }
+            }
+        }
+    }
+}
+
+fn index_button(
+    ev: Trigger<OnInsert, DisplayCellButton>,
+    q_button: Query<&DisplayCellButton, Without<MagnifiedButton>>,
+    mut index: ResMut<CrossRefIndex>,
+) {
+    let Ok(button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    index.buttons.insert(button.index, ev.entity());
+}
+
+fn unindex_button(
+    ev: Trigger<OnRemove, DisplayCellButton>,
+    q_button: Query<&DisplayCellButton>,
+    mut index: ResMut<CrossRefIndex>,
+) {
+    let Ok(button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    // A replacement may already have been indexed under the same candidate.
+    if index.buttons.get(&button.index) == Some(&ev.entity()) {
+        index.buttons.remove(&button.index);
+    }
+}
+
+fn index_clue(
+    ev: Trigger<OnInsert, PuzzleClueComponent>,
+    q_clue: Query<&PuzzleClueComponent>,
+    puzzle: Single<&Puzzle>,
+    mut index: ResMut<CrossRefIndex>,
+) {
+    let Ok(PuzzleClueComponent { clue, .. }) = q_clue.get(ev.entity()) else {
+        return;
+    };
+    index.forget_clue(ev.entity());
+    let items = clue
+        .locs()
+        .into_iter()
+        .map(|loc| puzzle.answer_at(loc).decay_column())
+        .collect::<Vec<_>>();
+    for &item in &items {
+        index
+            .clues_by_item
+            .entry(item)
+            .or_default()
+            .insert(ev.entity());
+    }
+    index.items_by_clue.insert(ev.entity(), items);
+}
+
+fn unindex_clue(ev: Trigger<OnRemove, PuzzleClueComponent>, mut index: ResMut<CrossRefIndex>) {
+    index.forget_clue(ev.entity());
+}
+
+pub struct CrossRefPlugin;
+
+impl Plugin for CrossRefPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CrossRefIndex>()
+            .register_type::<CrossRefIndex>()
+            .add_observer(index_button)
+            .add_observer(unindex_button)
+            .add_observer(index_clue)
+            .add_observer(unindex_clue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::{
+        clues::{PuzzleClue, SameColumnClue},
+        puzzle::{test_puzzle, CellLoc, LCol, LInd, LRow},
+    };
+
+    fn board() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins).add_plugins(CrossRefPlugin);
+        app.world_mut().spawn(test_puzzle(3, 5));
+        app
+    }
+
+    fn index_at(row: usize, col: isize, index: usize) -> CellLocIndex {
+        CellLocIndex {
+            loc: CellLoc {
+                row: LRow(row),
+                col: LCol(col),
+            },
+            index: LInd(index),
+        }
+    }
+
+    #[test]
+    fn finds_matrix_buttons_only() {
+        let mut app = board();
+        let index = index_at(1, 2, 3);
+        let button = app.world_mut().spawn(DisplayCellButton { index }).id();
+        app.world_mut()
+            .spawn((MagnifiedButton, DisplayCellButton { index }));
+        let crossref = app.world().resource::<CrossRefIndex>();
+        assert_eq!(crossref.button(index), Some(button));
+
+        app.world_mut().despawn(button);
+        let crossref = app.world().resource::<CrossRefIndex>();
+        assert_eq!(crossref.button(index), None);
+    }
+
+    #[test]
+    fn links_clues_and_their_items() {
+        let mut app = board();
+        let puzzle = test_puzzle(3, 5);
+        let clue = SameColumnClue::new_random(&mut ChaCha8Rng::seed_from_u64(0), &puzzle).unwrap();
+        let items = clue
+            .locs()
+            .into_iter()
+            .map(|loc| puzzle.answer_at(loc).decay_column())
+            .collect::<Vec<_>>();
+        let entity = app
+            .world_mut()
+            .spawn(PuzzleClueComponent::new(clue.into()))
+            .id();
+        let crossref = app.world().resource::<CrossRefIndex>();
+        assert_eq!(crossref.items_in_clue(entity), items.as_slice());
+        for &item in &items {
+            assert_eq!(crossref.clues_mentioning(item).collect::<Vec<_>>(), vec![
+                entity
+            ]);
+        }
+
+        app.world_mut().despawn(entity);
+        let crossref = app.world().resource::<CrossRefIndex>();
+        assert!(crossref.items_in_clue(entity).is_empty());
+        for &item in &items {
+            assert_eq!(crossref.clues_mentioning(item).count(), 0);
+        }
+    }
+}
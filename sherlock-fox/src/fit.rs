@@ -2,22 +2,35 @@
 //
 // SPDX-License-Identifier: EUPL-1.2
 
-use std::marker::PhantomData;
+use std::{any::TypeId, marker::PhantomData};
 
 use bevy::{
-    animation::{animated_field, AnimationTarget, AnimationTargetId, RepeatAnimation},
-    input::common_conditions::input_just_released,
+    animation::{
+        animated_field, AnimationEntityMut, AnimationEvaluationError, AnimationTarget,
+        AnimationTargetId, RepeatAnimation,
+    },
+    input::{
+        common_conditions::input_just_released,
+        mouse::{MouseScrollUnit, MouseWheel},
+        touch::Touches,
+    },
     prelude::*,
-    window::PrimaryWindow,
+    window::WindowResized,
 };
 use petgraph::graph::NodeIndex;
 use uuid::Uuid;
 
 use crate::{
     animation::{AnimatorPlugin, SavedAnimationNode},
+    archive::{ArchiveDrawer, ArchivedClue, ClueArchive},
+    camera::{BoardCamera, BOARD_SIZE},
+    cluebox::{ClueboxFold, ClueboxHandle, ClueboxScroll, SpentClue, CLUEBOX_HANDLE_HEIGHT},
+    pins::PinnedClue,
     puzzle::Puzzle,
+    settings::{MotionKind, Settings},
+    zoom::ZOOM_MODIFIERS,
     DisplayButtonbox, DisplayCell, DisplayCellButton, DisplayClue, DisplayCluebox, DisplayMatrix,
-    DisplayPuzzle, DisplayRow, DisplayTopButton, UIBorders,
+    DisplayPuzzle, DisplayRow, DisplayRowCounter, DisplayTopButton, UIBorders,
 };
 
 #[derive(Reflect, Debug, Clone, Component, Default)]
@@ -29,6 +42,10 @@ impl FitWithin {
     pub fn new(rect: Rect) -> Self {
         FitWithin { rect }
     }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
 }
 
 pub struct FitEntity<'e> {
@@ -87,6 +104,10 @@ pub struct FitHover;
 #[derive(Reflect, Debug, Component)]
 pub struct FitClicked;
 
+// A button that still shows hover and click feedback, but doesn't send its click event.
+#[derive(Reflect, Debug, Component)]
+pub struct FitDisabled;
+
 #[derive(Bundle)]
 pub struct FitWithinBundle {
     fit: FitWithin,
@@ -159,19 +180,28 @@ impl Default for FitTransformAnimationBundle {
     }
 }
 
-fn fit_inside_window(
-    q_camera: Query<(Entity, &Camera)>,
+// The board camera always shows `BOARD_SIZE`, so the root fit no longer follows the window. A new
+// root is fit to the board as soon as it's spawned, and once the window is resized or the board's
+// viewport changes, every root is set to the board again and its whole tree laid out in that
+// frame, easing to wherever it ends up.
+fn fit_inside_board(
+    mut resized_rx: EventReader<WindowResized>,
+    q_viewport: Query<(), (With<BoardCamera>, Changed<Camera>)>,
+    q_new_root: Query<(), (Added<FitWithin>, Without<Parent>)>,
     q_fit_root: Query<(Entity, &FitWithin), Without<Parent>>,
     mut commands: Commands,
 ) {
-    let (_camera_entity, camera) = q_camera.single();
-    let Some(logical_viewport) = camera.logical_viewport_rect() else {
+    let resized = resized_rx.read().count() > 0 || !q_viewport.is_empty();
+    if !resized && q_new_root.is_empty() {
         return;
-    };
-    let window_size = logical_viewport.inflate(-10.);
-    // info!("ensuring window fit of window({:?}) {:?} {:?}", window_size, camera_entity, camera);
+    }
+    let board_rect = Rect::from_corners(Vec2::ZERO, BOARD_SIZE).inflate(-10.);
     for e_fit in &q_fit_root {
-        e_fit.set_rect(&mut commands, window_size);
+        if e_fit.fit().rect != board_rect {
+            e_fit.set_rect(&mut commands, board_rect);
+        } else if resized {
+            e_fit.refresh_rect(&mut commands);
+        }
     }
 }
 
@@ -205,7 +235,8 @@ fn fit_inside_puzzle(
     q_matrix: Query<(Entity, &FitWithin), With<DisplayMatrix>>,
     q_clues: Query<(Entity, &FitWithin), With<DisplayCluebox>>,
     q_buttons: Query<(Entity, &FitWithin), With<DisplayButtonbox>>,
-    mut commands: Commands,
+    q_handle: Query<(Entity, &FitWithin), With<ClueboxHandle>>,
+    (fold, mut commands): (Res<ClueboxFold>, Commands),
 ) {
     // info!("testing matrix fit of {:?}", ev.entity());
     let Ok((within, children)) = q_about_target.get(ev.entity()) else {
@@ -214,12 +245,27 @@ fn fit_inside_puzzle(
     get_child!(matrix = q_matrix, children);
     get_child!(clues = q_clues, children);
     get_child!(buttons = q_buttons, children);
+    let handle = children.iter().find_map(|e| q_handle.get(*e).ok());
     let fit = within.rect;
     let buttonbox_width = fit.width() / 6.;
     let buttonbox_x = fit.max.x - buttonbox_width;
     let cluebox_height = fit.height() / 4.;
     let cluebox_y = fit.max.y - cluebox_height;
-    let matrix_rect = Rect::new(fit.min.x, fit.min.y, buttonbox_x, cluebox_y);
+    // A collapsed cluebox keeps its rect, hidden, so it comes back where it was.
+    let mut matrix_max_y = if fold.is_collapsed() {
+        fit.max.y
+    } else {
+        cluebox_y
+    };
+    if let Some(e_fit) = handle {
+        let handle_min_y = matrix_max_y - CLUEBOX_HANDLE_HEIGHT;
+        e_fit.set_rect(
+            &mut commands,
+            Rect::new(fit.min.x, handle_min_y, buttonbox_x, matrix_max_y),
+        );
+        matrix_max_y = handle_min_y;
+    }
+    let matrix_rect = Rect::new(fit.min.x, fit.min.y, buttonbox_x, matrix_max_y);
     let cluebox_rect = Rect::new(fit.min.x, cluebox_y, buttonbox_x, fit.max.y);
     let buttonbox_rect = Rect::new(buttonbox_x, fit.min.y, fit.max.x, fit.max.y);
     matrix.set_rect(&mut commands, matrix_rect);
@@ -227,29 +273,94 @@ fn fit_inside_puzzle(
     buttons.set_rect(&mut commands, buttonbox_rect);
 }
 
+// Every clue gets at least this much room, however many there are; once they don't all fit, the
+// cluebox scrolls a row at a time.
+const CLUE_SLOT_SIZE: Vec2 = Vec2::new(110., 110.);
+
+// How many clues fit across a cluebox of `size`, and how many rows of them are in view at once.
+fn clue_grid(size: Vec2) -> (usize, usize) {
+    let across = (size.x / CLUE_SLOT_SIZE.x).floor().max(1.);
+    let in_view = (size.y / CLUE_SLOT_SIZE.y).floor().max(1.);
+    (across as usize, in_view as usize)
+}
+
+// A clue, or the archive drawer, with whether it's pinned, archived, the drawer, or spent.
+type ClueboxEntryD<'r> = (
+    Entity,
+    &'r FitWithin,
+    Has<PinnedClue>,
+    Has<ArchivedClue>,
+    Has<ArchiveDrawer>,
+    Has<SpentClue>,
+);
+type ClueboxEntryF = Or<(With<DisplayClue>, With<ArchiveDrawer>)>;
+
 fn fit_inside_clues(
     ev: Trigger<OnInsert, (FitWithin, DisplayCluebox)>,
+    (archive, settings): (Res<ClueArchive>, Res<Settings>),
     q_about_target: Query<(&FitWithin, &Children), (With<DisplayCluebox>, Without<DisplayClue>)>,
-    q_children: Query<(Entity, &FitWithin), With<DisplayClue>>,
+    mut q_scroll: Query<&mut ClueboxScroll>,
+    q_children: Query<ClueboxEntryD, ClueboxEntryF>,
     mut commands: Commands,
 ) {
     let Ok((within, children)) = q_about_target.get(ev.entity()) else {
         return;
     };
-    let children = children
-        .iter()
-        .filter_map(|e| q_children.get(*e).ok())
-        .collect::<Vec<_>>();
+    let children = {
+        let children = children
+            .iter()
+            .filter_map(|e| q_children.get(*e).ok())
+            .collect::<Vec<_>>();
+        let n_archived = children.iter().filter(|c| c.3).count();
+        let (mut shown, left_out) =
+            children
+                .into_iter()
+                .partition::<Vec<_>, _>(|&(_, _, _, archived, drawer, spent)| {
+                    if drawer {
+                        n_archived > 0
+                    } else {
+                        (!archived || archive.is_open())
+                            && !(spent && settings.collapse_spent_clues)
+                    }
+                });
+        for (entity, ..) in left_out {
+            commands.entity(entity).insert(Visibility::Hidden);
+        }
+        // Stable, so pinned clues keep their cluebox order ahead of the rest, and archived clues
+        // theirs behind the drawer.
+        shown.sort_by_key(|&(_, _, pinned, archived, drawer, _)| {
+            (!pinned, archived || drawer, archived)
+        });
+        shown
+            .into_iter()
+            .map(|(entity, fit, ..)| (entity, fit))
+            .collect::<Vec<_>>()
+    };
     let fit = within.rect;
-    let fit_width = fit.width();
-    let clue_width = fit_width / children.len() as f32;
-    // let clue_width = 45.;
-    let mut current_x = fit.min.x;
-    for e_fit in children {
-        let new_x = current_x + clue_width;
-        let clue_rect = Rect::new(current_x, fit.min.y, new_x, fit.max.y);
-        e_fit.set_rect(&mut commands, clue_rect);
-        current_x = new_x;
+    let (across, in_view) = clue_grid(fit.size());
+    let first_row = match q_scroll.get_mut(ev.entity()) {
+        Ok(mut scroll) => {
+            let revealed_row = scroll
+                .revealing()
+                .and_then(|clue| children.iter().position(|&(entity, _)| entity == clue))
+                .map(|n| n / across);
+            scroll.settle(children.len().div_ceil(across), in_view, revealed_row)
+        }
+        Err(_) => 0,
+    };
+    let slot = fit.size() / Vec2::new(across as f32, in_view as f32);
+    for (n, e_fit) in children.into_iter().enumerate() {
+        // Rows scrolled out of view are still laid out, just above or below the cluebox, so that
+        // scrolling slides them in.
+        let row = (n / across) as f32 - first_row as f32;
+        let min = fit.min + slot * Vec2::new((n % across) as f32, row);
+        e_fit.set_rect(&mut commands, Rect::from_corners(min, min + slot));
+        let visibility = if row >= 0. && row < in_view as f32 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        commands.entity(e_fit.0).insert(visibility);
     }
 }
 
@@ -270,11 +381,11 @@ fn fit_inside_buttonbox(
         .filter_map(|e| q_children.get(*e).ok())
         .collect::<Vec<_>>();
     let fit = within.rect.inflate(-10.);
-    // let fit_height = fit.height();
-    let row_height = 50.;
+    // Rows shrink once there are more buttons than fit at full height.
+    let row_height = (fit.height() / children.len() as f32).min(70.);
     let mut current_y = fit.min.y;
     for e_fit in children {
-        let new_y = current_y + row_height + 20.;
+        let new_y = current_y + row_height;
         let row_rect = Rect::new(fit.min.x, current_y, fit.max.x, new_y).inflate(-5.);
         e_fit.set_rect(&mut commands, row_rect);
         current_y = new_y;
@@ -316,10 +427,13 @@ fn fit_inside_matrix(
     }
 }
 
+const ROW_COUNTER_WIDTH: f32 = 70.;
+
 fn fit_inside_row(
     ev: Trigger<OnInsert, (FitWithin, DisplayRow)>,
     q_about_target: Query<(&FitWithin, &Children), (With<DisplayRow>, Without<DisplayCell>)>,
     q_children: Query<((Entity, &FitWithin), &DisplayCell)>,
+    q_counter: Query<(Entity, &FitWithin), With<DisplayRowCounter>>,
     mut commands: Commands,
 ) {
     // info!("testing matrix row fit of {:?}", ev.entity());
@@ -331,6 +445,7 @@ fn fit_inside_row(
     //     within,
     //     children.len()
     // );
+    let counter = children.iter().find_map(|e| q_counter.get(*e).ok());
     let children = {
         let mut children = children
             .iter()
@@ -339,7 +454,15 @@ fn fit_inside_row(
         children.sort_by_key(|(_, cell)| cell.loc);
         children
     };
-    let fit = within.rect;
+    let mut fit = within.rect;
+    if let Some(e_fit) = counter {
+        let counter_min_x = fit.max.x - ROW_COUNTER_WIDTH;
+        e_fit.set_rect(
+            &mut commands,
+            Rect::new(counter_min_x, fit.min.y, fit.max.x, fit.max.y),
+        );
+        fit.max.x = counter_min_x;
+    }
     let fit_width = fit.width();
     let prospective_cell_width = fit_width / children.len() as f32;
     let cell_spacing = prospective_cell_width * 0.15;
@@ -359,6 +482,7 @@ fn fit_inside_cell(
     q_about_target: Query<(&FitWithin, &Children, &DisplayCell), Without<DisplayCellButton>>,
     q_children: Query<((Entity, &FitWithin), &DisplayCellButton)>,
     q_puzzle: Single<&Puzzle>,
+    settings: Res<Settings>,
     mut commands: Commands,
 ) {
     // info!("testing matrix cell fit of {:?}", ev.entity());
@@ -381,49 +505,132 @@ fn fit_inside_cell(
     let sel = q_puzzle.cell_selection(display.loc);
     let sel_solo = sel.is_any_solo();
     let fit = within.rect;
-    let fit_width = fit.width();
-    let button_width = fit_width / children.len() as f32;
-    let mut current_x = fit.min.x;
-    for (e_fit, button) in children {
-        let new_x = current_x + button_width;
+    let n_buttons = children.len();
+    let grid_rows = cell_grid_rows(n_buttons, fit.size(), settings.min_hit_target);
+    let grid_cols = n_buttons.div_ceil(grid_rows);
+    let button_size = fit.size() / Vec2::new(grid_cols as f32, grid_rows as f32);
+    for (nr, (e_fit, button)) in children.into_iter().enumerate() {
+        let min =
+            fit.min + button_size * Vec2::new((nr % grid_cols) as f32, (nr / grid_cols) as f32);
         // TODO: update the parent rect to lay this out
         let button_rect = if sel_solo == Some(button.index.index) {
-            Rect::from_center_size(Vec2::default(), Vec2::new(50., 50.))
+            Rect::from_center_size(
+                Vec2::default(),
+                Vec2::splat(fit.size().min_element().min(50.)),
+            )
         } else {
-            Rect::new(current_x, fit.min.y, new_x, fit.max.y)
+            Rect::from_corners(min, min + button_size)
         };
         e_fit.set_rect(&mut commands, button_rect);
-        current_x = new_x;
+    }
+}
+
+// Candidates sit in one strip while that leaves each at least `min_size` wide; past that, they're
+// wrapped into whichever grid keeps the narrower side of each slot largest.
+fn cell_grid_rows(n_buttons: usize, size: Vec2, min_size: f32) -> usize {
+    if n_buttons == 0 || size.x / n_buttons as f32 >= min_size {
+        return 1;
+    }
+    let slot_side = |rows: usize| {
+        let cols = n_buttons.div_ceil(rows);
+        (size.x / cols as f32).min(size.y / rows as f32)
+    };
+    // Reversed so that ties go to the grid with fewer rows.
+    (1..=n_buttons)
+        .rev()
+        .max_by(|&a, &b| slot_side(a).total_cmp(&slot_side(b)))
+        .unwrap_or(1)
+}
+
+fn refit_cells_for_settings(
+    settings: Res<Settings>,
+    q_cell: Query<(Entity, &FitWithin), With<DisplayCell>>,
+    mut commands: Commands,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for e_fit in &q_cell {
+        e_fit.refresh_rect(&mut commands);
     }
 }
 
 impl SavedAnimationNode for FitTransformEdge {
     type AnimatedFrom = Transform;
+    const MOTION: MotionKind = MotionKind::FitEasing;
 
     fn node_mut(&mut self) -> &mut Option<NodeIndex> {
         &mut self.0
     }
 }
 
+// Fit rects are laid out the way the window is, with y growing downward, but world space has y
+// growing upward. Maps a point in fit space to a translation from its parent's center.
+pub fn fit_to_world(point: Vec2, parent_center: Vec2) -> Vec2 {
+    (point - parent_center) * Vec2::new(1., -1.)
+}
+
+// The size of a fit background's sprite, so that it can ease to a new size as it moves.
+#[derive(Debug, Clone, Copy)]
+struct FitBackgroundSize;
+
+impl AnimatableProperty for FitBackgroundSize {
+    type Property = Vec2;
+
+    fn evaluator_id(&self) -> EvaluatorId<'_> {
+        EvaluatorId::Type(TypeId::of::<Self>())
+    }
+
+    fn get_mut<'a>(
+        &self,
+        entity: &'a mut AnimationEntityMut,
+    ) -> Result<&'a mut Self::Property, AnimationEvaluationError> {
+        entity
+            .get_mut::<Sprite>()
+            .ok_or(AnimationEvaluationError::ComponentNotPresent(TypeId::of::<
+                Sprite,
+            >(
+            )))?
+            .into_inner()
+            .custom_size
+            .as_mut()
+            .ok_or(AnimationEvaluationError::PropertyNotPresent(TypeId::of::<
+                Vec2,
+            >(
+            )))
+    }
+}
+
 fn fit_to_transform(
     ev: Trigger<OnInsert, FitWithin>,
     mut q_fit: Query<(Entity, &FitWithin, &Parent, &mut Transform)>,
     q_just_fit: Query<&FitWithin>,
     q_can_animate: Query<&AnimationTarget, With<FitTransformEdge>>,
+    mut q_background: Query<&mut Sprite, With<FitWithinBackground>>,
     mut commands: Commands,
 ) {
     let Ok((entity, fit, parent, mut transform)) = q_fit.get_mut(ev.entity()) else {
         return;
     };
+    let new_size = fit.rect.size();
+    // Backgrounds that ease into place are resized here rather than by `fit_background_sprite`,
+    // in the same clip as the move, so that a layout that changes mid-animation starts both over
+    // from wherever they'd got to. The first size a background gets is set right away.
+    let mut from_size = None;
+    if let (true, Ok(mut sprite)) = (q_can_animate.contains(entity), q_background.get_mut(entity)) {
+        from_size = sprite.custom_size.filter(|&size| size != new_size);
+        if sprite.custom_size.is_none() {
+            sprite.custom_size = Some(new_size);
+        }
+    }
     let Ok(parent_fit) = q_just_fit.get(**parent) else {
+        if let (Some(_), Ok(mut sprite)) = (from_size, q_background.get_mut(entity)) {
+            sprite.custom_size = Some(new_size);
+        }
         return;
     };
     // info!("fit to transform before={fit:?}");
-    // TODO: unsure why this needs to be Y-reflected
-    let new_translation = Vec3::from((
-        (fit.rect.center() - parent_fit.rect.center()) * Vec2::new(1., -1.),
-        1.,
-    ));
+    let new_translation = fit_to_world(fit.rect.center(), parent_fit.rect.center()).extend(1.);
     if q_can_animate.get(entity).is_ok() {
         AnimatorPlugin::<FitTransformEdge>::start_animation(
             &mut commands,
@@ -431,6 +638,7 @@ fn fit_to_transform(
             RepeatAnimation::Never,
             move |transform, target| {
                 let mut clip = AnimationClip::default();
+                let duration = interval(0., 0.5).unwrap();
                 clip.add_curve_to_target(
                     target,
                     AnimatableCurve::new(
@@ -440,10 +648,21 @@ fn fit_to_transform(
                             new_translation,
                             EaseFunction::CubicOut,
                         )
-                        .reparametrize_linear(interval(0., 0.5).unwrap())
+                        .reparametrize_linear(duration)
                         .unwrap(),
                     ),
                 );
+                if let Some(from_size) = from_size {
+                    clip.add_curve_to_target(
+                        target,
+                        AnimatableCurve::new(
+                            FitBackgroundSize,
+                            EasingCurve::new(from_size, new_size, EaseFunction::CubicOut)
+                                .reparametrize_linear(duration)
+                                .unwrap(),
+                        ),
+                    );
+                }
                 clip
             },
         );
@@ -454,12 +673,14 @@ fn fit_to_transform(
 
 fn fit_background_sprite(
     ev: Trigger<OnInsert, FitWithin>,
-    mut q_fit: Query<(&FitWithin, &mut Sprite), With<FitWithinBackground>>,
+    mut q_fit: Query<(&FitWithin, &mut Sprite, Has<FitTransformEdge>), With<FitWithinBackground>>,
 ) {
-    let Ok((fit, mut sprite)) = q_fit.get_mut(ev.entity()) else {
+    let Ok((fit, mut sprite, eases)) = q_fit.get_mut(ev.entity()) else {
         return;
     };
-    sprite.custom_size = Some(fit.rect.size());
+    if !eases {
+        sprite.custom_size = Some(fit.rect.size());
+    }
 }
 
 fn make_fit_background_sprite(
@@ -496,11 +717,65 @@ fn mouse_out_fit(ev: Trigger<Pointer<Out>>, mut commands: Commands) {
     cmd.remove::<FitHover>();
 }
 
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FitScrolledEvent {
+    pub entity: Entity,
+    pub steps: isize,
+}
+
+// Pixel-based scrolling (touchpads) is accumulated until it adds up to a whole line.
+const SCROLL_PIXELS_PER_STEP: f32 = 20.;
+
+pub fn wheel_steps(wheel: &MouseWheel, pixels: &mut f32) -> isize {
+    match wheel.unit {
+        MouseScrollUnit::Line => wheel.y.signum() as isize,
+        MouseScrollUnit::Pixel => {
+            *pixels += wheel.y;
+            let steps = (*pixels / SCROLL_PIXELS_PER_STEP).trunc();
+            *pixels -= steps * SCROLL_PIXELS_PER_STEP;
+            steps as isize
+        }
+    }
+}
+
+fn fit_hover_scroll(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel_rx: EventReader<MouseWheel>,
+    mut pixels: Local<f32>,
+    q_hovered: Query<Entity, With<FitHover>>,
+    mut ev_tx: EventWriter<FitScrolledEvent>,
+) {
+    // The wheel zooms instead while a zoom modifier is held.
+    if keys.any_pressed(ZOOM_MODIFIERS) {
+        wheel_rx.clear();
+        return;
+    }
+    for wheel in wheel_rx.read() {
+        let steps = wheel_steps(wheel, &mut pixels);
+        if steps == 0 {
+            continue;
+        }
+        for entity in &q_hovered {
+            ev_tx.send(FitScrolledEvent { entity, steps });
+        }
+    }
+}
+
+// Clicks are the primary button's; the secondary button sweeps instead. Fingers on candidates are
+// told apart as taps or long presses by the touch module, which clicks them itself.
 fn fit_clicked_down(
     mut ev: Trigger<Pointer<Down>>,
     q_hovered: Query<Entity, With<FitHover>>,
+    q_cell_button: Query<(), With<DisplayCellButton>>,
     mut commands: Commands,
 ) {
+    if ev.event().button != PointerButton::Primary {
+        return;
+    }
+    if ev.pointer_id.is_touch() && q_cell_button.contains(ev.entity()) {
+        ev.propagate(false);
+        return;
+    }
     let mut trapped = false;
     for entity in &q_hovered {
         commands.entity(entity).insert(FitClicked);
@@ -511,6 +786,10 @@ fn fit_clicked_down(
     }
 }
 
+fn any_touch_just_released(touches: Res<Touches>) -> bool {
+    touches.any_just_released()
+}
+
 fn fit_clear_clicked(q_clicked: Query<Entity, With<FitClicked>>, mut commands: Commands) {
     // info!("clicked up");
     for entity in &q_clicked {
@@ -519,6 +798,76 @@ fn fit_clear_clicked(q_clicked: Query<Entity, With<FitClicked>>, mut commands: C
     }
 }
 
+// A drag with the secondary button, through each fit entity it passes over. Every sweep is
+// numbered, so what it does can be told apart from the sweeps before it.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FitSweep {
+    nr: u32,
+    active: bool,
+    path: Vec<Entity>,
+}
+
+impl FitSweep {
+    fn start(&mut self) {
+        self.nr = self.nr.wrapping_add(1);
+        self.active = true;
+        self.path.clear();
+    }
+
+    // Returns whether the entity is new to the sweep; going back over one doesn't count twice.
+    fn visit(&mut self, entity: Entity) -> bool {
+        if !self.active || self.path.contains(&entity) {
+            return false;
+        }
+        self.path.push(entity);
+        true
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct FitSweptEvent {
+    pub entity: Entity,
+    pub sweep: u32,
+}
+
+// Down events bubble up through the parents, but each level reports the same target.
+fn fit_sweep_down(
+    ev: Trigger<Pointer<Down>>,
+    mut sweep: ResMut<FitSweep>,
+    mut ev_tx: EventWriter<FitSweptEvent>,
+) {
+    if ev.event().button != PointerButton::Secondary {
+        return;
+    }
+    if !sweep.active {
+        sweep.start();
+    }
+    if sweep.visit(ev.target) {
+        ev_tx.send(FitSweptEvent {
+            entity: ev.target,
+            sweep: sweep.nr,
+        });
+    }
+}
+
+fn fit_sweep_over(
+    ev: Trigger<Pointer<Over>>,
+    mut sweep: ResMut<FitSweep>,
+    mut ev_tx: EventWriter<FitSweptEvent>,
+) {
+    if sweep.visit(ev.target) {
+        ev_tx.send(FitSweptEvent {
+            entity: ev.target,
+            sweep: sweep.nr,
+        });
+    }
+}
+
+fn fit_sweep_end(mut sweep: ResMut<FitSweep>) {
+    sweep.active = false;
+}
+
 pub trait FitButton {
     type OnClick: Send + Sync + Clone + std::fmt::Debug + 'static;
     fn clicked(&self) -> Self::OnClick;
@@ -540,7 +889,7 @@ pub enum ButtonClick {}
 impl<C: FitButton + Component> FitButtonInteractionPlugin<C, ButtonClick> {
     fn button_click_up(
         ev: Trigger<OnRemove, FitClicked>,
-        q_target: Query<(Option<&FitHover>, &C)>,
+        q_target: Query<(Option<&FitHover>, &C), Without<FitDisabled>>,
         mut ev_tx: EventWriter<FitClickedEvent<C::OnClick>>,
     ) {
         let Ok((hover, data)) = q_target.get(ev.entity()) else {
@@ -644,6 +993,7 @@ pub struct HoverScaleEdge(Option<NodeIndex>);
 
 impl SavedAnimationNode for HoverScaleEdge {
     type AnimatedFrom = Transform;
+    const MOTION: MotionKind = MotionKind::HoverScale;
 
     fn node_mut(&mut self) -> &mut Option<NodeIndex> {
         &mut self.0
@@ -709,7 +1059,12 @@ pub struct FitPlugin;
 
 impl Plugin for FitPlugin {
     fn build(&self, app: &mut App) {
-        app.add_observer(fit_clicked_down)
+        app.add_event::<FitScrolledEvent>()
+            .add_event::<FitSweptEvent>()
+            .init_resource::<FitSweep>()
+            .register_type::<FitDisabled>()
+            .register_type::<FitSweep>()
+            .add_observer(fit_clicked_down)
             .add_observer(fit_background_sprite)
             .add_observer(fit_inside_buttonbox)
             .add_observer(fit_inside_cell)
@@ -717,6 +1072,8 @@ impl Plugin for FitPlugin {
             .add_observer(fit_inside_matrix)
             .add_observer(fit_inside_puzzle)
             .add_observer(fit_inside_row)
+            .add_observer(fit_sweep_down)
+            .add_observer(fit_sweep_over)
             .add_observer(fit_to_transform)
             .add_observer(make_fit_background_sprite)
             .add_observer(mouse_out_fit)
@@ -724,9 +1081,104 @@ impl Plugin for FitPlugin {
             .add_systems(
                 Update,
                 (
-                    fit_clear_clicked.run_if(input_just_released(MouseButton::Left)),
-                    fit_inside_window.run_if(any_with_component::<PrimaryWindow>),
+                    fit_clear_clicked
+                        .run_if(input_just_released(MouseButton::Left).or(any_touch_just_released)),
+                    fit_sweep_end.run_if(input_just_released(MouseButton::Right)),
+                    fit_hover_scroll,
+                    refit_cells_for_settings,
                 ),
-            );
+            )
+            .add_systems(PreUpdate, fit_inside_board);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_space_points_down_and_world_space_up() {
+        let parent = Rect::new(0., 0., 100., 50.);
+        let center = parent.center();
+        assert_eq!(fit_to_world(center, center), Vec2::ZERO);
+        // The top-left corner in fit space is up and to the left in the world.
+        assert_eq!(fit_to_world(parent.min, center), Vec2::new(-50., 25.));
+        assert_eq!(fit_to_world(parent.max, center), Vec2::new(50., -25.));
+        // Further down the fit rect is further down the world.
+        assert!(fit_to_world(Vec2::new(50., 40.), center).y < fit_to_world(center, center).y);
+    }
+
+    #[test]
+    fn the_board_is_laid_out_again_on_resize() {
+        let mut app = App::new();
+        app.add_event::<WindowResized>()
+            .add_systems(Update, fit_inside_board);
+        let root = app.world_mut().spawn(FitWithin::default()).id();
+        let child = app
+            .world_mut()
+            .spawn(FitWithin::default())
+            .set_parent(root)
+            .id();
+        let rect = |app: &App, entity| app.world().get::<FitWithin>(entity).unwrap().rect;
+        app.update();
+        let board_rect = rect(&app, root);
+        assert_eq!(board_rect.size(), BOARD_SIZE - 20.);
+        assert_eq!(rect(&app, child), Rect::default());
+
+        // Nothing polls the root after that.
+        app.world_mut().get_mut::<FitWithin>(root).unwrap().rect = Rect::default();
+        app.update();
+        assert_eq!(rect(&app, root), Rect::default());
+
+        // A new root is fit on its own.
+        let other_root = app.world_mut().spawn(FitWithin::default()).id();
+        app.update();
+        assert_eq!(rect(&app, other_root), board_rect);
+        assert_eq!(rect(&app, root), board_rect);
+
+        // A resize sets every root again, even one that's already fit, so its tree is laid out.
+        app.world_mut().get_mut::<FitWithin>(root).unwrap().rect = Rect::default();
+        let changes = |app: &mut App| {
+            app.world_mut()
+                .query::<Ref<FitWithin>>()
+                .get(app.world(), other_root)
+                .unwrap()
+                .last_changed()
+        };
+        let before = changes(&mut app);
+        app.world_mut().send_event(WindowResized {
+            window: Entity::PLACEHOLDER,
+            width: 800.,
+            height: 600.,
+        });
+        app.update();
+        assert_eq!(rect(&app, root), board_rect);
+        assert!(changes(&mut app).is_newer_than(before, app.world().read_change_tick()));
+    }
+
+    #[test]
+    fn clues_keep_their_room_however_many_there_are() {
+        assert_eq!(clue_grid(Vec2::new(1000., 180.)), (9, 1));
+        assert_eq!(clue_grid(Vec2::new(440., 330.)), (4, 3));
+        // Even a cluebox smaller than one clue shows one.
+        assert_eq!(clue_grid(Vec2::new(50., 50.)), (1, 1));
+    }
+
+    #[test]
+    fn sweeps_pass_each_entity_once() {
+        let [a, b] = [Entity::from_raw(1), Entity::from_raw(2)];
+        let mut sweep = FitSweep::default();
+        assert!(!sweep.visit(a));
+        sweep.start();
+        assert!(sweep.visit(a));
+        assert!(sweep.visit(b));
+        assert!(!sweep.visit(a));
+        assert_eq!(sweep.path, vec![a, b]);
+        let first = sweep.nr;
+        sweep.active = false;
+        assert!(!sweep.visit(b));
+        sweep.start();
+        assert!(sweep.visit(a));
+        assert_ne!(sweep.nr, first);
     }
 }
@@ -0,0 +1,408 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    buttons::TopButtonState,
+    cluebox::ClueboxFold,
+    fit::FitClickedEvent,
+    highlight_operation,
+    magnifier::MagnifiedButton,
+    modal::no_modal,
+    notes::editing_notes,
+    operation_available,
+    operations::OperationTheme,
+    puzzle::{CellLoc, CellLocIndex, LCol, LInd, LRow, Puzzle, UpdateCellIndexOperation},
+    settings::Settings,
+    spawn_operation_menu, DisplayCellButton, DragTarget, DragUITarget, GameState, TopButtonAction,
+    UpdateCellIndex, NO_PICK,
+};
+
+// Keyboard and gamepad play. Directions move a focused candidate around the board; confirming
+// opens a prompt with the radial menu's operations, which are then picked by direction. A gamepad
+// skips the prompt: each face button is the operation on that side of the radial menu.
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct CellFocus {
+    index: Option<CellLocIndex>,
+    prompt: Option<OperationPrompt>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Reflect)]
+struct OperationPrompt {
+    selected: Option<UpdateCellIndexOperation>,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+enum FocusInput {
+    Move(IVec2),
+    Confirm,
+    Cancel,
+    // Shortcuts that skip the prompt. Toggling names a candidate in the focused cell by number.
+    Toggle(LInd),
+    Operate(UpdateCellIndexOperation),
+    // Whichever operation is on that side of the radial menu.
+    OperateToward(IVec2),
+    TopButton(TopButtonAction),
+}
+
+#[derive(Reflect, Debug, Component)]
+struct FocusMarker;
+
+#[derive(Reflect, Debug, Component)]
+struct FocusPromptMenu;
+
+const FOCUS_COLOR: Color = Color::hsla(200., 1., 0.6, 0.5);
+
+// How far a stick has to lean before it moves the focus. It moves once per lean, like a d-pad press.
+const STICK_THRESHOLD: f32 = 0.5;
+
+fn stick_direction(stick: Vec2) -> Option<IVec2> {
+    if stick.length() < STICK_THRESHOLD {
+        None
+    } else if stick.x.abs() > stick.y.abs() {
+        Some(IVec2::new(stick.x.signum() as i32, 0))
+    } else {
+        Some(IVec2::new(0, stick.y.signum() as i32))
+    }
+}
+
+fn read_focus_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    q_gamepad: Query<(Entity, &Gamepad)>,
+    mut leaning: Local<HashMap<Entity, IVec2>>,
+    mut fold: ResMut<ClueboxFold>,
+    mut input_tx: EventWriter<FocusInput>,
+) {
+    let key_map = [
+        (KeyCode::ArrowUp, FocusInput::Move(IVec2::Y)),
+        (KeyCode::ArrowDown, FocusInput::Move(IVec2::NEG_Y)),
+        (KeyCode::ArrowLeft, FocusInput::Move(IVec2::NEG_X)),
+        (KeyCode::ArrowRight, FocusInput::Move(IVec2::X)),
+        (KeyCode::Enter, FocusInput::Confirm),
+        (KeyCode::Space, FocusInput::Confirm),
+        (KeyCode::Escape, FocusInput::Cancel),
+        (
+            KeyCode::KeyS,
+            FocusInput::Operate(UpdateCellIndexOperation::Solo),
+        ),
+        (
+            KeyCode::KeyC,
+            FocusInput::Operate(UpdateCellIndexOperation::Clear),
+        ),
+        // Pencil marks: mark the focused candidate, then confirm (yes) or revert (no) the cell's marks.
+        (
+            KeyCode::KeyT,
+            FocusInput::Operate(UpdateCellIndexOperation::MarkTentative),
+        ),
+        (
+            KeyCode::KeyY,
+            FocusInput::Operate(UpdateCellIndexOperation::ConfirmAll),
+        ),
+        (
+            KeyCode::KeyN,
+            FocusInput::Operate(UpdateCellIndexOperation::RevertTentative),
+        ),
+        (KeyCode::KeyU, FocusInput::TopButton(TopButtonAction::Undo)),
+        (KeyCode::KeyR, FocusInput::TopButton(TopButtonAction::Redo)),
+    ];
+    let digits = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let button_map = [
+        (GamepadButton::DPadUp, FocusInput::Move(IVec2::Y)),
+        (GamepadButton::DPadDown, FocusInput::Move(IVec2::NEG_Y)),
+        (GamepadButton::DPadLeft, FocusInput::Move(IVec2::NEG_X)),
+        (GamepadButton::DPadRight, FocusInput::Move(IVec2::X)),
+        (GamepadButton::East, FocusInput::OperateToward(IVec2::X)),
+        (
+            GamepadButton::South,
+            FocusInput::OperateToward(IVec2::NEG_Y),
+        ),
+        (GamepadButton::West, FocusInput::OperateToward(IVec2::NEG_X)),
+        (GamepadButton::North, FocusInput::OperateToward(IVec2::Y)),
+        (
+            GamepadButton::LeftTrigger,
+            FocusInput::TopButton(TopButtonAction::Undo),
+        ),
+        (
+            GamepadButton::RightTrigger,
+            FocusInput::TopButton(TopButtonAction::Redo),
+        ),
+        (GamepadButton::Select, FocusInput::Cancel),
+    ];
+    for (key, input) in key_map {
+        if keys.just_pressed(key) {
+            input_tx.send(input);
+        }
+    }
+    for (n, key) in digits.into_iter().enumerate() {
+        if keys.just_pressed(key) {
+            input_tx.send(FocusInput::Toggle(LInd(n)));
+        }
+    }
+    for (entity, gamepad) in &q_gamepad {
+        for (button, input) in button_map {
+            if gamepad.just_pressed(button) {
+                input_tx.send(input);
+            }
+        }
+        // Start opens the clues, or folds them away again.
+        if gamepad.just_pressed(GamepadButton::Start) {
+            fold.toggle_collapsed();
+        }
+        match stick_direction(gamepad.left_stick()) {
+            Some(direction) => {
+                if leaning.insert(entity, direction) != Some(direction) {
+                    input_tx.send(FocusInput::Move(direction));
+                }
+            }
+            None => {
+                leaning.remove(&entity);
+            }
+        }
+    }
+}
+
+// Left and right step through a row's candidates in reading order, crossing into the neighboring
+// cells; up and down keep the column and candidate while changing rows.
+fn move_focus(puzzle: &Puzzle, current: Option<CellLocIndex>, direction: IVec2) -> CellLocIndex {
+    let first = CellLocIndex {
+        loc: CellLoc {
+            row: LRow(0),
+            col: LCol(0),
+        },
+        index: LInd(0),
+    };
+    let Some(current) = current else {
+        return first;
+    };
+    if puzzle.n_rows() == 0 {
+        return first;
+    }
+    let n_cols = puzzle.iter_cols().count() as isize;
+    let row =
+        (current.loc.row.0 as isize - direction.y as isize).clamp(0, puzzle.n_rows() as isize - 1);
+    let row = LRow(row as usize);
+    let n_indices = puzzle.row_at(row).iter_indices().count().max(1) as isize;
+    let index = (current.index.0 as isize).min(n_indices - 1);
+    let flat = (current.loc.col.0 * n_indices + index + direction.x as isize)
+        .clamp(0, n_cols * n_indices - 1);
+    CellLocIndex {
+        loc: CellLoc {
+            row,
+            col: LCol(flat / n_indices),
+        },
+        index: LInd((flat % n_indices) as usize),
+    }
+}
+
+fn apply_focus_input(
+    mut input_rx: EventReader<FocusInput>,
+    mut focus: ResMut<CellFocus>,
+    (q_puzzle, settings): (Single<&Puzzle>, Res<Settings>),
+    q_drag: Query<(), With<DragTarget>>,
+    mut update_tx: EventWriter<UpdateCellIndex>,
+    mut top_button_tx: EventWriter<FitClickedEvent<TopButtonAction>>,
+    button_state: Res<TopButtonState>,
+) {
+    // The pointer's radial menu takes over while a drag is in progress.
+    if !q_drag.is_empty() {
+        input_rx.clear();
+        if focus.prompt.is_some() {
+            focus.prompt = None;
+        }
+        return;
+    }
+    for &input in input_rx.read() {
+        match (input, focus.prompt) {
+            // The same events the top buttons send, so undo and redo behave exactly as if clicked.
+            (FocusInput::TopButton(action), _) => {
+                if button_state.is_enabled(action) {
+                    top_button_tx.send(FitClickedEvent(action));
+                }
+            }
+            (FocusInput::Toggle(index), _) => {
+                let Some(current) = focus.index else {
+                    continue;
+                };
+                if !q_puzzle
+                    .row_at(current.loc.row)
+                    .iter_indices()
+                    .any(|i| i == index)
+                {
+                    continue;
+                }
+                let toggled = CellLocIndex {
+                    loc: current.loc,
+                    index,
+                };
+                update_tx.send(UpdateCellIndex::manual(
+                    toggled,
+                    UpdateCellIndexOperation::Toggle,
+                ));
+                focus.index = Some(toggled);
+                focus.prompt = None;
+            }
+            (FocusInput::Operate(_) | FocusInput::OperateToward(_), _) => {
+                let op = match input {
+                    FocusInput::OperateToward(direction) => {
+                        settings.radial.operation_toward(direction.as_vec2())
+                    }
+                    FocusInput::Operate(op) => Some(op),
+                    _ => None,
+                };
+                let (Some(op), Some(index)) = (op, focus.index) else {
+                    continue;
+                };
+                if operation_available(&q_puzzle, index, op) {
+                    update_tx.send(UpdateCellIndex::manual(index, op));
+                }
+                focus.prompt = None;
+            }
+            (FocusInput::Move(direction), Some(_)) => {
+                let selected = settings
+                    .radial
+                    .operation_toward(direction.as_vec2())
+                    .filter(|&op| {
+                        focus
+                            .index
+                            .is_some_and(|index| operation_available(&q_puzzle, index, op))
+                    });
+                focus.prompt = Some(OperationPrompt { selected });
+            }
+            (FocusInput::Confirm, Some(prompt)) => {
+                // Confirming before choosing a direction leaves the prompt up.
+                if let (Some(op), Some(index)) = (prompt.selected, focus.index) {
+                    update_tx.send(UpdateCellIndex::manual(index, op));
+                    focus.prompt = None;
+                }
+            }
+            (FocusInput::Cancel, Some(_)) => focus.prompt = None,
+            (FocusInput::Move(direction), None) => {
+                focus.index = Some(move_focus(&q_puzzle, focus.index, direction));
+            }
+            (FocusInput::Confirm, None) => {
+                if focus.index.is_some() {
+                    focus.prompt = Some(OperationPrompt::default());
+                }
+            }
+            (FocusInput::Cancel, None) => focus.index = None,
+        }
+    }
+}
+
+fn show_focus(
+    mut commands: Commands,
+    focus: Res<CellFocus>,
+    q_marker: Query<Entity, With<FocusMarker>>,
+    q_menu: Query<Entity, With<FocusPromptMenu>>,
+    q_button: Query<(Entity, &DisplayCellButton, &GlobalTransform), Without<MagnifiedButton>>,
+    (q_puzzle, theme, settings): (Single<&Puzzle>, Res<OperationTheme>, Res<Settings>),
+    mut q_target: Query<(&mut Transform, &DragUITarget)>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+    for entity in &q_marker {
+        commands.entity(entity).despawn_recursive();
+    }
+    let focused = focus
+        .index
+        .and_then(|index| q_button.iter().find(|(_, b, _)| b.index == index));
+    if let Some((entity, _, _)) = focused {
+        commands.entity(entity).with_child(focus_marker());
+    }
+    match (focused, focus.prompt) {
+        (Some((_, button, transform)), Some(prompt)) => {
+            if q_menu.is_empty() {
+                let mut transform = transform.compute_transform();
+                transform.translation.z += 10.;
+                spawn_operation_menu(
+                    &mut commands,
+                    transform,
+                    &q_puzzle,
+                    &theme,
+                    &settings.radial,
+                    button.index,
+                )
+                .insert(FocusPromptMenu);
+            }
+            highlight_operation(prompt.selected, &mut q_target);
+        }
+        _ => {
+            for entity in &q_menu {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn focus_marker() -> impl Bundle {
+    (
+        FocusMarker,
+        Sprite::from_color(FOCUS_COLOR, Vec2::new(32., 32.)),
+        Transform::from_xyz(0., 0., 3.),
+        NO_PICK,
+    )
+}
+
+pub fn has_focus(focus: Res<CellFocus>) -> bool {
+    focus.index.is_some()
+}
+
+fn clear_focus(
+    mut commands: Commands,
+    mut focus: ResMut<CellFocus>,
+    q_menu: Query<Entity, With<FocusPromptMenu>>,
+) {
+    *focus = CellFocus::default();
+    for entity in &q_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FocusInput>()
+            .init_resource::<CellFocus>()
+            .register_type::<CellFocus>()
+            .register_type::<FocusMarker>()
+            .register_type::<FocusPromptMenu>()
+            .add_systems(OnExit(GameState::Playing), clear_focus)
+            .add_systems(
+                Update,
+                (
+                    read_focus_input.run_if(not(editing_notes)).run_if(no_modal),
+                    apply_focus_input,
+                    show_focus,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sticks_lean_toward_one_direction() {
+        assert_eq!(stick_direction(Vec2::new(0.2, -0.1)), None);
+        assert_eq!(stick_direction(Vec2::new(0.9, 0.3)), Some(IVec2::X));
+        assert_eq!(stick_direction(Vec2::new(-0.3, -0.8)), Some(IVec2::NEG_Y));
+    }
+}
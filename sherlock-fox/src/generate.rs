@@ -0,0 +1,848 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{ops::RangeInclusive, str::FromStr};
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::{
+    distr::{weighted::WeightedIndex, Distribution},
+    seq::SliceRandom,
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clues::{
+        AdjacentColumnClue, BetweenColumnsClue, ClueKind, DynPuzzleClue, EdgeColumnClue,
+        LeftOfClue, NotInSameColumnClue, SameColumnClue,
+    },
+    puzzle::{CellLoc, CellLocIndex, LRow, Puzzle, PuzzleRow},
+    solver::{self, DeductionTier, SolveReport},
+    Tileset, PROCEDURAL_TILESET, TILESETS,
+};
+
+pub fn random_clue<R: Rng>(
+    rng: &mut R,
+    puzzle: &Puzzle,
+    config: &GeneratorConfig,
+) -> Option<DynPuzzleClue> {
+    let n_kinds = match config.version {
+        GeneratorVersion::V1 | GeneratorVersion::V2 | GeneratorVersion::V3 => 3,
+        GeneratorVersion::V4 => 4,
+        GeneratorVersion::V5 => 5,
+        GeneratorVersion::V6 => 6,
+        // Every roll past the other kinds makes an edge clue.
+        GeneratorVersion::V7 => 6 + i32::from(config.edge_clue_weight),
+        GeneratorVersion::V8 | GeneratorVersion::V9 => {
+            let weights = ClueKind::ALL.map(|kind| config.clue_weight(kind));
+            let kind = ClueKind::ALL[WeightedIndex::new(weights).ok()?.sample(rng)];
+            return random_clue_of_kind(rng, puzzle, kind);
+        }
+    };
+    // Earlier versions rolled a fixed table, with adjacent clues taking whatever rolls were left.
+    let kind = match rng.random_range(0..n_kinds) {
+        0 => ClueKind::SameColumn,
+        3 => ClueKind::DifferentColumns,
+        4 => ClueKind::LeftOf,
+        5 => ClueKind::BetweenColumns,
+        6.. => ClueKind::EdgeColumn,
+        _ => ClueKind::AdjacentColumn,
+    };
+    random_clue_of_kind(rng, puzzle, kind)
+}
+
+fn random_clue_of_kind<R: Rng>(
+    rng: &mut R,
+    puzzle: &Puzzle,
+    kind: ClueKind,
+) -> Option<DynPuzzleClue> {
+    let clue = match kind {
+        ClueKind::SameColumn => SameColumnClue::new_random(rng, puzzle)?.into(),
+        ClueKind::AdjacentColumn => AdjacentColumnClue::new_random(rng, puzzle)?.into(),
+        ClueKind::DifferentColumns => NotInSameColumnClue::new_random(rng, puzzle)?.into(),
+        ClueKind::LeftOf => LeftOfClue::new_random(rng, puzzle)?.into(),
+        ClueKind::BetweenColumns => BetweenColumnsClue::new_random(rng, puzzle)?.into(),
+        ClueKind::EdgeColumn => EdgeColumnClue::new_random(rng, puzzle)?.into(),
+    };
+    Some(clue)
+}
+
+// Starting points for `GeneratorConfig::clue_weights`; the weights themselves can still be tweaked
+// afterward, e.g. from the inspector.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum ClueMix {
+    #[default]
+    Balanced,
+    // Favors clues that pin items to columns.
+    Easy,
+    // Favors clues that only say where items aren't, or are relative to each other.
+    Hard,
+}
+
+impl ClueMix {
+    pub const ALL: [ClueMix; 3] = [ClueMix::Balanced, ClueMix::Easy, ClueMix::Hard];
+
+    pub fn weights(self) -> HashMap<ClueKind, f32> {
+        let weights = match self {
+            ClueMix::Balanced => [1., 1., 1., 1., 1., 1.],
+            ClueMix::Easy => [3., 2., 0.5, 0.5, 1., 2.],
+            ClueMix::Hard => [0.5, 1., 2., 2., 1.5, 0.5],
+        };
+        ClueKind::ALL.into_iter().zip(weights).collect()
+    }
+}
+
+impl FromStr for ClueMix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "balanced" => Ok(ClueMix::Balanced),
+            "easy" => Ok(ClueMix::Easy),
+            "hard" => Ok(ClueMix::Hard),
+            _ => Err(format!(
+                "unknown clue mix {s:?}; expected balanced, easy, or hard"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ClueMix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ClueMix::Balanced => "balanced",
+            ClueMix::Easy => "easy",
+            ClueMix::Hard => "hard",
+        })
+    }
+}
+
+// Changes to what a seed generates get a new version, and the old behavior stays selectable so
+// seeds shared from older builds still reproduce their puzzles.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Reflect, Serialize, Deserialize,
+)]
+pub enum GeneratorVersion {
+    // Row colors drawn from the puzzle's RNG, and only drawn tilesets.
+    V1,
+    // Row colors from a per-row palette seed, and the procedural tileset for leftover rows.
+    V2,
+    // Clues made redundant by later ones are pruned.
+    V3,
+    // Not-in-same-column clues join the mix.
+    V4,
+    // And so do left-of clues.
+    V5,
+    // And between-columns clues.
+    V6,
+    // And edge-column clues, weighted by `GeneratorConfig::edge_clue_weight`.
+    V7,
+    // Every kind is rolled by its weight in `GeneratorConfig::clue_weights`.
+    V8,
+    // Reveals the clues can solve the puzzle without are dropped, so every one left is needed.
+    #[default]
+    V9,
+}
+
+impl GeneratorVersion {
+    // Saves from before versioning.
+    pub fn unversioned() -> Self {
+        GeneratorVersion::V1
+    }
+}
+
+impl FromStr for GeneratorVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(GeneratorVersion::V1),
+            "2" => Ok(GeneratorVersion::V2),
+            "3" => Ok(GeneratorVersion::V3),
+            "4" => Ok(GeneratorVersion::V4),
+            "5" => Ok(GeneratorVersion::V5),
+            "6" => Ok(GeneratorVersion::V6),
+            "7" => Ok(GeneratorVersion::V7),
+            "8" => Ok(GeneratorVersion::V8),
+            "9" => Ok(GeneratorVersion::V9),
+            _ => Err(format!(
+                "unknown generator version {s:?}; expected 1 through 9"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for GeneratorVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", *self as usize + 1)
+    }
+}
+
+// Only tilesets with at least a tile per column are usable. Rows without a chosen tileset are
+// dealt the rest at random, and once those run out get the procedural tileset; None means the board
+// is too wide even for that.
+pub fn pick_tilesets<R: Rng>(rng: &mut R, config: &GeneratorConfig) -> Option<Vec<Tileset>> {
+    let picks = config.tileset_picks();
+    let mut pool = TILESETS
+        .iter()
+        .filter(|t| t.len() >= config.n_cols && !is_picked(&picks, t))
+        .cloned()
+        .collect::<Vec<_>>();
+    let n_random = picks.iter().filter(|pick| pick.is_none()).count();
+    if config.version == GeneratorVersion::V1 && pool.len() < n_random {
+        return None;
+    }
+    pool.shuffle(rng);
+    let mut pool = pool.into_iter();
+    picks
+        .into_iter()
+        .map(|pick| match pick {
+            Some(tileset) => Some(tileset.clone()),
+            None => pool.next().or_else(|| {
+                (PROCEDURAL_TILESET.len() >= config.n_cols).then(|| PROCEDURAL_TILESET.clone())
+            }),
+        })
+        .collect()
+}
+
+fn is_picked(picks: &[Option<&Tileset>], tileset: &Tileset) -> bool {
+    picks
+        .iter()
+        .flatten()
+        .any(|pick| pick.asset_path == tileset.asset_path)
+}
+
+// The tilesets a row can be given: any usable one that no other row was given.
+pub fn tileset_choices(config: &GeneratorConfig, row: usize) -> Vec<&'static Tileset> {
+    let mut picks = config.tileset_picks();
+    if let Some(pick) = picks.get_mut(row) {
+        *pick = None;
+    }
+    TILESETS
+        .iter()
+        .filter(|t| t.len() >= config.n_cols && !is_picked(&picks, t))
+        .collect()
+}
+
+// The board sizes players can pick. Smaller boards leave too little to deduce, and on bigger ones the
+// candidates get too small to click.
+pub const PUZZLE_SIZES: RangeInclusive<usize> = 4..=8;
+
+// Each reveal goes in its own row and column, so even the smallest board has room for all of them.
+pub const REVEAL_COUNTS: RangeInclusive<usize> = 0..=3;
+
+// Easier puzzles give away more to start from.
+pub fn reveals_for_tier(tier: DeductionTier) -> usize {
+    match tier {
+        DeductionTier::SingleClue => 2,
+        DeductionTier::InferencePair | DeductionTier::MultiClue => 1,
+        DeductionTier::Hypothesis => 0,
+    }
+}
+
+// The reveal count to use when none was asked for. Before version 9 that was always one, whatever
+// the tier, so seeds for those versions still make the same puzzles.
+pub fn default_reveals(version: GeneratorVersion, tier: DeductionTier) -> usize {
+    if version < GeneratorVersion::V9 {
+        1
+    } else {
+        reveals_for_tier(tier)
+    }
+}
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct GeneratorConfig {
+    pub n_rows: usize,
+    pub n_cols: usize,
+    pub max_clues: usize,
+    pub max_tier: DeductionTier,
+    // How many cells start out revealed. From version 9 this is the most, as reveals the clues
+    // don't need are dropped.
+    pub n_reveals: usize,
+    pub version: GeneratorVersion,
+    pub clue_mix: ClueMix,
+    // Relative odds of rolling each kind of clue; kinds left out are never rolled.
+    pub clue_weights: HashMap<ClueKind, f32>,
+    // How many times as likely an edge-column clue is as any one other kind, for version 7 only,
+    // which predates the weight table.
+    pub edge_clue_weight: u8,
+    // Tilesets chosen for rows by number; rows without one are dealt one at random.
+    pub row_tilesets: Vec<Option<Tileset>>,
+}
+
+impl GeneratorConfig {
+    pub fn clue_weight(&self, kind: ClueKind) -> f32 {
+        self.clue_weights.get(&kind).copied().unwrap_or(0.)
+    }
+
+    pub fn set_clue_mix(&mut self, mix: ClueMix) {
+        self.clue_mix = mix;
+        self.clue_weights = mix.weights();
+    }
+
+    // Each row's chosen tileset. Choices stay put when the board is resized, but any that became
+    // too narrow, or that repeat an earlier row's, are left to chance until it's resized back.
+    pub fn tileset_picks(&self) -> Vec<Option<&Tileset>> {
+        let mut picks = vec![];
+        for nr in 0..self.n_rows {
+            let pick = self
+                .row_tilesets
+                .get(nr)
+                .and_then(Option::as_ref)
+                .filter(|t| t.len() >= self.n_cols && !is_picked(&picks, t));
+            picks.push(pick);
+        }
+        picks
+    }
+
+    pub fn pick_tileset(&mut self, row: usize, tileset: Option<&Tileset>) {
+        if self.row_tilesets.len() <= row {
+            self.row_tilesets.resize(row + 1, None);
+        }
+        self.row_tilesets[row] = tileset.cloned();
+    }
+
+    // Whether `pick_reveals` can give every reveal a row and column of its own.
+    pub fn check_reveals(&self) -> Result<(), String> {
+        if !REVEAL_COUNTS.contains(&self.n_reveals) {
+            return Err(format!(
+                "{} reveals is outside the supported {} to {}",
+                self.n_reveals,
+                REVEAL_COUNTS.start(),
+                REVEAL_COUNTS.end()
+            ));
+        }
+        if self.n_reveals > self.n_rows.min(self.n_cols) {
+            return Err(format!(
+                "{} reveals don't fit a {}x{} board",
+                self.n_reveals, self.n_rows, self.n_cols
+            ));
+        }
+        Ok(())
+    }
+
+    // Whether the board is a size players can pick and `pick_tilesets` can fill every row of.
+    pub fn check_size(&self) -> Result<(), String> {
+        for (n, what) in [(self.n_rows, "rows"), (self.n_cols, "columns")] {
+            if !PUZZLE_SIZES.contains(&n) {
+                return Err(format!(
+                    "{n} {what} is outside the supported {} to {}",
+                    PUZZLE_SIZES.start(),
+                    PUZZLE_SIZES.end()
+                ));
+            }
+        }
+        let usable = TILESETS.iter().filter(|t| t.len() >= self.n_cols).count();
+        let procedural =
+            self.version != GeneratorVersion::V1 && PROCEDURAL_TILESET.len() >= self.n_cols;
+        if usable < self.n_rows && !procedural {
+            return Err(format!(
+                "only {usable} tilesets have {} tiles, not enough for {} rows",
+                self.n_cols, self.n_rows
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            n_rows: 5,
+            n_cols: 5,
+            max_clues: 40,
+            max_tier: DeductionTier::InferencePair,
+            n_reveals: reveals_for_tier(DeductionTier::InferencePair),
+            version: GeneratorVersion::default(),
+            clue_mix: ClueMix::default(),
+            clue_weights: ClueMix::default().weights(),
+            edge_clue_weight: 1,
+            row_tilesets: vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GeneratedPuzzle {
+    pub puzzle: Puzzle,
+    pub clues: Vec<DynPuzzleClue>,
+    pub reveals: Vec<CellLocIndex>,
+    pub report: SolveReport,
+    // How many of the reveals the clues can't solve the puzzle without.
+    pub needed_reveals: usize,
+}
+
+// Each reveal gets a row and column of its own. Returns None if there aren't enough of them.
+fn pick_reveals<R: Rng>(
+    rng: &mut R,
+    puzzle: &Puzzle,
+    n_reveals: usize,
+) -> Option<Vec<CellLocIndex>> {
+    if n_reveals > puzzle.n_rows().min(puzzle.iter_cols().count()) {
+        return None;
+    }
+    let mut reveals = Vec::<CellLocIndex>::new();
+    while reveals.len() < n_reveals {
+        let loc = CellLoc {
+            row: puzzle.random_row(rng),
+            col: puzzle.random_column(rng),
+        };
+        if reveals
+            .iter()
+            .any(|r| r.loc.row == loc.row || r.loc.col == loc.col)
+        {
+            continue;
+        }
+        reveals.push(puzzle.answer_at(loc).decay_to_ind());
+    }
+    Some(reveals)
+}
+
+// Solving without each reveal in turn shows which ones the clues lean on.
+pub fn count_needed_reveals(
+    puzzle: &Puzzle,
+    reveals: &[CellLocIndex],
+    clues: &[DynPuzzleClue],
+    max_tier: DeductionTier,
+) -> usize {
+    (0..reveals.len())
+        .filter(|&nr| {
+            let mut others = reveals.to_vec();
+            others.remove(nr);
+            !solver::solve_fresh(puzzle, &others, clues, max_tier).solved
+        })
+        .count()
+}
+
+// Drops each reveal that the clues can still solve the puzzle without, like `prune_clues`.
+pub fn prune_reveals(
+    puzzle: &Puzzle,
+    reveals: &mut Vec<CellLocIndex>,
+    clues: &[DynPuzzleClue],
+    max_tier: DeductionTier,
+) {
+    let mut nr = 0;
+    while nr < reveals.len() {
+        let removed = reveals.remove(nr);
+        if solver::solve_fresh(puzzle, reveals, clues, max_tier).solved {
+            continue;
+        }
+        reveals.insert(nr, removed);
+        nr += 1;
+    }
+}
+
+// Drops each clue that the rest can still solve the puzzle without. Clues are tried from the first
+// added, since the earliest random clues are the ones most likely to be covered by later ones.
+pub fn prune_clues(
+    puzzle: &Puzzle,
+    reveals: &[CellLocIndex],
+    clues: &mut Vec<DynPuzzleClue>,
+    max_tier: DeductionTier,
+) {
+    let mut nr = 0;
+    while nr < clues.len() {
+        let removed = clues.remove(nr);
+        if solver::solve_fresh(puzzle, reveals, clues.iter(), max_tier).solved {
+            continue;
+        }
+        clues.insert(nr, removed);
+        nr += 1;
+    }
+}
+
+// Builds a puzzle without any display assets and adds random clues until the solver can finish it
+// from the revealed cell using reasoning no harder than `max_tier`. Returns None if `max_clues`
+// weren't enough.
+pub fn generate_puzzle<R: Rng>(rng: &mut R, config: &GeneratorConfig) -> Option<GeneratedPuzzle> {
+    if config.n_rows < 2 || config.n_cols < 2 {
+        return None;
+    }
+    let palette_seed = match config.version {
+        GeneratorVersion::V1 => None,
+        _ => Some(rng.random()),
+    };
+    let mut puzzle = Puzzle::default();
+    for (nr, tileset) in pick_tilesets(rng, config)?.into_iter().enumerate() {
+        let colors = match palette_seed {
+            Some(palette_seed) => crate::row_colors(config.n_cols, palette_seed, LRow(nr)),
+            None => crate::random_colors(config.n_cols, rng),
+        };
+        puzzle.add_row(PuzzleRow::new_shuffled(
+            rng,
+            config.n_cols,
+            &tileset,
+            colors,
+            Handle::default(),
+            Handle::default(),
+        ));
+    }
+    // A single reveal is drawn the same way it was before the count was configurable, so seeds
+    // from then still reproduce.
+    let mut reveals = pick_reveals(rng, &puzzle, config.n_reveals)?;
+
+    // Adding a clue only ever adds deductions, so the scratch state can keep solving from where the
+    // previous clue set got stuck.
+    let mut scratch = puzzle.clone();
+    solver::reveal(&mut scratch, &reveals).expect("reveals are picked from the puzzle");
+    let mut clues = Vec::<DynPuzzleClue>::new();
+    let mut attempts = 0;
+    while clues.len() < config.max_clues && attempts < config.max_clues * 10 {
+        attempts += 1;
+        let Some(clue) = random_clue(rng, &puzzle, config) else {
+            continue;
+        };
+        if clue.validate(&puzzle, &clues).is_err() {
+            continue;
+        }
+        clues.push(clue);
+        if solver::solve_up_to(&mut scratch, &clues, config.max_tier).solved {
+            if config.version >= GeneratorVersion::V3 {
+                prune_clues(&puzzle, &reveals, &mut clues, config.max_tier);
+            }
+            if config.version >= GeneratorVersion::V9 {
+                prune_reveals(&puzzle, &mut reveals, &clues, config.max_tier);
+            }
+            let report = solver::solve_fresh(&puzzle, &reveals, &clues, config.max_tier);
+            // Every solver deduction is sound, so reaching the answer from the clues means the
+            // clues allow no other answer.
+            debug_assert!(
+                report.solved,
+                "clues only solved from a partial state: {report:?}"
+            );
+            let needed_reveals = count_needed_reveals(&puzzle, &reveals, &clues, config.max_tier);
+            return Some(GeneratedPuzzle {
+                puzzle,
+                clues,
+                reveals,
+                report,
+                needed_reveals,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+    use itertools::Itertools;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::puzzle::test_puzzle;
+
+    #[test]
+    fn weights_pick_only_weighted_kinds() {
+        let puzzle = test_puzzle(3, 5);
+        let config = GeneratorConfig {
+            clue_weights: [(ClueKind::LeftOf, 1.), (ClueKind::EdgeColumn, 2.)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let kinds = (0..50)
+            .filter_map(|_| random_clue(&mut rng, &puzzle, &config))
+            .map(|clue| clue.kind())
+            .collect::<Vec<_>>();
+        assert!(kinds.contains(&ClueKind::LeftOf));
+        assert!(kinds.contains(&ClueKind::EdgeColumn));
+        assert!(kinds
+            .iter()
+            .all(|&kind| kind == ClueKind::LeftOf || kind == ClueKind::EdgeColumn));
+    }
+
+    #[test]
+    fn version_7_ignores_the_clue_mix() {
+        let puzzle = test_puzzle(3, 5);
+        let kinds = |mix| {
+            let mut config = GeneratorConfig {
+                version: GeneratorVersion::V7,
+                ..Default::default()
+            };
+            config.set_clue_mix(mix);
+            let mut rng = ChaCha8Rng::seed_from_u64(0);
+            (0..50)
+                .filter_map(|_| random_clue(&mut rng, &puzzle, &config))
+                .map(|clue| clue.to_saved().to_parts())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kinds(ClueMix::Easy), kinds(ClueMix::Balanced));
+        assert_eq!(kinds(ClueMix::Hard), kinds(ClueMix::Balanced));
+    }
+
+    #[test]
+    fn no_weights_rolls_nothing() {
+        let puzzle = test_puzzle(3, 5);
+        let mut config = GeneratorConfig::default();
+        config.clue_weights.clear();
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(random_clue(&mut rng, &puzzle, &config).is_none());
+    }
+
+    #[test]
+    fn sizes_are_checked_against_the_range_and_tilesets() {
+        let sized = |n_rows, n_cols, version| GeneratorConfig {
+            n_rows,
+            n_cols,
+            version,
+            ..Default::default()
+        };
+        for n in PUZZLE_SIZES {
+            assert_eq!(
+                sized(n, n, GeneratorVersion::default()).check_size(),
+                Ok(())
+            );
+        }
+        assert!(sized(3, 5, GeneratorVersion::default())
+            .check_size()
+            .is_err());
+        assert!(sized(5, 9, GeneratorVersion::default())
+            .check_size()
+            .is_err());
+        // Before the procedural tileset, only two tilesets were wide enough for eight columns.
+        assert!(sized(8, 8, GeneratorVersion::V1).check_size().is_err());
+    }
+
+    #[test]
+    fn chosen_tilesets_keep_their_rows() {
+        let by_path = |path| Tileset::by_asset_path(path).unwrap();
+        let mut config = GeneratorConfig {
+            n_rows: 4,
+            n_cols: 6,
+            ..Default::default()
+        };
+        config.pick_tileset(1, Some(by_path("letters.png")));
+        config.pick_tileset(3, Some(by_path("foods.png")));
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let tilesets = pick_tilesets(&mut rng, &config).unwrap();
+        let paths = tilesets.iter().map(|t| t.asset_path).collect::<Vec<_>>();
+        assert_eq!(paths[1], "letters.png");
+        assert_eq!(paths[3], "foods.png");
+        assert_eq!(paths.iter().collect::<HashSet<_>>().len(), 4);
+        // Another row can't take a tileset that's already been chosen.
+        assert!(!tileset_choices(&config, 0)
+            .iter()
+            .any(|t| t.asset_path == "letters.png"));
+        assert!(tileset_choices(&config, 1)
+            .iter()
+            .any(|t| t.asset_path == "letters.png"));
+
+        // Letters are too narrow for seven columns, so that row goes back to chance.
+        config.n_cols = 7;
+        assert!(config.tileset_picks()[1].is_none());
+        let tilesets = pick_tilesets(&mut rng, &config).unwrap();
+        assert!(tilesets.iter().all(|t| t.asset_path != "letters.png"));
+        assert_eq!(tilesets[3].asset_path, "foods.png");
+    }
+
+    #[test]
+    fn reveals_are_spread_out_and_checked() {
+        for n_reveals in REVEAL_COUNTS {
+            let config = GeneratorConfig {
+                n_rows: 4,
+                n_cols: 4,
+                n_reveals,
+                ..Default::default()
+            };
+            assert_eq!(config.check_reveals(), Ok(()));
+            let mut rng = ChaCha8Rng::seed_from_u64(n_reveals as u64);
+            let generated = (0..10)
+                .find_map(|_| generate_puzzle(&mut rng, &config))
+                .unwrap();
+            let reveals = &generated.reveals;
+            assert!(reveals.len() <= n_reveals);
+            for (nr, reveal) in reveals.iter().enumerate() {
+                assert!(reveals[..nr]
+                    .iter()
+                    .all(|r| r.loc.row != reveal.loc.row && r.loc.col != reveal.loc.col));
+            }
+            assert_eq!(generated.needed_reveals, reveals.len());
+        }
+        let too_many = GeneratorConfig {
+            n_reveals: 4,
+            ..Default::default()
+        };
+        assert!(too_many.check_reveals().is_err());
+    }
+
+    #[test]
+    fn only_newer_versions_default_reveals_by_tier() {
+        use DeductionTier::*;
+        for tier in [SingleClue, InferencePair, MultiClue, Hypothesis] {
+            assert_eq!(default_reveals(GeneratorVersion::V8, tier), 1);
+            assert_eq!(
+                default_reveals(GeneratorVersion::V9, tier),
+                reveals_for_tier(tier)
+            );
+        }
+        assert_eq!(default_reveals(GeneratorVersion::V9, SingleClue), 2);
+        assert_eq!(default_reveals(GeneratorVersion::V9, Hypothesis), 0);
+    }
+
+    #[test]
+    fn reveals_that_dont_fit_are_refused() {
+        // Past `REVEAL_COUNTS`, as the inspector allows.
+        let config = GeneratorConfig {
+            n_rows: 4,
+            n_cols: 4,
+            n_reveals: 5,
+            ..Default::default()
+        };
+        assert!(config.check_reveals().is_err());
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        assert!(generate_puzzle(&mut rng, &config).is_none());
+    }
+
+    #[test]
+    fn pruned_clues_are_all_needed() {
+        // Before V3 the clue set comes back exactly as it was when it first solved the puzzle.
+        let config = GeneratorConfig {
+            n_rows: 3,
+            n_cols: 4,
+            version: GeneratorVersion::V2,
+            ..Default::default()
+        };
+        let mut n_pruned = 0;
+        for seed in 0..5 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let Some(generated) = generate_puzzle(&mut rng, &config) else {
+                continue;
+            };
+            let (puzzle, reveals) = (&generated.puzzle, &generated.reveals);
+            let mut clues = generated.clues;
+            let n_clues = clues.len();
+            prune_clues(puzzle, reveals, &mut clues, config.max_tier);
+            n_pruned += n_clues - clues.len();
+            assert!(solver::solve_fresh(puzzle, reveals, &clues, config.max_tier).solved);
+            for nr in 0..clues.len() {
+                let without = clues
+                    .iter()
+                    .enumerate()
+                    .filter(|&(other, _)| other != nr)
+                    .map(|(_, clue)| clue);
+                assert!(
+                    !solver::solve_fresh(puzzle, reveals, without, config.max_tier).solved,
+                    "seed {seed}: clue {nr} was left in but isn't needed"
+                );
+            }
+        }
+        assert!(n_pruned > 0);
+    }
+
+    // One seed's puzzle, as answers row by row, then reveals, then each clue's kind, cells and
+    // whether it's flipped.
+    fn fingerprint(generated: &GeneratedPuzzle) -> String {
+        let puzzle = &generated.puzzle;
+        let answers = puzzle
+            .iter_rows()
+            .map(|row| {
+                let row = puzzle.row_at(row);
+                row.iter_cols()
+                    .map(|col| row.answer_at(col).0.to_string())
+                    .join("")
+            })
+            .join("/");
+        let cell = |loc: CellLoc| format!("{}.{}", loc.row.0, loc.col.0);
+        let reveals = generated
+            .reveals
+            .iter()
+            .map(|r| format!("{}={}", cell(r.loc), r.index.0))
+            .join(" ");
+        let clues = generated
+            .clues
+            .iter()
+            .map(|clue| {
+                let (kind, locs, flipped) = clue.to_saved().to_parts();
+                let locs = locs.into_iter().map(cell).join(" ");
+                format!("{kind:?}({locs}){}", if flipped { "!" } else { "" })
+            })
+            .join(" ");
+        [answers, reveals, clues].join(" | ")
+    }
+
+    // Shared seeds only mean anything while every version keeps making the puzzles it did, so
+    // each version's output for one seed is pinned here.
+    #[test]
+    fn versions_keep_making_their_puzzles() {
+        let expected = [
+            (
+                GeneratorVersion::V1,
+                "3201/2103/0321/0312 | 2.1=3 | SameColumn(0.2 1.2 2.2) AdjacentColumn(3.0 3.3) \
+                 AdjacentColumn(1.2 0.1) SameColumn(0.1 1.1) SameColumn(1.1 3.1) SameColumn(3.0 \
+                 2.0) SameColumn(2.1 3.1 1.1) SameColumn(3.3 0.3) AdjacentColumn(1.2 0.3) \
+                 AdjacentColumn(3.1 0.3) AdjacentColumn(1.2 3.3) AdjacentColumn(2.2 2.3) \
+                 AdjacentColumn(1.3 3.2)",
+            ),
+            (
+                GeneratorVersion::V2,
+                "0312/2310/0312/3012 | 3.3=2 | SameColumn(3.0 1.0) AdjacentColumn(0.2 2.3) \
+                 AdjacentColumn(1.2 2.0) SameColumn(3.0 2.0) SameColumn(1.3 2.3) \
+                 AdjacentColumn(1.1 1.0) SameColumn(0.1 1.1) AdjacentColumn(1.1 1.3) \
+                 AdjacentColumn(0.3 0.0) SameColumn(1.3 3.3) AdjacentColumn(0.1 1.3) \
+                 AdjacentColumn(3.0 1.2) AdjacentColumn(1.2 0.3) AdjacentColumn(3.1 0.3) \
+                 AdjacentColumn(1.2 3.3) SameColumn(1.1 3.1) AdjacentColumn(2.2 2.3)",
+            ),
+            (
+                GeneratorVersion::V3,
+                "0312/2310/0312/3012 | 3.3=2 | SameColumn(3.0 2.0) SameColumn(1.3 2.3) \
+                 AdjacentColumn(0.3 0.0) AdjacentColumn(0.1 1.3) AdjacentColumn(3.0 1.2) \
+                 AdjacentColumn(3.1 0.3) AdjacentColumn(1.2 3.3) SameColumn(1.1 3.1) \
+                 AdjacentColumn(2.2 2.3)",
+            ),
+            (
+                GeneratorVersion::V4,
+                "0312/2310/0312/3012 | 3.3=2 | SameColumn(0.2 1.2 2.2) DifferentColumns(0.3 3.1) \
+                 SameColumn(3.0 0.0 1.0) AdjacentColumn(1.2 3.3) AdjacentColumn(3.2 2.3) \
+                 AdjacentColumn(3.1 1.2) SameColumn(0.1 1.1 2.1)",
+            ),
+            (
+                GeneratorVersion::V5,
+                "0312/2310/0312/3012 | 3.3=2 | LeftOf(0.2 2.3) SameColumn(3.0 2.0) SameColumn(2.1 \
+                 3.1 1.1) SameColumn(3.0 0.0 1.0) AdjacentColumn(1.2 3.3) AdjacentColumn(1.1 1.3) \
+                 LeftOf(0.1 1.2)",
+            ),
+            (
+                GeneratorVersion::V6,
+                "0312/2310/0312/3012 | 3.3=2 | SameColumn(3.0 1.0) BetweenColumns(1.1 2.2 1.3)! \
+                 AdjacentColumn(2.0 0.1) SameColumn(0.3 2.3) LeftOf(1.1 0.2) AdjacentColumn(0.1 \
+                 1.3) BetweenColumns(3.1 1.2 1.3)",
+            ),
+            (
+                GeneratorVersion::V7,
+                "0312/2310/0312/3012 | 3.3=2 | BetweenColumns(0.0 2.1 3.2)! AdjacentColumn(2.2 \
+                 1.0) SameColumn(0.2 1.2 2.2) EdgeColumn(3.0) BetweenColumns(1.1 1.2 0.3) \
+                 AdjacentColumn(2.0 2.1) AdjacentColumn(1.1 3.3)",
+            ),
+            (
+                GeneratorVersion::V8,
+                "0312/2310/0312/3012 | 3.3=2 | BetweenColumns(0.0 2.1 3.2)! AdjacentColumn(2.2 \
+                 1.0) SameColumn(0.2 1.2 2.2) EdgeColumn(3.0) EdgeColumn(1.0) AdjacentColumn(2.0 \
+                 2.1) AdjacentColumn(1.1 3.3) LeftOf(0.2 0.3)",
+            ),
+            (
+                GeneratorVersion::V9,
+                "0312/2310/0312/3012 | 3.3=2 | BetweenColumns(0.0 2.1 3.2)! AdjacentColumn(2.2 \
+                 1.0) SameColumn(0.2 1.2 2.2) EdgeColumn(3.0) EdgeColumn(1.0) AdjacentColumn(2.0 \
+                 2.1) AdjacentColumn(1.1 3.3) LeftOf(0.2 0.3)",
+            ),
+        ];
+        for (version, expected) in expected {
+            let config = GeneratorConfig {
+                n_rows: 4,
+                n_cols: 4,
+                version,
+                ..Default::default()
+            };
+            let mut rng = ChaCha8Rng::seed_from_u64(2025);
+            let generated = (0..100)
+                .find_map(|_| generate_puzzle(&mut rng, &config))
+                .unwrap();
+            assert_eq!(fingerprint(&generated), expected, "{version:?}");
+        }
+    }
+}
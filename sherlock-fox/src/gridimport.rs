@@ -0,0 +1,337 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+use bevy_inspector_egui::{bevy_egui::EguiContexts, egui};
+use fixedbitset::FixedBitSet;
+
+use crate::{
+    puzzle::{CellLoc, Puzzle, PuzzleCellSelection, PuzzleValidationError},
+    toast::ShowToast,
+    undo::{UndoTree, UndoTreeLocation},
+    GameState, PendingInference, UpdateCellDisplay,
+};
+
+// A board as plain text: one line per row, cells separated by `|`, and each cell its remaining
+// candidates numbered from 1, e.g. `1345|2|12345|...`. Past nine, candidates continue with
+// letters.
+const GRID_RADIX: u32 = 36;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridImportError {
+    Rows {
+        found: usize,
+        expected: usize,
+    },
+    Columns {
+        row: usize,
+        found: usize,
+        expected: usize,
+    },
+    Candidate {
+        row: usize,
+        col: usize,
+        found: char,
+    },
+    Puzzle(PuzzleValidationError),
+}
+
+impl std::fmt::Display for GridImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridImportError::Rows { found, expected } => {
+                write!(f, "found {found} rows, but the board has {expected}")
+            }
+            GridImportError::Columns {
+                row,
+                found,
+                expected,
+            } => write!(
+                f,
+                "row {} has {found} cells, but the board has {expected}",
+                row + 1
+            ),
+            GridImportError::Candidate { row, col, found } => write!(
+                f,
+                "row {} cell {} has {found:?}, which isn't one of its candidates",
+                row + 1,
+                col + 1
+            ),
+            GridImportError::Puzzle(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for GridImportError {}
+
+pub fn board_to_grid(puzzle: &Puzzle) -> String {
+    puzzle
+        .iter_rows()
+        .map(|row| {
+            let puzzle_row = puzzle.row_at(row);
+            puzzle_row
+                .iter_cols()
+                .map(|col| {
+                    let selection = puzzle.cell_selection(CellLoc { row, col });
+                    puzzle_row
+                        .iter_indices()
+                        .filter(|&index| selection.is_enabled(index))
+                        .filter_map(|index| char::from_digit(index.0 as u32 + 1, GRID_RADIX))
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("|")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Only the candidates are taken from the text; the answers and tiles stay those of `puzzle`, which
+// has to be the same size. Pencil marks on candidates that are left are kept, and soloed cells
+// whose one candidate is left stay soloed.
+pub fn grid_to_board(text: &str, puzzle: &Puzzle) -> Result<Puzzle, GridImportError> {
+    let lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.len() != puzzle.n_rows() {
+        return Err(GridImportError::Rows {
+            found: lines.len(),
+            expected: puzzle.n_rows(),
+        });
+    }
+    let mut imported = puzzle.clone();
+    for (row, line) in puzzle.iter_rows().zip(lines) {
+        let cols = puzzle.row_at(row).iter_cols().collect::<Vec<_>>();
+        let cells = line.split('|').collect::<Vec<_>>();
+        if cells.len() != cols.len() {
+            return Err(GridImportError::Columns {
+                row: row.0,
+                found: cells.len(),
+                expected: cols.len(),
+            });
+        }
+        let width = puzzle.row_at(row).iter_indices().count();
+        for (col, cell) in cols.into_iter().zip(cells) {
+            let mut enabled = FixedBitSet::with_capacity(width);
+            for found in cell.chars().filter(|c| !c.is_whitespace()) {
+                match found.to_digit(GRID_RADIX) {
+                    Some(n) if (1..=width).contains(&(n as usize)) => {
+                        enabled.insert(n as usize - 1)
+                    }
+                    _ => {
+                        return Err(GridImportError::Candidate {
+                            row: row.0,
+                            col: col.0 as usize,
+                            found,
+                        })
+                    }
+                }
+            }
+            let loc = CellLoc { row, col };
+            let selection = match puzzle.cell_selection(loc) {
+                solo @ &PuzzleCellSelection::Solo { index, .. } if enabled.ones().eq([index.0]) => {
+                    solo.clone()
+                }
+                PuzzleCellSelection::Enabled(_, tentative) => {
+                    let mut tentative = tentative.clone();
+                    tentative.intersect_with(&enabled);
+                    PuzzleCellSelection::Enabled(enabled, tentative)
+                }
+                _ => PuzzleCellSelection::new(enabled),
+            };
+            *imported.cell_selection_mut(loc) = selection;
+        }
+    }
+    imported.validate().map_err(GridImportError::Puzzle)?;
+    Ok(imported)
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct GridImportDialog {
+    open: bool,
+    text: String,
+    error: Option<String>,
+}
+
+const GRID_IMPORT_KEY: KeyCode = KeyCode::F9;
+
+// The dialog opens with the current board already filled in, so a reported position can be made
+// by editing it, and the current one copied out.
+fn toggle_grid_import(
+    keys: Res<ButtonInput<KeyCode>>,
+    puzzle: Single<&Puzzle>,
+    mut dialog: ResMut<GridImportDialog>,
+) {
+    if !keys.just_pressed(GRID_IMPORT_KEY) {
+        return;
+    }
+    if dialog.open {
+        dialog.open = false;
+    } else {
+        *dialog = GridImportDialog {
+            open: true,
+            text: board_to_grid(&puzzle),
+            error: None,
+        };
+    }
+}
+
+// The imported board has no history to undo back through, so it becomes the root of a new undo
+// tree, and any inference still pending from before is dropped.
+fn show_grid_import(
+    mut contexts: EguiContexts,
+    mut dialog: ResMut<GridImportDialog>,
+    mut puzzle: Single<&mut Puzzle>,
+    mut tree: Single<(&mut UndoTree, &mut UndoTreeLocation)>,
+    mut pending: ResMut<PendingInference>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    if !dialog.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+    let dialog = &mut *dialog;
+    let mut open = true;
+    let mut apply = false;
+    egui::Window::new("Import board")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label("One line per row, cells separated by |, candidates numbered from 1.");
+            ui.add(
+                egui::TextEdit::multiline(&mut dialog.text)
+                    .code_editor()
+                    .desired_rows(8),
+            );
+            if let Some(error) = &dialog.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            apply = ui.button("Apply").clicked();
+        });
+    dialog.open = open;
+    if !apply {
+        return;
+    }
+    match grid_to_board(&dialog.text, &puzzle) {
+        Ok(imported) => {
+            let changed = puzzle.changed_cells(&imported);
+            let (undo_tree, undo_loc) = UndoTree::new(imported.clone());
+            **puzzle = imported;
+            let (tree, loc) = &mut *tree;
+            **tree = undo_tree;
+            **loc = undo_loc;
+            pending.action = None;
+            toast_tx.send(ShowToast::info(format!(
+                "Imported the board; {} cells changed.",
+                changed.len()
+            )));
+            update_display_tx.send_batch(changed.into_iter().map(|loc| UpdateCellDisplay { loc }));
+            dialog.open = false;
+        }
+        Err(e) => dialog.error = Some(e.to_string()),
+    }
+}
+
+fn close_grid_import(mut dialog: ResMut<GridImportDialog>) {
+    dialog.open = false;
+}
+
+pub struct GridImportPlugin;
+
+impl Plugin for GridImportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridImportDialog>()
+            .register_type::<GridImportDialog>()
+            .add_systems(OnExit(GameState::Playing), close_grid_import)
+            .add_systems(
+                Update,
+                (toggle_grid_import, show_grid_import)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle, LCol, LInd, LRow, UpdateCellIndexOperation};
+
+    #[test]
+    fn grid_round_trips_the_board() {
+        let mut puzzle = test_puzzle(3, 4);
+        let loc = CellLoc {
+            row: LRow(1),
+            col: LCol(2),
+        };
+        puzzle
+            .cell_selection_mut(loc)
+            .apply(LInd(0), UpdateCellIndexOperation::Clear);
+        let text = board_to_grid(&puzzle);
+        assert_eq!(text.lines().nth(1), Some("1234|1234|234|1234"));
+        let imported = grid_to_board(&text, &test_puzzle(3, 4)).unwrap();
+        assert_eq!(imported.changed_cells(&puzzle), vec![]);
+    }
+
+    #[test]
+    fn grid_keeps_pencil_marks_and_solos() {
+        use UpdateCellIndexOperation::*;
+        let mut puzzle = test_puzzle(3, 4);
+        let marked = puzzle.cell_selection_mut(loc(0, 1));
+        marked.apply(LInd(1), MarkTentative);
+        marked.apply(LInd(2), MarkTentative);
+        puzzle.cell_selection_mut(loc(2, 3)).apply(LInd(3), Solo);
+        let text = board_to_grid(&puzzle);
+        assert_eq!(
+            grid_to_board(&text, &puzzle)
+                .unwrap()
+                .changed_cells(&puzzle),
+            vec![]
+        );
+
+        // Taking away a marked candidate takes its mark, and the solo stays a solo.
+        let text = text.replacen("1234|1234", "1234|134", 1);
+        let imported = grid_to_board(&text, &puzzle).unwrap();
+        let marked = imported.cell_selection(loc(0, 1));
+        assert!(!marked.is_enabled(LInd(1)) && !marked.is_tentative(LInd(1)));
+        assert!(marked.is_tentative(LInd(2)));
+        assert!(matches!(
+            imported.cell_selection(loc(2, 3)),
+            PuzzleCellSelection::Solo { index: LInd(3), .. }
+        ));
+    }
+
+    #[test]
+    fn grid_rejects_the_wrong_shape() {
+        let puzzle = test_puzzle(2, 3);
+        assert_eq!(
+            grid_to_board("123|123|123", &puzzle).err(),
+            Some(GridImportError::Rows {
+                found: 1,
+                expected: 2
+            })
+        );
+        assert_eq!(
+            grid_to_board("123|123|123\n123|123", &puzzle).err(),
+            Some(GridImportError::Columns {
+                row: 1,
+                found: 2,
+                expected: 3
+            })
+        );
+        assert_eq!(
+            grid_to_board("123|14|123\n123|123|123", &puzzle).err(),
+            Some(GridImportError::Candidate {
+                row: 0,
+                col: 1,
+                found: '4'
+            })
+        );
+    }
+}
@@ -0,0 +1,174 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    cellindex::CellEntities,
+    fit::FitWithin,
+    puzzle::{CellLoc, LCol, Puzzle},
+    DisplayCell, ExplainClueComponent, ExplanationArrows, PlaceArrow, PuzzleClueComponent, NO_PICK,
+};
+
+// Clues about neighbors reason about where an item would be if it were shifted or reflected, and
+// that can be off the board: an item can't be left of something in the first column. While an
+// explanation reasons about such a place, a faint gutter column is drawn past each edge of the
+// board, so that the explanation's arrows have somewhere to point.
+const GUTTER_COLOR: Color = Color::hsla(0., 0., 1., 0.06);
+const GUTTER_POINTED_COLOR: Color = Color::hsla(50., 0.9, 0.7, 0.3);
+
+#[derive(Debug, Component, Reflect)]
+struct BoardGutter;
+
+// Where an off-board location is drawn: beside the nearest cell in its row, one cell further out
+// for each column past the edge. Returns that cell and the offset from it.
+fn gutter_placement(
+    puzzle: &Puzzle,
+    cells: &CellEntities,
+    q_cell: &Query<&FitWithin, With<DisplayCell>>,
+    loc: CellLoc,
+) -> Option<(Entity, Rect, f32)> {
+    let edge_col = loc.col.0.clamp(0, puzzle.max_column().0);
+    let edge = CellLoc {
+        row: loc.row,
+        col: LCol(edge_col),
+    };
+    let inward = CellLoc {
+        row: loc.row,
+        col: LCol(if edge_col == 0 { 1 } else { edge_col - 1 }),
+    };
+    let edge_entity = cells.background(edge)?;
+    let edge_rect = q_cell.get(edge_entity).ok()?.rect();
+    // Cells are spaced out a little more than their width.
+    let pitch = cells
+        .background(inward)
+        .and_then(|entity| q_cell.get(entity).ok())
+        .map_or(edge_rect.width(), |fit| {
+            (fit.rect().center().x - edge_rect.center().x).abs()
+        });
+    Some((
+        edge_entity,
+        edge_rect,
+        (loc.col.0 - edge_col) as f32 * pitch,
+    ))
+}
+
+fn gutter_locs(puzzle: &Puzzle, pointed: &HashSet<CellLoc>) -> Vec<CellLoc> {
+    let mut locs = puzzle
+        .iter_rows()
+        .flat_map(|row| {
+            [-1, puzzle.max_column().0 + 1].map(|col| CellLoc {
+                row,
+                col: LCol(col),
+            })
+        })
+        .collect::<HashSet<_>>();
+    locs.extend(pointed);
+    let mut locs = locs.into_iter().collect::<Vec<_>>();
+    locs.sort();
+    locs
+}
+
+fn show_explanation_gutters(
+    mut commands: Commands,
+    (puzzle, cells): (Single<&Puzzle>, Res<CellEntities>),
+    q_explanation: Query<Ref<ExplainClueComponent>>,
+    q_clues: Query<&PuzzleClueComponent>,
+    q_cell: Query<&FitWithin, With<DisplayCell>>,
+    q_gutter: Query<Entity, With<BoardGutter>>,
+    mut arrow_tx: EventWriter<PlaceArrow>,
+) {
+    let explanation = q_explanation.iter().next();
+    match &explanation {
+        Some(explanation) if !explanation.is_added() => return,
+        None if q_gutter.is_empty() => return,
+        _ => (),
+    }
+    for entity in &q_gutter {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(explanation) = explanation else {
+        return;
+    };
+    let pointed = explanation
+        .update
+        .explanation
+        .as_ref()
+        .map(|explanation| explanation.cells())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|index| !puzzle.contains_loc(index.loc) && index.loc.row.0 < puzzle.n_rows())
+        .collect::<Vec<_>>();
+    if pointed.is_empty() {
+        return;
+    }
+    let pointed_locs = pointed.iter().map(|index| index.loc).collect();
+    let mut gutter_entities = vec![];
+    for loc in gutter_locs(&puzzle, &pointed_locs) {
+        let Some((edge, rect, offset)) = gutter_placement(&puzzle, &cells, &q_cell, loc) else {
+            continue;
+        };
+        let color = if pointed_locs.contains(&loc) {
+            GUTTER_POINTED_COLOR
+        } else {
+            GUTTER_COLOR
+        };
+        let gutter = commands
+            .spawn((
+                BoardGutter,
+                Sprite::from_color(color, rect.size()),
+                Transform::from_xyz(offset, 0., 0.),
+                NO_PICK,
+            ))
+            .set_parent(edge)
+            .id();
+        gutter_entities.push((loc, gutter));
+    }
+
+    let Ok(clue) = q_clues.get(explanation.clue) else {
+        return;
+    };
+    let parent = commands
+        .spawn((
+            ExplanationArrows,
+            Transform::default(),
+            InheritedVisibility::VISIBLE,
+        ))
+        .id();
+    for index in pointed {
+        let gutter = gutter_entities.iter().find(|&&(loc, _)| loc == index.loc);
+        let from = clue.cells.get(&index.decay_column().upgrade_to_answer());
+        if let (Some(&(_, to)), Some(&from)) = (gutter, from) {
+            arrow_tx.send(PlaceArrow { parent, from, to });
+        }
+    }
+}
+
+pub struct GutterPlugin;
+
+impl Plugin for GutterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<BoardGutter>()
+            .add_systems(Update, show_explanation_gutters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle};
+
+    #[test]
+    fn gutters_run_past_both_edges() {
+        let puzzle = test_puzzle(2, 4);
+        let pointed = [loc(1, -2)].into_iter().collect();
+        assert_eq!(gutter_locs(&puzzle, &pointed), vec![
+            loc(0, -1),
+            loc(0, 4),
+            loc(1, -2),
+            loc(1, -1),
+            loc(1, 4),
+        ]);
+    }
+}
@@ -0,0 +1,187 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+// Read-only views of the board as plain data, for scripts, tests, and bridges to other front ends.
+// Nothing here holds an entity, an asset handle, or any other rendering type, and nothing writes
+// to the world, so a view can be serialized or compared as is.
+
+use std::fs;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use serde::Serialize;
+
+use crate::{
+    clues::DynPuzzleClue,
+    puzzle::{CellLoc, Puzzle},
+    toast::ShowToast,
+    DisplayCluebox, GameState, PuzzleClueComponent,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardView {
+    pub rows: Vec<RowView>,
+    // In the cluebox's order.
+    pub clues: Vec<ClueView>,
+    pub solved: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RowView {
+    pub tileset: String,
+    pub cells: Vec<CellView>,
+}
+
+// Items are numbered within their row, from 0, the same way the board numbers candidates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CellView {
+    pub candidates: Vec<usize>,
+    // Candidates the player has tentatively eliminated, which are still among `candidates`.
+    pub tentative: Vec<usize>,
+    pub answer: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClueView {
+    pub kind: String,
+    // The (row, item) pairs the clue names, in the clue's order.
+    pub items: Vec<(usize, usize)>,
+    pub description: String,
+}
+
+pub fn cell_view(puzzle: &Puzzle, loc: CellLoc) -> CellView {
+    let selection = puzzle.cell_selection(loc);
+    CellView {
+        candidates: selection.iter_ones().map(|index| index.0).collect(),
+        tentative: puzzle
+            .row_at(loc.row)
+            .iter_indices()
+            .filter(|&index| selection.is_tentative(index))
+            .map(|index| index.0)
+            .collect(),
+        answer: puzzle.answer_at(loc).index.0,
+    }
+}
+
+pub fn clue_view(puzzle: &Puzzle, clue: &DynPuzzleClue) -> ClueView {
+    let kind = format!("{:?}", clue.kind());
+    let items = clue
+        .locs()
+        .into_iter()
+        .map(|loc| (loc.row.0, puzzle.answer_at(loc).index.0))
+        .collect::<Vec<_>>();
+    let named = items
+        .iter()
+        .map(|(row, item)| format!("item {item} of row {row}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    ClueView {
+        description: format!("{kind}: {named}"),
+        kind,
+        items,
+    }
+}
+
+pub fn board_view<'c>(
+    puzzle: &Puzzle,
+    clues: impl IntoIterator<Item = &'c DynPuzzleClue>,
+) -> BoardView {
+    BoardView {
+        rows: puzzle
+            .iter_rows()
+            .map(|row| RowView {
+                tileset: puzzle.row_at(row).tileset().asset_path.to_string(),
+                cells: puzzle
+                    .iter_cols()
+                    .map(|col| cell_view(puzzle, CellLoc { row, col }))
+                    .collect(),
+            })
+            .collect(),
+        clues: clues
+            .into_iter()
+            .map(|clue| clue_view(puzzle, clue))
+            .collect(),
+        solved: puzzle.is_solved(),
+    }
+}
+
+// The board in the world, or None before a puzzle is on it.
+pub fn inspect_board(world: &mut World) -> Option<BoardView> {
+    let mut q_puzzle = world.query::<&Puzzle>();
+    let mut q_cluebox = world.query_filtered::<&Children, With<DisplayCluebox>>();
+    let mut q_clues = world.query::<&PuzzleClueComponent>();
+    let world = &*world;
+    let puzzle = q_puzzle.get_single(world).ok()?;
+    let clues = q_cluebox
+        .iter(world)
+        .flat_map(|children| children.iter())
+        .filter_map(|&entity| q_clues.get(world, entity).ok())
+        .map(|c| &c.clue);
+    Some(board_view(puzzle, clues))
+}
+
+const BOARD_VIEW_PATH: &str = "board-view.ron";
+const BOARD_VIEW_KEY: KeyCode = KeyCode::F10;
+
+fn dump_board_view(world: &mut World) {
+    let Some(view) = inspect_board(world) else {
+        return;
+    };
+    let toast = match ron::ser::to_string_pretty(&view, ron::ser::PrettyConfig::default())
+        .map_err(std::io::Error::other)
+        .and_then(|data| fs::write(BOARD_VIEW_PATH, data))
+    {
+        Ok(()) => ShowToast::info(format!("Wrote the board to {BOARD_VIEW_PATH}.")),
+        Err(e) => ShowToast::error(format!("Couldn't write {BOARD_VIEW_PATH}: {e}")),
+    };
+    world.send_event(toast);
+}
+
+pub struct InspectionPlugin;
+
+impl Plugin for InspectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            dump_board_view
+                .run_if(input_just_pressed(BOARD_VIEW_KEY))
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clues::{ClueKind, SavedClue},
+        puzzle::{loc, test_puzzle},
+    };
+
+    #[test]
+    fn board_view_reads_candidates_answers_and_clues() {
+        let puzzle = test_puzzle(2, 3);
+        let locs = [loc(0, 1), loc(1, 1)];
+        let clue =
+            DynPuzzleClue::from(SavedClue::from_parts(ClueKind::SameColumn, &locs, false).unwrap());
+        let view = board_view(&puzzle, [&clue]);
+
+        assert_eq!(view.rows.len(), 2);
+        assert!(!view.solved);
+        let cell = &view.rows[1].cells[1];
+        assert_eq!(cell.candidates, vec![0, 1, 2]);
+        assert!(cell.tentative.is_empty());
+        assert_eq!(cell.answer, puzzle.answer_at(locs[1]).index.0);
+
+        let answers = locs.map(|loc| puzzle.answer_at(loc).index.0);
+        assert_eq!(view.clues[0].kind, "SameColumn");
+        assert_eq!(view.clues[0].items, vec![(0, answers[0]), (1, answers[1])]);
+        assert_eq!(
+            view.clues[0].description,
+            format!(
+                "SameColumn: item {} of row 0, item {} of row 1",
+                answers[0], answers[1]
+            )
+        );
+    }
+}
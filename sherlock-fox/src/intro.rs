@@ -0,0 +1,372 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::BoardCamera,
+    crossref::CrossRefIndex,
+    palette::Swatch,
+    puzzle::{CellLocIndex, Puzzle},
+    settings::SizedText,
+    ThemedPanel, NO_PICK, PANEL_PADDING,
+};
+
+// Sent for each cell the puzzle starts out revealing, so the player sees what they were given.
+#[derive(Event, Debug, Clone, Copy, Reflect)]
+pub struct IntroduceReveal {
+    pub index: CellLocIndex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+enum IntroStage {
+    ZoomIn,
+    Callout,
+    ZoomOut,
+}
+
+impl IntroStage {
+    fn duration(self) -> Duration {
+        match self {
+            IntroStage::ZoomIn | IntroStage::ZoomOut => Duration::from_millis(800),
+            IntroStage::Callout => Duration::from_millis(2500),
+        }
+    }
+}
+
+#[derive(Debug, Reflect)]
+struct IntroStep {
+    index: CellLocIndex,
+    stage: IntroStage,
+    timer: Timer,
+    // Where the camera zooms to, kept up to date while the board is still settling.
+    target: Vec2,
+    callout: Option<Entity>,
+}
+
+impl IntroStep {
+    fn enter(&mut self, stage: IntroStage) {
+        self.stage = stage;
+        self.timer = Timer::new(stage.duration(), TimerMode::Once);
+    }
+
+    fn despawn_callout(&mut self, commands: &mut Commands) {
+        if let Some(callout) = self.callout.take() {
+            commands.entity(callout).despawn_recursive();
+        }
+    }
+
+    // Zoom back out from wherever the camera got to; the easing is symmetric, so that's as far
+    // from the end of zooming out as it was into zooming in.
+    fn zoom_out_early(&mut self, commands: &mut Commands) {
+        let zoomed_in = match self.stage {
+            IntroStage::ZoomIn => self.timer.fraction(),
+            IntroStage::Callout => 1.,
+            IntroStage::ZoomOut => return,
+        };
+        self.despawn_callout(commands);
+        self.enter(IntroStage::ZoomOut);
+        let elapsed = self.timer.duration().mul_f32(1. - zoomed_in);
+        self.timer.set_elapsed(elapsed);
+    }
+}
+
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+pub struct RevealIntro {
+    queue: VecDeque<CellLocIndex>,
+    current: Option<IntroStep>,
+}
+
+#[derive(Reflect, Debug, Component)]
+struct RevealCallout;
+
+// How much of the board stays in view when zoomed all the way in on the revealed cell.
+const INTRO_ZOOM: f32 = 0.35;
+
+const SKIP_INTRO_KEY: KeyCode = KeyCode::Escape;
+
+// Escape skips the intro instead of pausing while it runs.
+pub fn intro_running(intro: Res<RevealIntro>) -> bool {
+    intro.current.is_some()
+}
+
+fn queue_reveal_intros(mut ev_rx: EventReader<IntroduceReveal>, mut intro: ResMut<RevealIntro>) {
+    intro.queue.extend(ev_rx.read().map(|ev| ev.index));
+}
+
+fn spawn_callout(commands: &mut Commands, puzzle: &Puzzle, index: CellLocIndex) -> Entity {
+    let row = puzzle.row_at(index.loc.row);
+    commands
+        .spawn((
+            RevealCallout,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(12.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            NO_PICK,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::all(PANEL_PADDING),
+                        column_gap: Val::Px(10.),
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    ThemedPanel::default(),
+                    NO_PICK,
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new("You are given:"), SizedText, NO_PICK));
+                    panel
+                        .spawn((
+                            Node {
+                                width: Val::Px(48.),
+                                height: Val::Px(48.),
+                                padding: UiRect::all(Val::Px(4.)),
+                                ..Default::default()
+                            },
+                            BackgroundColor(row.display_color(index.index)),
+                            Swatch::new(index),
+                            NO_PICK,
+                        ))
+                        .with_child((
+                            row.display_image_node(index.index),
+                            Node {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                ..Default::default()
+                            },
+                            NO_PICK,
+                        ));
+                });
+        })
+        .id()
+}
+
+fn run_reveal_intro(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut intro: ResMut<RevealIntro>,
+    crossref: Res<CrossRefIndex>,
+    q_transform: Query<&GlobalTransform>,
+    q_puzzle: Query<&Puzzle>,
+    camera: Single<(&mut Transform, &mut OrthographicProjection), With<BoardCamera>>,
+) {
+    let (mut camera_transform, mut projection) = camera.into_inner();
+    let intro = &mut *intro;
+    if intro.current.is_none() {
+        let Some(index) = intro.queue.pop_front() else {
+            return;
+        };
+        intro.current = Some(IntroStep {
+            index,
+            stage: IntroStage::ZoomIn,
+            timer: Timer::new(IntroStage::ZoomIn.duration(), TimerMode::Once),
+            target: Vec2::ZERO,
+            callout: None,
+        });
+    }
+    let Some(step) = &mut intro.current else {
+        unreachable!()
+    };
+
+    // The board can go away mid-intro, like on a restart; there's nothing left to point at then.
+    let button = crossref
+        .button(step.index)
+        .and_then(|entity| q_transform.get(entity).ok());
+    match button {
+        Some(transform) if step.stage != IntroStage::ZoomOut => {
+            step.target = transform.translation().truncate();
+        }
+        None => step.zoom_out_early(&mut commands),
+        _ => (),
+    }
+
+    step.timer.tick(time.delta());
+    let eased =
+        EasingCurve::new(0., 1., EaseFunction::CubicInOut).sample_clamped(step.timer.fraction());
+    let zoomed = match step.stage {
+        IntroStage::ZoomIn => eased,
+        IntroStage::Callout => 1.,
+        IntroStage::ZoomOut => 1. - eased,
+    };
+    let translation = Vec2::ZERO.lerp(step.target, zoomed);
+    camera_transform.translation.x = translation.x;
+    camera_transform.translation.y = translation.y;
+    projection.scale = 1f32.lerp(INTRO_ZOOM, zoomed);
+
+    if !step.timer.finished() {
+        return;
+    }
+    match step.stage {
+        IntroStage::ZoomIn => {
+            step.callout = q_puzzle
+                .get_single()
+                .ok()
+                .map(|puzzle| spawn_callout(&mut commands, puzzle, step.index));
+            step.enter(IntroStage::Callout);
+        }
+        IntroStage::Callout => {
+            step.despawn_callout(&mut commands);
+            step.enter(IntroStage::ZoomOut);
+        }
+        IntroStage::ZoomOut => intro.current = None,
+    }
+}
+
+// Skipping drops the rest of the queue too, and zooms back out rather than jumping.
+fn skip_reveal_intro(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut intro: ResMut<RevealIntro>,
+) {
+    if !keys.just_pressed(SKIP_INTRO_KEY) {
+        return;
+    }
+    intro.queue.clear();
+    if let Some(step) = &mut intro.current {
+        step.zoom_out_early(&mut commands);
+    }
+}
+
+pub struct RevealIntroPlugin;
+
+impl Plugin for RevealIntroPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<IntroduceReveal>()
+            .init_resource::<RevealIntro>()
+            .register_type::<RevealCallout>()
+            .register_type::<RevealIntro>()
+            .add_systems(
+                Update,
+                (
+                    queue_reveal_intros,
+                    skip_reveal_intro.run_if(intro_running),
+                    run_reveal_intro,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::{
+        crossref::CrossRefPlugin,
+        puzzle::{loc, test_puzzle, LInd},
+        DisplayCellButton,
+    };
+
+    const TARGET: Vec2 = Vec2::new(120., -60.);
+
+    // A board with one button to zoom in on, and the frames ticking over a tenth of a second each.
+    fn board() -> (App, CellLocIndex) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((CrossRefPlugin, RevealIntroPlugin))
+            .init_resource::<ButtonInput<KeyCode>>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )));
+        app.world_mut().spawn(test_puzzle(2, 3));
+        app.world_mut().spawn((
+            BoardCamera,
+            Transform::default(),
+            OrthographicProjection::default_2d(),
+        ));
+        let index = CellLocIndex {
+            loc: loc(1, 2),
+            index: LInd(0),
+        };
+        app.world_mut().spawn((
+            DisplayCellButton { index },
+            GlobalTransform::from_translation(TARGET.extend(0.)),
+        ));
+        (app, index)
+    }
+
+    fn camera(app: &mut App) -> (Vec2, f32) {
+        let (transform, projection) = app
+            .world_mut()
+            .query_filtered::<(&Transform, &OrthographicProjection), With<BoardCamera>>()
+            .single(app.world());
+        (transform.translation.truncate(), projection.scale)
+    }
+
+    fn callouts(app: &mut App) -> usize {
+        app.world_mut()
+            .query_filtered::<(), With<RevealCallout>>()
+            .iter(app.world())
+            .count()
+    }
+
+    fn running(app: &App) -> bool {
+        app.world().resource::<RevealIntro>().current.is_some()
+    }
+
+    #[test]
+    fn the_intro_zooms_in_calls_out_and_zooms_back() {
+        let (mut app, index) = board();
+        app.world_mut().send_event(IntroduceReveal { index });
+        let mut frames = 0;
+        while callouts(&mut app) == 0 {
+            app.update();
+            frames += 1;
+            assert!(frames < 20, "never called out the reveal");
+        }
+        let (translation, scale) = camera(&mut app);
+        assert_eq!(translation, TARGET);
+        assert!((scale - INTRO_ZOOM).abs() < 1e-6);
+        while running(&app) {
+            app.update();
+            frames += 1;
+            assert!(frames < 60, "never zoomed back out");
+        }
+        assert_eq!(callouts(&mut app), 0);
+        assert_eq!(camera(&mut app), (Vec2::ZERO, 1.));
+    }
+
+    #[test]
+    fn escape_skips_the_rest_of_the_intro() {
+        let (mut app, index) = board();
+        app.world_mut().send_event(IntroduceReveal { index });
+        app.world_mut().send_event(IntroduceReveal { index });
+        for _ in 0..4 {
+            app.update();
+        }
+        let (_, zoomed_in) = camera(&mut app);
+        assert!(zoomed_in < 1.);
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(SKIP_INTRO_KEY);
+        app.update();
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+        let (_, scale) = camera(&mut app);
+        assert!(scale > zoomed_in, "didn't start zooming back out");
+        let mut frames = 0;
+        while running(&app) {
+            app.update();
+            frames += 1;
+            assert!(frames < 10, "took as long as watching it");
+        }
+        assert_eq!(callouts(&mut app), 0);
+        assert_eq!(camera(&mut app), (Vec2::ZERO, 1.));
+        // The second reveal was skipped along with the first.
+        app.update();
+        assert!(!running(&app));
+    }
+}
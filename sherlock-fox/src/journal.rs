@@ -0,0 +1,345 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    ui::RelativeCursorPosition,
+};
+
+use crate::{
+    analytics::ClueDeductionApplied,
+    crossref::CrossRefIndex,
+    fit::FitClickedEvent,
+    hint_hilight,
+    operations::OperationTheme,
+    puzzle::CellLocIndex,
+    toast::ShowToast,
+    undo::{Action, PushNewAction},
+    DisplayCluebox, ExplanationHilight, GameState, HintHilight, TopButtonAction, UpdateCellIndex,
+    NO_PICK,
+};
+
+// Every action applied to the puzzle, in order, including ones that were later undone.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct DeductionLog {
+    pub entries: Vec<Action>,
+}
+
+impl DeductionLog {
+    pub fn to_markdown(&self, theme: &OperationTheme) -> String {
+        let mut out = String::from("# Deduction log\n\n");
+        for (n, action) in self.entries.iter().enumerate() {
+            let UpdateCellIndex {
+                index,
+                op,
+                explanation,
+                ..
+            } = &action.update;
+            let reason = match explanation {
+                Some(explanation) => explanation.to_text(),
+                None => "Chosen by hand.".to_string(),
+            };
+            writeln!(
+                out,
+                "{}. **{}** {index}: {reason} ({} updated, {} inferred)",
+                n + 1,
+                theme.label(*op),
+                action.update_count,
+                action.inferred_count,
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+fn record_deductions(mut ev_rx: EventReader<PushNewAction>, mut log: ResMut<DeductionLog>) {
+    log.entries.extend(ev_rx.read().map(|ev| ev.action.clone()));
+}
+
+fn export_deduction_log(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    log: Res<DeductionLog>,
+    theme: Res<OperationTheme>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        let TopButtonAction::Export = action else {
+            continue;
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let path = format!("journal-{timestamp}.md");
+        let n = log.entries.len();
+        let plural = if n == 1 { "" } else { "s" };
+        let toast = match std::fs::write(&path, log.to_markdown(&theme)) {
+            Ok(()) => ShowToast::info(format!("Wrote {n} deduction{plural} to {path}.")),
+            Err(e) => ShowToast::error(format!("Couldn't write {path}: {e}")),
+        };
+        toast_tx.send(toast);
+    }
+}
+
+// Every clue deduction the player went along with, so how the puzzle got to where it is can be
+// reviewed after the explanations are gone. Like `DeductionLog`, undoing doesn't remove entries.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ClueLog {
+    pub entries: Vec<ClueLogEntry>,
+    shown: bool,
+}
+
+#[derive(Debug, Clone, Reflect)]
+pub struct ClueLogEntry {
+    pub clue: Entity,
+    pub update: UpdateCellIndex,
+}
+
+impl ClueLogEntry {
+    // The clue's reasoning, then the candidate it changed.
+    fn cells(&self) -> Vec<CellLocIndex> {
+        let mut cells = self
+            .update
+            .explanation
+            .as_ref()
+            .map(|explanation| explanation.cells())
+            .unwrap_or_default();
+        cells.push(self.update.index);
+        cells
+    }
+
+    fn label(&self, n: usize, clue_nr: Option<usize>, theme: &OperationTheme) -> String {
+        let UpdateCellIndex {
+            index,
+            op,
+            explanation,
+            ..
+        } = &self.update;
+        let clue = match clue_nr {
+            Some(nr) => format!("Clue {}", nr + 1),
+            None => "A removed clue".to_string(),
+        };
+        let reason = explanation
+            .as_ref()
+            .map(|explanation| explanation.to_text())
+            .unwrap_or_default();
+        format!("{}. {clue}: {} {index}\n{reason}", n + 1, theme.label(*op))
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ClueLogPanel;
+
+#[derive(Debug, Component, Reflect)]
+struct ClueLogRow(usize);
+
+const LOG_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const LOG_ROW_COLOR: Color = Color::hsla(0., 0., 0.2, 1.);
+const LOG_SCROLL_LINE: f32 = 20.;
+
+fn record_clue_log(mut ev_rx: EventReader<ClueDeductionApplied>, mut log: ResMut<ClueLog>) {
+    log.entries.extend(ev_rx.read().map(|ev| ClueLogEntry {
+        clue: ev.clue,
+        update: ev.update.clone(),
+    }));
+}
+
+fn toggle_clue_log(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut log: ResMut<ClueLog>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if let TopButtonAction::Log = action {
+            log.shown = !log.shown;
+        }
+    }
+}
+
+fn rebuild_clue_log(
+    mut commands: Commands,
+    log: Res<ClueLog>,
+    theme: Res<OperationTheme>,
+    q_cluebox: Query<&Children, With<DisplayCluebox>>,
+    q_panel: Query<Entity, With<ClueLogPanel>>,
+) {
+    if !log.is_changed() && !theme.is_changed() {
+        return;
+    }
+    for entity in &q_panel {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !log.shown {
+        return;
+    }
+    let cluebox = q_cluebox.get_single().ok();
+    commands
+        .spawn((
+            ClueLogPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.),
+                left: Val::Px(10.),
+                width: Val::Px(360.),
+                max_height: Val::Percent(60.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.),
+                padding: UiRect::all(Val::Px(5.)),
+                overflow: Overflow::scroll_y(),
+                ..Default::default()
+            },
+            BackgroundColor(LOG_BACKGROUND_COLOR),
+            RelativeCursorPosition::default(),
+        ))
+        .with_children(|parent| {
+            let title = if log.entries.is_empty() {
+                "No clue deductions yet"
+            } else {
+                "Click a deduction to highlight its clue and cells"
+            };
+            parent.spawn((Text::new(title), NO_PICK));
+            for (n, entry) in log.entries.iter().enumerate() {
+                let clue_nr = cluebox.and_then(|c| c.iter().position(|&e| e == entry.clue));
+                parent
+                    .spawn((
+                        ClueLogRow(n),
+                        Node {
+                            padding: UiRect::axes(Val::Px(5.), Val::Px(2.)),
+                            flex_shrink: 0.,
+                            ..Default::default()
+                        },
+                        BackgroundColor(LOG_ROW_COLOR),
+                    ))
+                    .with_child((
+                        Text::new(entry.label(n, clue_nr, &theme)),
+                        TextFont {
+                            font_size: 14.,
+                            ..Default::default()
+                        },
+                        TextColor(theme.color(entry.update.op)),
+                        NO_PICK,
+                    ));
+            }
+        });
+}
+
+// UI nodes don't scroll by themselves; the wheel moves whichever log panel is under the cursor.
+fn scroll_clue_log(
+    mut wheel_rx: EventReader<MouseWheel>,
+    mut q_panel: Query<(&mut ScrollPosition, &RelativeCursorPosition), With<ClueLogPanel>>,
+) {
+    for wheel in wheel_rx.read() {
+        let dy = match wheel.unit {
+            MouseScrollUnit::Line => wheel.y * LOG_SCROLL_LINE,
+            MouseScrollUnit::Pixel => wheel.y,
+        };
+        for (mut scroll, cursor) in &mut q_panel {
+            if cursor.mouse_over() {
+                scroll.offset_y = (scroll.offset_y - dy).max(0.);
+            }
+        }
+    }
+}
+
+fn clue_log_row_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_row: Query<&ClueLogRow>,
+    log: Res<ClueLog>,
+    crossref: Res<CrossRefIndex>,
+    q_hint: Query<Entity, With<HintHilight>>,
+    mut commands: Commands,
+) {
+    let Some(entry) = q_row
+        .get(ev.entity())
+        .ok()
+        .and_then(|&ClueLogRow(n)| log.entries.get(n))
+    else {
+        return;
+    };
+    for entity in &q_hint {
+        commands
+            .entity(entity)
+            .remove::<(ExplanationHilight, HintHilight)>();
+    }
+    hint_hilight(&mut commands, &crossref, entry.clue, entry.cells());
+}
+
+// The logs are of the puzzle that was left, so the next one starts them over.
+fn clear_logs(mut deductions: ResMut<DeductionLog>, mut clue_log: ResMut<ClueLog>) {
+    *deductions = DeductionLog::default();
+    *clue_log = ClueLog::default();
+}
+
+pub struct JournalPlugin;
+
+impl Plugin for JournalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClueLog>()
+            .init_resource::<DeductionLog>()
+            .register_type::<ClueLog>()
+            .register_type::<ClueLogPanel>()
+            .register_type::<ClueLogRow>()
+            .register_type::<DeductionLog>()
+            .add_observer(clue_log_row_clicked)
+            .add_systems(OnExit(GameState::Playing), clear_logs)
+            .add_systems(
+                Update,
+                (
+                    record_deductions,
+                    export_deduction_log,
+                    (record_clue_log, toggle_clue_log, rebuild_clue_log).chain(),
+                    scroll_clue_log,
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle};
+
+    #[test]
+    fn the_log_lists_every_move_in_order() {
+        let puzzle = test_puzzle(2, 3);
+        let answer = puzzle.answer_at(loc(0, 1)).decay_to_ind();
+        let other = puzzle.answer_at(loc(1, 2)).decay_to_ind();
+        let log = DeductionLog {
+            entries: vec![
+                Action {
+                    update: answer.as_solo(),
+                    update_count: 3,
+                    inferred_count: 2,
+                    joined: vec![],
+                },
+                Action {
+                    update: other.as_clear(),
+                    update_count: 1,
+                    inferred_count: 0,
+                    joined: vec![],
+                },
+            ],
+        };
+        let theme = OperationTheme::default();
+        let markdown = log.to_markdown(&theme);
+        assert_eq!(markdown.lines().collect::<Vec<_>>(), [
+            "# Deduction log",
+            "",
+            &format!("1. **! Solo** {answer}: Chosen by hand. (3 updated, 2 inferred)"),
+            &format!("2. **x Clear** {other}: Chosen by hand. (1 updated, 0 inferred)"),
+        ]);
+        assert_eq!(
+            DeductionLog::default().to_markdown(&theme),
+            "# Deduction log\n\n"
+        );
+    }
+}
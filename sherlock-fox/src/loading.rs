@@ -0,0 +1,471 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{
+    f32::consts::TAU,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use bevy::{
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+    time::Stopwatch,
+};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    autosave::{self, AUTOSAVE_DIR},
+    fit::FitClickedEvent,
+    generate::{self, ClueMix, GeneratedPuzzle, GeneratorConfig},
+    notes::PuzzleNotes,
+    save::{PuzzleMetadata, SavedPuzzle, SAVE_GAME_PATH},
+    solver::DeductionTier,
+    toast::ShowToast,
+    GameState, PuzzleSeed, PuzzleSpawn, SeededRng, TopButtonAction, NO_PICK,
+};
+
+#[derive(Resource)]
+struct PuzzleGeneration {
+    seed: u64,
+    config: GeneratorConfig,
+    task: Task<Option<GeneratedPuzzle>>,
+    attempts: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    elapsed: Stopwatch,
+}
+
+// The seed and config the puzzle in play was generated from; None for saved and imported puzzles.
+#[derive(Debug, Default, Resource)]
+pub struct GeneratedFrom(pub Option<(u64, GeneratorConfig)>);
+
+#[derive(Reflect, Debug, Component)]
+struct LoadingScreen;
+
+#[derive(Reflect, Debug, Component)]
+struct LoadingSpinner;
+
+#[derive(Reflect, Debug, Component)]
+struct LoadingProgress;
+
+#[derive(Reflect, Debug, Component)]
+pub struct ConfigScreen;
+
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfigField {
+    Rows,
+    Cols,
+    Tier,
+    Reveals,
+    ClueMix,
+}
+
+impl ConfigField {
+    fn describe(&self, config: &GeneratorConfig) -> String {
+        match self {
+            ConfigField::Rows => format!("Rows: {}", config.n_rows),
+            ConfigField::Cols => format!("Columns: {}", config.n_cols),
+            ConfigField::Tier => format!("Hardest deduction: {:?}", config.max_tier),
+            ConfigField::Reveals => format!("Revealed cells: up to {}", config.n_reveals),
+            ConfigField::ClueMix => format!("Clue mix: {:?}", config.clue_mix),
+        }
+    }
+
+    fn step(&self, config: &mut GeneratorConfig, delta: isize) {
+        match self {
+            // Sizes only step to boards that can be generated, so the menu can't offer a bad one.
+            ConfigField::Rows | ConfigField::Cols => {
+                let mut stepped = config.clone();
+                let n = match self {
+                    ConfigField::Rows => &mut stepped.n_rows,
+                    _ => &mut stepped.n_cols,
+                };
+                *n = n.saturating_add_signed(delta);
+                if stepped.check_size().is_ok() {
+                    *config = stepped;
+                }
+            }
+            ConfigField::Tier => {
+                let tiers = DeductionTier::ALL;
+                let current = tiers
+                    .iter()
+                    .position(|&t| t == config.max_tier)
+                    .unwrap_or(0);
+                let next = current.saturating_add_signed(delta).min(tiers.len() - 1);
+                config.max_tier = tiers[next];
+                config.n_reveals = generate::reveals_for_tier(config.max_tier);
+            }
+            ConfigField::Reveals => {
+                let n_reveals = config.n_reveals.saturating_add_signed(delta);
+                if generate::REVEAL_COUNTS.contains(&n_reveals) {
+                    config.n_reveals = n_reveals;
+                }
+            }
+            ConfigField::ClueMix => {
+                let mixes = ClueMix::ALL;
+                let current = mixes
+                    .iter()
+                    .position(|&m| m == config.clue_mix)
+                    .unwrap_or(0);
+                let next = current.saturating_add_signed(delta).min(mixes.len() - 1);
+                config.set_clue_mix(mixes[next]);
+            }
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Component)]
+struct ConfigFieldText(ConfigField);
+
+#[derive(Reflect, Debug, Component, Clone, Copy)]
+enum MenuButton {
+    Step(ConfigField, isize),
+    Generate,
+    CancelGeneration,
+    ResumeSaved,
+    RestoreSession,
+    Quit,
+}
+
+pub const MENU_BUTTON_COLOR: Color = Color::hsla(33., 1., 0.32, 1.);
+
+fn screen_node() -> Node {
+    Node {
+        width: Val::Percent(100.),
+        height: Val::Percent(100.),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: Val::Px(20.),
+        ..Default::default()
+    }
+}
+
+fn spawn_menu_button(parent: &mut ChildBuilder, button: MenuButton, label: &str) {
+    parent
+        .spawn((
+            button,
+            Node {
+                padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                ..Default::default()
+            },
+            BackgroundColor(MENU_BUTTON_COLOR),
+        ))
+        .with_child((Text::new(label), NO_PICK));
+}
+
+fn spawn_resume_button(parent: &mut ChildBuilder) {
+    if Path::new(SAVE_GAME_PATH).exists() {
+        spawn_menu_button(parent, MenuButton::ResumeSaved, "Resume saved game");
+    }
+    if autosave::has_checkpoints(Path::new(AUTOSAVE_DIR)) {
+        spawn_menu_button(parent, MenuButton::RestoreSession, "Continue");
+    }
+}
+
+fn spawn_config_screen(mut commands: Commands, config: Res<GeneratorConfig>) {
+    commands
+        .spawn((ConfigScreen, screen_node(), NO_PICK))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Sherlock Fox"),
+                TextFont {
+                    font_size: 48.,
+                    ..Default::default()
+                },
+                NO_PICK,
+            ));
+            for field in [
+                ConfigField::Rows,
+                ConfigField::Cols,
+                ConfigField::Tier,
+                ConfigField::Reveals,
+                ConfigField::ClueMix,
+            ] {
+                parent
+                    .spawn((
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        NO_PICK,
+                    ))
+                    .with_children(|parent| {
+                        spawn_menu_button(parent, MenuButton::Step(field, -1), "-");
+                        parent.spawn((
+                            ConfigFieldText(field),
+                            Text::new(field.describe(&config)),
+                            NO_PICK,
+                        ));
+                        spawn_menu_button(parent, MenuButton::Step(field, 1), "+");
+                    });
+            }
+            spawn_menu_button(parent, MenuButton::Generate, "New puzzle");
+            spawn_resume_button(parent);
+            spawn_menu_button(parent, MenuButton::Quit, "Quit");
+        });
+}
+
+fn update_config_text(
+    config: Res<GeneratorConfig>,
+    mut q_text: Query<(&ConfigFieldText, &mut Text)>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    for (ConfigFieldText(field), mut text) in &mut q_text {
+        text.0 = field.describe(&config);
+    }
+}
+
+fn despawn_config_screen(mut commands: Commands, q_screen: Query<Entity, With<ConfigScreen>>) {
+    for entity in &q_screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn menu_button_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_button: Query<&MenuButton>,
+    mut config: ResMut<GeneratorConfig>,
+    generation: Option<Res<PuzzleGeneration>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toast_tx: EventWriter<ShowToast>,
+    mut exit_tx: EventWriter<AppExit>,
+) {
+    let Ok(&button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    match button {
+        MenuButton::Step(field, delta) => field.step(&mut config, delta),
+        // The config can also be edited in the inspector, which doesn't go through `step`.
+        MenuButton::Generate => match config.check_size().and_then(|()| config.check_reveals()) {
+            Ok(()) => next_state.set(GameState::Loading),
+            Err(e) => {
+                toast_tx.send(ShowToast::error(format!(
+                    "Can't generate that puzzle: {e}."
+                )));
+            }
+        },
+        MenuButton::CancelGeneration => {
+            if let Some(generation) = generation {
+                generation.cancel.store(true, Ordering::Relaxed);
+            }
+            next_state.set(GameState::MainMenu);
+        }
+        MenuButton::Quit => {
+            exit_tx.send(AppExit::Success);
+        }
+        // Handled by `resume_saved_clicked`.
+        MenuButton::ResumeSaved | MenuButton::RestoreSession => (),
+    }
+}
+
+fn resume_saved_clicked(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_button: Query<&MenuButton>,
+    mut notes: Single<&mut PuzzleNotes>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    let (read, source) = match q_button.get(ev.entity()) {
+        Ok(MenuButton::ResumeSaved) => (
+            SavedPuzzle::read_from(Path::new(SAVE_GAME_PATH)),
+            SAVE_GAME_PATH,
+        ),
+        Ok(MenuButton::RestoreSession) => (
+            autosave::read_latest_checkpoint(Path::new(AUTOSAVE_DIR)),
+            AUTOSAVE_DIR,
+        ),
+        _ => return,
+    };
+    match read {
+        Ok(saved) => {
+            notes.text = saved.notes.clone();
+            commands.insert_resource(PuzzleSpawn::from_saved(saved));
+            next_state.set(GameState::Playing);
+        }
+        Err(e) => {
+            toast_tx.send(ShowToast::error(format!("Couldn't read {source}: {e}")));
+        }
+    }
+}
+
+// Leaving the puzzle in play clears it away; a fresh one is then generated with the same config.
+fn new_puzzle_clicked(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if let TopButtonAction::NewPuzzle = action {
+            next_state.set(GameState::Loading);
+        }
+    }
+}
+
+fn start_generation(
+    mut commands: Commands,
+    mut rng: ResMut<SeededRng>,
+    mut puzzle_seed: ResMut<PuzzleSeed>,
+    config: Res<GeneratorConfig>,
+) {
+    let seed = puzzle_seed.0.take().unwrap_or_else(|| rng.0.random());
+    info!("generating puzzle from seed {seed}");
+    let config = config.clone();
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let cancel = Arc::new(AtomicBool::new(false));
+    let task = AsyncComputeTaskPool::get().spawn({
+        let attempts = attempts.clone();
+        let cancel = cancel.clone();
+        let config = config.clone();
+        async move {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            // Dropping the task can't interrupt a synchronous loop, so cancellation is checked
+            // between attempts.
+            while !cancel.load(Ordering::Relaxed) {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                if let Some(generated) = generate::generate_puzzle(&mut rng, &config) {
+                    return Some(generated);
+                }
+            }
+            None
+        }
+    });
+    commands.insert_resource(PuzzleGeneration {
+        seed,
+        config,
+        task,
+        attempts,
+        cancel,
+        elapsed: Stopwatch::new(),
+    });
+
+    commands
+        .spawn((LoadingScreen, screen_node(), NO_PICK))
+        .with_children(|parent| {
+            parent.spawn((
+                LoadingSpinner,
+                Node {
+                    width: Val::Px(40.),
+                    height: Val::Px(40.),
+                    border: UiRect::all(Val::Px(4.)),
+                    ..Default::default()
+                },
+                BorderColor(Color::hsla(0., 0., 0.8, 1.)),
+                NO_PICK,
+            ));
+            parent.spawn((LoadingProgress, Text::new("Generating puzzle..."), NO_PICK));
+            spawn_menu_button(parent, MenuButton::CancelGeneration, "Cancel");
+            spawn_resume_button(parent);
+        });
+}
+
+fn spin_spinner(time: Res<Time>, mut q_spinner: Query<&mut Transform, With<LoadingSpinner>>) {
+    for mut transform in &mut q_spinner {
+        transform.rotate_z(time.delta_secs() * TAU / 2.);
+    }
+}
+
+fn show_generation_progress(
+    time: Res<Time>,
+    mut generation: ResMut<PuzzleGeneration>,
+    mut q_text: Query<&mut Text, With<LoadingProgress>>,
+) {
+    generation.elapsed.tick(time.delta());
+    let attempts = generation.attempts.load(Ordering::Relaxed);
+    let elapsed = generation.elapsed.elapsed_secs();
+    let seed = generation.seed;
+    for mut text in &mut q_text {
+        text.0 =
+            format!("Generating puzzle from seed {seed}... {attempts} attempts in {elapsed:.1}s");
+    }
+}
+
+fn finish_generation(
+    mut commands: Commands,
+    mut generation: ResMut<PuzzleGeneration>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(generated) = block_on(future::poll_once(&mut generation.task)) else {
+        return;
+    };
+    // A canceled task finishes with nothing, but the state change has already been requested.
+    let Some(generated) = generated else {
+        return;
+    };
+    info!(
+        "generated {:?} puzzle with {} of {} reveals needed: {:?}",
+        generated.report.difficulty(),
+        generated.needed_reveals,
+        generated.reveals.len(),
+        generated.report
+    );
+    let metadata = PuzzleMetadata::generated(generation.seed, &generation.config, &generated);
+    commands.insert_resource(PuzzleSpawn::new(generated, metadata));
+    commands.insert_resource(GeneratedFrom(Some((
+        generation.seed,
+        generation.config.clone(),
+    ))));
+    next_state.set(GameState::Playing);
+}
+
+fn forget_generated_from(mut generated_from: ResMut<GeneratedFrom>) {
+    generated_from.0 = None;
+}
+
+fn despawn_loading_screen(
+    mut commands: Commands,
+    generation: Option<Res<PuzzleGeneration>>,
+    q_screen: Query<Entity, With<LoadingScreen>>,
+) {
+    if let Some(generation) = generation {
+        generation.cancel.store(true, Ordering::Relaxed);
+    }
+    commands.remove_resource::<PuzzleGeneration>();
+    for entity in &q_screen {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GeneratedFrom>()
+            .init_resource::<GeneratorConfig>()
+            .register_type::<GeneratorConfig>()
+            .register_type::<ConfigFieldText>()
+            .register_type::<ConfigScreen>()
+            .register_type::<LoadingProgress>()
+            .register_type::<LoadingScreen>()
+            .register_type::<LoadingSpinner>()
+            .register_type::<MenuButton>()
+            .add_observer(menu_button_clicked)
+            .add_observer(resume_saved_clicked)
+            .add_systems(OnEnter(GameState::MainMenu), spawn_config_screen)
+            .add_systems(
+                Update,
+                update_config_text.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(OnExit(GameState::MainMenu), despawn_config_screen)
+            .add_systems(
+                Update,
+                new_puzzle_clicked.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(GameState::Loading), start_generation)
+            .add_systems(
+                Update,
+                (spin_spinner, show_generation_progress, finish_generation)
+                    .run_if(in_state(GameState::Loading)),
+            )
+            .add_systems(OnExit(GameState::Loading), despawn_loading_screen)
+            .add_systems(OnExit(GameState::Playing), forget_generated_from);
+    }
+}
@@ -0,0 +1,242 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    fit::FitHover,
+    palette::Swatch,
+    puzzle::{CellLoc, CellLocIndex, Puzzle},
+    settings::Settings,
+    DisplayCellButton, DragTarget, GameState, HoverAnimationBundle, NO_PICK,
+};
+
+// With `Settings::magnifier`, hovering a cell's candidates covers the cell with an enlarged copy
+// of them. The copies are `DisplayCellButton`s themselves, so clicking, dragging, and display
+// updates all go through the same systems as the real cell.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct Magnifier {
+    loc: Option<CellLoc>,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct MagnifierOverlay;
+
+#[derive(Debug, Component, Reflect)]
+pub struct MagnifiedButton;
+
+const MAGNIFIED_BUTTON_SIZE: f32 = 64.;
+const MAGNIFIER_PADDING: f32 = 10.;
+const MAGNIFIER_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.9);
+
+fn track_magnified_cell(
+    settings: Res<Settings>,
+    q_hovered_cell: Query<&DisplayCellButton, (With<FitHover>, Without<MagnifiedButton>)>,
+    q_hovered_overlay: Query<(Has<MagnifierOverlay>, Has<MagnifiedButton>), With<FitHover>>,
+    q_drag: Query<(), With<DragTarget>>,
+    mut magnifier: ResMut<Magnifier>,
+) {
+    // Dragging out of the magnifier to pick an operation shouldn't close it mid-drag.
+    if !q_drag.is_empty() {
+        return;
+    }
+    let loc = if !settings.magnifier {
+        None
+    } else if q_hovered_overlay
+        .iter()
+        .any(|(overlay, button)| overlay || button)
+    {
+        magnifier.loc
+    } else {
+        q_hovered_cell.iter().next().map(|b| b.index.loc)
+    };
+    if magnifier.loc != loc {
+        magnifier.loc = loc;
+    }
+}
+
+fn show_magnifier(
+    mut commands: Commands,
+    magnifier: Res<Magnifier>,
+    puzzle: Single<&Puzzle>,
+    q_button: Query<(&DisplayCellButton, &GlobalTransform), Without<MagnifiedButton>>,
+    q_overlay: Query<Entity, With<MagnifierOverlay>>,
+    mut animation_graphs: ResMut<Assets<AnimationGraph>>,
+) {
+    if !magnifier.is_changed() {
+        return;
+    }
+    for entity in &q_overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(loc) = magnifier.loc else {
+        return;
+    };
+    // The cell itself isn't hoverable, so the overlay is centered on its candidates instead.
+    let translations = q_button
+        .iter()
+        .filter(|(b, _)| b.index.loc == loc)
+        .map(|(_, t)| t.translation().truncate())
+        .collect::<Vec<_>>();
+    if translations.is_empty() {
+        return;
+    }
+    let center = translations.iter().sum::<Vec2>() / translations.len() as f32;
+
+    let puzzle_row = puzzle.row_at(loc.row);
+    let sel = puzzle.cell_selection(loc);
+    let n_buttons = puzzle_row.iter_indices().count();
+    let grid_cols = (n_buttons as f32).sqrt().ceil() as usize;
+    let grid_rows = n_buttons.div_ceil(grid_cols);
+    let grid_size = Vec2::new(grid_cols as f32, grid_rows as f32) * MAGNIFIED_BUTTON_SIZE;
+    let player = commands
+        .spawn((
+            AnimationPlayer::default(),
+            AnimationGraphHandle(animation_graphs.add(AnimationGraph::new())),
+        ))
+        .id();
+    commands
+        .spawn((
+            MagnifierOverlay,
+            Sprite::from_color(
+                MAGNIFIER_BACKGROUND_COLOR,
+                grid_size + Vec2::splat(MAGNIFIER_PADDING * 2.),
+            ),
+            Transform::from_translation(center.extend(20.)),
+        ))
+        .add_child(player)
+        .with_children(|parent| {
+            for (nr, index) in puzzle_row.iter_indices().enumerate() {
+                let offset = (Vec2::new((nr % grid_cols) as f32, -((nr / grid_cols) as f32))
+                    + Vec2::new(0.5, -0.5))
+                    * MAGNIFIED_BUTTON_SIZE
+                    + grid_size * Vec2::new(-0.5, 0.5);
+                let alpha = if sel.is_enabled(index) { 1. } else { 0.2 };
+                let mut sprite = puzzle_row.display_sprite(index);
+                sprite.custom_size = Some(Vec2::splat(MAGNIFIED_BUTTON_SIZE - 10.));
+                sprite.color = Color::hsla(0., 0., 1., 1.);
+                parent
+                    .spawn((
+                        MagnifiedButton,
+                        Sprite::from_color(
+                            puzzle_row.display_color(index).with_alpha(alpha),
+                            Vec2::splat(MAGNIFIED_BUTTON_SIZE),
+                        ),
+                        Transform::from_translation(offset.extend(1.)),
+                        DisplayCellButton {
+                            index: CellLocIndex { loc, index },
+                        },
+                        Swatch::new(CellLocIndex { loc, index }),
+                        HoverAnimationBundle::new(player),
+                    ))
+                    .with_child((sprite, Transform::from_xyz(0., 0., 1.), NO_PICK));
+            }
+        });
+}
+
+fn close_magnifier(
+    mut commands: Commands,
+    mut magnifier: ResMut<Magnifier>,
+    q_overlay: Query<Entity, With<MagnifierOverlay>>,
+) {
+    magnifier.loc = None;
+    for entity in &q_overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct MagnifierPlugin;
+
+impl Plugin for MagnifierPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Magnifier>()
+            .register_type::<MagnifiedButton>()
+            .register_type::<Magnifier>()
+            .register_type::<MagnifierOverlay>()
+            .add_systems(OnExit(GameState::Playing), close_magnifier)
+            .add_systems(
+                Update,
+                (track_magnified_cell, show_magnifier)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cellindex::{CellEntities, CellIndexPlugin},
+        crossref::{CrossRefIndex, CrossRefPlugin},
+        puzzle::{loc, test_puzzle, LInd},
+    };
+
+    fn count<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> usize {
+        app.world_mut()
+            .query_filtered::<(), F>()
+            .iter(app.world())
+            .count()
+    }
+
+    #[test]
+    fn hovering_a_cell_magnifies_copies_of_its_candidates() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((CellIndexPlugin, CrossRefPlugin))
+            .insert_resource(Settings {
+                magnifier: true,
+                ..Default::default()
+            })
+            .init_resource::<Assets<AnimationGraph>>()
+            .init_resource::<Magnifier>()
+            .add_systems(Update, (track_magnified_cell, show_magnifier).chain());
+        app.world_mut().spawn(test_puzzle(2, 3));
+        let board = (0..3)
+            .map(|index| {
+                let index = CellLocIndex {
+                    loc: loc(0, 1),
+                    index: LInd(index),
+                };
+                app.world_mut()
+                    .spawn((DisplayCellButton { index }, GlobalTransform::default()))
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        app.world_mut().entity_mut(board[0]).insert(FitHover);
+        app.update();
+        assert_eq!(count::<With<MagnifierOverlay>>(&mut app), 1);
+        assert_eq!(count::<With<MagnifiedButton>>(&mut app), 3);
+
+        // Each candidate now has two buttons showing it; only the board's is the real one.
+        let index = CellLocIndex {
+            loc: loc(0, 1),
+            index: LInd(2),
+        };
+        let showing = app
+            .world()
+            .resource::<CellEntities>()
+            .buttons_showing(index)
+            .collect::<Vec<_>>();
+        assert_eq!(showing.len(), 2);
+        let real = showing
+            .into_iter()
+            .filter(|&e| !app.world().entity(e).contains::<MagnifiedButton>())
+            .collect::<Vec<_>>();
+        assert_eq!(real, vec![board[2]]);
+        let crossref = app.world().resource::<CrossRefIndex>();
+        assert_eq!(crossref.button(index), Some(board[2]));
+
+        app.world_mut().entity_mut(board[0]).remove::<FitHover>();
+        app.update();
+        assert_eq!(count::<With<MagnifierOverlay>>(&mut app), 0);
+        assert_eq!(count::<With<MagnifiedButton>>(&mut app), 0);
+        let cell_entities = app.world().resource::<CellEntities>();
+        assert_eq!(
+            cell_entities.buttons_showing(index).collect::<Vec<_>>(),
+            vec![board[2]]
+        );
+    }
+}
@@ -4,43 +4,108 @@
 
 #![feature(try_blocks, cmp_minmax, lazy_get)]
 
+mod analytics;
 mod animation;
+mod archive;
+mod autosave;
+mod bank;
+mod buttons;
+mod camera;
+mod cellindex;
+mod cluebox;
 mod clues;
+mod columns;
+mod consequences;
+mod crosshair;
+mod crossref;
 mod fit;
+mod focus;
+mod generate;
+mod gridimport;
+mod gutter;
+mod inspection;
+mod intro;
+mod journal;
+mod loading;
+mod magnifier;
+mod menu;
+mod modal;
+mod notes;
+mod operations;
+mod outcome;
+mod packs;
+mod palette;
+mod pins;
+mod procedural;
 mod puzzle;
+mod radial;
+mod replay;
+mod resolver;
+mod review;
+mod rowtilesets;
+mod save;
+mod selftest;
+mod settings;
+mod share;
+mod solver;
+mod speedrun;
+mod stats;
+mod toast;
+mod touch;
 mod undo;
+mod zoom;
 
-use std::{any::TypeId, cell::LazyCell, time::Duration};
+use std::{any::TypeId, fs, io, time::Duration};
 
-use animation::{AnimatorPlugin, SavedAnimationNode};
+use analytics::ClueDeductionApplied;
+use animation::{AnimationFinished, AnimatorPlugin, Playback, SavedAnimationNode};
+use archive::ArchivedClue;
 use bevy::{
     animation::{
         animated_field, AnimationEntityMut, AnimationEvaluationError, AnimationTarget,
         AnimationTargetId, RepeatAnimation,
     },
     color::palettes::css,
+    ecs::{component::Tick, system::EntityCommands},
     prelude::*,
     sprite::Anchor,
-    utils::hashbrown::{HashMap, HashSet},
+    ui::widget::NodeImageMode,
+    utils::{
+        hashbrown::{HashMap, HashSet},
+        Instant,
+    },
     window::PrimaryWindow,
 };
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use clues::{
-    AdjacentColumnClue, ClueExplanation, ClueExplanationResolvedChunk, DynPuzzleClue, PuzzleClues,
-    SameColumnClue,
-};
+use camera::BoardCamera;
+use cellindex::CellEntities;
+use clues::{ClueExplanation, ClueExplanationResolvedChunk, DynPuzzleClue, SameColumnClue};
+use crossref::CrossRefIndex;
 use fit::{
-    ButtonClick, ButtonColorBackground, ButtonScale, FitButton, FitClicked, FitClickedEvent,
-    FitColorBackground, FitHover, FitHoverScale, FitManip, FitTransformAnimationBundle,
-    FitTransformEdge, FitWithin, FitWithinBackground, FitWithinBundle, HoverScaleEdge,
+    fit_to_world, ButtonClick, ButtonColorBackground, ButtonScale, FitButton, FitClicked,
+    FitClickedEvent, FitColorBackground, FitHover, FitHoverScale, FitManip, FitScrolledEvent,
+    FitSweptEvent, FitTransformAnimationBundle, FitTransformEdge, FitWithin, FitWithinBackground,
+    FitWithinBundle, HoverScaleEdge,
 };
+use generate::{GeneratedPuzzle, GeneratorConfig};
+use intro::IntroduceReveal;
+use magnifier::MagnifiedButton;
+use modal::{modal_closed, modal_on_top, modal_open, no_modal, Modal, ModalStack};
+use notes::PuzzleNotes;
+use operations::OperationTheme;
+use palette::Swatch;
 use petgraph::graph::NodeIndex;
 use puzzle::{
-    CellLoc, CellLocAnswer, CellLocIndex, LRow, Puzzle, PuzzleCellDisplay, PuzzleCellSelection,
-    PuzzleRow, RowAnswer, UpdateCellIndexOperation,
+    CellLoc, CellLocAnswer, CellLocIndex, InferenceRun, LRow, Puzzle, PuzzleCellDisplay,
+    PuzzleCellSelection, PuzzleRow, RowAnswer, UpdateCellIndexOperation,
 };
+use radial::RadialLayout;
 use rand::{distr::Distribution, seq::SliceRandom, Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
+use save::{PuzzleMetadata, SavedPuzzle, SavedUndoTree};
+use settings::{AnimationSettings, MistakeCheck, MotionKind, Settings, SizedText};
+use toast::ShowToast;
+use touch::TouchGesture;
 use undo::{Action, PushNewAction, UndoTree, UndoTreeLocation};
 use uuid::Uuid;
 
@@ -49,9 +114,79 @@ const NO_PICK: PickingBehavior = PickingBehavior {
     is_hoverable: false,
 };
 
-fn main() {
+fn main() -> AppExit {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("generate-bank") => return bank::generate_bank(&args[1..]),
+        Some("export") => return share::export(&args[1..]),
+        Some("verify") => return speedrun::verify(&args[1..]),
+        _ => (),
+    }
+    let (seed, version, reveals) = match (
+        flag_value(&args, "--seed"),
+        flag_value(&args, "--generator-version"),
+        flag_value(&args, "--reveals"),
+    ) {
+        (Ok(seed), Ok(version), Ok(reveals)) => (seed, version, reveals),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            eprintln!("{e}");
+            return AppExit::error();
+        }
+    };
+    let mut config = GeneratorConfig {
+        version: version.unwrap_or_default(),
+        ..Default::default()
+    };
+    if let Some(reveals) = reveals {
+        config.n_reveals = reveals;
+    }
+    if let Err(e) = config.check_reveals() {
+        eprintln!("--reveals: {e}");
+        return AppExit::error();
+    }
+    if args.iter().any(|a| a == "--self-test") {
+        return selftest::self_test(seed, config);
+    }
+    let imported = match flag_value::<String>(&args, "--import") {
+        Ok(None) => None,
+        Ok(Some(code)) => match share::import(&code) {
+            Ok(saved) => Some(saved),
+            Err(e) => {
+                eprintln!("--import: {e}");
+                return AppExit::error();
+            }
+        },
+        Err(e) => {
+            eprintln!("{e}");
+            return AppExit::error();
+        }
+    };
+    let animation = if args.iter().any(|a| a == "--reduced-motion") {
+        AnimationSettings::reduced_motion()
+    } else {
+        AnimationSettings::default()
+    };
+    // A puzzle asked for on the command line skips the main menu.
+    let initial_state = if seed.is_some() || imported.is_some() {
+        GameState::Loading
+    } else {
+        GameState::MainMenu
+    };
+
     App::new()
         .add_plugins(DefaultPlugins)
+        .add_plugins(analytics::ClueAnalyticsPlugin)
+        .add_plugins(animation::AnimationTrackingPlugin)
+        .add_plugins(archive::ArchivePlugin)
+        .add_plugins(autosave::AutosavePlugin)
+        .add_plugins(buttons::TopButtonStatePlugin)
+        .add_plugins(camera::CameraPlugin)
+        .add_plugins(cellindex::CellIndexPlugin)
+        .add_plugins(cluebox::ClueboxPlugin)
+        .add_plugins(columns::ColumnsPlugin)
+        .add_plugins(consequences::ConsequencesPlugin)
+        .add_plugins(crosshair::CrosshairPlugin)
+        .add_plugins(crossref::CrossRefPlugin)
         .add_plugins(fit::FitPlugin)
         .add_plugins(fit::FitButtonInteractionPlugin::<
             DisplayCellButton,
@@ -69,20 +204,69 @@ fn main() {
             DisplayTopButton,
             ButtonColorBackground,
         >::default())
+        .add_plugins(focus::FocusPlugin)
+        .add_plugins(gridimport::GridImportPlugin)
+        .add_plugins(gutter::GutterPlugin)
+        .add_plugins(inspection::InspectionPlugin)
+        .add_plugins(intro::RevealIntroPlugin)
+        .add_plugins(journal::JournalPlugin)
+        .add_plugins(loading::LoadingPlugin)
+        .add_plugins(magnifier::MagnifierPlugin)
+        .add_plugins(menu::MenuPlugin)
+        .add_plugins(modal::ModalPlugin)
+        .add_plugins(notes::NotesPlugin)
+        .add_plugins(operations::OperationThemePlugin)
+        .add_plugins(outcome::OutcomePlugin)
+        .add_plugins(packs::PacksPlugin)
+        .add_plugins(palette::PalettePlugin)
+        .add_plugins(pins::PinsPlugin)
+        .add_plugins(procedural::ProceduralTilesetPlugin)
+        .add_plugins(radial::RadialPlugin)
+        .add_plugins(replay::ReplayPlugin)
+        .add_plugins(review::InferenceReviewPlugin)
+        .add_plugins(rowtilesets::RowTilesetsPlugin)
+        .add_plugins(save::SavePlugin)
+        .add_plugins(settings::SettingsPlugin)
+        .add_plugins(share::SharePlugin)
+        .add_plugins(speedrun::SpeedrunPlugin)
+        .add_plugins(stats::StatsPlugin)
+        .add_plugins(toast::ToastPlugin)
+        .add_plugins(touch::TouchPlugin)
         .add_plugins(undo::UndoPlugin)
-        .init_resource::<Assets<DynPuzzleClue>>()
+        .add_plugins(zoom::ZoomPlugin)
+        .init_resource::<ArrowStyle>()
+        .init_resource::<CandidatePreview>()
+        .init_resource::<ExplanationQueue>()
+        .init_resource::<GestureIds>()
+        .init_resource::<LastClueScan>()
+        .init_resource::<LastInferenceRun>()
+        .init_resource::<PendingInference>()
+        .insert_resource(PuzzleSeed(seed))
+        .insert_resource(animation)
+        .insert_resource(share::ImportedPuzzle(imported))
+        .insert_resource(speedrun::Speedrun::new(
+            args.iter().any(|a| a == "--speedrun"),
+        ))
+        .insert_resource(config)
         .init_resource::<SeededRng>()
-        .init_state::<ClueExplanationState>()
-        .add_plugins(WorldInspectorPlugin::new())
+        .insert_state(initial_state)
+        // The inspector would show the board the pause menu hides.
+        .add_plugins(WorldInspectorPlugin::new().run_if(not(in_state(PauseState::Paused))))
         .add_event::<AddClue>()
+        .add_event::<RemoveClue>()
         .add_event::<AddRow>()
         .add_event::<PlaceArrow>()
+        .register_type::<ArrowAnchors>()
+        .register_type::<ArrowStyle>()
+        .add_event::<AppliedCellUpdate>()
         .add_event::<PushNewAction>()
+        .add_event::<RejectedCellUpdate>()
         .add_event::<UpdateCellDisplay>()
         .add_event::<UpdateCellIndex>()
-        .register_asset_reflect::<DynPuzzleClue>()
         .register_type::<Action>()
         .register_type::<AssignRandomColor>()
+        .register_type::<CandidatePreview>()
+        .register_type::<CandidatePreviewMarker>()
         .register_type::<CellLoc>()
         .register_type::<CellLocIndex>()
         .register_type::<DisplayButtonbox>()
@@ -90,30 +274,42 @@ fn main() {
         .register_type::<DisplayCellButton>()
         .register_type::<DisplayMatrix>()
         .register_type::<DisplayRow>()
+        .register_type::<DisplayRowCounter>()
         .register_type::<DisplayTopButton>()
         .register_type::<DragTarget>()
         .register_type::<DragUI>()
         .register_type::<DragUITarget>()
         .register_type::<DynPuzzleClue>()
         .register_type::<ExplainClueComponent>()
+        .register_type::<ExplanationDismissed>()
+        .register_type::<ExplanationQueue>()
         .register_type::<ExplanationBounceEdge>()
         .register_type::<ExplanationHilight>()
+        .register_type::<HintHilight>()
+        .register_type::<ThemedPanel>()
         .register_type::<FitHover>()
         .register_type::<FitTransformEdge>()
         .register_type::<FitWithin>()
         .register_type::<FitWithinBackground>()
+        .register_type::<FlashEdge>()
         .register_type::<HoverAlphaEdge>()
         .register_type::<HoverScaleEdge>()
+        .register_type::<LastClueScan>()
+        .register_type::<LastInferenceRun>()
         .register_type::<PushNewAction>()
         .register_type::<Puzzle>()
         .register_type::<PuzzleCellDisplay>()
         .register_type::<PuzzleCellSelection>()
         .register_type::<PuzzleClueComponent>()
-        .register_type::<PuzzleClues>()
+        .register_type::<PuzzleEntity>()
+        .register_type::<PuzzleReveals>()
+        .register_type::<PuzzleOrigin>()
         .register_type::<PuzzleRow>()
         .register_type::<PuzzleSpawn>()
         .register_type::<SameColumnClue>()
+        .register_type::<PuzzleSeed>()
         .register_type::<SeededRng>()
+        .register_type::<ShakeEdge>()
         .register_type::<UndoTree>()
         .register_type::<UndoTreeLocation>()
         .register_type::<UpdateCellIndexOperation>()
@@ -122,26 +318,78 @@ fn main() {
         .add_observer(cell_release_drag)
         .add_observer(clue_explanation_clicked)
         .add_observer(interact_drag_ui_move)
+        .add_observer(make_themed_panel_image)
         .add_observer(remove_clue_highlight)
         .add_observer(show_clue_highlight)
         .add_observer(show_dyn_clue)
         .add_observer(spawn_top_buttons)
         .add_systems(Startup, setup)
+        .add_systems(OnExit(GameState::Playing), despawn_puzzle)
+        .add_systems(
+            Last,
+            check_puzzle_singleton.run_if(resource_exists::<PuzzleEntity>),
+        )
+        .add_systems(
+            PreUpdate,
+            run_deferred_inference.run_if(not(in_state(PauseState::Paused))),
+        )
         .add_systems(
             Update,
             (
                 assign_random_color,
                 show_clues,
-                (cell_update, cell_update_display).chain(),
-                (spawn_row, add_row).chain(),
-                add_clue,
+                (clear_hint, show_hint).chain(),
+                (
+                    sweep_clear_candidates
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(no_modal),
+                    cell_update,
+                    run_pending_inference.run_if(not(in_state(PauseState::Paused))),
+                    cell_update_display,
+                )
+                    .chain(),
+                reject_cell_update,
+                (flash_applied_update, despawn_finished_flashes),
+                (
+                    cycle_candidate_preview,
+                    clear_candidate_preview,
+                    show_candidate_preview,
+                )
+                    .chain(),
+                (spawn_row.run_if(in_state(GameState::Playing)), add_row).chain(),
+                (add_clue, remove_clue).chain(),
+                update_row_counters,
                 animate_arrow,
-                place_arrow,
+                (place_arrow, reanchor_arrows).chain(),
             ),
         )
-        .add_systems(OnEnter(ClueExplanationState::Shown), show_clue_explanation)
-        .add_systems(OnExit(ClueExplanationState::Shown), hide_clue_explanation)
-        .run();
+        .add_systems(
+            Update,
+            (
+                explanation_keys.run_if(modal_on_top(Modal::Explanation)),
+                show_clue_explanation,
+            )
+                .chain()
+                .run_if(modal_open(Modal::Explanation)),
+        )
+        .add_systems(
+            PreUpdate,
+            hide_clue_explanation.run_if(modal_closed(Modal::Explanation)),
+        )
+        .run()
+}
+
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> Result<Option<T>, String> {
+    let Some(n) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    let value = args
+        .get(n + 1)
+        .ok_or_else(|| format!("{flag} needs a value"))?;
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("{flag}: couldn't parse {value:?}"))
 }
 
 #[derive(Resource, Reflect)]
@@ -156,37 +404,85 @@ impl FromReflect for SeededRng {
 }
 
 impl FromWorld for SeededRng {
-    fn from_world(_world: &mut World) -> Self {
-        SeededRng(ChaCha8Rng::from_os_rng())
+    fn from_world(world: &mut World) -> Self {
+        match world.get_resource::<PuzzleSeed>().and_then(|s| s.0) {
+            Some(seed) => SeededRng(ChaCha8Rng::seed_from_u64(seed)),
+            None => SeededRng(ChaCha8Rng::from_os_rng()),
+        }
     }
 }
 
+// The seed the next puzzle is generated from, as given by `--seed`; when unset, one is drawn from
+// `SeededRng`. The same seed and generator config always produce the same puzzle, and a seed
+// printed by `generate-bank` reproduces that bank puzzle.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct PuzzleSeed(Option<u64>);
+
 #[derive(States, Default, Debug, Clone, PartialEq, Eq, Hash)]
-enum ClueExplanationState {
+enum GameState {
+    #[default]
+    MainMenu,
+    Loading,
+    Playing,
+}
+
+// Follows the puzzle through every change, including undo, so backing out of a contradiction or
+// out of the solution returns to `InProgress`.
+#[derive(SubStates, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Playing)]
+enum PuzzleOutcome {
     #[default]
-    NotShown,
-    Shown,
+    InProgress,
+    Solved,
+    Contradicted,
+}
+
+// The pause menu sits over the puzzle without leaving it, so nothing about the puzzle is torn down
+// or started over on resuming.
+#[derive(SubStates, Default, Debug, Clone, PartialEq, Eq, Hash)]
+#[source(GameState = GameState::Playing)]
+enum PauseState {
+    #[default]
+    Running,
+    Paused,
 }
 
 #[derive(Debug, Component, Reflect)]
 struct ExplanationHilight;
 
+// Highlights placed by a hint rather than a shown explanation; they stay until the puzzle changes.
+#[derive(Debug, Component, Reflect)]
+struct HintHilight;
+
 #[derive(Debug, Component, Reflect)]
 struct ExplanationArrows;
 
 #[derive(Debug, Component, Reflect)]
 struct ExplainClueComponent {
-    clue: Handle<DynPuzzleClue>,
+    clue: Entity,
     update: UpdateCellIndex,
 }
 
+// An explanation closed with this is dismissed rather than applied.
+#[derive(Debug, Component, Reflect)]
+struct ExplanationDismissed;
+
+// Every deduction the clues offered when the explanation was asked for; the arrow keys cycle
+// through them.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct ExplanationQueue {
+    entries: Vec<(Entity, UpdateCellIndex)>,
+    current: usize,
+}
+
 fn show_clue_explanation(
     mut commands: Commands,
     q_puzzle: Single<&Puzzle>,
-    q_clue: Query<(Entity, &ExplainClueComponent)>,
-    q_clues: Query<(Entity, &PuzzleClueComponent)>,
-    q_cell: Query<(Entity, &DisplayCellButton)>,
-    q_transform: Query<&GlobalTransform>,
+    q_clue: Query<(Entity, &ExplainClueComponent), Added<ExplainClueComponent>>,
+    q_clues: Query<&PuzzleClueComponent>,
+    crossref: Res<CrossRefIndex>,
     mut arrow_tx: EventWriter<PlaceArrow>,
     // clues: Res<Assets<DynPuzzleClue>>,
 ) {
@@ -204,27 +500,19 @@ fn show_clue_explanation(
         }
         fn drain_into(&mut self, parent: &mut ChildBuilder) {
             if let Some(text) = self.0.take() {
-                parent.spawn((
-                    Text::new(text),
-                    BackgroundColor(Color::hsla(0., 0., 0.1, 0.8)),
-                    NO_PICK,
-                ));
+                parent.spawn((Text::new(text), SizedText, NO_PICK));
             }
         }
     }
     let Ok((clue_exp_entity, clue_exp_component)) = q_clue.get_single() else {
         return;
     };
-    let clue_id = clue_exp_component.clue.id();
-    // let Some(clue) = clues.get(clue_id) else {
-    //     return;
-    // };
     let Some(ref explanation) = clue_exp_component.update.explanation else {
         warn!("couldn't show explanation on {clue_exp_component:#?}");
         return;
     };
-    let Some((clue_entity, clue_component)) = q_clues.iter().find(|(_, c)| c.clue.id() == clue_id)
-    else {
+    let clue_entity = clue_exp_component.clue;
+    let Ok(clue_component) = q_clues.get(clue_entity) else {
         return;
     };
     commands.entity(clue_entity).insert(ExplanationHilight);
@@ -236,11 +524,12 @@ fn show_clue_explanation(
                 width: Val::Vw(35.),
                 height: Val::Vh(30.),
                 margin: UiRect::all(Val::Auto),
+                padding: UiRect::all(PANEL_PADDING),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
                 ..Default::default()
             },
-            BackgroundColor(Color::hsla(0., 0., 0.3, 0.25)),
+            ThemedPanel::default(),
         ))
         .with_children(|parent| {
             use ClueExplanationResolvedChunk as Ch;
@@ -274,55 +563,66 @@ fn show_clue_explanation(
         ))
         .id();
     info!("ok what's this map {:#?}", clue_component.cells);
-    for (cell, button) in &q_cell {
-        if cell_highlight.contains(&button.index) {
-            info!("highlighting {:?} at {:?}", button, cell);
-            commands.entity(cell).insert(ExplanationHilight);
-            let Ok(to_transform) = q_transform.get(cell) else {
-                continue;
-            };
-            let Some(from_entity) = clue_component
-                .cells
-                .get(&button.index.decay_column().upgrade_to_answer())
-            else {
-                continue;
-            };
-            info!("  from {from_entity:?}");
-            let Ok(from_transform) = q_transform.get(*from_entity) else {
-                continue;
-            };
-            info!("  from {from_transform:?} to {to_transform:?}");
-            arrow_tx.send(PlaceArrow {
-                parent,
-                from_loc: from_transform.compute_transform().translation,
-                to_loc: to_transform.compute_transform().translation,
-            });
-        }
+    for index in cell_highlight {
+        let Some(cell) = crossref.button(index) else {
+            continue;
+        };
+        info!("highlighting {:?} at {:?}", index, cell);
+        commands.entity(cell).insert(ExplanationHilight);
+        let Some(from_entity) = clue_component
+            .cells
+            .get(&index.decay_column().upgrade_to_answer())
+        else {
+            continue;
+        };
+        info!("  from {from_entity:?}");
+        arrow_tx.send(PlaceArrow {
+            parent,
+            from: *from_entity,
+            to: cell,
+        });
     }
 }
 
 fn hide_clue_explanation(
     mut commands: Commands,
     // q_puzzle: Single<&Puzzle>,
-    q_explanation: Query<(Entity, &ExplainClueComponent)>,
+    q_explanation: Query<(Entity, &ExplainClueComponent, Has<ExplanationDismissed>)>,
     q_clues: Query<Entity, With<ExplanationHilight>>,
     q_arrows: Query<Entity, With<ExplanationArrows>>,
     mut writer: EventWriter<UpdateCellIndex>,
+    mut usage_tx: EventWriter<ClueDeductionApplied>,
 ) {
-    for (explanation_entity, explanation) in &q_explanation {
+    for (explanation_entity, explanation, dismissed) in &q_explanation {
         commands.entity(explanation_entity).despawn_recursive();
+        if dismissed {
+            continue;
+        }
         writer.send(explanation.update.clone());
+        usage_tx.send(ClueDeductionApplied {
+            clue: explanation.clue,
+            update: explanation.update.clone(),
+        });
     }
-    for clue_entity in &q_clues {
-        commands.entity(clue_entity).remove::<ExplanationHilight>();
+    clear_explanation_display(&mut commands, &q_clues, &q_arrows);
+}
+
+fn clear_explanation_display(
+    commands: &mut Commands,
+    q_hilight: &Query<Entity, With<ExplanationHilight>>,
+    q_arrows: &Query<Entity, With<ExplanationArrows>>,
+) {
+    for entity in q_hilight {
+        commands.entity(entity).remove::<ExplanationHilight>();
     }
-    for arrows_entity in &q_arrows {
+    for arrows_entity in q_arrows {
         commands.entity(arrows_entity).despawn_recursive();
     }
 }
 
 impl SavedAnimationNode for ExplanationBounceEdge {
     type AnimatedFrom = Transform;
+    const MOTION: MotionKind = MotionKind::ExplanationBounce;
 
     fn node_mut(&mut self) -> &mut Option<NodeIndex> {
         &mut self.0
@@ -389,14 +689,15 @@ fn remove_clue_highlight(
     );
 }
 
+// Clues live on their entries in the cluebox; removing a clue from the puzzle is despawning it.
 #[derive(Debug, Component, Reflect)]
 struct PuzzleClueComponent {
-    clue: Handle<DynPuzzleClue>,
+    clue: DynPuzzleClue,
     cells: HashMap<RowAnswer, Entity>,
 }
 
 impl PuzzleClueComponent {
-    fn new(clue: Handle<DynPuzzleClue>) -> Self {
+    fn new(clue: DynPuzzleClue) -> Self {
         PuzzleClueComponent {
             clue,
             cells: Default::default(),
@@ -408,20 +709,20 @@ fn show_dyn_clue(
     ev: Trigger<OnInsert, PuzzleClueComponent>,
     mut q_clue: Query<&mut PuzzleClueComponent>,
     q_puzzle: Single<&Puzzle>,
-    clues: Res<Assets<DynPuzzleClue>>,
     mut commands: Commands,
 ) {
     let puzzle = *q_puzzle;
     let Ok(mut clue_component) = q_clue.get_mut(ev.entity()) else {
         return;
     };
-    let Some(clue) = clues.get(clue_component.clue.id()) else {
-        return;
-    };
+    let PuzzleClueComponent { clue, cells } = &mut *clue_component;
     info!("dyn clue ev={ev:?} clue={clue:?}");
     commands
         .entity(ev.entity())
-        .with_children(|parent| clue.spawn_into(parent, puzzle, &mut clue_component.cells));
+        .with_children(|parent| clue.spawn_into(parent, puzzle, cells));
+    for (&answer, &entity) in cells.iter() {
+        commands.entity(entity).insert(Swatch::of_answer(answer));
+    }
 }
 
 #[derive(Bundle)]
@@ -430,6 +731,7 @@ struct HoverAnimationBundle {
     scale_tracker: HoverScaleEdge,
     alpha_tracker: HoverAlphaEdge,
     explanation_tracker: ExplanationBounceEdge,
+    shake_tracker: ShakeEdge,
 }
 
 impl HoverAnimationBundle {
@@ -442,6 +744,7 @@ impl HoverAnimationBundle {
             scale_tracker: Default::default(),
             alpha_tracker: Default::default(),
             explanation_tracker: Default::default(),
+            shake_tracker: Default::default(),
         }
     }
 }
@@ -516,6 +819,12 @@ struct DisplayRow {
     row: LRow,
 }
 
+// Shows how many of a row's cells are still unsolved, in the margin after its last cell.
+#[derive(Reflect, Debug, Component)]
+struct DisplayRowCounter {
+    row: LRow,
+}
+
 #[derive(Reflect, Debug, Component)]
 struct DisplayCell {
     loc: CellLoc,
@@ -532,25 +841,172 @@ struct DisplayCellButtonEnlarge;
 #[derive(Reflect, Debug, Component, Clone)]
 struct DisplayTopButton(TopButtonAction);
 
-#[derive(Reflect, Debug, Clone, Copy)]
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum TopButtonAction {
     Undo,
     Redo,
     Clue,
+    Hint,
+    Log,
+    History,
+    Replay,
+    Export,
+    Notes,
+    Save,
+    Share,
+    Restart,
+    NewPuzzle,
+    Menu,
 }
 
 #[derive(Reflect, Debug, Component, Clone, Default)]
 struct HoverAlphaEdge(Option<NodeIndex>);
 
+#[derive(Reflect, Debug, Component, Clone, Default)]
+struct ShakeEdge(Option<NodeIndex>);
+
 #[derive(Reflect, Debug, Component, Clone, Default)]
 struct ExplanationBounceEdge(Option<NodeIndex>);
 
+// On the overlay flashed over a candidate button, which only lives as long as its one clip.
+#[derive(Reflect, Debug, Component, Clone, Default)]
+struct FlashEdge(Option<NodeIndex>);
+
 #[derive(Resource, Reflect)]
 #[reflect(Resource)]
 struct PuzzleSpawn {
-    tileset_pool: Vec<Tileset>,
     timer: Timer,
-    show_clues: usize,
+    // Stored in reverse so they can be popped in order.
+    rows: Vec<PuzzleRow>,
+    // Each with whether the player had archived it.
+    #[reflect(ignore)]
+    clues: Vec<(DynPuzzleClue, bool)>,
+    reveals: Vec<CellLocIndex>,
+    #[reflect(ignore)]
+    metadata: PuzzleMetadata,
+    // A saved game's history replaces the fresh one started once every row is added.
+    #[reflect(ignore)]
+    undo: Option<SavedUndoTree>,
+    history_spawned: bool,
+}
+
+impl PuzzleSpawn {
+    fn new(generated: GeneratedPuzzle, metadata: PuzzleMetadata) -> Self {
+        let mut rows = generated.puzzle.into_rows();
+        rows.reverse();
+        let mut clues = generated
+            .clues
+            .into_iter()
+            .map(|clue| (clue, false))
+            .collect::<Vec<_>>();
+        clues.reverse();
+        PuzzleSpawn {
+            timer: Timer::new(Duration::from_secs_f32(0.05), TimerMode::Repeating),
+            rows,
+            clues,
+            reveals: generated.reveals,
+            metadata,
+            undo: None,
+            history_spawned: false,
+        }
+    }
+
+    fn from_saved(saved: SavedPuzzle) -> Self {
+        let mut rows = saved.puzzle.into_rows();
+        rows.reverse();
+        let mut clues = saved
+            .clues
+            .into_iter()
+            .enumerate()
+            .map(|(nr, clue)| (DynPuzzleClue::from(clue), saved.archived.contains(&nr)))
+            .collect::<Vec<_>>();
+        clues.reverse();
+        PuzzleSpawn {
+            timer: Timer::new(Duration::from_secs_f32(0.05), TimerMode::Repeating),
+            rows,
+            clues,
+            reveals: saved.reveals,
+            metadata: saved.metadata,
+            undo: saved.undo,
+            history_spawned: false,
+        }
+    }
+}
+
+// The cells revealed at the start of the puzzle on the board, which saves and share codes keep.
+#[derive(Reflect, Debug, Component, Default)]
+struct PuzzleReveals(Vec<CellLocIndex>);
+
+// How the puzzle on the board was made, which saves keep so its seed and generator version aren't
+// lost.
+#[derive(Reflect, Debug, Component, Default)]
+struct PuzzleOrigin(#[reflect(ignore)] PuzzleMetadata);
+
+// The entity holding the puzzle being played, along with its notes and reveals. Systems find it
+// with `Single`, which quietly skips them if there's ever a second one, so it's only made or reset
+// through `respawn_puzzle`.
+#[derive(Reflect, Debug, Resource)]
+#[reflect(Resource)]
+struct PuzzleEntity(Entity);
+
+#[derive(Bundle)]
+struct PuzzleBundle {
+    name: Name,
+    puzzle: Puzzle,
+    notes: PuzzleNotes,
+    reveals: PuzzleReveals,
+    origin: PuzzleOrigin,
+}
+
+impl Default for PuzzleBundle {
+    fn default() -> Self {
+        PuzzleBundle {
+            name: Name::new("Puzzle"),
+            puzzle: Puzzle::default(),
+            notes: PuzzleNotes::default(),
+            reveals: PuzzleReveals::default(),
+            origin: PuzzleOrigin::default(),
+        }
+    }
+}
+
+// Empties the puzzle entity, spawning it the first time. Any other entity with a puzzle is
+// despawned, so whatever raced to make one doesn't leave the game without a board.
+fn respawn_puzzle(world: &mut World) {
+    let existing = world
+        .get_resource::<PuzzleEntity>()
+        .map(|e| e.0)
+        .filter(|&e| world.get_entity(e).is_ok());
+    let strays = world
+        .query_filtered::<Entity, With<Puzzle>>()
+        .iter(world)
+        .filter(|&e| Some(e) != existing)
+        .collect::<Vec<_>>();
+    for entity in strays {
+        warn!("despawning stray puzzle entity {entity}");
+        world.entity_mut(entity).despawn_recursive();
+    }
+    match existing {
+        Some(entity) => {
+            world.entity_mut(entity).insert(PuzzleBundle::default());
+        }
+        None => {
+            let entity = world.spawn(PuzzleBundle::default()).id();
+            world.insert_resource(PuzzleEntity(entity));
+        }
+    }
+}
+
+fn check_puzzle_singleton(puzzle_entity: Res<PuzzleEntity>, q_puzzle: Query<Entity, With<Puzzle>>) {
+    let strays = q_puzzle
+        .iter()
+        .filter(|&e| e != puzzle_entity.0)
+        .collect::<Vec<_>>();
+    debug_assert!(
+        strays.is_empty(),
+        "puzzle entities besides {}: {strays:?}",
+        puzzle_entity.0
+    );
 }
 
 #[derive(Event, Debug)]
@@ -560,7 +1016,14 @@ struct AddRow {
 
 #[derive(Event, Debug)]
 struct AddClue {
-    clue: Handle<DynPuzzleClue>,
+    clue: DynPuzzleClue,
+    archived: bool,
+}
+
+// Takes a clue out of the puzzle, along with its entry in the cluebox.
+#[derive(Event, Debug)]
+struct RemoveClue {
+    clue: Entity,
 }
 
 #[derive(Event, Debug, Reflect, Clone)]
@@ -568,15 +1031,59 @@ struct UpdateCellIndex {
     index: CellLocIndex,
     op: UpdateCellIndexOperation,
     explanation: Option<ClueExplanation>,
+    // Updates from one gesture, like a sweep or applying every pending inference, share an id, and
+    // undo takes them back together.
+    gesture: Option<u32>,
 }
 
 impl UpdateCellIndex {
+    // An update the player asked for directly, whether by pointer or by keyboard.
+    fn manual(index: CellLocIndex, op: UpdateCellIndexOperation) -> Self {
+        UpdateCellIndex {
+            index,
+            op,
+            explanation: None,
+            gesture: None,
+        }
+    }
+
+    fn in_gesture(mut self, gesture: u32) -> Self {
+        self.gesture = Some(gesture);
+        self
+    }
+
     fn with_explanation(mut self, explanation: impl Into<ClueExplanation>) -> Self {
         self.explanation = Some(explanation.into());
         self
     }
 }
 
+// Gesture ids aren't reused, so two gestures in a row never run together in the undo history.
+#[derive(Resource, Debug, Default)]
+struct GestureIds(u32);
+
+impl GestureIds {
+    fn next(&mut self) -> u32 {
+        self.0 = self.0.wrapping_add(1);
+        self.0
+    }
+}
+
+// A player's update that changed the puzzle, as opposed to one from a clue.
+#[derive(Event, Debug)]
+struct AppliedCellUpdate {
+    index: CellLocIndex,
+    op: UpdateCellIndexOperation,
+    // The update ruled out the cell's answer.
+    mistake: bool,
+}
+
+#[derive(Event, Debug)]
+struct RejectedCellUpdate {
+    index: CellLocIndex,
+    reason: String,
+}
+
 #[derive(Event, Debug)]
 struct UpdateCellDisplay {
     loc: CellLoc,
@@ -586,7 +1093,10 @@ struct UpdateCellDisplay {
 struct DragUI;
 
 #[derive(Reflect, Debug, Component)]
-struct DragUITarget(UpdateCellIndexOperation);
+struct DragUITarget {
+    op: UpdateCellIndexOperation,
+    available: bool,
+}
 
 #[derive(Reflect, Debug, Component, Default)]
 struct DragTarget {
@@ -608,7 +1118,22 @@ impl DragTarget {
 fn spawn_top_buttons(ev: Trigger<OnAdd, DisplayButtonbox>, mut commands: Commands) {
     commands.entity(ev.entity()).with_children(|parent| {
         use TopButtonAction as B;
-        for action in [B::Undo, B::Redo, B::Clue] {
+        for action in [
+            B::Undo,
+            B::Redo,
+            B::Clue,
+            B::Hint,
+            B::Log,
+            B::History,
+            B::Replay,
+            B::Export,
+            B::Notes,
+            B::Save,
+            B::Share,
+            B::Restart,
+            B::NewPuzzle,
+            B::Menu,
+        ] {
             parent
                 .spawn((
                     DisplayTopButton(action),
@@ -628,70 +1153,45 @@ fn spawn_row(
     mut new_clue_tx: EventWriter<AddClue>,
     time: Res<Time>,
     mut config: ResMut<PuzzleSpawn>,
-    puzzle: Single<&Puzzle>,
-    mut rng: ResMut<SeededRng>,
-    mut update_cell_tx: EventWriter<UpdateCellIndex>,
-    mut clue_assets: ResMut<Assets<DynPuzzleClue>>,
+    q_puzzle: Single<(&Puzzle, &mut PuzzleReveals, &mut PuzzleOrigin)>,
+    (mut update_cell_tx, mut gestures): (EventWriter<UpdateCellIndex>, ResMut<GestureIds>),
+    mut intro_tx: EventWriter<IntroduceReveal>,
     asset_server: Res<AssetServer>,
     mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
 ) {
-    // static LENGTH_SAMPLE: &[usize] = &[4, 5, 5, 5, 5, 6, 6, 7];
     config.timer.tick(time.delta());
     if config.timer.finished() {
-        if puzzle.n_rows() < 5 {
-            // let len = LENGTH_SAMPLE.choose(&mut rng.0).cloned().unwrap();
-            let len = 5;
-            let tileset = config.tileset_pool.pop().unwrap();
-            let image = asset_server.load(tileset.asset_path);
-            let layout = TextureAtlasLayout::from_grid(
-                UVec2::new(tileset.tile_size, tileset.tile_size),
-                tileset.columns,
-                tileset.rows,
-                None,
-                None,
-            );
-            let atlas_len = layout.len();
-            let layout_handle = texture_atlas_layouts.add(layout);
-            let row = PuzzleRow::new_shuffled(
-                &mut rng.0,
-                len,
-                image.clone(),
-                layout_handle.clone(),
-                atlas_len,
-                tileset.shuffle,
-            );
+        if let Some(mut row) = config.rows.pop() {
+            let tileset = row.tileset();
+            let image = tileset.image(&asset_server);
+            let layout_handle = texture_atlas_layouts.add(tileset.atlas_layout());
+            row.set_display_assets(image, layout_handle);
             new_row_tx.send(AddRow { row });
-        } else if config.show_clues > 0 {
-            config.show_clues -= 1;
-            if config.show_clues == 0 {
-                let mut tree = petgraph::Graph::new();
-                let root = tree.add_node((*puzzle).clone());
-                commands.spawn(UndoTree { tree, root });
-                commands.spawn(UndoTreeLocation { current: root });
-
-                let loc = CellLoc {
-                    row: puzzle.random_row(&mut rng.0),
-                    col: puzzle.random_column(&mut rng.0),
-                };
-                let index = puzzle.answer_at(loc).decay_to_ind();
-                update_cell_tx.send(UpdateCellIndex {
-                    index,
-                    op: UpdateCellIndexOperation::Solo,
-                    explanation: None,
-                });
-            }
-            // let (cluebox, cluebox_fit) = q_cluebox.single();
-            let Some(clue): Option<Handle<DynPuzzleClue>> = (try {
-                match rng.0.random_range(0..3) {
-                    0 => clue_assets.add(SameColumnClue::new_random(&mut rng.0, &puzzle)?),
-                    _ => clue_assets.add(AdjacentColumnClue::new_random(&mut rng.0, &puzzle)?),
-                    // 2 => clue_assets.add(BetweenColumnsClue::new_random(&mut rng.0, &puzzle)?),
-                    // _ => unreachable!(),
+        } else if !config.history_spawned {
+            config.history_spawned = true;
+            let (puzzle, mut reveals, mut origin) = q_puzzle.into_inner();
+            reveals.0.clone_from(&config.reveals);
+            origin.0.clone_from(&config.metadata);
+            // A saved game's history already starts from its reveals.
+            let (tree, location) = match config.undo.take() {
+                Some(saved) => saved.restore(puzzle),
+                None => {
+                    // One action, so stepping back through history doesn't take the reveals
+                    // away one at a time.
+                    let gesture = gestures.next();
+                    for &index in &config.reveals {
+                        update_cell_tx.send(index.as_solo().in_gesture(gesture));
+                        intro_tx.send(IntroduceReveal { index });
+                    }
+                    let (mut tree, location) = UndoTree::new(puzzle.clone());
+                    tree.reveal_gesture = Some(gesture);
+                    (tree, location)
                 }
-            }) else {
-                return;
             };
-            new_clue_tx.send(AddClue { clue });
+            commands.spawn(tree);
+            commands.spawn(location);
+        } else if let Some((clue, archived)) = config.clues.pop() {
+            new_clue_tx.send(AddClue { clue, archived });
         }
     }
 }
@@ -716,6 +1216,21 @@ fn assign_random_color(
     }
 }
 
+// SplitMix64's output function, which spreads nearby inputs like consecutive row numbers apart.
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Each row's colors come from their own generator, so they only depend on the puzzle's palette
+// seed and the row number, and not on whatever else drew from the puzzle's RNG first.
+fn row_colors(n_colors: usize, palette_seed: u64, row: LRow) -> Vec<Color> {
+    let mut rng = ChaCha8Rng::seed_from_u64(splitmix64(palette_seed.wrapping_add(row.0 as u64)));
+    random_colors(n_colors, &mut rng)
+}
+
 fn random_colors<R: Rng>(n_colors: usize, rng: &mut R) -> Vec<Color> {
     let n_samples = n_colors * 3;
     let saturation_dist = rand::distr::Uniform::new(0.5, 0.9).unwrap();
@@ -748,6 +1263,62 @@ struct Tileset {
     rows: u32,
 }
 
+impl Tileset {
+    fn len(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    fn by_asset_path(asset_path: &str) -> Option<&'static Tileset> {
+        TILESETS
+            .iter()
+            .chain([&PROCEDURAL_TILESET])
+            .find(|t| t.asset_path == asset_path)
+    }
+
+    fn image(&self, asset_server: &AssetServer) -> Handle<Image> {
+        if self.asset_path == procedural::ASSET_PATH {
+            procedural::IMAGE_HANDLE
+        } else {
+            asset_server.load(self.asset_path)
+        }
+    }
+
+    fn atlas_layout(&self) -> TextureAtlasLayout {
+        TextureAtlasLayout::from_grid(
+            UVec2::new(self.tile_size, self.tile_size),
+            self.columns,
+            self.rows,
+            None,
+            None,
+        )
+    }
+}
+
+// Tilesets are saved by asset path and resolved against TILESETS when loaded.
+impl serde::Serialize for Tileset {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.asset_path)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Tileset {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let asset_path = String::deserialize(deserializer)?;
+        Tileset::by_asset_path(&asset_path)
+            .cloned()
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown tileset {asset_path:?}")))
+    }
+}
+
+// Used for rows that none of the drawn tilesets are long enough for.
+static PROCEDURAL_TILESET: Tileset = Tileset {
+    asset_path: procedural::ASSET_PATH,
+    shuffle: true,
+    tile_size: procedural::TILE_SIZE,
+    columns: procedural::N_TILES,
+    rows: 1,
+};
+
 static TILESETS: [Tileset; 6] = [
     Tileset {
         asset_path: "foods.png",
@@ -851,6 +1422,7 @@ fn add_row(
                                                 DisplayCellButton {
                                                     index: CellLocIndex { loc, index },
                                                 },
+                                                Swatch::new(CellLocIndex { loc, index }),
                                                 HoverAnimationBundle::new(cell_player),
                                             ))
                                             .with_child((
@@ -866,6 +1438,12 @@ fn add_row(
                                     }
                                 });
                         }
+                        row_spawner.spawn((
+                            FitWithinBundle::new(),
+                            DisplayRowCounter { row },
+                            Text2d::default(),
+                            NO_PICK,
+                        ));
                     });
             });
 
@@ -879,22 +1457,63 @@ fn add_row(
 
 fn add_clue(
     mut commands: Commands,
-    mut reader: EventReader<AddClue>,
-    mut q_puzzle: Single<(&Puzzle, &mut PuzzleClues)>,
+    // Drained rather than read, since the clue entities take ownership of the clues.
+    mut events: ResMut<Events<AddClue>>,
+    q_puzzle: Single<&Puzzle>,
+    q_cluebox: Single<(Entity, &FitWithin), (With<DisplayCluebox>, With<AnimationPlayer>)>,
+    q_clues: Query<&PuzzleClueComponent>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    let puzzle = *q_puzzle;
+    let cluebox_e_fit = *q_cluebox;
+    // Clues added this frame aren't spawned yet, so they're checked against each other here.
+    let mut accepted = Vec::<(DynPuzzleClue, bool)>::new();
+    for AddClue { clue, archived } in events.drain() {
+        let existing = q_clues
+            .iter()
+            .map(|c| &c.clue)
+            .chain(accepted.iter().map(|(clue, _)| clue));
+        if let Err(e) = clue.validate(puzzle, existing) {
+            toast_tx.send(ShowToast::error(format!(
+                "Rejected {:?} clue: it {e}.",
+                clue.kind()
+            )));
+            continue;
+        }
+        accepted.push((clue, archived));
+    }
+    if accepted.is_empty() {
+        return;
+    }
+    commands.entity(cluebox_e_fit.0).with_children(|parent| {
+        for (clue, archived) in accepted {
+            let mut clue_e = parent.spawn((
+                PuzzleClueComponent::new(clue),
+                FitWithinBundle::new(),
+                DisplayClue,
+                ExplanationBounceAnimationBundle::new(cluebox_e_fit.0),
+            ));
+            if archived {
+                clue_e.insert(ArchivedClue);
+            }
+        }
+    });
+    cluebox_e_fit.refresh_rect(&mut commands);
+}
+
+fn remove_clue(
+    mut commands: Commands,
+    mut reader: EventReader<RemoveClue>,
     q_cluebox: Single<(Entity, &FitWithin), (With<DisplayCluebox>, With<AnimationPlayer>)>,
+    q_clues: Query<(), With<PuzzleClueComponent>>,
 ) {
-    let (_puzzle, ref mut puzzle_clues) = *q_puzzle;
     let cluebox_e_fit = *q_cluebox;
     let mut updated = false;
-    for AddClue { clue } in reader.read() {
-        puzzle_clues.clues.push(clue.clone());
-        commands.entity(cluebox_e_fit.0).with_child((
-            PuzzleClueComponent::new(clue.clone_weak()),
-            FitWithinBundle::new(),
-            DisplayClue,
-            ExplanationBounceAnimationBundle::new(cluebox_e_fit.0),
-        ));
-        updated = true;
+    for &RemoveClue { clue } in reader.read() {
+        if q_clues.contains(clue) {
+            commands.entity(clue).despawn_recursive();
+            updated = true;
+        }
     }
     if updated {
         cluebox_e_fit.refresh_rect(&mut commands);
@@ -937,24 +1556,17 @@ fn interact_drag_ui_move(
     let Some(drag_target) = q_target.iter().next() else {
         return;
     };
-    for (mut transform, ui_target) in &mut q_transform {
-        let scale = if drag_target.op == Some(ui_target.0) {
-            1.25
-        } else {
-            1.
-        };
-        transform.scale.x = scale;
-        transform.scale.y = scale;
-    }
+    highlight_operation(drag_target.op, &mut q_transform);
 }
 
 fn show_clues(
     mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
-    q_clues: Single<&PuzzleClues>,
     q_puzzle: Single<&Puzzle>,
-    clues: Res<Assets<DynPuzzleClue>>,
+    q_cluebox: Single<&Children, With<DisplayCluebox>>,
+    q_clues: Query<&PuzzleClueComponent>,
     mut commands: Commands,
-    mut clue_state: ResMut<NextState<ClueExplanationState>>,
+    mut queue: ResMut<ExplanationQueue>,
+    mut modals: ResMut<ModalStack>,
 ) {
     let show_clue = {
         let mut seen = false;
@@ -969,62 +1581,246 @@ fn show_clues(
         return;
     }
 
-    let puzzle = *q_puzzle;
-    let mut to_enact = None;
-    for clue_handle in q_clues.clues.iter() {
-        let Some(clue) = clues.get(clue_handle.id()) else {
+    // Clues are queued in the order they sit in the cluebox, and the first is shown.
+    queue.entries = available_deductions(*q_puzzle, *q_cluebox, &q_clues, &mut commands);
+    queue.current = 0;
+    if let Some((clue, update)) = queue.entries.first().cloned() {
+        commands.spawn(ExplainClueComponent { clue, update });
+        modals.push(Modal::Explanation);
+        // writer.send(ev);
+    }
+}
+
+// What the last scan of the cluebox for deductions did, for watching the solver in the inspector.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct LastClueScan {
+    // The clue whose deduction comes first, which is the one explained or hinted at.
+    clue: Option<Entity>,
+    clues_scanned: usize,
+    shifts_tested: usize,
+    deductions_found: usize,
+}
+
+// What inference did after the last update the player or a clue made, and how many steps it has
+// taken over the whole puzzle.
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct LastInferenceRun {
+    steps: usize,
+    updates: usize,
+    total_runs: usize,
+    total_steps: usize,
+}
+
+fn available_deductions(
+    puzzle: &Puzzle,
+    cluebox: &Children,
+    q_clues: &Query<&PuzzleClueComponent>,
+    commands: &mut Commands,
+) -> Vec<(Entity, UpdateCellIndex)> {
+    let mut deductions = vec![];
+    let mut scan = LastClueScan::default();
+    for &entity in cluebox.iter() {
+        let Ok(PuzzleClueComponent { clue, .. }) = q_clues.get(entity) else {
             continue;
         };
-        let next = clue.advance_puzzle(puzzle);
+        let next = clue.advance_puzzle_counted(puzzle, &mut scan.shifts_tested);
+        scan.clues_scanned += 1;
         info!("next from {clue:?} => {next:?}");
         if let Some(next) = next {
-            to_enact = Some((clue_handle, next));
-            break;
+            deductions.push((entity, next));
         }
     }
-    if let Some((clue, update)) = to_enact {
-        let clue = clue.clone();
-        commands.spawn(ExplainClueComponent { clue, update });
-        clue_state.set(ClueExplanationState::Shown);
-        // writer.send(ev);
-    }
+    scan.clue = deductions.first().map(|&(entity, _)| entity);
+    scan.deductions_found = deductions.len();
+    commands.insert_resource(scan);
+    deductions
 }
 
-fn clue_explanation_clicked(
-    _ev: Trigger<Pointer<Up>>,
-    q_explanation: Query<(Entity, &ExplainClueComponent), With<FitClicked>>,
-    mut clue_state: ResMut<NextState<ClueExplanationState>>,
+// Points at the clue that would be explained and the candidates its reasoning mentions, without
+// saying what follows; the Clue button still explains and applies it.
+fn show_hint(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    q_puzzle: Single<&Puzzle>,
+    q_cluebox: Single<&Children, With<DisplayCluebox>>,
+    q_clues: Query<&PuzzleClueComponent>,
+    crossref: Res<CrossRefIndex>,
+    mut toast_tx: EventWriter<ShowToast>,
+    mut commands: Commands,
 ) {
-    // info!("clicked in ?");
-    let Ok(_) = q_explanation.get_single() else {
+    let show_hint = {
+        let mut seen = false;
+        for &FitClickedEvent(action) in ev_rx.read() {
+            if let TopButtonAction::Hint = action {
+                seen = true;
+            }
+        }
+        seen
+    };
+    if !show_hint {
+        return;
+    }
+
+    let deductions = available_deductions(*q_puzzle, *q_cluebox, &q_clues, &mut commands);
+    let Some((clue, update)) = deductions.into_iter().next() else {
+        toast_tx.send(ShowToast::info(
+            "None of the clues can be applied right now.",
+        ));
         return;
     };
-    // info!("clicked next {update:#?}");
-    clue_state.set(ClueExplanationState::NotShown);
+    let cells = update
+        .explanation
+        .map(|explanation| explanation.cells())
+        .unwrap_or_default();
+    hint_hilight(&mut commands, &crossref, clue, cells);
 }
 
-fn cell_clicked_down(
-    ev: Trigger<OnInsert, FitClicked>,
-    q_camera: Single<&Camera>,
-    q_window: Query<&Window, With<PrimaryWindow>>,
-    q_cell: Query<(&DisplayCellButton, &GlobalTransform, &Sprite), With<FitClicked>>,
-    // q_ui: Query<Entity, With<DragUI>>,
+// Highlights a clue and some of its candidates until the puzzle next changes.
+fn hint_hilight(
+    commands: &mut Commands,
+    crossref: &CrossRefIndex,
+    clue: Entity,
+    cells: impl IntoIterator<Item = CellLocIndex>,
+) {
+    if let Some(mut clue) = commands.get_entity(clue) {
+        clue.insert((ExplanationHilight, HintHilight));
+    }
+    for cell in cells.into_iter().filter_map(|index| crossref.button(index)) {
+        commands
+            .entity(cell)
+            .insert((ExplanationHilight, HintHilight));
+    }
+}
+
+fn clear_hint(
+    puzzle: Single<Ref<Puzzle>>,
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    q_hint: Query<Entity, With<HintHilight>>,
     mut commands: Commands,
 ) {
-    let Ok((button, &transform, sprite)) = q_cell.get(ev.entity()) else {
-        return;
-    };
-    let Some(logical_viewport) = q_camera.logical_viewport_rect() else {
-        return;
+    // Asking for another hint starts over too, in case the clues changed.
+    let hint_asked = {
+        let mut seen = false;
+        for &FitClickedEvent(action) in ev_rx.read() {
+            if let TopButtonAction::Hint = action {
+                seen = true;
+            }
+        }
+        seen
     };
-    let Some(window) = q_window.iter().next() else {
+    if !puzzle.is_changed() && !hint_asked {
         return;
-    };
-    let Some(cursor_loc) = window.cursor_position() else {
+    }
+    for entity in &q_hint {
+        commands
+            .entity(entity)
+            .remove::<(ExplanationHilight, HintHilight)>();
+    }
+}
+
+fn explanation_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut commands: Commands,
+    q_explanation: Query<Entity, With<ExplainClueComponent>>,
+    q_hilight: Query<Entity, With<ExplanationHilight>>,
+    q_arrows: Query<Entity, With<ExplanationArrows>>,
+    mut queue: ResMut<ExplanationQueue>,
+    mut modals: ResMut<ModalStack>,
+) {
+    if keys.just_pressed(KeyCode::Enter) {
+        modals.pop(Modal::Explanation);
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        for entity in &q_explanation {
+            commands.entity(entity).insert(ExplanationDismissed);
+        }
+        modals.pop(Modal::Explanation);
+        return;
+    }
+    let step = if keys.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowUp]) {
+        -1
+    } else if keys.any_just_pressed([KeyCode::ArrowRight, KeyCode::ArrowDown]) {
+        1
+    } else {
+        return;
+    };
+    let n_entries = queue.entries.len();
+    if n_entries < 2 {
+        return;
+    }
+    queue.current = (queue.current + n_entries).saturating_add_signed(step) % n_entries;
+    for entity in &q_explanation {
+        commands.entity(entity).despawn_recursive();
+    }
+    clear_explanation_display(&mut commands, &q_hilight, &q_arrows);
+    let (clue, update) = queue.entries[queue.current].clone();
+    commands.spawn(ExplainClueComponent { clue, update });
+}
+
+fn clue_explanation_clicked(
+    _ev: Trigger<Pointer<Up>>,
+    q_explanation: Query<(Entity, &ExplainClueComponent), With<FitClicked>>,
+    mut modals: ResMut<ModalStack>,
+) {
+    // info!("clicked in ?");
+    let Ok(_) = q_explanation.get_single() else {
+        return;
+    };
+    if modals.top() != Some(Modal::Explanation) {
+        return;
+    }
+    // info!("clicked next {update:#?}");
+    modals.pop(Modal::Explanation);
+}
+
+// Goes through the camera rather than assuming the viewport fills the window at a scale factor of 1.
+fn cursor_to_world(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor_loc: Vec2,
+) -> Option<Vec2> {
+    let viewport_min = camera.logical_viewport_rect()?.min;
+    camera
+        .viewport_to_world_2d(camera_transform, cursor_loc - viewport_min)
+        .ok()
+}
+
+fn cell_clicked_down(
+    ev: Trigger<OnInsert, FitClicked>,
+    q_camera: Single<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    (q_window, touch, modals): (
+        Query<&Window, With<PrimaryWindow>>,
+        Res<TouchGesture>,
+        Res<ModalStack>,
+    ),
+    q_cell: Query<(&DisplayCellButton, &GlobalTransform, &Sprite), With<FitClicked>>,
+    q_puzzle: Single<&Puzzle>,
+    (theme, settings): (Res<OperationTheme>, Res<Settings>),
+    mut commands: Commands,
+) {
+    let Ok((button, &transform, sprite)) = q_cell.get(ev.entity()) else {
+        return;
+    };
+    // Nothing opens on the board while something's shown over it.
+    if !modals.is_empty() {
+        return;
+    }
+    let puzzle = *q_puzzle;
+    // A long press opens the menu under the finger; otherwise it's under the cursor.
+    let Some(cursor_loc) = touch.menu_position(ev.entity()).or_else(|| {
+        q_window
+            .iter()
+            .next()
+            .and_then(|window| window.cursor_position())
+    }) else {
+        return;
+    };
+    let (camera, camera_transform) = *q_camera;
+    let Some(translate) = cursor_to_world(camera, camera_transform, cursor_loc) else {
         return;
     };
-    let window_center = logical_viewport.center();
-    let translate = (cursor_loc - window_center) * Vec2::new(1., -1.);
     commands.spawn((
         Sprite::from_color(sprite.color.with_alpha(0.5), Vec2::new(100., 100.)),
         Transform::from_xyz(translate.x, translate.y, 15.),
@@ -1033,70 +1829,140 @@ fn cell_clicked_down(
     ));
     let mut transform = transform.compute_transform();
     transform.translation.z += 10.;
-    commands
-        .spawn((
-            Sprite::from_color(Color::hsla(0., 0., 0.5, 0.8), Vec2::new(200., 200.)),
-            transform,
-            DragUI,
-        ))
-        .with_children(|parent| {
-            parent.spawn((
-                Text2d::new("Clear"),
-                Transform::from_xyz(50., 0., 1.),
-                DragUITarget(UpdateCellIndexOperation::Clear),
-            ));
-            parent.spawn((
-                Text2d::new("Set"),
-                Transform::from_xyz(0., -50., 1.),
-                DragUITarget(UpdateCellIndexOperation::Set),
-            ));
+    spawn_operation_menu(
+        &mut commands,
+        transform,
+        puzzle,
+        &theme,
+        &settings.radial,
+        button.index,
+    );
+}
+
+const UNAVAILABLE_OPERATION_COLOR: Color = Color::hsla(0., 0., 0.3, 1.);
+
+// Operations that wouldn't change the candidate are still shown in their place, but greyed out and
+// never picked.
+fn operation_available(puzzle: &Puzzle, index: CellLocIndex, op: UpdateCellIndexOperation) -> bool {
+    puzzle
+        .cell_selection(index.loc)
+        .would_change(index.index, op)
+}
+
+fn spawn_operation_menu<'a>(
+    commands: &'a mut Commands,
+    transform: Transform,
+    puzzle: &Puzzle,
+    theme: &OperationTheme,
+    layout: &RadialLayout,
+    index: CellLocIndex,
+) -> EntityCommands<'a> {
+    let mut menu = commands.spawn((
+        Sprite::from_color(Color::hsla(0., 0., 0.5, 0.8), Vec2::new(200., 200.)),
+        transform,
+        DragUI,
+    ));
+    menu.with_children(|parent| {
+        if layout.shows_cancel() {
             parent.spawn((
-                Text2d::new("Toggle"),
-                Transform::from_xyz(-50., 0., 1.),
-                DragUITarget(UpdateCellIndexOperation::Toggle),
+                Text2d::new("cancel"),
+                TextColor(UNAVAILABLE_OPERATION_COLOR),
+                Transform::from_xyz(0., 0., 1.),
             ));
+        }
+        for (op, offset) in layout.labels() {
+            let available = operation_available(puzzle, index, op);
+            let color = if available {
+                theme.color(op)
+            } else {
+                UNAVAILABLE_OPERATION_COLOR
+            };
             parent.spawn((
-                Text2d::new("Solo"),
-                Transform::from_xyz(0., 50., 1.),
-                DragUITarget(UpdateCellIndexOperation::Solo),
+                Text2d::new(theme.label(op)),
+                TextColor(color),
+                Transform::from_translation(offset.extend(1.)),
+                DragUITarget { op, available },
             ));
-        });
+        }
+    });
+    menu
+}
+
+fn highlight_operation(
+    selected: Option<UpdateCellIndexOperation>,
+    q_target: &mut Query<(&mut Transform, &DragUITarget)>,
+) {
+    for (mut transform, ui_target) in q_target {
+        let scale = if selected == Some(ui_target.op) {
+            1.25
+        } else {
+            1.
+        };
+        transform.scale.x = scale;
+        transform.scale.y = scale;
+    }
+}
+
+// The radial menu operation a drag points at. Window positions are y-down like fit space, so they
+// go through the same conversion to compare against the menu's world-space layout.
+fn drag_operation(
+    layout: &RadialLayout,
+    start: Vec2,
+    latest: Vec2,
+) -> Option<UpdateCellIndexOperation> {
+    layout.pick(fit_to_world(latest, start))
 }
 
 fn cell_continue_drag(
     ev: Trigger<Pointer<Move>>,
-    q_camera: Single<&Camera>,
-    mut q_transform: Query<(&mut Transform, &mut DragTarget)>,
+    q_camera: Single<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    q_puzzle: Single<&Puzzle>,
+    settings: Res<Settings>,
+    mut q_transform: Query<(&mut Transform, &mut DragTarget, &DisplayCellButton)>,
 ) {
-    let Some(logical_viewport) = q_camera.logical_viewport_rect() else {
+    let cursor_loc = ev.pointer_location.position;
+    let (camera, camera_transform) = *q_camera;
+    let Some(translate) = cursor_to_world(camera, camera_transform, cursor_loc) else {
         return;
     };
-    let cursor_loc = ev.pointer_location.position;
-    let window_center = logical_viewport.center();
-    let translate = (cursor_loc - window_center) * Vec2::new(1., -1.);
-    for (mut transform, mut drag_target) in &mut q_transform {
+    for (mut transform, mut drag_target, button) in &mut q_transform {
         transform.translation.x = translate.x;
         transform.translation.y = translate.y;
         drag_target.latest = cursor_loc;
-        let distance = drag_target.start.distance(drag_target.latest);
-        let angle = (drag_target.start - drag_target.latest).to_angle() + std::f32::consts::PI;
-        let sectors = 4;
-        let frac_adjust = 1. / sectors as f32 / 2.;
-        let pre_angle_frac = angle / std::f32::consts::TAU;
-        let angle_frac = (pre_angle_frac + frac_adjust) % 1.;
-        let sector = (angle_frac * sectors as f32).floor();
-        // info!("drag release distance={distance} sector={sector}");
-        drag_target.op = if distance > 10. && distance < 125. {
-            match sector as u8 {
-                0 => Some(UpdateCellIndexOperation::Clear),
-                1 => Some(UpdateCellIndexOperation::Set),
-                2 => Some(UpdateCellIndexOperation::Toggle),
-                3 => Some(UpdateCellIndexOperation::Solo),
-                _ => None,
+        drag_target.op = drag_operation(&settings.radial, drag_target.start, drag_target.latest)
+            .filter(|&op| operation_available(&q_puzzle, button.index, op));
+    }
+}
+
+// Sweeping across candidates with the secondary button clears each one it passes, like rubbing
+// out pencil marks in one stroke, and undo takes back the whole sweep at once.
+fn sweep_clear_candidates(
+    mut swept_rx: EventReader<FitSweptEvent>,
+    q_button: Query<&DisplayCellButton>,
+    puzzle: Single<&Puzzle>,
+    mut gestures: ResMut<GestureIds>,
+    // The sweep in progress, and the gesture id it was given.
+    mut current: Local<Option<(u32, u32)>>,
+    mut writer: EventWriter<UpdateCellIndex>,
+) {
+    for &FitSweptEvent { entity, sweep } in swept_rx.read() {
+        let Ok(&DisplayCellButton { index }) = q_button.get(entity) else {
+            continue;
+        };
+        if !puzzle.cell_selection(index.loc).is_enabled(index.index) {
+            continue;
+        }
+        let gesture = match *current {
+            Some((nr, gesture)) if nr == sweep => gesture,
+            _ => {
+                let gesture = gestures.next();
+                *current = Some((sweep, gesture));
+                gesture
             }
-        } else {
-            None
         };
+        writer.send(
+            UpdateCellIndex::manual(index, UpdateCellIndexOperation::Clear).in_gesture(gesture),
+        );
     }
 }
 
@@ -1106,6 +1972,7 @@ fn cell_release_drag(
     mut commands: Commands,
     q_cell: Query<(Entity, &DisplayCellButton, &DragTarget)>,
     q_dragui: Query<Entity, With<DragUI>>,
+    mut preview: ResMut<CandidatePreview>,
     mut writer: EventWriter<UpdateCellIndex>,
 ) {
     let Ok(_) = q_orig.get(ev.entity()) else {
@@ -1113,11 +1980,11 @@ fn cell_release_drag(
     };
     for (entity, &DisplayCellButton { index }, drag_target) in &q_cell {
         if let Some(op) = drag_target.op {
-            writer.send(UpdateCellIndex {
-                index,
-                op,
-                explanation: None,
-            });
+            writer.send(UpdateCellIndex::manual(index, op));
+        } else if let Some(previewed) = preview.0.filter(|p| p.loc == index.loc) {
+            // A plain click on a cell with a wheel preview commits to the previewed candidate.
+            writer.send(previewed.as_solo());
+            preview.0 = None;
         }
         commands.entity(entity).despawn_recursive();
     }
@@ -1126,35 +1993,458 @@ fn cell_release_drag(
     }
 }
 
+impl SavedAnimationNode for ShakeEdge {
+    type AnimatedFrom = Transform;
+
+    fn node_mut(&mut self) -> &mut Option<NodeIndex> {
+        &mut self.0
+    }
+}
+
+fn reject_cell_update(
+    mut rejected_rx: EventReader<RejectedCellUpdate>,
+    cell_entities: Res<CellEntities>,
+    q_button: Query<(), (With<ShakeEdge>, Without<MagnifiedButton>)>,
+    mut toast_tx: EventWriter<ShowToast>,
+    mut commands: Commands,
+) {
+    for ev in rejected_rx.read() {
+        toast_tx.send(ShowToast::error(ev.reason.clone()));
+        let Some(entity) = cell_entities
+            .buttons_showing(ev.index)
+            .find(|&entity| q_button.contains(entity))
+        else {
+            continue;
+        };
+        AnimatorPlugin::<ShakeEdge>::start_animation(
+            &mut commands,
+            entity,
+            RepeatAnimation::Never,
+            |_transform, target| {
+                let mut clip = AnimationClip::default();
+                clip.add_curve_to_target(
+                    target,
+                    AnimatableCurve::new(
+                        animated_field!(Transform::rotation),
+                        EasingCurve::new(
+                            Quat::from_rotation_z(0.3),
+                            Quat::IDENTITY,
+                            EaseFunction::ElasticOut,
+                        )
+                        .reparametrize_linear(interval(0., 0.5).unwrap())
+                        .unwrap(),
+                    ),
+                );
+                clip
+            },
+        );
+    }
+}
+
+impl SavedAnimationNode for FlashEdge {
+    type AnimatedFrom = Sprite;
+
+    fn node_mut(&mut self) -> &mut Option<NodeIndex> {
+        &mut self.0
+    }
+}
+
+const APPLIED_FLASH_ALPHA: f32 = 0.7;
+const APPLIED_FLASH_FINISHED: &str = "applied flash";
+
+// Confirms an update landed, which matters most when it came from releasing a drag over the
+// radial menu instead of clicking a visible button. With `MistakeCheck::Warn`, a move that ruled
+// out the answer flashes in the invalid border color instead.
+fn flash_applied_update(
+    mut applied_rx: EventReader<AppliedCellUpdate>,
+    settings: Res<Settings>,
+    theme: Res<OperationTheme>,
+    cell_entities: Res<CellEntities>,
+    q_button: Query<&AnimationTarget, Without<MagnifiedButton>>,
+    mut commands: Commands,
+) {
+    for ev in applied_rx.read() {
+        let Some((entity, target)) = cell_entities
+            .buttons_showing(ev.index)
+            .find_map(|entity| Some((entity, q_button.get(entity).ok()?)))
+        else {
+            continue;
+        };
+        let color = if ev.mistake && settings.mistake_check == MistakeCheck::Warn {
+            INVALID_CELL_BORDER_COLOR
+        } else {
+            theme.color(ev.op).with_alpha(APPLIED_FLASH_ALPHA)
+        };
+        let flash = commands
+            .spawn((
+                FlashEdge::default(),
+                Sprite::from_color(color, Vec2::new(32., 32.)),
+                Transform::from_xyz(0., 0., 2.),
+                NO_PICK,
+                AnimationTarget {
+                    id: AnimationTargetId(Uuid::new_v4()),
+                    player: target.player,
+                },
+            ))
+            .set_parent(entity)
+            .id();
+        AnimatorPlugin::<FlashEdge>::start_animation(
+            &mut commands,
+            flash,
+            Playback::new(RepeatAnimation::Never).notify_finished(APPLIED_FLASH_FINISHED),
+            |sprite, target| {
+                let mut clip = AnimationClip::default();
+                clip.add_curve_to_target(
+                    target,
+                    AnimatableCurve::new(
+                        ButtonOpacityAnimation,
+                        EasingCurve::new(sprite.color.alpha(), 0., EaseFunction::CubicOut)
+                            .reparametrize_linear(interval(0., 0.3).unwrap())
+                            .unwrap(),
+                    ),
+                );
+                clip
+            },
+        );
+    }
+}
+
+fn despawn_finished_flashes(
+    mut finished_rx: EventReader<AnimationFinished>,
+    q_flash: Query<(), With<FlashEdge>>,
+    mut commands: Commands,
+) {
+    for ev in finished_rx.read() {
+        if ev.tag == APPLIED_FLASH_FINISHED && q_flash.contains(ev.entity) {
+            commands.entity(ev.entity).despawn_recursive();
+        }
+    }
+}
+
+#[derive(Resource, Reflect, Debug, Default)]
+#[reflect(Resource)]
+struct CandidatePreview(Option<CellLocIndex>);
+
+#[derive(Reflect, Debug, Component)]
+struct CandidatePreviewMarker;
+
+fn cycle_candidate_preview(
+    mut ev_rx: EventReader<FitScrolledEvent>,
+    q_button: Query<&DisplayCellButton>,
+    puzzle: Single<&Puzzle>,
+    mut preview: ResMut<CandidatePreview>,
+) {
+    for &FitScrolledEvent { entity, steps } in ev_rx.read() {
+        let Ok(button) = q_button.get(entity) else {
+            continue;
+        };
+        let loc = button.index.loc;
+        let candidates = puzzle.cell_selection(loc).iter_ones().collect::<Vec<_>>();
+        if candidates.is_empty() {
+            continue;
+        }
+        let current = preview
+            .0
+            .filter(|p| p.loc == loc)
+            .and_then(|p| candidates.iter().position(|&i| i == p.index));
+        let next = match current {
+            Some(n) => (n as isize + steps).rem_euclid(candidates.len() as isize) as usize,
+            None => candidates
+                .iter()
+                .position(|&i| i == button.index.index)
+                .unwrap_or(0),
+        };
+        preview.0 = Some(CellLocIndex {
+            loc,
+            index: candidates[next],
+        });
+    }
+}
+
+fn clear_candidate_preview(
+    q_hovered: Query<&DisplayCellButton, With<FitHover>>,
+    puzzle: Single<&Puzzle>,
+    mut preview: ResMut<CandidatePreview>,
+) {
+    let Some(previewed) = preview.0 else {
+        return;
+    };
+    let still_hovered = q_hovered.iter().any(|b| b.index.loc == previewed.loc);
+    if !still_hovered
+        || !puzzle
+            .cell_selection(previewed.loc)
+            .is_enabled(previewed.index)
+    {
+        preview.0 = None;
+    }
+}
+
+fn show_candidate_preview(
+    mut commands: Commands,
+    preview: Res<CandidatePreview>,
+    q_marker: Query<Entity, With<CandidatePreviewMarker>>,
+    cell_entities: Res<CellEntities>,
+    q_board_button: Query<(), Without<MagnifiedButton>>,
+) {
+    if !preview.is_changed() {
+        return;
+    }
+    for entity in &q_marker {
+        commands.entity(entity).despawn_recursive();
+    }
+    let Some(previewed) = preview.0 else {
+        return;
+    };
+    for entity in cell_entities.buttons_showing(previewed) {
+        if q_board_button.contains(entity) {
+            commands.entity(entity).with_child((
+                CandidatePreviewMarker,
+                Sprite::from_color(Color::hsla(60., 1., 0.6, 0.45), Vec2::new(32., 32.)),
+                Transform::from_xyz(0., 0., 2.),
+                NO_PICK,
+            ));
+        }
+    }
+}
+
+// An update that's been applied and displayed, but whose inference hasn't run yet. The undo tree
+// only hears about it once inference has, so undo still takes back both at once.
+#[derive(Debug)]
+struct PendingAction {
+    update: UpdateCellIndex,
+    update_count: usize,
+    to_update: HashSet<CellLoc>,
+    // If the board changes after this (an undo, say), the inference no longer applies to it.
+    applied_at: Tick,
+}
+
+#[derive(Resource, Debug, Default)]
+struct PendingInference {
+    action: Option<PendingAction>,
+    // How long inference took last time, to guess whether the next run fits in this frame.
+    last_duration: Duration,
+}
+
+// Past this much of the frame, inference waits for the next one so the click is drawn first.
+const INFERENCE_FRAME_BUDGET: Duration = Duration::from_millis(8);
+
+const INFERENCE_DUMP_PATH: &str = "inference-dump.txt";
+
 fn cell_update(
     mut puzzle: Single<&mut Puzzle>,
+    settings: Res<Settings>,
     mut update_cell_rx: EventReader<UpdateCellIndex>,
     mut update_display_tx: EventWriter<UpdateCellDisplay>,
     mut undo_tx: EventWriter<PushNewAction>,
+    (mut rejected_tx, mut applied_tx, mut toast_tx): (
+        EventWriter<RejectedCellUpdate>,
+        EventWriter<AppliedCellUpdate>,
+        EventWriter<ShowToast>,
+    ),
+    (mut pending, mut last_inference): (ResMut<PendingInference>, ResMut<LastInferenceRun>),
 ) {
-    let mut all_to_update = HashSet::new();
-    for update @ &UpdateCellIndex { index, op, .. } in update_cell_rx.read() {
-        let puzzle_cell = puzzle.cell_selection_mut(index.loc);
-        let update_count = puzzle_cell.apply(index.index, op);
-        if update_count == 0 {
+    for update in update_cell_rx.read() {
+        // Each update is checked against the board its predecessor's inference left.
+        finish_inference(
+            &mut puzzle,
+            &mut pending,
+            &mut last_inference,
+            settings.debug.max_inference_steps,
+            &mut update_display_tx,
+            &mut undo_tx,
+            &mut toast_tx,
+        );
+        // Updates without an explanation came from the player rather than a clue.
+        let from_player = update.explanation.is_none();
+        let loc = update.index.loc;
+        let allow_empty = !(settings.prevent_emptying_cells && from_player);
+        if let Err(e) = puzzle.check_update(update, allow_empty) {
+            rejected_tx.send(RejectedCellUpdate {
+                index: update.index,
+                reason: format!("Couldn't update the cell: {e}."),
+            });
+            continue;
+        }
+        let mistake = from_player
+            && puzzle.cell_selection(loc).would_remove(
+                update.index.index,
+                update.op,
+                puzzle.answer_at(loc).index.decay_to_ind(),
+            );
+        if mistake && settings.mistake_check == MistakeCheck::Strict {
+            rejected_tx.send(RejectedCellUpdate {
+                index: update.index,
+                reason: "That would rule out this cell's answer.".to_string(),
+            });
             continue;
         }
         let mut to_update = HashSet::new();
-        to_update.insert(index.loc);
-        let inferred_count = puzzle.run_inference(&mut to_update);
-        undo_tx.send(PushNewAction {
-            new_state: puzzle.clone(),
-            action: Action {
+        // Already checked, so the only thing left to skip is an update that changes nothing.
+        let Ok(Some(update_count)) = puzzle.apply_update_without_inference(update, &mut to_update)
+        else {
+            continue;
+        };
+        update_display_tx.send(UpdateCellDisplay { loc });
+        if from_player {
+            applied_tx.send(AppliedCellUpdate {
+                index: update.index,
+                op: update.op,
+                mistake,
+            });
+        }
+        if settings.pause_inference {
+            undo_tx.send(PushNewAction {
+                new_state: puzzle.clone(),
+                action: Action {
+                    update: update.clone(),
+                    update_count,
+                    inferred_count: 0,
+                    joined: vec![],
+                },
+            });
+        } else {
+            pending.action = Some(PendingAction {
                 update: update.clone(),
                 update_count,
-                inferred_count,
-            },
-        });
-        all_to_update.extend(to_update);
+                to_update,
+                applied_at: puzzle.last_changed(),
+            });
+        }
+    }
+}
+
+fn run_pending_inference(
+    mut puzzle: Single<&mut Puzzle>,
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+    mut undo_tx: EventWriter<PushNewAction>,
+    mut toast_tx: EventWriter<ShowToast>,
+    (mut pending, mut last_inference): (ResMut<PendingInference>, ResMut<LastInferenceRun>),
+) {
+    if pending.action.is_none() {
+        return;
     }
-    for loc in all_to_update {
+    let frame_used = time
+        .last_update()
+        .map(|frame_start| frame_start.elapsed())
+        .unwrap_or_default();
+    if frame_used + pending.last_duration > INFERENCE_FRAME_BUDGET {
+        return;
+    }
+    finish_inference(
+        &mut puzzle,
+        &mut pending,
+        &mut last_inference,
+        settings.debug.max_inference_steps,
+        &mut update_display_tx,
+        &mut undo_tx,
+        &mut toast_tx,
+    );
+}
+
+// Whatever was left over from the last frame runs before anything else can look at the board.
+fn run_deferred_inference(
+    mut puzzle: Single<&mut Puzzle>,
+    settings: Res<Settings>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+    mut undo_tx: EventWriter<PushNewAction>,
+    mut toast_tx: EventWriter<ShowToast>,
+    (mut pending, mut last_inference): (ResMut<PendingInference>, ResMut<LastInferenceRun>),
+) {
+    finish_inference(
+        &mut puzzle,
+        &mut pending,
+        &mut last_inference,
+        settings.debug.max_inference_steps,
+        &mut update_display_tx,
+        &mut undo_tx,
+        &mut toast_tx,
+    );
+}
+
+// What's needed to reproduce inference that ran into the step cap: the action, how far inference
+// got, and the board it left.
+fn dump_capped_inference(
+    update: &UpdateCellIndex,
+    inference: &InferenceRun,
+    puzzle: &Puzzle,
+) -> io::Result<()> {
+    let board = ron::ser::to_string_pretty(puzzle, ron::ser::PrettyConfig::default())
+        .map_err(io::Error::other)?;
+    fs::write(
+        INFERENCE_DUMP_PATH,
+        format!("update: {update:?}\ninference: {inference:?}\n\n{board}\n"),
+    )
+}
+
+fn finish_inference(
+    puzzle: &mut Mut<Puzzle>,
+    pending: &mut PendingInference,
+    last_inference: &mut LastInferenceRun,
+    max_steps: usize,
+    update_display_tx: &mut EventWriter<UpdateCellDisplay>,
+    undo_tx: &mut EventWriter<PushNewAction>,
+    toast_tx: &mut EventWriter<ShowToast>,
+) {
+    let Some(PendingAction {
+        update,
+        update_count,
+        mut to_update,
+        applied_at,
+    }) = pending.action.take()
+    else {
+        return;
+    };
+    if puzzle.last_changed() != applied_at {
+        info!("dropping inference from {update:?}, since the board has changed since");
+        return;
+    }
+    let started = Instant::now();
+    let inference = puzzle.run_inference_capped(&mut to_update, max_steps);
+    pending.last_duration = started.elapsed();
+    *last_inference = LastInferenceRun {
+        steps: inference.steps,
+        updates: inference.updates,
+        total_runs: last_inference.total_runs + 1,
+        total_steps: last_inference.total_steps + inference.steps,
+    };
+    if inference.capped {
+        let dumped = match dump_capped_inference(&update, &inference, puzzle) {
+            Ok(()) => format!("; wrote diagnostics to {INFERENCE_DUMP_PATH}"),
+            Err(e) => {
+                warn!("couldn't write {INFERENCE_DUMP_PATH}: {e}");
+                String::new()
+            }
+        };
+        toast_tx.send(ShowToast::error(format!(
+            "Inference stopped after {} steps{dumped}.",
+            inference.steps
+        )));
+    }
+    for loc in to_update {
         update_display_tx.send(UpdateCellDisplay { loc });
     }
+    undo_tx.send(PushNewAction {
+        new_state: puzzle.clone(),
+        action: Action {
+            update,
+            update_count,
+            inferred_count: inference.updates,
+            joined: vec![],
+        },
+    });
+}
+
+fn update_row_counters(
+    puzzle: Single<Ref<Puzzle>>,
+    mut q_counter: Query<(Ref<DisplayRowCounter>, &mut Text2d)>,
+) {
+    for (counter, mut text) in &mut q_counter {
+        if puzzle.is_changed() || counter.is_added() {
+            text.0 = format!("{} left", puzzle.unsolved_in_row(counter.row));
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -1195,6 +2485,7 @@ const CLICKED_BUTTON_BORDER_COLOR: Color = Color::hsla(0., 0., 0.8, 1.);
 const DEFAULT_CELL_BORDER_COLOR: Color = Color::hsla(33., 1., 0.26, 1.);
 // const DEFAULT_CELL_BORDER_COLOR: Color = Color::hsla(0., 0., 0.8, 1.);
 const INVALID_CELL_BORDER_COLOR: Color = Color::hsla(0., 1., 0.5, 1.);
+const COMPLETE_COLUMN_CELL_BORDER_COLOR: Color = Color::hsla(45., 1., 0.45, 1.);
 
 impl animation::SavedAnimationNode for HoverAlphaEdge {
     type AnimatedFrom = Sprite;
@@ -1207,47 +2498,23 @@ impl animation::SavedAnimationNode for HoverAlphaEdge {
 fn cell_update_display(
     puzzle: Single<&Puzzle>,
     mut reader: EventReader<UpdateCellDisplay>,
-    mut q_bg: Query<(&DisplayCell, &mut Sprite), Without<DisplayCellButton>>,
-    q_cell: Query<(Entity, &DisplayCellButton), Without<DisplayCell>>,
+    cell_entities: Res<CellEntities>,
+    mut q_bg: Query<&mut Sprite, (With<DisplayCell>, Without<DisplayCellButton>)>,
     mut commands: Commands,
 ) {
-    let mut bg_map = LazyCell::new(|| {
-        let mut bg_map = HashMap::new();
-        for (cell, sprite) in &mut q_bg {
-            bg_map.insert(cell.loc, sprite);
-        }
-        bg_map
-    });
-    let mut entity_map = LazyCell::new(|| {
-        let mut entity_map = HashMap::<_, Vec<_>>::new();
-        for (entity, &DisplayCellButton { index }) in &q_cell {
-            entity_map
-                .entry(index.loc)
-                .or_default()
-                .push((entity, index));
-        }
-        entity_map
-    });
+    // A cell changing can complete or uncomplete its column, so borders are redone per column.
+    let mut columns = HashSet::new();
     for &UpdateCellDisplay { loc } in reader.read() {
         let sel = puzzle.cell_selection(loc);
-        let Some(buttons) = LazyCell::force_mut(&mut entity_map).get_mut(&loc) else {
-            unreachable!()
-        };
+        let buttons = cell_entities.buttons(loc);
         // info!("updating cell={cell:?}");
-        buttons.sort_by_key(|t| t.0);
         let sel_solo = sel.is_any_solo();
-
-        if let Some(sprite) = LazyCell::force_mut(&mut bg_map).get_mut(&loc) {
-            let color = if !sel.is_enabled(puzzle.answer_at(loc).index.decay_to_ind()) {
-                INVALID_CELL_BORDER_COLOR
-            } else {
-                DEFAULT_CELL_BORDER_COLOR
-            };
-            sprite.color = color;
-        }
+        columns.insert(loc.col);
 
         for (entity, index) in buttons.iter() {
-            let alpha = if sel.is_enabled(index.index) {
+            let alpha = if sel.is_tentative(index.index) {
+                0.5
+            } else if sel.is_enabled(index.index) {
                 1.
             } else if sel_solo.is_some() {
                 0.03
@@ -1275,6 +2542,28 @@ fn cell_update_display(
             );
         }
     }
+
+    for col in columns {
+        let complete = puzzle.is_column_complete(col);
+        for row in puzzle.iter_rows() {
+            let loc = CellLoc { row, col };
+            let Some(mut sprite) = cell_entities
+                .background(loc)
+                .and_then(|bg| q_bg.get_mut(bg).ok())
+            else {
+                continue;
+            };
+            let sel = puzzle.cell_selection(loc);
+            let color = if !sel.is_enabled(puzzle.answer_at(loc).index.decay_to_ind()) {
+                INVALID_CELL_BORDER_COLOR
+            } else if complete {
+                COMPLETE_COLUMN_CELL_BORDER_COLOR
+            } else {
+                DEFAULT_CELL_BORDER_COLOR
+            };
+            sprite.color = color;
+        }
+    }
 }
 
 #[derive(Resource, Reflect)]
@@ -1295,6 +2584,52 @@ impl UIBorders {
         sprite.image_mode = SpriteImageMode::Sliced(self.slicer.clone());
         sprite
     }
+
+    fn make_image_node(&self, index: usize, color: Color) -> ImageNode {
+        ImageNode::from_atlas_image(self.texture.clone(), TextureAtlas {
+            index,
+            layout: self.atlas_layout.clone(),
+        })
+        .with_color(color)
+        .with_mode(NodeImageMode::Sliced(self.slicer.clone()))
+    }
+}
+
+const PANEL_BORDER_INDEX: usize = 24;
+const PANEL_PADDING: Val = Val::Px(20.);
+
+// The UI counterpart to `FitWithinBackground`: a nine-sliced border behind a UI node.
+#[derive(Debug, Component, Reflect, Clone, Copy)]
+struct ThemedPanel {
+    color: Color,
+}
+
+impl Default for ThemedPanel {
+    fn default() -> Self {
+        ThemedPanel {
+            color: DEFAULT_BORDER_COLOR,
+        }
+    }
+}
+
+impl ThemedPanel {
+    fn colored(color: Color) -> Self {
+        ThemedPanel { color }
+    }
+}
+
+fn make_themed_panel_image(
+    ev: Trigger<OnInsert, ThemedPanel>,
+    borders: Res<UIBorders>,
+    q_target: Query<&ThemedPanel>,
+    mut commands: Commands,
+) {
+    let Ok(panel) = q_target.get(ev.entity()) else {
+        return;
+    };
+    commands
+        .entity(ev.entity())
+        .insert(borders.make_image_node(PANEL_BORDER_INDEX, panel.color));
 }
 
 #[derive(Debug, Component, Reflect)]
@@ -1303,14 +2638,60 @@ struct AnimatedArrow {
     frame_timer: Timer,
 }
 
-fn animate_arrow(time: Res<Time>, mut q_anim: Query<(&mut AnimatedArrow, &mut Sprite)>) {
+// How arrow shafts are drawn: frames are laid out in a single column of the image, and the
+// animation cycles through however many the atlas layout has.
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+struct ArrowStyle {
+    texture: Handle<Image>,
+    atlas_layout: Handle<TextureAtlasLayout>,
+    frame_duration: Duration,
+    stretch_value: f32,
+    thickness: f32,
+}
+
+impl FromWorld for ArrowStyle {
+    fn from_world(world: &mut World) -> Self {
+        let texture = world.resource::<AssetServer>().load("arrow-shaft.png");
+        let atlas_layout =
+            world
+                .resource_mut::<Assets<TextureAtlasLayout>>()
+                .add(TextureAtlasLayout::from_grid(
+                    UVec2::new(6, 13),
+                    1,
+                    3,
+                    None,
+                    None,
+                ));
+        ArrowStyle {
+            texture,
+            atlas_layout,
+            frame_duration: Duration::from_secs_f32(0.05),
+            stretch_value: 0.5,
+            thickness: 10.,
+        }
+    }
+}
+
+// Arrows hold their first frame when motion is turned off.
+fn animate_arrow(
+    time: Res<Time>,
+    settings: Res<AnimationSettings>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut q_anim: Query<(&mut AnimatedArrow, &mut Sprite)>,
+) {
+    if !settings.plays(MotionKind::Other) {
+        return;
+    }
     for (mut anim, mut sprite) in &mut q_anim {
-        anim.frame_timer.tick(time.delta());
+        anim.frame_timer
+            .tick(time.delta().mul_f32(settings.speed.max(0.)));
         if anim.frame_timer.just_finished() {
-            anim.index = (anim.index + 1) % 3;
             let Some(atlas) = &mut sprite.texture_atlas else {
                 continue;
             };
+            let n_frames = layouts.get(&atlas.layout).map_or(1, |l| l.len()).max(1);
+            anim.index = (anim.index + 1) % n_frames;
             atlas.index = anim.index;
         }
     }
@@ -1319,77 +2700,88 @@ fn animate_arrow(time: Res<Time>, mut q_anim: Query<(&mut AnimatedArrow, &mut Sp
 #[derive(Event, Debug)]
 struct PlaceArrow {
     parent: Entity,
-    from_loc: Vec3,
-    to_loc: Vec3,
+    from: Entity,
+    to: Entity,
+}
+
+// The entities an arrow is drawn between; its endpoints are derived from them every frame.
+#[derive(Debug, Component, Reflect)]
+struct ArrowAnchors {
+    from: Entity,
+    to: Entity,
+}
+
+// Arrows are anchored at their head, pointing back toward where they came from.
+fn arrow_transform(from_loc: Vec3, to_loc: Vec3) -> (Transform, f32) {
+    let from_xy = from_loc.xy();
+    let to_xy = to_loc.xy();
+    let distance = from_xy.distance(to_xy);
+    let angle = (from_xy - to_xy).to_angle();
+    let mut transform = Transform::from_translation(to_loc.with_z(10.));
+    transform.rotate_z(angle);
+    (transform, distance)
 }
 
 fn place_arrow(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    style: Res<ArrowStyle>,
     mut loc_rx: EventReader<PlaceArrow>,
 ) {
-    let layout = LazyCell::new(move || {
-        texture_atlases.add(TextureAtlasLayout::from_grid(
-            UVec2::new(6, 13),
-            1,
-            3,
-            None,
-            None,
-        ))
-    });
     for ev in loc_rx.read() {
-        let from_xy = ev.from_loc.xy();
-        let to_xy = ev.to_loc.xy();
-        let distance = from_xy.distance(to_xy);
-        let angle = (from_xy - to_xy).to_angle();
-        info!("placing {ev:#?} => distance {distance} angle {angle}");
-        let mut transform = Transform::from_translation(ev.to_loc.with_z(10.));
-        transform.rotate_z(angle);
+        info!("placing {ev:#?}");
         commands.entity(ev.parent).with_child((
             Sprite {
-                image: asset_server.load("arrow-shaft.png"),
+                image: style.texture.clone(),
                 image_mode: SpriteImageMode::Tiled {
                     tile_x: true,
                     tile_y: false,
-                    stretch_value: 0.5,
+                    stretch_value: style.stretch_value,
                 },
                 texture_atlas: Some(TextureAtlas {
-                    layout: (*layout).clone(),
+                    layout: style.atlas_layout.clone(),
                     index: 0,
                 }),
-                custom_size: Some(Vec2::new(distance, 10.)),
                 anchor: Anchor::CenterLeft,
                 ..default()
             },
-            transform,
+            // Sized and positioned by `reanchor_arrows` once spawned.
+            Transform::default(),
             AnimatedArrow {
                 index: 0,
-                frame_timer: Timer::new(Duration::from_secs_f32(0.05), TimerMode::Repeating),
+                frame_timer: Timer::new(style.frame_duration, TimerMode::Repeating),
+            },
+            ArrowAnchors {
+                from: ev.from,
+                to: ev.to,
             },
         ));
     }
 }
 
+fn reanchor_arrows(
+    mut commands: Commands,
+    style: Res<ArrowStyle>,
+    mut q_arrow: Query<(Entity, &ArrowAnchors, &mut Transform, &mut Sprite)>,
+    q_anchor: Query<&GlobalTransform>,
+) {
+    for (entity, anchors, mut transform, mut sprite) in &mut q_arrow {
+        let Ok([from, to]) = q_anchor.get_many([anchors.from, anchors.to]) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        let (new_transform, distance) = arrow_transform(from.translation(), to.translation());
+        *transform = new_transform;
+        sprite.custom_size = Some(Vec2::new(distance, style.thickness));
+    }
+}
+
 fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    mut rng: ResMut<SeededRng>,
     mut animation_graphs: ResMut<Assets<AnimationGraph>>,
 ) {
-    commands.spawn(Camera2d);
-    commands.spawn((Puzzle::default(), PuzzleClues::default()));
-
-    commands.insert_resource({
-        let mut tileset_pool = TILESETS.iter().cloned().collect::<Vec<_>>();
-        tileset_pool.shuffle(&mut rng.0);
-        PuzzleSpawn {
-            timer: Timer::new(Duration::from_secs_f32(0.05), TimerMode::Repeating),
-            show_clues: 10,
-            tileset_pool,
-        }
-    });
+    commands.queue(respawn_puzzle);
 
     commands.insert_resource({
         let texture = asset_server.load("fantasy_ui_border_sheet.png");
@@ -1435,3 +2827,266 @@ fn setup(
             ));
         });
 }
+
+// Leaving for the main menu clears the board, so the next puzzle starts from nothing.
+fn despawn_puzzle(
+    mut commands: Commands,
+    (q_rows, q_clues): (
+        Query<Entity, With<DisplayRow>>,
+        Query<Entity, With<PuzzleClueComponent>>,
+    ),
+    (q_tree, q_tree_loc): (
+        Query<Entity, With<UndoTree>>,
+        Query<Entity, With<UndoTreeLocation>>,
+    ),
+    (q_drag_ui, q_drag_target): (Query<Entity, With<DragUI>>, Query<Entity, With<DragTarget>>),
+    q_explanation: Query<Entity, With<ExplainClueComponent>>,
+    mut modals: ResMut<ModalStack>,
+) {
+    commands.queue(respawn_puzzle);
+    for entity in q_rows
+        .iter()
+        .chain(&q_clues)
+        .chain(&q_tree)
+        .chain(&q_tree_loc)
+        .chain(&q_drag_ui)
+        .chain(&q_drag_target)
+    {
+        commands.entity(entity).despawn_recursive();
+    }
+    // An explanation still up would otherwise have its deduction applied to the next puzzle.
+    for entity in &q_explanation {
+        commands.entity(entity).insert(ExplanationDismissed);
+    }
+    commands.remove_resource::<PuzzleSpawn>();
+    commands.insert_resource(CandidatePreview::default());
+    commands.insert_resource(ExplanationQueue::default());
+    commands.insert_resource(LastClueScan::default());
+    commands.insert_resource(LastInferenceRun::default());
+    commands.insert_resource(PendingInference::default());
+    modals.pop(Modal::Explanation);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        render::camera::{camera_system, ManualTextureViews, Viewport},
+        window::{WindowCreated, WindowResized, WindowResolution, WindowScaleFactorChanged},
+    };
+
+    use super::*;
+
+    // Runs the camera system once against an 800x600 logical window at the given scale factor.
+    fn camera_fixture(scale_factor: f32, viewport: Option<Viewport>) -> (Camera, GlobalTransform) {
+        let mut app = App::new();
+        app.add_event::<WindowCreated>()
+            .add_event::<WindowResized>()
+            .add_event::<WindowScaleFactorChanged>()
+            .add_event::<AssetEvent<Image>>()
+            .init_resource::<Assets<Image>>()
+            .init_resource::<ManualTextureViews>()
+            .add_systems(Update, camera_system::<OrthographicProjection>);
+        let window = app
+            .world_mut()
+            .spawn((
+                Window {
+                    resolution: WindowResolution::new(800. * scale_factor, 600. * scale_factor)
+                        .with_scale_factor_override(scale_factor),
+                    ..Default::default()
+                },
+                PrimaryWindow,
+            ))
+            .id();
+        app.world_mut().send_event(WindowCreated { window });
+        let camera = app
+            .world_mut()
+            .spawn((Camera2d, Camera {
+                viewport,
+                ..Default::default()
+            }))
+            .id();
+        app.update();
+        let camera = app.world().entity(camera);
+        (
+            camera.get::<Camera>().unwrap().clone(),
+            *camera.get::<GlobalTransform>().unwrap(),
+        )
+    }
+
+    #[test]
+    fn leaving_the_puzzle_clears_the_board() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<ModalStack>();
+        let puzzle = puzzle::test_puzzle(2, 4);
+        let (tree, location) = UndoTree::new(puzzle.clone());
+        let puzzle_e = world
+            .spawn((
+                puzzle,
+                PuzzleNotes {
+                    text: "left off here".to_string(),
+                },
+                PuzzleReveals::default(),
+            ))
+            .id();
+        world.insert_resource(PuzzleEntity(puzzle_e));
+        world
+            .spawn(DisplayRow { row: LRow(0) })
+            .with_child(DisplayRowCounter { row: LRow(0) });
+        world.spawn(tree);
+        world.spawn(location);
+        world.spawn(DragUI);
+        world.run_system_once(despawn_puzzle).unwrap();
+
+        let puzzle_e = world.entity(puzzle_e);
+        assert_eq!(puzzle_e.get::<Puzzle>().unwrap().iter_rows().count(), 0);
+        assert!(puzzle_e.get::<PuzzleNotes>().unwrap().text.is_empty());
+        assert_eq!(world.query::<&DisplayRowCounter>().iter(&world).count(), 0);
+        assert_eq!(world.query::<&UndoTree>().iter(&world).count(), 0);
+        assert_eq!(world.query::<&DragUI>().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn respawning_leaves_one_named_puzzle() {
+        let mut world = World::new();
+        world.commands().queue(respawn_puzzle);
+        world.flush();
+        let puzzle_e = world.resource::<PuzzleEntity>().0;
+        // Say a load raced a new game and made its own.
+        world.spawn(PuzzleBundle::default());
+        world.commands().queue(respawn_puzzle);
+        world.flush();
+
+        let puzzles = world
+            .query_filtered::<(Entity, &Name), With<Puzzle>>()
+            .iter(&world)
+            .map(|(e, name)| (e, name.as_str().to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(puzzles, vec![(puzzle_e, "Puzzle".to_string())]);
+        assert_eq!(world.resource::<PuzzleEntity>().0, puzzle_e);
+    }
+
+    #[test]
+    fn inference_after_a_click_lands_as_one_action() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let puzzle = puzzle::test_puzzle(2, 4);
+        let loc = CellLoc {
+            row: LRow(0),
+            col: puzzle::LCol(0),
+        };
+        let update = puzzle.answer_at(loc).decay_to_ind().as_solo();
+        let mut expected = puzzle.clone();
+        let (update_count, inferred_count) = expected
+            .apply_update(&update, &mut HashSet::new())
+            .unwrap()
+            .unwrap();
+        assert!(inferred_count > 0);
+
+        let mut app = App::new();
+        app.add_event::<UpdateCellIndex>()
+            .add_event::<UpdateCellDisplay>()
+            .add_event::<PushNewAction>()
+            .add_event::<RejectedCellUpdate>()
+            .add_event::<AppliedCellUpdate>()
+            .add_event::<ShowToast>()
+            .init_resource::<Settings>()
+            .init_resource::<PendingInference>()
+            .init_resource::<LastInferenceRun>()
+            .add_systems(Update, cell_update);
+        app.world_mut().spawn(puzzle.clone());
+        app.world_mut().send_event(update);
+        app.update();
+        let pushed = |app: &App| {
+            let events = app.world().resource::<Events<PushNewAction>>();
+            events
+                .get_cursor()
+                .read(events)
+                .map(|ev| (ev.action.update_count, ev.action.inferred_count))
+                .collect::<Vec<_>>()
+        };
+        let board = |app: &mut App| {
+            let world = app.world_mut();
+            world.query::<&Puzzle>().single(world).clone()
+        };
+
+        // Only the clicked cell has changed, and the undo tree hasn't heard about it yet.
+        assert_eq!(board(&mut app).changed_cells(&puzzle), vec![loc]);
+        assert_eq!(pushed(&app), vec![]);
+
+        app.world_mut()
+            .run_system_once(run_deferred_inference)
+            .unwrap();
+        assert_eq!(board(&mut app).changed_cells(&expected), vec![]);
+        assert_eq!(pushed(&app), vec![(update_count, inferred_count)]);
+    }
+
+    const SCALE_FACTORS: [f32; 3] = [1., 1.5, 2.];
+
+    #[test]
+    fn cursor_maps_to_world_at_any_scale_factor() {
+        for scale_factor in SCALE_FACTORS {
+            let (camera, transform) = camera_fixture(scale_factor, None);
+            for (cursor, world) in [
+                (Vec2::new(400., 300.), Vec2::ZERO),
+                (Vec2::ZERO, Vec2::new(-400., 300.)),
+                (Vec2::new(800., 600.), Vec2::new(400., -300.)),
+            ] {
+                let mapped = cursor_to_world(&camera, &transform, cursor).unwrap();
+                assert!(
+                    mapped.abs_diff_eq(world, 1e-3),
+                    "scale factor {scale_factor}: {cursor} mapped to {mapped}, not {world}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_maps_through_a_partial_viewport() {
+        for scale_factor in SCALE_FACTORS {
+            // The right half of the window.
+            let viewport = Viewport {
+                physical_position: UVec2::new((400. * scale_factor) as u32, 0),
+                physical_size: UVec2::new(
+                    (400. * scale_factor) as u32,
+                    (600. * scale_factor) as u32,
+                ),
+                ..Default::default()
+            };
+            let (camera, transform) = camera_fixture(scale_factor, Some(viewport));
+            let mapped = cursor_to_world(&camera, &transform, Vec2::new(600., 300.)).unwrap();
+            assert!(
+                mapped.abs_diff_eq(Vec2::ZERO, 1e-3),
+                "scale factor {scale_factor}: viewport center mapped to {mapped}"
+            );
+        }
+    }
+
+    #[test]
+    fn drags_pick_the_operation_shown_in_that_direction() {
+        let layout = RadialLayout::default();
+        let start = Vec2::new(400., 300.);
+        // Window coordinates, so a drag down the screen has a growing y.
+        for (drag, op) in [
+            (Vec2::new(50., 0.), UpdateCellIndexOperation::Clear),
+            (Vec2::new(0., 50.), UpdateCellIndexOperation::Set),
+            (Vec2::new(-50., 0.), UpdateCellIndexOperation::Toggle),
+            (Vec2::new(0., -50.), UpdateCellIndexOperation::Solo),
+        ] {
+            assert_eq!(
+                drag_operation(&layout, start, start + drag),
+                Some(op),
+                "dragged by {drag}"
+            );
+        }
+        assert_eq!(
+            drag_operation(&layout, start, start + Vec2::new(5., 0.)),
+            None
+        );
+        assert_eq!(
+            drag_operation(&layout, start, start + Vec2::new(200., 0.)),
+            None
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    fit::FitClickedEvent,
+    focus::has_focus,
+    intro::intro_running,
+    modal::{no_modal, pop_modal, push_modal, Modal},
+    notes::editing_notes,
+    settings::SizedText,
+    DisplayRow, GameState, PauseState, TopButtonAction, NO_PICK,
+};
+
+#[derive(Debug, Component, Reflect)]
+struct PauseMenu;
+
+#[derive(Debug, Component, Reflect, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    MainMenu,
+    Quit,
+}
+
+const PAUSE_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0., 0.7);
+const PAUSE_BUTTON_COLOR: Color = Color::hsla(33., 1., 0.32, 1.);
+
+fn pause_clicked(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if let TopButtonAction::Menu = action {
+            next_pause.set(PauseState::Paused);
+        }
+    }
+}
+
+fn pause_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    pause: Res<State<PauseState>>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+) {
+    let paused = *pause.get() == PauseState::Paused;
+    if keys.just_pressed(KeyCode::KeyP) || (paused && keys.just_pressed(KeyCode::Escape)) {
+        next_pause.set(if paused {
+            PauseState::Running
+        } else {
+            PauseState::Paused
+        });
+    }
+}
+
+// Escape only pauses when it has nothing else to close: a focused cell or its prompt, an open
+// explanation, or the reveal intro, take it first.
+fn escape_pauses(keys: Res<ButtonInput<KeyCode>>, mut next_pause: ResMut<NextState<PauseState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_pause.set(PauseState::Paused);
+    }
+}
+
+// The backdrop covers the whole window, so nothing on the board can be clicked through it. The
+// rows are hidden too, so a timed puzzle can't be studied while its clock is stopped.
+fn spawn_pause_menu(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    q_rows: Query<Entity, With<DisplayRow>>,
+) {
+    time.pause();
+    for entity in &q_rows {
+        commands.entity(entity).insert(Visibility::Hidden);
+    }
+    commands
+        .spawn((
+            PauseMenu,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.),
+                ..Default::default()
+            },
+            BackgroundColor(PAUSE_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Paused"), SizedText, NO_PICK));
+            for (button, label) in [
+                (PauseMenuButton::Resume, "Resume"),
+                (PauseMenuButton::MainMenu, "Main menu"),
+                (PauseMenuButton::Quit, "Quit"),
+            ] {
+                parent
+                    .spawn((
+                        button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                            ..Default::default()
+                        },
+                        BackgroundColor(PAUSE_BUTTON_COLOR),
+                    ))
+                    .with_child((Text::new(label), SizedText, NO_PICK));
+            }
+        });
+}
+
+fn despawn_pause_menu(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    q_menu: Query<Entity, With<PauseMenu>>,
+    q_rows: Query<Entity, With<DisplayRow>>,
+) {
+    time.unpause();
+    for entity in &q_rows {
+        commands.entity(entity).insert(Visibility::Inherited);
+    }
+    for entity in &q_menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn pause_menu_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_button: Query<&PauseMenuButton>,
+    mut next_pause: ResMut<NextState<PauseState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_tx: EventWriter<AppExit>,
+) {
+    let Ok(&button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    match button {
+        PauseMenuButton::Resume => next_pause.set(PauseState::Running),
+        PauseMenuButton::MainMenu => next_state.set(GameState::MainMenu),
+        PauseMenuButton::Quit => {
+            exit_tx.send(AppExit::Success);
+        }
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PauseState>()
+            .register_type::<PauseMenu>()
+            .register_type::<PauseMenuButton>()
+            .add_observer(pause_menu_clicked)
+            .add_systems(Update, pause_clicked.run_if(in_state(GameState::Playing)))
+            // Before `Update`, so the pause has already happened by the time the focus and
+            // explanation keys would see the same press.
+            .add_systems(
+                PreUpdate,
+                (
+                    pause_keys,
+                    escape_pauses
+                        .run_if(no_modal)
+                        .run_if(not(has_focus))
+                        .run_if(not(intro_running)),
+                )
+                    .chain()
+                    .run_if(not(editing_notes))
+                    .run_if(in_state(GameState::Playing))
+                    .after(InputSystem),
+            )
+            .add_systems(
+                OnEnter(PauseState::Paused),
+                (spawn_pause_menu, push_modal(Modal::Pause)),
+            )
+            .add_systems(
+                OnExit(PauseState::Paused),
+                (despawn_pause_menu, pop_modal(Modal::Pause)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::puzzle::LRow;
+
+    #[test]
+    fn pausing_hides_the_board_and_stops_the_clock() {
+        let mut world = World::new();
+        world.init_resource::<Time<Virtual>>();
+        let row = world.spawn(DisplayRow { row: LRow(0) }).id();
+
+        world.run_system_once(spawn_pause_menu).unwrap();
+        assert!(world.resource::<Time<Virtual>>().is_paused());
+        assert_eq!(world.get::<Visibility>(row), Some(&Visibility::Hidden));
+        assert_eq!(world.query::<&PauseMenu>().iter(&world).count(), 1);
+
+        world.run_system_once(despawn_pause_menu).unwrap();
+        assert!(!world.resource::<Time<Virtual>>().is_paused());
+        assert_eq!(world.get::<Visibility>(row), Some(&Visibility::Inherited));
+        assert_eq!(world.query::<&PauseMenu>().iter(&world).count(), 0);
+    }
+}
@@ -0,0 +1,129 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+// Anything shown over the board that keeps input from reaching what's below it. Layers stack, so
+// the pause menu can open over an explanation, and only the topmost layer sees keys and clicks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum Modal {
+    Pause,
+    Explanation,
+    Outcome,
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct ModalStack {
+    layers: Vec<Modal>,
+}
+
+impl ModalStack {
+    // A layer that's already open moves to the top rather than being stacked twice.
+    pub fn push(&mut self, modal: Modal) {
+        self.layers.retain(|&layer| layer != modal);
+        self.layers.push(modal);
+    }
+
+    // Layers can close in any order; whatever was over this one stays where it was.
+    pub fn pop(&mut self, modal: Modal) {
+        self.layers.retain(|&layer| layer != modal);
+    }
+
+    pub fn top(&self) -> Option<Modal> {
+        self.layers.last().copied()
+    }
+
+    pub fn is_open(&self, modal: Modal) -> bool {
+        self.layers.contains(&modal)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+}
+
+// Run conditions for input on the board, and for input belonging to a layer.
+pub fn no_modal(stack: Res<ModalStack>) -> bool {
+    stack.is_empty()
+}
+
+pub fn modal_on_top(modal: Modal) -> impl FnMut(Res<ModalStack>) -> bool + Clone {
+    move |stack: Res<ModalStack>| stack.top() == Some(modal)
+}
+
+pub fn modal_open(modal: Modal) -> impl FnMut(Res<ModalStack>) -> bool + Clone {
+    move |stack: Res<ModalStack>| stack.is_open(modal)
+}
+
+// True once, the first time it's checked after the layer closed.
+pub fn modal_closed(modal: Modal) -> impl FnMut(Res<ModalStack>, Local<bool>) -> bool + Clone {
+    move |stack: Res<ModalStack>, mut was_open: Local<bool>| {
+        let open = stack.is_open(modal);
+        let closed = *was_open && !open;
+        *was_open = open;
+        closed
+    }
+}
+
+// For layers that follow a state, added to its `OnEnter` and `OnExit`.
+pub fn push_modal(modal: Modal) -> impl FnMut(ResMut<ModalStack>) + Clone {
+    move |mut stack: ResMut<ModalStack>| stack.push(modal)
+}
+
+pub fn pop_modal(modal: Modal) -> impl FnMut(ResMut<ModalStack>) + Clone {
+    move |mut stack: ResMut<ModalStack>| stack.pop(modal)
+}
+
+pub struct ModalPlugin;
+
+impl Plugin for ModalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ModalStack>()
+            .register_type::<Modal>()
+            .register_type::<ModalStack>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    #[test]
+    fn only_the_topmost_layer_sees_input() {
+        let mut stack = ModalStack::default();
+        assert!(stack.is_empty());
+        stack.push(Modal::Explanation);
+        stack.push(Modal::Pause);
+        assert_eq!(stack.top(), Some(Modal::Pause));
+        assert!(stack.is_open(Modal::Explanation));
+        // Reopening a layer brings it back to the top.
+        stack.push(Modal::Explanation);
+        assert_eq!(stack.top(), Some(Modal::Explanation));
+        stack.pop(Modal::Pause);
+        assert_eq!(stack.top(), Some(Modal::Explanation));
+        stack.pop(Modal::Explanation);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn closing_is_noticed_once() {
+        let mut world = World::new();
+        world.init_resource::<ModalStack>();
+        let closed = world.register_system(modal_closed(Modal::Explanation));
+        let check = |world: &mut World| world.run_system(closed).unwrap();
+        assert!(!check(&mut world));
+        world
+            .run_system_once(push_modal(Modal::Explanation))
+            .unwrap();
+        assert!(!check(&mut world));
+        world
+            .run_system_once(pop_modal(Modal::Explanation))
+            .unwrap();
+        assert!(check(&mut world));
+        assert!(!check(&mut world));
+    }
+}
@@ -0,0 +1,240 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+};
+
+use crate::{
+    fit::FitClickedEvent, puzzle::Puzzle, settings::SizedText, GameState, TopButtonAction, NO_PICK,
+};
+
+// Free-form notes the player keeps alongside the puzzle; not part of the undo history.
+#[derive(Debug, Default, Component, Reflect)]
+pub struct PuzzleNotes {
+    pub text: String,
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct NotesPanel {
+    shown: bool,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct NotesPanelNode;
+
+const NOTES_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const NOTES_PLACEHOLDER: &str = "Type to take notes; Escape closes.";
+
+// While the panel is open, typing goes to the notes rather than to board navigation.
+pub fn editing_notes(panel: Res<NotesPanel>) -> bool {
+    panel.shown
+}
+
+fn toggle_notes(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut panel: ResMut<NotesPanel>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if let TopButtonAction::Notes = action {
+            panel.shown = !panel.shown;
+        }
+    }
+}
+
+fn edit_notes(
+    mut key_rx: EventReader<KeyboardInput>,
+    mut panel: ResMut<NotesPanel>,
+    mut notes: Single<&mut PuzzleNotes, With<Puzzle>>,
+) {
+    if !panel.shown {
+        key_rx.clear();
+        return;
+    }
+    for ev in key_rx.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+        match &ev.logical_key {
+            Key::Character(s) => notes.text.push_str(s),
+            Key::Space => notes.text.push(' '),
+            Key::Enter => notes.text.push('\n'),
+            Key::Backspace => {
+                notes.text.pop();
+            }
+            Key::Escape => panel.shown = false,
+            _ => (),
+        }
+    }
+}
+
+fn rebuild_notes_panel(
+    mut commands: Commands,
+    panel: Res<NotesPanel>,
+    notes: Single<Ref<PuzzleNotes>, With<Puzzle>>,
+    q_node: Query<Entity, With<NotesPanelNode>>,
+) {
+    if !panel.is_changed() && !notes.is_changed() {
+        return;
+    }
+    for entity in &q_node {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !panel.shown {
+        return;
+    }
+    let text = if notes.text.is_empty() {
+        NOTES_PLACEHOLDER.to_string()
+    } else {
+        format!("{}_", notes.text)
+    };
+    commands
+        .spawn((
+            NotesPanelNode,
+            // Sits above the speedrun timer, which has the bottom-right corner.
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.),
+                right: Val::Px(10.),
+                width: Val::Percent(30.),
+                min_height: Val::Px(100.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            BackgroundColor(NOTES_BACKGROUND_COLOR),
+        ))
+        .with_child((Text::new(text), SizedText, NO_PICK));
+}
+
+fn close_notes(mut panel: ResMut<NotesPanel>) {
+    panel.shown = false;
+}
+
+pub struct NotesPlugin;
+
+impl Plugin for NotesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NotesPanel>()
+            .register_type::<NotesPanel>()
+            .register_type::<NotesPanelNode>()
+            .register_type::<PuzzleNotes>()
+            .add_systems(OnExit(GameState::Playing), close_notes)
+            .add_systems(
+                Update,
+                (toggle_notes, edit_notes, rebuild_notes_panel).chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::{
+        puzzle::test_puzzle,
+        save::{capture_game, SavedPuzzle},
+        undo::{UndoTree, UndoTreeLocation},
+        ArchivedClue, DisplayCluebox, PuzzleBundle, PuzzleClueComponent, PuzzleOrigin,
+        PuzzleReveals,
+    };
+
+    #[derive(Debug, Default, Resource)]
+    struct ShortcutsRead(usize);
+
+    // Notes read the key events; shortcuts read the key state.
+    fn press(app: &mut App, key_code: KeyCode, logical_key: Key) {
+        let window = Entity::PLACEHOLDER;
+        app.world_mut().send_event(KeyboardInput {
+            key_code,
+            logical_key,
+            state: ButtonState::Pressed,
+            repeat: false,
+            window,
+        });
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(key_code);
+        app.update();
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(key_code);
+        keys.clear();
+    }
+
+    #[test]
+    fn open_notes_take_the_keyboard_from_shortcuts() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(NotesPlugin)
+            .add_event::<FitClickedEvent<TopButtonAction>>()
+            .add_event::<KeyboardInput>()
+            .init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<ShortcutsRead>()
+            // Gated the way the board's own shortcuts are, and run ahead of the notes like the
+            // pause keys, so the Escape that closes the notes isn't also taken as a shortcut.
+            .add_systems(
+                PreUpdate,
+                (|keys: Res<ButtonInput<KeyCode>>, mut read: ResMut<ShortcutsRead>| {
+                    read.0 += keys.get_just_pressed().count();
+                })
+                .run_if(not(editing_notes)),
+            );
+        app.world_mut()
+            .spawn((test_puzzle(2, 3), PuzzleNotes::default()));
+        let notes = |app: &mut App| {
+            app.world_mut()
+                .query::<&PuzzleNotes>()
+                .single(app.world())
+                .text
+                .clone()
+        };
+
+        press(&mut app, KeyCode::KeyZ, Key::Character("z".into()));
+        assert_eq!(app.world().resource::<ShortcutsRead>().0, 1);
+        assert_eq!(notes(&mut app), "");
+
+        app.world_mut()
+            .send_event(FitClickedEvent(TopButtonAction::Notes));
+        app.update();
+        press(&mut app, KeyCode::KeyZ, Key::Character("z".into()));
+        press(&mut app, KeyCode::Space, Key::Space);
+        assert_eq!(app.world().resource::<ShortcutsRead>().0, 1);
+        assert_eq!(notes(&mut app), "z ");
+
+        press(&mut app, KeyCode::Escape, Key::Escape);
+        press(&mut app, KeyCode::KeyZ, Key::Character("z".into()));
+        assert_eq!(app.world().resource::<ShortcutsRead>().0, 2);
+        assert_eq!(notes(&mut app), "z ");
+    }
+
+    #[test]
+    fn notes_survive_a_save() {
+        let text = "fox is\nleft of \"hound\" ({[x]})";
+        let mut world = World::new();
+        world.spawn(PuzzleBundle {
+            puzzle: test_puzzle(2, 3),
+            notes: PuzzleNotes {
+                text: text.to_string(),
+            },
+            ..Default::default()
+        });
+        let saved = world
+            .run_system_once(
+                |q_puzzle: Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+                 q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+                 q_cluebox: Query<&Children, With<DisplayCluebox>>,
+                 q_clues: Query<(&PuzzleClueComponent, Has<ArchivedClue>)>| {
+                    capture_game(&q_puzzle, &q_tree, &q_cluebox, &q_clues)
+                },
+            )
+            .unwrap()
+            .unwrap();
+        let loaded = SavedPuzzle::from_ron(&saved.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.notes, text);
+    }
+}
@@ -0,0 +1,112 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::puzzle::UpdateCellIndexOperation;
+
+// How an operation on a candidate looks, wherever it's shown: the radial menu, the flash after it's
+// applied, the logs, and the undo history. Icons are plain text, so they need to be in the font.
+#[derive(Debug, Clone, Reflect)]
+pub struct OperationStyle {
+    pub color: Color,
+    pub icon: String,
+}
+
+impl OperationStyle {
+    fn new(hue: f32, icon: &str) -> Self {
+        OperationStyle {
+            color: Color::hsla(hue, 0.7, 0.7, 1.),
+            icon: icon.to_string(),
+        }
+    }
+}
+
+// One style per operation; adjustable from the inspector.
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct OperationTheme {
+    pub clear: OperationStyle,
+    pub set: OperationStyle,
+    pub toggle: OperationStyle,
+    pub solo: OperationStyle,
+    pub mark_tentative: OperationStyle,
+    pub confirm_all: OperationStyle,
+    pub revert_tentative: OperationStyle,
+}
+
+impl Default for OperationTheme {
+    fn default() -> Self {
+        OperationTheme {
+            clear: OperationStyle::new(0., "x"),
+            set: OperationStyle::new(120., "+"),
+            toggle: OperationStyle::new(45., "~"),
+            solo: OperationStyle::new(210., "!"),
+            mark_tentative: OperationStyle::new(280., "?"),
+            confirm_all: OperationStyle::new(165., "="),
+            revert_tentative: OperationStyle::new(25., "<"),
+        }
+    }
+}
+
+impl OperationTheme {
+    pub fn style(&self, op: UpdateCellIndexOperation) -> &OperationStyle {
+        use UpdateCellIndexOperation as Op;
+        match op {
+            Op::Clear => &self.clear,
+            Op::Set => &self.set,
+            Op::Toggle => &self.toggle,
+            Op::Solo => &self.solo,
+            Op::MarkTentative => &self.mark_tentative,
+            Op::ConfirmAll => &self.confirm_all,
+            Op::RevertTentative => &self.revert_tentative,
+        }
+    }
+
+    pub fn color(&self, op: UpdateCellIndexOperation) -> Color {
+        self.style(op).color
+    }
+
+    // The icon, then the name.
+    pub fn label(&self, op: UpdateCellIndexOperation) -> String {
+        format!("{} {op}", self.style(op).icon)
+    }
+}
+
+pub struct OperationThemePlugin;
+
+impl Plugin for OperationThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OperationTheme>()
+            .register_type::<OperationStyle>()
+            .register_type::<OperationTheme>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_operation_looks_different() {
+        use UpdateCellIndexOperation as Op;
+        let theme = OperationTheme::default();
+        let ops = [
+            Op::Clear,
+            Op::Set,
+            Op::Toggle,
+            Op::Solo,
+            Op::MarkTentative,
+            Op::ConfirmAll,
+            Op::RevertTentative,
+        ];
+        for (i, &a) in ops.iter().enumerate() {
+            for &b in &ops[..i] {
+                assert_ne!(theme.style(a).icon, theme.style(b).icon, "{a:?} and {b:?}");
+                assert_ne!(theme.color(a), theme.color(b), "{a:?} and {b:?}");
+            }
+        }
+        assert_eq!(theme.label(Op::MarkTentative), "? Mark tentative");
+    }
+}
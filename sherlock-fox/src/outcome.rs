@@ -0,0 +1,257 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    buttons::TopButtonState,
+    fit::FitClickedEvent,
+    modal::{pop_modal, push_modal, Modal, ModalStack},
+    puzzle::Puzzle,
+    settings::SizedText,
+    GameState, PuzzleOutcome, ThemedPanel, TopButtonAction, NO_PICK, PANEL_PADDING,
+};
+
+#[derive(Debug, Component, Reflect)]
+struct OutcomeOverlay;
+
+#[derive(Debug, Component, Reflect, Clone, Copy)]
+struct OutcomeButton(TopButtonAction);
+
+const OUTCOME_BUTTON_COLOR: Color = Color::hsla(33., 1., 0.32, 1.);
+
+fn detect_outcome(
+    puzzle: Single<Ref<Puzzle>>,
+    outcome: Res<State<PuzzleOutcome>>,
+    mut next_outcome: ResMut<NextState<PuzzleOutcome>>,
+) {
+    if !puzzle.is_changed() {
+        return;
+    }
+    let detected = if puzzle.is_solved() {
+        PuzzleOutcome::Solved
+    } else if puzzle.excludes_answer() {
+        PuzzleOutcome::Contradicted
+    } else {
+        PuzzleOutcome::InProgress
+    };
+    if *outcome.get() != detected {
+        next_outcome.set(detected);
+    }
+}
+
+fn outcome_overlay_node() -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        align_self: AlignSelf::Center,
+        justify_self: JustifySelf::Center,
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(10.),
+        padding: UiRect::all(PANEL_PADDING),
+        ..Default::default()
+    }
+}
+
+fn spawn_outcome_button(parent: &mut ChildBuilder, action: TopButtonAction) {
+    parent
+        .spawn((
+            OutcomeButton(action),
+            Node {
+                padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                ..Default::default()
+            },
+            BackgroundColor(OUTCOME_BUTTON_COLOR),
+        ))
+        .with_child((Text::new(format!("{action:?}")), SizedText, NO_PICK));
+}
+
+fn spawn_victory_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            OutcomeOverlay,
+            outcome_overlay_node(),
+            ThemedPanel::colored(Color::hsla(120., 0.5, 0.35, 1.)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Solved! Click to close."), SizedText, NO_PICK));
+            spawn_outcome_button(parent, TopButtonAction::Menu);
+        });
+}
+
+fn spawn_failure_overlay(mut commands: Commands) {
+    commands
+        .spawn((
+            OutcomeOverlay,
+            outcome_overlay_node(),
+            ThemedPanel::colored(Color::hsla(0., 0.6, 0.35, 1.)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("A cell has lost its answer."), SizedText, NO_PICK));
+            parent
+                .spawn((
+                    Node {
+                        column_gap: Val::Px(10.),
+                        ..Default::default()
+                    },
+                    NO_PICK,
+                ))
+                .with_children(|parent| {
+                    for action in [TopButtonAction::Undo, TopButtonAction::Restart] {
+                        spawn_outcome_button(parent, action);
+                    }
+                });
+        });
+}
+
+fn despawn_outcome_overlay(mut commands: Commands, q_overlay: Query<Entity, With<OutcomeOverlay>>) {
+    for entity in &q_overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// The buttons go through the same event as the top bar, so undo doesn't need a second path.
+fn outcome_button_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_button: Query<&OutcomeButton>,
+    button_state: Res<TopButtonState>,
+    mut action_tx: EventWriter<FitClickedEvent<TopButtonAction>>,
+) {
+    if let Ok(&OutcomeButton(action)) = q_button.get(ev.entity()) {
+        if button_state.is_enabled(action) {
+            action_tx.send(FitClickedEvent(action));
+        }
+    }
+}
+
+// Only the victory overlay closes on a click; the failure overlay stays until it's undone.
+fn victory_overlay_clicked(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_overlay: Query<(), With<OutcomeOverlay>>,
+    outcome: Res<State<PuzzleOutcome>>,
+    mut modals: ResMut<ModalStack>,
+) {
+    if *outcome.get() == PuzzleOutcome::Solved && q_overlay.contains(ev.entity()) {
+        commands.entity(ev.entity()).despawn_recursive();
+        modals.pop(Modal::Outcome);
+    }
+}
+
+pub struct OutcomePlugin;
+
+impl Plugin for OutcomePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<PuzzleOutcome>()
+            .register_type::<OutcomeButton>()
+            .register_type::<OutcomeOverlay>()
+            .add_observer(outcome_button_clicked)
+            .add_observer(victory_overlay_clicked)
+            .add_systems(Update, detect_outcome.run_if(in_state(GameState::Playing)))
+            .add_systems(
+                OnEnter(PuzzleOutcome::Solved),
+                (spawn_victory_overlay, push_modal(Modal::Outcome)),
+            )
+            .add_systems(
+                OnExit(PuzzleOutcome::Solved),
+                (despawn_outcome_overlay, pop_modal(Modal::Outcome)),
+            )
+            .add_systems(
+                OnEnter(PuzzleOutcome::Contradicted),
+                (spawn_failure_overlay, push_modal(Modal::Outcome)),
+            )
+            .add_systems(
+                OnExit(PuzzleOutcome::Contradicted),
+                (despawn_outcome_overlay, pop_modal(Modal::Outcome)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle, UpdateCellIndexOperation};
+
+    fn playing() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<GameState>()
+            .init_resource::<ModalStack>()
+            .add_plugins(OutcomePlugin);
+        app.world_mut().spawn(test_puzzle(2, 3));
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        app.update();
+        app
+    }
+
+    // Applies `f` to the board, then gives the outcome a frame to be detected and one to be entered.
+    fn change(app: &mut App, f: impl FnOnce(&mut Puzzle)) {
+        f(&mut app
+            .world_mut()
+            .query::<&mut Puzzle>()
+            .single_mut(app.world_mut()));
+        app.update();
+        app.update();
+    }
+
+    fn outcome(app: &App) -> PuzzleOutcome {
+        app.world().resource::<State<PuzzleOutcome>>().get().clone()
+    }
+
+    fn overlays(app: &mut App) -> usize {
+        app.world_mut()
+            .query_filtered::<(), With<OutcomeOverlay>>()
+            .iter(app.world())
+            .count()
+    }
+
+    #[test]
+    fn outcome_follows_the_board() {
+        let mut app = playing();
+        let fresh = app
+            .world_mut()
+            .query::<&Puzzle>()
+            .single(app.world())
+            .clone();
+        change(&mut app, |_| ());
+        assert_eq!(outcome(&app), PuzzleOutcome::InProgress);
+        assert_eq!(overlays(&mut app), 0);
+
+        change(&mut app, |puzzle| {
+            for loc in puzzle.iter_locs().collect::<Vec<_>>() {
+                let answer = puzzle.answer_at(loc).index.decay_to_ind();
+                puzzle
+                    .cell_selection_mut(loc)
+                    .apply(answer, UpdateCellIndexOperation::Solo);
+            }
+        });
+        assert_eq!(outcome(&app), PuzzleOutcome::Solved);
+        assert_eq!(overlays(&mut app), 1);
+        assert_eq!(
+            app.world().resource::<ModalStack>().top(),
+            Some(Modal::Outcome)
+        );
+
+        // Taking an answer away swaps the victory overlay for the failure one.
+        change(&mut app, |puzzle| {
+            let answer = puzzle.answer_at(loc(1, 2)).index.decay_to_ind();
+            puzzle
+                .cell_selection_mut(loc(1, 2))
+                .apply(answer, UpdateCellIndexOperation::Clear);
+        });
+        assert_eq!(outcome(&app), PuzzleOutcome::Contradicted);
+        assert_eq!(overlays(&mut app), 1);
+        assert!(app.world().resource::<ModalStack>().is_open(Modal::Outcome));
+
+        // Undoing back to a board in progress closes it.
+        change(&mut app, |puzzle| puzzle.clone_from(&fresh));
+        assert_eq!(outcome(&app), PuzzleOutcome::InProgress);
+        assert_eq!(overlays(&mut app), 0);
+        assert!(app.world().resource::<ModalStack>().is_empty());
+    }
+}
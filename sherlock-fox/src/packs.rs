@@ -0,0 +1,524 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    notes::PuzzleNotes,
+    puzzle::Puzzle,
+    save::{SavedPuzzle, SAVE_EXTENSION},
+    solver::DeductionTier,
+    toast::ShowToast,
+    GameState, PuzzleSpawn, NO_PICK,
+};
+
+// Packs are either a single .ron file holding a `SavedPuzzlePack`, or a directory of saved
+// puzzles (like `generate-bank` writes) with an optional `pack.ron` manifest, which can also be
+// zipped up.
+const PACKS_DIR: &str = "packs";
+const PACK_ZIP_EXTENSION: &str = "zip";
+const PACK_MANIFEST: &str = "pack.ron";
+const PACK_PROGRESS: &str = "progress.ron";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPuzzlePack {
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    pub puzzles: Vec<SavedPuzzle>,
+}
+
+#[derive(Debug)]
+struct PuzzlePack {
+    // The file or directory name, which is what completion is tracked under.
+    id: String,
+    manifest: PackManifest,
+    puzzles: Vec<SavedPuzzle>,
+}
+
+impl PuzzlePack {
+    fn difficulty(&self) -> DeductionTier {
+        self.puzzles
+            .iter()
+            .map(|p| p.metadata.deduction_tier)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+struct PuzzlePacks(Vec<PuzzlePack>);
+
+// Pack id to the indices of its completed puzzles.
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+struct PackProgress(HashMap<String, BTreeSet<usize>>);
+
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+struct PlayingPackPuzzle {
+    pack: String,
+    index: usize,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct PackBrowser;
+
+#[derive(Debug, Clone, Component, Reflect)]
+struct PlayPackButton(usize);
+
+const PACK_BROWSER_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const PACK_BUTTON_COLOR: Color = Color::hsla(33., 1., 0.32, 1.);
+
+fn read_ron<T: serde::de::DeserializeOwned>(path: &Path) -> io::Result<T> {
+    let data = fs::read_to_string(path)?;
+    ron::from_str(&data).map_err(io::Error::other)
+}
+
+fn is_ron(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == SAVE_EXTENSION)
+}
+
+fn is_zip(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == PACK_ZIP_EXTENSION)
+}
+
+// Reads a pack laid out as files named like those in a pack directory, in path order. The
+// manifest is whichever file is named like one, in any folder.
+fn read_pack_files(
+    files: impl IntoIterator<Item = (String, String)>,
+) -> io::Result<(PackManifest, Vec<SavedPuzzle>)> {
+    let mut manifest = PackManifest::default();
+    let mut files = files
+        .into_iter()
+        .filter(|(name, _)| is_ron(Path::new(name)))
+        .collect::<Vec<_>>();
+    files.sort();
+    let mut puzzles = vec![];
+    for (name, data) in files {
+        if Path::new(&name)
+            .file_name()
+            .is_some_and(|n| n == PACK_MANIFEST)
+        {
+            manifest = ron::from_str(&data).map_err(io::Error::other)?;
+        } else {
+            puzzles.push(SavedPuzzle::from_ron(&data)?);
+        }
+    }
+    Ok((manifest, puzzles))
+}
+
+fn read_pack_dir(dir: &Path) -> io::Result<(PackManifest, Vec<SavedPuzzle>)> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_ron(&path) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            files.push((name.into_owned(), fs::read_to_string(&path)?));
+        }
+    }
+    read_pack_files(files)
+}
+
+// Hidden files and macOS's `__MACOSX` folder, which zipping on a Mac adds resource forks to under
+// the same names as the real files.
+fn is_zip_clutter(path: &Path) -> bool {
+    path.components().any(|c| {
+        let c = c.as_os_str().to_string_lossy();
+        c.starts_with('.') || c == "__MACOSX"
+    })
+}
+
+// A zipped pack directory. Entries can be anywhere in the archive, since zipping a directory
+// usually puts everything inside a folder of the same name.
+fn read_pack_zip(reader: impl io::Read + io::Seek) -> io::Result<(PackManifest, Vec<SavedPuzzle>)> {
+    let mut archive = zip::ZipArchive::new(reader)?;
+    let mut files = vec![];
+    for nr in 0..archive.len() {
+        let mut entry = archive.by_index(nr)?;
+        let Some(path) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir() || !is_ron(&path) || is_zip_clutter(&path) {
+            continue;
+        }
+        let name = path.to_string_lossy().into_owned();
+        let mut data = String::new();
+        io::Read::read_to_string(&mut entry, &mut data)?;
+        files.push((name, data));
+    }
+    read_pack_files(files)
+}
+
+fn read_pack(path: &Path) -> io::Result<(PackManifest, Vec<SavedPuzzle>)> {
+    if path.is_dir() {
+        read_pack_dir(path)
+    } else if is_zip(path) {
+        read_pack_zip(fs::File::open(path)?)
+    } else {
+        let SavedPuzzlePack {
+            title,
+            author,
+            puzzles,
+        } = read_ron(path)?;
+        for saved in &puzzles {
+            saved.validate().map_err(io::Error::other)?;
+        }
+        Ok((PackManifest { title, author }, puzzles))
+    }
+}
+
+fn scan_packs(dir: &Path) -> Vec<PuzzlePack> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_dir()
+                || is_zip(p)
+                || (is_ron(p) && p.file_name().is_some_and(|n| n != PACK_PROGRESS))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let id = path.file_name()?.to_string_lossy().into_owned();
+            match read_pack(&path) {
+                Ok((mut manifest, puzzles)) if !puzzles.is_empty() => {
+                    if manifest.title.is_empty() {
+                        manifest.title = id.clone();
+                    }
+                    Some(PuzzlePack {
+                        id,
+                        manifest,
+                        puzzles,
+                    })
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("couldn't read puzzle pack {path:?}: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn progress_path() -> PathBuf {
+    Path::new(PACKS_DIR).join(PACK_PROGRESS)
+}
+
+fn load_packs(mut commands: Commands) {
+    let packs = scan_packs(Path::new(PACKS_DIR));
+    info!("found {} puzzle packs", packs.len());
+    commands.insert_resource(PuzzlePacks(packs));
+    let progress = read_ron(&progress_path()).unwrap_or_default();
+    commands.insert_resource::<PackProgress>(progress);
+}
+
+fn spawn_pack_browser(
+    mut commands: Commands,
+    packs: Res<PuzzlePacks>,
+    progress: Res<PackProgress>,
+) {
+    if packs.0.is_empty() {
+        return;
+    }
+    commands
+        .spawn((
+            PackBrowser,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Px(10.),
+                max_height: Val::Percent(90.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.),
+                padding: UiRect::all(Val::Px(10.)),
+                overflow: Overflow::clip_y(),
+                ..Default::default()
+            },
+            BackgroundColor(PACK_BROWSER_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Puzzle packs"), NO_PICK));
+            for (nr, pack) in packs.0.iter().enumerate() {
+                let done = progress.0.get(&pack.id).map_or(0, |d| d.len());
+                let author = if pack.manifest.author.is_empty() {
+                    String::new()
+                } else {
+                    format!(" by {}", pack.manifest.author)
+                };
+                let label = format!(
+                    "{}{author}\n{} puzzles, up to {:?}, {done} solved",
+                    pack.manifest.title,
+                    pack.puzzles.len(),
+                    pack.difficulty(),
+                );
+                parent
+                    .spawn((
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        NO_PICK,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Text::new(label),
+                            TextFont {
+                                font_size: 14.,
+                                ..Default::default()
+                            },
+                            NO_PICK,
+                        ));
+                        parent
+                            .spawn((
+                                PlayPackButton(nr),
+                                Node {
+                                    padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                                    ..Default::default()
+                                },
+                                BackgroundColor(PACK_BUTTON_COLOR),
+                            ))
+                            .with_child((Text::new("Play"), NO_PICK));
+                    });
+            }
+        });
+}
+
+fn despawn_pack_browser(mut commands: Commands, q_browser: Query<Entity, With<PackBrowser>>) {
+    for entity in &q_browser {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn play_pack_clicked(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_button: Query<&PlayPackButton>,
+    packs: Res<PuzzlePacks>,
+    progress: Res<PackProgress>,
+    mut notes: Single<&mut PuzzleNotes>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Ok(&PlayPackButton(nr)) = q_button.get(ev.entity()) else {
+        return;
+    };
+    let Some(pack) = packs.0.get(nr) else {
+        return;
+    };
+    // Picks up with the first puzzle that hasn't been solved, starting over once all have.
+    let completed = progress.0.get(&pack.id);
+    let index = (0..pack.puzzles.len())
+        .find(|i| !completed.is_some_and(|c| c.contains(i)))
+        .unwrap_or(0);
+    let saved = pack.puzzles[index].clone();
+    notes.text = saved.notes.clone();
+    commands.insert_resource(PuzzleSpawn::from_saved(saved));
+    commands.insert_resource(PlayingPackPuzzle {
+        pack: pack.id.clone(),
+        index,
+    });
+    next_state.set(GameState::Playing);
+}
+
+fn track_pack_completion(
+    puzzle: Single<Ref<Puzzle>>,
+    playing: Option<Res<PlayingPackPuzzle>>,
+    mut progress: ResMut<PackProgress>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    let Some(playing) = playing else {
+        return;
+    };
+    if !puzzle.is_changed() || !puzzle.is_solved() {
+        return;
+    }
+    let newly_done = progress
+        .0
+        .entry(playing.pack.clone())
+        .or_default()
+        .insert(playing.index);
+    if !newly_done {
+        return;
+    }
+    let path = progress_path();
+    let toast = match ron::to_string(&*progress)
+        .map_err(io::Error::other)
+        .and_then(|data| fs::write(&path, data))
+    {
+        Ok(()) => ShowToast::info(format!("Solved puzzle {} of this pack!", playing.index + 1)),
+        Err(e) => ShowToast::error(format!("Couldn't write {path:?}: {e}")),
+    };
+    toast_tx.send(toast);
+}
+
+// Whatever is played next, be it generated, resumed or imported, isn't this pack's puzzle.
+fn forget_pack_puzzle(mut commands: Commands) {
+    commands.remove_resource::<PlayingPackPuzzle>();
+}
+
+pub struct PacksPlugin;
+
+impl Plugin for PacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PackBrowser>()
+            .register_type::<PlayPackButton>()
+            .register_type::<PlayingPackPuzzle>()
+            .add_observer(play_pack_clicked)
+            .add_systems(Startup, load_packs)
+            .add_systems(OnEnter(GameState::MainMenu), spawn_pack_browser)
+            .add_systems(OnExit(GameState::MainMenu), despawn_pack_browser)
+            .add_systems(
+                Update,
+                track_pack_completion.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), forget_pack_puzzle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use bevy::state::app::StatesPlugin;
+
+    use super::*;
+    use crate::{
+        puzzle::{loc, test_puzzle, CellLocIndex, LInd, UpdateCellIndexOperation},
+        save::PuzzleMetadata,
+    };
+
+    fn saved_puzzle(notes: &str) -> String {
+        SavedPuzzle {
+            metadata: PuzzleMetadata::default(),
+            puzzle: test_puzzle(3, 4),
+            reveals: vec![],
+            clues: vec![],
+            archived: vec![],
+            notes: notes.to_string(),
+            undo: None,
+        }
+        .to_ron()
+        .unwrap()
+    }
+
+    fn zipped(files: &[(&str, &[u8])]) -> io::Cursor<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn zipped_packs_read_like_directories() {
+        let manifest = ron::to_string(&PackManifest {
+            title: "Zipped".into(),
+            author: "fox".into(),
+        })
+        .unwrap();
+        let (manifest, puzzles) = read_pack_zip(zipped(&[
+            ("pack/2.ron", saved_puzzle("second").as_bytes()),
+            ("pack/pack.ron", manifest.as_bytes()),
+            ("pack/1.ron", saved_puzzle("first").as_bytes()),
+            ("pack/README.txt", b"not a puzzle"),
+            // Neither UTF-8 nor RON, so reading any of these would fail the pack.
+            ("pack/cover.png", b"\x89PNG\r\n\x1a\n\xff"),
+            ("__MACOSX/pack/._1.ron", b"\x00\x05\x16\x07\xff"),
+            ("pack/.hidden.ron", b"\xff"),
+        ]))
+        .unwrap();
+        assert_eq!(manifest.title, "Zipped");
+        assert_eq!(manifest.author, "fox");
+        let notes = puzzles.iter().map(|p| p.notes.as_str()).collect::<Vec<_>>();
+        assert_eq!(notes, ["first", "second"]);
+    }
+
+    #[test]
+    fn zipped_files_in_different_folders_are_both_read() {
+        let (_, puzzles) = read_pack_zip(zipped(&[
+            ("pack/a/1.ron", saved_puzzle("a").as_bytes()),
+            ("pack/b/1.ron", saved_puzzle("b").as_bytes()),
+        ]))
+        .unwrap();
+        let notes = puzzles.iter().map(|p| p.notes.as_str()).collect::<Vec<_>>();
+        assert_eq!(notes, ["a", "b"]);
+    }
+
+    #[test]
+    fn zipped_puzzles_are_validated() {
+        let mut saved = ron::from_str::<SavedPuzzle>(&saved_puzzle("")).unwrap();
+        // A reveal off the board, which only validation notices.
+        saved.reveals.push(CellLocIndex {
+            loc: loc(9, 0),
+            index: LInd(0),
+        });
+        let data = ron::to_string(&saved).unwrap();
+        assert!(read_pack_zip(zipped(&[("1.ron", data.as_bytes())])).is_err());
+        assert!(read_pack_zip(io::Cursor::new(b"not a zip".to_vec())).is_err());
+    }
+
+    #[test]
+    fn only_the_pack_puzzle_counts_toward_its_pack() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .init_state::<GameState>()
+            .add_event::<ShowToast>()
+            .init_resource::<PackProgress>()
+            .insert_resource(PlayingPackPuzzle {
+                pack: "pack".into(),
+                index: 0,
+            })
+            .add_systems(
+                Update,
+                track_pack_completion.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), forget_pack_puzzle);
+        let puzzle = app.world_mut().spawn(test_puzzle(2, 3)).id();
+        let set_state = |app: &mut App, state| {
+            app.world_mut()
+                .resource_mut::<NextState<GameState>>()
+                .set(state);
+            app.update();
+        };
+        set_state(&mut app, GameState::Playing);
+        set_state(&mut app, GameState::MainMenu);
+        assert!(!app.world().contains_resource::<PlayingPackPuzzle>());
+
+        // The next puzzle isn't from the pack, so solving it records nothing.
+        set_state(&mut app, GameState::Playing);
+        let mut solved = app.world_mut().get_mut::<Puzzle>(puzzle).unwrap();
+        for loc in solved.iter_locs().collect::<Vec<_>>() {
+            let answer = solved.answer_at(loc).index.decay_to_ind();
+            solved
+                .cell_selection_mut(loc)
+                .apply(answer, UpdateCellIndexOperation::Solo);
+        }
+        app.update();
+        assert!(app.world().get::<Puzzle>(puzzle).unwrap().is_solved());
+        assert!(app.world().resource::<PackProgress>().0.is_empty());
+    }
+}
@@ -0,0 +1,388 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    puzzle::{CellLocIndex, LInd, LRow, Puzzle, RowAnswer},
+    settings::Settings,
+    NO_PICK,
+};
+
+// The colors behind a row's items, as shown. A puzzle's own colors are generated, saved, and shared
+// as they always were; a palette only changes what's drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum Palette {
+    // The hues generated with the puzzle.
+    Generated,
+    // Okabe and Ito's palette, from "Color Universal Design".
+    OkabeIto,
+    // Paul Tol's bright qualitative scheme.
+    TolBright,
+}
+
+const OKABE_ITO: &[[u8; 3]] = &[
+    [0x00, 0x00, 0x00],
+    [0xe6, 0x9f, 0x00],
+    [0x56, 0xb4, 0xe9],
+    [0x00, 0x9e, 0x73],
+    [0xf0, 0xe4, 0x42],
+    [0x00, 0x72, 0xb2],
+    [0xd5, 0x5e, 0x00],
+    [0xcc, 0x79, 0xa7],
+];
+
+const TOL_BRIGHT: &[[u8; 3]] = &[
+    [0x44, 0x77, 0xaa],
+    [0xee, 0x66, 0x77],
+    [0x22, 0x88, 0x33],
+    [0xcc, 0xbb, 0x44],
+    [0x66, 0xcc, 0xee],
+    [0xaa, 0x33, 0x77],
+    [0xbb, 0xbb, 0xbb],
+];
+
+// Curated colors are darkened to at most this, so the white item art on top stays readable.
+const MAX_LIGHTNESS: f32 = 0.45;
+
+impl Palette {
+    fn curated(self) -> Option<&'static [[u8; 3]]> {
+        match self {
+            Palette::Generated => None,
+            Palette::OkabeIto => Some(OKABE_ITO),
+            Palette::TolBright => Some(TOL_BRIGHT),
+        }
+    }
+
+    // Always an Hsla color, which the opacity animations on candidate buttons need. Rows longer
+    // than a curated palette reuse its colors, and only the patterns tell those items apart.
+    pub fn color(self, puzzle: &Puzzle, swatch: &Swatch) -> Color {
+        let Some(colors) = self.curated() else {
+            return puzzle.row_at(swatch.row).display_color(swatch.index);
+        };
+        let [r, g, b] = colors[swatch.index.0 % colors.len()];
+        let color = Hsla::from(Color::srgb_u8(r, g, b));
+        Color::Hsla(color.with_lightness(color.lightness.min(MAX_LIGHTNESS)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+enum Pattern {
+    Stripes,
+    Dots,
+}
+
+impl Pattern {
+    // Items go plain, striped, dotted, and around again. Three shares no factor with the length of
+    // either curated palette, so the first 21 items of a row all look different.
+    fn for_index(index: LInd) -> Option<Self> {
+        match index.0 % 3 {
+            0 => None,
+            1 => Some(Pattern::Stripes),
+            _ => Some(Pattern::Dots),
+        }
+    }
+
+    fn atlas_index(self) -> usize {
+        match self {
+            Pattern::Stripes => 0,
+            Pattern::Dots => 1,
+        }
+    }
+
+    fn covers(self, x: u32, y: u32) -> bool {
+        match self {
+            Pattern::Stripes => ((x + y) / 4).is_multiple_of(2),
+            Pattern::Dots => {
+                let offset = |v: u32| (v % 8) as f32 - 3.5;
+                offset(x).hypot(offset(y)) < 2.
+            }
+        }
+    }
+}
+
+const PATTERN_SIZE: u32 = 32;
+const PATTERN_COLOR: Color = Color::hsla(0., 0., 0., 0.35);
+
+const PATTERN_IMAGE_HANDLE: Handle<Image> =
+    Handle::weak_from_u128(0x2c5e_91d4_7a03_4f6b_b812_6e0d_c9f4_3a17);
+
+// Both patterns side by side, white where they're drawn, to be tinted by the sprite.
+fn pattern_image() -> Image {
+    let patterns = [Pattern::Stripes, Pattern::Dots];
+    let width = PATTERN_SIZE * patterns.len() as u32;
+    let mut data = vec![0u8; (width * PATTERN_SIZE * 4) as usize];
+    for pattern in patterns {
+        let left = pattern.atlas_index() as u32 * PATTERN_SIZE;
+        for y in 0..PATTERN_SIZE {
+            for x in 0..PATTERN_SIZE {
+                if pattern.covers(x, y) {
+                    let offset = ((y * width + left + x) * 4) as usize;
+                    data[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+                }
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height: PATTERN_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+struct PatternAtlas {
+    atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+impl FromWorld for PatternAtlas {
+    fn from_world(world: &mut World) -> Self {
+        world
+            .resource_mut::<Assets<Image>>()
+            .insert(&PATTERN_IMAGE_HANDLE, pattern_image());
+        let atlas_layout =
+            world
+                .resource_mut::<Assets<TextureAtlasLayout>>()
+                .add(TextureAtlasLayout::from_grid(
+                    UVec2::splat(PATTERN_SIZE),
+                    2,
+                    1,
+                    None,
+                    None,
+                ));
+        PatternAtlas { atlas_layout }
+    }
+}
+
+// A background, for the board or a clue, colored after one of a row's items.
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+pub struct Swatch {
+    row: LRow,
+    index: LInd,
+}
+
+impl Swatch {
+    pub fn new(index: CellLocIndex) -> Self {
+        Swatch {
+            row: index.loc.row,
+            index: index.index,
+        }
+    }
+
+    pub fn of_answer(answer: RowAnswer) -> Self {
+        Swatch {
+            row: answer.row(),
+            index: answer.index().decay_to_ind(),
+        }
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct SwatchPattern;
+
+// Swatches are sprites on the board and in clues, and UI nodes elsewhere.
+type SwatchD<'r> = (
+    &'r Swatch,
+    Option<&'r mut Sprite>,
+    Option<&'r mut BackgroundColor>,
+);
+
+fn pattern_visibility(settings: &Settings) -> Visibility {
+    if settings.patterns {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+// Patterns fade along with the sprite they're drawn over, so an eliminated candidate's pattern
+// goes as faint as its button.
+fn pattern_color(swatch_sprite: &Sprite) -> Color {
+    PATTERN_COLOR.with_alpha(PATTERN_COLOR.alpha() * swatch_sprite.color.alpha())
+}
+
+fn add_swatch(
+    ev: Trigger<OnAdd, Swatch>,
+    settings: Res<Settings>,
+    atlas: Res<PatternAtlas>,
+    q_puzzle: Query<&Puzzle>,
+    mut q_swatch: Query<SwatchD, Without<SwatchPattern>>,
+    mut commands: Commands,
+) {
+    let (Ok(puzzle), Ok((swatch, sprite, background))) =
+        (q_puzzle.get_single(), q_swatch.get_mut(ev.entity()))
+    else {
+        return;
+    };
+    let color = settings.palette.color(puzzle, swatch);
+    if let Some(mut background) = background {
+        background.0 = color;
+    }
+    // Only sprites get patterns; the UI nodes colored after items are each shown on their own.
+    let Some(mut sprite) = sprite else {
+        return;
+    };
+    sprite.color = color.with_alpha(sprite.color.alpha());
+    let Some(pattern) = Pattern::for_index(swatch.index) else {
+        return;
+    };
+    let mut pattern_sprite = Sprite::from_atlas_image(PATTERN_IMAGE_HANDLE, TextureAtlas {
+        layout: atlas.atlas_layout.clone(),
+        index: pattern.atlas_index(),
+    });
+    pattern_sprite.color = pattern_color(&sprite);
+    pattern_sprite.custom_size = sprite.custom_size;
+    commands.entity(ev.entity()).with_child((
+        SwatchPattern,
+        pattern_sprite,
+        Transform::from_xyz(0., 0., 0.5),
+        pattern_visibility(&settings),
+        NO_PICK,
+    ));
+}
+
+fn apply_palette(
+    settings: Res<Settings>,
+    q_puzzle: Query<&Puzzle>,
+    mut q_swatch: Query<SwatchD, Without<SwatchPattern>>,
+    mut q_pattern: Query<&mut Visibility, With<SwatchPattern>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Ok(puzzle) = q_puzzle.get_single() else {
+        return;
+    };
+    for (swatch, sprite, background) in &mut q_swatch {
+        let color = settings.palette.color(puzzle, swatch);
+        if let Some(mut sprite) = sprite {
+            // Candidate buttons fade in and out, which is kept.
+            let alpha = sprite.color.alpha();
+            sprite.color = color.with_alpha(alpha);
+        }
+        if let Some(mut background) = background {
+            background.0 = color;
+        }
+    }
+    for mut visibility in &mut q_pattern {
+        visibility.set_if_neq(pattern_visibility(&settings));
+    }
+}
+
+fn fade_patterns(
+    q_swatch: Query<Ref<Sprite>, (With<Swatch>, Without<SwatchPattern>)>,
+    mut q_pattern: Query<(&Parent, &mut Sprite), With<SwatchPattern>>,
+) {
+    for (parent, mut sprite) in &mut q_pattern {
+        let Ok(swatch_sprite) = q_swatch.get(parent.get()) else {
+            continue;
+        };
+        if swatch_sprite.is_changed() {
+            let color = pattern_color(&swatch_sprite);
+            if sprite.color != color {
+                sprite.color = color;
+            }
+        }
+    }
+}
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatternAtlas>()
+            .register_type::<PatternAtlas>()
+            .register_type::<Swatch>()
+            .register_type::<SwatchPattern>()
+            .add_observer(add_swatch)
+            .add_systems(Update, apply_palette)
+            .add_systems(PostUpdate, fade_patterns.after(bevy::app::Animation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curated_colors_stay_dark_and_distinct() {
+        let puzzle = Puzzle::default();
+        for palette in [Palette::OkabeIto, Palette::TolBright] {
+            let len = palette.curated().unwrap().len();
+            let looks = (0..21)
+                .map(|i| {
+                    let swatch = Swatch {
+                        row: LRow(0),
+                        index: LInd(i),
+                    };
+                    let Color::Hsla(color) = palette.color(&puzzle, &swatch) else {
+                        panic!("{palette:?} gave a color buttons can't fade");
+                    };
+                    assert!(color.lightness <= MAX_LIGHTNESS);
+                    (i % len, Pattern::for_index(swatch.index))
+                })
+                .collect::<Vec<_>>();
+            for (i, look) in looks.iter().enumerate() {
+                assert!(!looks[..i].contains(look), "{palette:?} repeats item {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn patterns_are_drawn_into_their_own_tiles() {
+        let image = pattern_image();
+        assert_eq!(image.width(), PATTERN_SIZE * 2);
+        let alpha = |x: u32, y: u32| image.data[((y * image.width() + x) * 4 + 3) as usize];
+        // Stripes start filled in the corner, and dots leave theirs empty.
+        assert_eq!(alpha(0, 0), 255);
+        assert_eq!(alpha(PATTERN_SIZE, 0), 0);
+        assert_eq!(alpha(PATTERN_SIZE + 3, 3), 255);
+    }
+
+    #[test]
+    fn patterns_fade_with_their_swatch() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_systems(PostUpdate, fade_patterns);
+        let swatch = Swatch {
+            row: LRow(0),
+            index: LInd(1),
+        };
+        let button = app
+            .world_mut()
+            .spawn((
+                swatch,
+                Sprite::from_color(Color::hsla(0., 0.5, 0.4, 1.), Vec2::ONE),
+            ))
+            .id();
+        let pattern = app
+            .world_mut()
+            .spawn((SwatchPattern, Sprite::from_color(PATTERN_COLOR, Vec2::ONE)))
+            .set_parent(button)
+            .id();
+        let alpha = |app: &App| app.world().get::<Sprite>(pattern).unwrap().color.alpha();
+        app.update();
+        assert_eq!(alpha(&app), PATTERN_COLOR.alpha());
+
+        // Eliminating the candidate fades its button, as `ButtonOpacityAnimation` does.
+        let mut sprite = app.world_mut().get_mut::<Sprite>(button).unwrap();
+        sprite.color.set_alpha(0.2);
+        app.update();
+        assert!((alpha(&app) - PATTERN_COLOR.alpha() * 0.2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,74 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{color::palettes::css, prelude::*};
+
+use crate::{
+    fit::{FitManip, FitWithin},
+    toast::ShowToast,
+    DisplayClue, DisplayCluebox, NO_PICK,
+};
+
+// More than this and the pinned clues would crowd out the rest of the cluebox.
+pub const MAX_PINNED_CLUES: usize = 3;
+
+// A clue that always sits at the start of the cluebox at full width. Right-clicking a clue toggles
+// it.
+#[derive(Debug, Component, Reflect)]
+pub struct PinnedClue;
+
+#[derive(Debug, Component, Reflect)]
+struct PinMarker;
+
+const PIN_MARKER_COLOR: Srgba = css::GOLD;
+
+fn toggle_pinned_clue(
+    ev: Trigger<Pointer<Click>>,
+    mut commands: Commands,
+    q_clue: Query<Option<&Children>, With<DisplayClue>>,
+    q_pinned: Query<(), With<PinnedClue>>,
+    q_marker: Query<(), With<PinMarker>>,
+    q_cluebox: Single<(Entity, &FitWithin), With<DisplayCluebox>>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    if ev.event().button != PointerButton::Secondary {
+        return;
+    }
+    let clue = ev.entity();
+    let Ok(children) = q_clue.get(clue) else {
+        return;
+    };
+    if q_pinned.contains(clue) {
+        commands.entity(clue).remove::<PinnedClue>();
+        for &child in children.into_iter().flatten() {
+            if q_marker.contains(child) {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+    } else if q_pinned.iter().count() >= MAX_PINNED_CLUES {
+        toast_tx.send(ShowToast::error(format!(
+            "Only {MAX_PINNED_CLUES} clues can be pinned at once."
+        )));
+        return;
+    } else {
+        commands.entity(clue).insert(PinnedClue).with_child((
+            PinMarker,
+            Sprite::from_color(PIN_MARKER_COLOR, Vec2::new(24., 6.)),
+            Transform::from_xyz(0., 56., 0.),
+            NO_PICK,
+        ));
+    }
+    let cluebox_e_fit = *q_cluebox;
+    cluebox_e_fit.refresh_rect(&mut commands);
+}
+
+pub struct PinsPlugin;
+
+impl Plugin for PinsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PinnedClue>()
+            .register_type::<PinMarker>()
+            .add_observer(toggle_pinned_clue);
+    }
+}
@@ -0,0 +1,87 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+// A tileset drawn at startup instead of loaded from disk, for boards wider than any of the art.
+// Tiles are regular polygons, solid and then outlined, from triangles up to decagons.
+pub const TILE_SIZE: u32 = 128;
+pub const N_TILES: u32 = 16;
+pub const ASSET_PATH: &str = "procedural";
+
+pub const IMAGE_HANDLE: Handle<Image> =
+    Handle::weak_from_u128(0x5f0b_36c1_8e2d_4a57_9c44_1d7e_a3b2_c901);
+
+const MIN_SIDES: u32 = 3;
+const N_SHAPES: u32 = N_TILES / 2;
+
+// Whether a point, relative to the center and scaled so the polygon's corners are at radius 1, is
+// inside the polygon.
+fn in_polygon(point: Vec2, sides: u32) -> bool {
+    let sector = TAU / sides as f32;
+    // Point the first corner straight up.
+    let angle = (point.to_angle() - PI / 2.).rem_euclid(sector);
+    let edge_distance = (PI / sides as f32).cos() / (angle - sector / 2.).cos();
+    point.length() <= edge_distance
+}
+
+fn tile_covers(tile: u32, point: Vec2) -> bool {
+    let sides = MIN_SIDES + tile % N_SHAPES;
+    let outer = in_polygon(point / 0.85, sides);
+    if tile < N_SHAPES {
+        outer
+    } else {
+        outer && !in_polygon(point / 0.55, sides)
+    }
+}
+
+fn tileset_image() -> Image {
+    let width = TILE_SIZE * N_TILES;
+    let mut data = vec![0u8; (width * TILE_SIZE * 4) as usize];
+    let half = TILE_SIZE as f32 / 2.;
+    for tile in 0..N_TILES {
+        for y in 0..TILE_SIZE {
+            for x in 0..TILE_SIZE {
+                // Image rows run top to bottom, so y is flipped to keep shapes upright.
+                let point = Vec2::new(x as f32 + 0.5 - half, half - y as f32 - 0.5) / half;
+                if !tile_covers(tile, point) {
+                    continue;
+                }
+                let offset = ((y * width + tile * TILE_SIZE + x) * 4) as usize;
+                data[offset..offset + 4].copy_from_slice(&[255, 255, 255, 255]);
+            }
+        }
+    }
+    Image::new(
+        Extent3d {
+            width,
+            height: TILE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+fn add_tileset_image(mut images: ResMut<Assets<Image>>) {
+    images.insert(&IMAGE_HANDLE, tileset_image());
+}
+
+pub struct ProceduralTilesetPlugin;
+
+impl Plugin for ProceduralTilesetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, add_tileset_image);
+    }
+}
@@ -11,13 +11,40 @@ use bevy::{
 use fixedbitset::FixedBitSet;
 use itertools::MinMaxResult;
 use rand::{seq::SliceRandom, Rng};
-
-use crate::UpdateCellIndex;
-
-#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+use serde::{Deserialize, Serialize};
+
+use crate::{Tileset, UpdateCellIndex};
+
+#[derive(
+    Reflect,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct LRow(pub usize);
 
-#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Reflect,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct LCol(pub isize);
 
 impl LCol {
@@ -57,10 +84,36 @@ impl From<MinMaxResult<LCol>> for LColspan {
     }
 }
 
-#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Reflect,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct LInd(pub usize);
 
-#[derive(Reflect, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Reflect,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 pub struct LAns(pub usize);
 
 impl LAns {
@@ -69,7 +122,9 @@ impl LAns {
     }
 }
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub struct CellLoc {
     pub row: LRow,
     pub col: LCol,
@@ -91,7 +146,9 @@ impl CellLoc {
     }
 }
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Reflect, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub struct CellLocIndexed<I> {
     pub loc: CellLoc,
     pub index: I,
@@ -120,6 +177,7 @@ impl CellLocIndex {
             index: *self,
             op,
             explanation: None,
+            gesture: None,
         }
     }
 
@@ -132,6 +190,17 @@ impl CellLocIndex {
     }
 }
 
+// Position-based naming for places that can't show the cell's icon, like logs.
+impl std::fmt::Display for CellLocIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "item {} at row {} column {}",
+            self.index.0, self.loc.row.0, self.loc.col.0
+        )
+    }
+}
+
 impl CellLocAnswer {
     pub fn decay_to_ind(&self) -> CellLocIndex {
         CellLocIndex {
@@ -159,6 +228,16 @@ pub struct RowIndexed<I> {
 pub type RowIndex = RowIndexed<LInd>;
 pub type RowAnswer = RowIndexed<LAns>;
 
+impl<I: Copy> RowIndexed<I> {
+    pub fn row(&self) -> LRow {
+        self.row
+    }
+
+    pub fn index(&self) -> I {
+        self.index
+    }
+}
+
 impl RowIndex {
     pub fn upgrade_to_answer(&self) -> RowAnswer {
         RowAnswer {
@@ -168,18 +247,62 @@ impl RowIndex {
     }
 }
 
-#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UpdateCellIndexOperation {
     Clear,
     Set,
     Toggle,
     Solo,
+    // Pencil marks: a tentatively eliminated candidate is still a candidate to everything but the
+    // display until the cell's marks are confirmed, which clears them, or reverted.
+    MarkTentative,
+    ConfirmAll,
+    RevertTentative,
 }
 
-#[derive(Debug, Clone, Reflect)]
+impl UpdateCellIndexOperation {
+    pub const ALL: [Self; 7] = [
+        Self::Clear,
+        Self::Set,
+        Self::Toggle,
+        Self::Solo,
+        Self::MarkTentative,
+        Self::ConfirmAll,
+        Self::RevertTentative,
+    ];
+}
+
+impl std::fmt::Display for UpdateCellIndexOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use UpdateCellIndexOperation as Op;
+        f.write_str(match self {
+            Op::Clear => "Clear",
+            Op::Set => "Set",
+            Op::Toggle => "Toggle",
+            Op::Solo => "Solo",
+            Op::MarkTentative => "Mark tentative",
+            Op::ConfirmAll => "Confirm all",
+            Op::RevertTentative => "Revert tentative",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
 pub enum PuzzleCellSelection {
-    Enabled(#[reflect(ignore)] FixedBitSet),
-    Solo { width: usize, index: LInd },
+    // The remaining candidates, then those of them the player has tentatively eliminated. Saves from
+    // before pencil marks have no second set.
+    Enabled(
+        #[reflect(ignore)]
+        #[serde(with = "crate::save::bitset")]
+        FixedBitSet,
+        #[reflect(ignore)]
+        #[serde(default, with = "crate::save::bitset")]
+        FixedBitSet,
+    ),
+    Solo {
+        width: usize,
+        index: LInd,
+    },
     Void,
 }
 
@@ -193,7 +316,8 @@ impl Default for PuzzleCellSelection {
 
 impl PuzzleCellSelection {
     pub fn new(enabled: FixedBitSet) -> Self {
-        PuzzleCellSelection::Enabled(enabled)
+        let tentative = FixedBitSet::with_capacity(enabled.len());
+        PuzzleCellSelection::Enabled(enabled, tentative)
     }
 
     pub fn is_void(&self) -> bool {
@@ -203,16 +327,23 @@ impl PuzzleCellSelection {
     pub fn is_enabled(&self, index: LInd) -> bool {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => s.contains(index.0),
+            Enabled(s, _) => s.contains(index.0),
             &Solo { index: i, .. } => index == i,
             Void => false,
         }
     }
 
+    pub fn is_tentative(&self, index: LInd) -> bool {
+        match self {
+            PuzzleCellSelection::Enabled(_, tentative) => tentative.contains(index.0),
+            _ => false,
+        }
+    }
+
     pub fn is_solo(&self, index: LInd) -> bool {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => s.contains(index.0) && s.count_ones(..) == 1,
+            Enabled(s, _) => s.contains(index.0) && s.count_ones(..) == 1,
             &Solo { index: i, .. } => index == i,
             Void => false,
         }
@@ -221,7 +352,7 @@ impl PuzzleCellSelection {
     pub fn is_any_solo(&self) -> Option<LInd> {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => {
+            Enabled(s, _) => {
                 let mut ones = s.ones();
                 let ret = ones.next();
                 if ret.is_some() && ones.next().is_none() {
@@ -238,7 +369,7 @@ impl PuzzleCellSelection {
     pub fn width(&self) -> usize {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => s.len(),
+            Enabled(s, _) => s.len(),
             &Solo { width, .. } => width,
             Void => 0,
         }
@@ -247,7 +378,7 @@ impl PuzzleCellSelection {
     pub fn count_ones(&self) -> usize {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => s.count_ones(..),
+            Enabled(s, _) => s.count_ones(..),
             Solo { .. } => 1,
             Void => 0,
         }
@@ -256,12 +387,31 @@ impl PuzzleCellSelection {
     pub fn iter_ones(&self) -> Box<dyn Iterator<Item = LInd> + '_> {
         use PuzzleCellSelection::*;
         match self {
-            Enabled(s) => Box::new(s.ones().map(LInd)),
+            Enabled(s, _) => Box::new(s.ones().map(LInd)),
             &Solo { index, .. } => Box::new(std::iter::once(index)),
             Void => Box::new(std::iter::empty()),
         }
     }
 
+    // Whether applying `op` would change anything, e.g. clearing an already-cleared candidate
+    // wouldn't.
+    pub fn would_change(&self, index: LInd, op: UpdateCellIndexOperation) -> bool {
+        !self.is_void() && self.clone().apply(index, op) > 0
+    }
+
+    pub fn would_empty(&self, index: LInd, op: UpdateCellIndexOperation) -> bool {
+        let mut scratch = self.clone();
+        scratch.apply(index, op);
+        scratch.count_ones() == 0
+    }
+
+    // Whether applying `op` would take `candidate` out of the cell.
+    pub fn would_remove(&self, index: LInd, op: UpdateCellIndexOperation, candidate: LInd) -> bool {
+        let mut scratch = self.clone();
+        scratch.apply(index, op);
+        self.is_enabled(candidate) && !scratch.is_enabled(candidate)
+    }
+
     pub fn apply(&mut self, index: LInd, op: UpdateCellIndexOperation) -> usize {
         use UpdateCellIndexOperation::*;
         if self.is_void() {
@@ -277,29 +427,53 @@ impl PuzzleCellSelection {
             return ret;
         }
         match self {
-            PuzzleCellSelection::Enabled(enabled) => match op {
-                Clear => {
-                    let ret = if enabled.contains(index.0) { 1 } else { 0 };
-                    enabled.remove(index.0);
-                    ret
-                }
-                Set => {
-                    if enabled.put(index.0) {
-                        0
-                    } else {
+            PuzzleCellSelection::Enabled(enabled, tentative) => {
+                let ret = match op {
+                    Clear => {
+                        let ret = if enabled.contains(index.0) { 1 } else { 0 };
+                        enabled.remove(index.0);
+                        ret
+                    }
+                    Set => {
+                        if enabled.put(index.0) {
+                            0
+                        } else {
+                            1
+                        }
+                    }
+                    Toggle => {
+                        enabled.toggle(index.0);
                         1
                     }
-                }
-                Toggle => {
-                    enabled.toggle(index.0);
-                    1
-                }
-                Solo => unreachable!(),
-            },
+                    MarkTentative => {
+                        if enabled.contains(index.0) && !tentative.contains(index.0) {
+                            tentative.grow_and_insert(index.0);
+                            1
+                        } else {
+                            0
+                        }
+                    }
+                    ConfirmAll => {
+                        let ret = tentative.count_ones(..);
+                        enabled.difference_with(tentative);
+                        tentative.clear();
+                        ret
+                    }
+                    RevertTentative => {
+                        let ret = tentative.count_ones(..);
+                        tentative.clear();
+                        ret
+                    }
+                    Solo => unreachable!(),
+                };
+                // Only remaining candidates can be tentatively eliminated.
+                tentative.intersect_with(enabled);
+                ret
+            }
             &mut PuzzleCellSelection::Solo { width, index: i } => {
                 let mut enabled = FixedBitSet::with_capacity(width);
                 enabled.insert(i.0);
-                *self = PuzzleCellSelection::Enabled(enabled);
+                *self = PuzzleCellSelection::new(enabled);
                 return self.apply(index, op);
             }
             PuzzleCellSelection::Void => unreachable!(),
@@ -307,13 +481,14 @@ impl PuzzleCellSelection {
     }
 }
 
-#[derive(Debug, Clone, Reflect)]
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
 pub struct PuzzleCellDisplay {
     atlas_index: usize,
+    #[serde(with = "crate::save::hsla")]
     color: Color,
 }
 
-#[derive(Debug, Clone, Reflect)]
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize)]
 pub struct PuzzleRow {
     // LCol -> [LInd]
     cell_selection: Vec<PuzzleCellSelection>,
@@ -321,26 +496,33 @@ pub struct PuzzleRow {
     cell_display: Vec<PuzzleCellDisplay>,
     // LCol -> LAns
     cell_answers: Vec<LAns>,
+    tileset: Tileset,
+    #[serde(skip)]
     atlas: Handle<Image>,
+    #[serde(skip)]
     atlas_layout: Handle<TextureAtlasLayout>,
+    // LInd -> how many cells still have it. Inference keeps this up to date as it goes; any other
+    // change drops it to be recounted.
+    #[serde(skip)]
+    #[reflect(ignore)]
+    candidate_counts: Option<Vec<usize>>,
 }
 
 impl PuzzleRow {
     pub fn new_shuffled<R: Rng>(
         rng: &mut R,
         len: usize,
+        tileset: &Tileset,
+        colors: Vec<Color>,
         atlas: Handle<Image>,
         atlas_layout: Handle<TextureAtlasLayout>,
-        atlas_len: usize,
-        shuffle_atlas: bool,
     ) -> Self {
-        let colors = crate::random_colors(len, rng);
         let mut cell_answers = (0..len).map(LAns).collect::<Vec<_>>();
         cell_answers.shuffle(rng);
         let mut bitset = FixedBitSet::with_capacity(len);
         bitset.insert_range(..);
-        let mut atlas_index_map = (0..atlas_len).collect::<Vec<_>>();
-        if shuffle_atlas {
+        let mut atlas_index_map = (0..tileset.len()).collect::<Vec<_>>();
+        if tileset.shuffle {
             atlas_index_map.shuffle(rng);
         }
         let cell_display = atlas_index_map
@@ -356,8 +538,32 @@ impl PuzzleRow {
             cell_selection,
             cell_display,
             cell_answers,
+            tileset: tileset.clone(),
             atlas,
             atlas_layout,
+            candidate_counts: None,
+        }
+    }
+
+    // A row with every candidate still open, for puzzles rebuilt from a share code. The display
+    // assets are set when it's spawned, as for loaded rows.
+    pub fn from_parts(tileset: &Tileset, answers: Vec<LAns>, display: Vec<(usize, Color)>) -> Self {
+        let mut bitset = FixedBitSet::with_capacity(answers.len());
+        bitset.insert_range(..);
+        PuzzleRow {
+            cell_selection: answers
+                .iter()
+                .map(|_| PuzzleCellSelection::new(bitset.clone()))
+                .collect(),
+            cell_display: display
+                .into_iter()
+                .map(|(atlas_index, color)| PuzzleCellDisplay { atlas_index, color })
+                .collect(),
+            cell_answers: answers,
+            tileset: tileset.clone(),
+            atlas: Default::default(),
+            atlas_layout: Default::default(),
+            candidate_counts: None,
         }
     }
 
@@ -377,6 +583,20 @@ impl PuzzleRow {
         LCol(self.cell_selection.len().saturating_sub(1) as isize)
     }
 
+    pub fn tileset(&self) -> &Tileset {
+        &self.tileset
+    }
+
+    // Rows built off the main thread or loaded from disk don't have their display assets yet.
+    pub fn set_display_assets(
+        &mut self,
+        atlas: Handle<Image>,
+        atlas_layout: Handle<TextureAtlasLayout>,
+    ) {
+        self.atlas = atlas;
+        self.atlas_layout = atlas_layout;
+    }
+
     pub fn selection_at(&self, col: LCol) -> Option<&PuzzleCellSelection> {
         let col: usize = col.0.try_into().ok()?;
         self.cell_selection.get(col)
@@ -384,9 +604,44 @@ impl PuzzleRow {
 
     pub fn selection_mut_at(&mut self, col: LCol) -> Option<&mut PuzzleCellSelection> {
         let col: usize = col.0.try_into().ok()?;
+        self.candidate_counts = None;
         self.cell_selection.get_mut(col)
     }
 
+    fn candidate_counts(&mut self) -> &[usize] {
+        self.candidate_counts.get_or_insert_with(|| {
+            let mut counts = vec![0; self.cell_selection.len()];
+            for index in self.cell_selection.iter().flat_map(|sel| sel.iter_ones()) {
+                counts[index.0] += 1;
+            }
+            counts
+        })
+    }
+
+    // Like applying `op` through `selection_mut_at`, but adjusts the cached candidate counts
+    // instead of dropping them.
+    fn apply_counted(&mut self, col: LCol, index: LInd, op: UpdateCellIndexOperation) -> usize {
+        let sel = &mut self.cell_selection[col.0 as usize];
+        let Some(counts) = &mut self.candidate_counts else {
+            return sel.apply(index, op);
+        };
+        if let UpdateCellIndexOperation::Clear = op {
+            let ret = sel.apply(index, op);
+            if ret > 0 {
+                counts[index.0] -= 1;
+            }
+            return ret;
+        }
+        for i in sel.iter_ones() {
+            counts[i.0] -= 1;
+        }
+        let ret = sel.apply(index, op);
+        for i in sel.iter_ones() {
+            counts[i.0] += 1;
+        }
+        ret
+    }
+
     pub fn answer_at(&self, col: LCol) -> LAns {
         self.cell_answers[col.0 as usize]
     }
@@ -410,6 +665,10 @@ impl PuzzleRow {
         self.cell_display[index].color
     }
 
+    pub fn display_atlas_index(&self, LInd(index): LInd) -> usize {
+        self.cell_display[index].atlas_index
+    }
+
     // TODO: should be less safe? or should LInd/LAns be combined?
     fn answer_as_index(&self, col: LCol) -> LInd {
         LInd(self.answer_at(col).0)
@@ -424,7 +683,110 @@ impl PuzzleRow {
     }
 }
 
-#[derive(Debug, Clone, Component, Default, Reflect)]
+// How many rounds inference took to settle, and how many candidates it changed along the way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub struct InferenceRun {
+    pub steps: usize,
+    pub updates: usize,
+    // Whether it was stopped at the step cap rather than settling.
+    pub capped: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleDiff {
+    pub added: Vec<CellLocIndex>,
+    pub removed: Vec<CellLocIndex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleValidationError {
+    SelectionWidth {
+        loc: CellLoc,
+        width: usize,
+        row_len: usize,
+    },
+    SoloOutOfRange {
+        loc: CellLoc,
+        index: LInd,
+    },
+    TentativeNotCandidate(CellLoc),
+    AnswersNotPermutation(LRow),
+    RaggedRow(LRow),
+}
+
+impl std::fmt::Display for PuzzleValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleValidationError::SelectionWidth {
+                loc,
+                width,
+                row_len,
+            } => write!(
+                f,
+                "row {} column {} has {width} candidates in a row of {row_len}",
+                loc.row.0, loc.col.0
+            ),
+            PuzzleValidationError::SoloOutOfRange { loc, index } => write!(
+                f,
+                "row {} column {} is soloed to item {}, past the end of the row",
+                loc.row.0, loc.col.0, index.0
+            ),
+            PuzzleValidationError::TentativeNotCandidate(loc) => write!(
+                f,
+                "row {} column {} tentatively eliminates an item that isn't a candidate",
+                loc.row.0, loc.col.0
+            ),
+            PuzzleValidationError::AnswersNotPermutation(row) => {
+                write!(f, "row {}'s answers aren't a permutation", row.0)
+            }
+            PuzzleValidationError::RaggedRow(row) => {
+                write!(f, "row {} doesn't span the puzzle's columns", row.0)
+            }
+        }
+    }
+}
+
+// Why an update couldn't be applied. The board is left as it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleError {
+    OutOfBounds(CellLoc),
+    VoidCell(CellLoc),
+    // Only when the caller asked for updates that empty a cell to be refused.
+    ContradictionDetected(CellLoc),
+    InvalidOperation {
+        index: CellLocIndex,
+        op: UpdateCellIndexOperation,
+    },
+}
+
+impl std::fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuzzleError::OutOfBounds(loc) => write!(
+                f,
+                "row {} column {} isn't on the board",
+                loc.row.0, loc.col.0
+            ),
+            PuzzleError::VoidCell(loc) => write!(
+                f,
+                "row {} column {} has no candidates to update",
+                loc.row.0, loc.col.0
+            ),
+            PuzzleError::ContradictionDetected(loc) => write!(
+                f,
+                "row {} column {} would be left without any candidates",
+                loc.row.0, loc.col.0
+            ),
+            PuzzleError::InvalidOperation { index, op } => {
+                write!(f, "{op:?} on {index}, which isn't in its row")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+#[derive(Debug, Clone, Component, Default, Reflect, Serialize, Deserialize)]
 pub struct Puzzle {
     rows: Vec<PuzzleRow>,
     max_column: LCol,
@@ -438,6 +800,17 @@ impl Puzzle {
         ret
     }
 
+    pub fn into_rows(self) -> Vec<PuzzleRow> {
+        self.rows
+    }
+
+    // States restored from disk share the display assets of the puzzle they were saved from.
+    pub fn copy_display_assets_from(&mut self, other: &Puzzle) {
+        for (row, other_row) in self.rows.iter_mut().zip(&other.rows) {
+            row.set_display_assets(other_row.atlas.clone(), other_row.atlas_layout.clone());
+        }
+    }
+
     pub fn n_rows(&self) -> usize {
         self.rows.len()
     }
@@ -464,6 +837,10 @@ impl Puzzle {
         0..=self.max_column.0
     }
 
+    pub fn max_column(&self) -> LCol {
+        self.max_column
+    }
+
     pub fn iter_cols(&self) -> impl Iterator<Item = LCol> {
         self.col_range().map(LCol)
     }
@@ -484,6 +861,10 @@ impl Puzzle {
         LCol(rng.random_range(0..=self.max_column.0 as usize) as isize)
     }
 
+    pub fn contains_loc(&self, loc: CellLoc) -> bool {
+        self.row_range().contains(&loc.row.0) && self.col_range().contains(&loc.col.0)
+    }
+
     pub fn row_at(&self, row: LRow) -> &PuzzleRow {
         &self.rows[row.0]
     }
@@ -497,9 +878,20 @@ impl Puzzle {
     }
 
     pub fn cell_selection_mut(&mut self, loc: CellLoc) -> &mut PuzzleCellSelection {
+        self.try_cell_selection_mut(loc)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    pub fn try_cell_selection_mut(
+        &mut self,
+        loc: CellLoc,
+    ) -> Result<&mut PuzzleCellSelection, PuzzleError> {
+        if !self.contains_loc(loc) {
+            return Err(PuzzleError::OutOfBounds(loc));
+        }
         self.row_mut_at(loc.row)
             .selection_mut_at(loc.col)
-            .unwrap_or_else(|| todo!())
+            .ok_or(PuzzleError::OutOfBounds(loc))
     }
 
     // TODO: too many `as usize`
@@ -534,73 +926,281 @@ impl Puzzle {
         CellLocAnswer { loc, index }
     }
 
-    fn one_inference_step(
-        &mut self,
-        to_update: &mut HashSet<CellLoc>,
-        considering: &mut HashSet<CellLoc>,
-    ) -> usize {
-        #[derive(Debug)]
-        enum SoloInf {
-            None,
-            One(CellLocIndex),
-            Many,
-        }
-
-        impl SoloInf {
-            fn insert(&mut self, index: CellLocIndex) {
-                *self = match self {
-                    SoloInf::None => SoloInf::One(index),
-                    _ => SoloInf::Many,
-                };
+    pub fn iter_locs(&self) -> impl Iterator<Item = CellLoc> + use<'_> {
+        self.iter_rows()
+            .flat_map(|row| self.iter_cols().map(move |col| CellLoc { row, col }))
+    }
+
+    pub fn is_column_complete(&self, col: LCol) -> bool {
+        self.n_rows() > 0
+            && self.iter_rows().all(|row| {
+                self.cell_selection(CellLoc { row, col })
+                    .is_any_solo()
+                    .is_some()
+            })
+    }
+
+    pub fn unsolved_in_row(&self, row: LRow) -> usize {
+        self.iter_cols()
+            .filter(|&col| {
+                self.cell_selection(CellLoc { row, col })
+                    .is_any_solo()
+                    .is_none()
+            })
+            .count()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.n_rows() > 0
+            && self.iter_locs().all(|loc| {
+                self.cell_selection(loc).is_any_solo()
+                    == Some(self.answer_at(loc).index.decay_to_ind())
+            })
+    }
+
+    // Candidates enabled in `other` but not in `self` are added; the reverse are removed.
+    pub fn candidate_diff(&self, other: &Puzzle) -> PuzzleDiff {
+        let mut diff = PuzzleDiff::default();
+        for loc in self.iter_locs() {
+            let before = self.cell_selection(loc);
+            let after = other.cell_selection(loc);
+            for index in self.row_at(loc.row).iter_indices() {
+                let cell_index = CellLocIndex { loc, index };
+                match (before.is_enabled(index), after.is_enabled(index)) {
+                    (false, true) => diff.added.push(cell_index),
+                    (true, false) => diff.removed.push(cell_index),
+                    _ => (),
+                }
             }
+        }
+        diff
+    }
 
-            fn drain_into(self, into: &mut HashMap<LRow, HashSet<CellLocIndex>>) {
-                if let SoloInf::One(index) = self {
-                    into.entry(index.loc.row).or_default().insert(index);
+    // Cells whose candidates or pencil marks differ between the two puzzles.
+    pub fn changed_cells(&self, other: &Puzzle) -> Vec<CellLoc> {
+        self.iter_locs()
+            .filter(|&loc| {
+                let before = self.cell_selection(loc);
+                let after = other.cell_selection(loc);
+                self.row_at(loc.row).iter_indices().any(|index| {
+                    before.is_enabled(index) != after.is_enabled(index)
+                        || before.is_tentative(index) != after.is_tentative(index)
+                })
+            })
+            .collect()
+    }
+
+    // Checks the structural invariants that the rest of the code assumes rather than rechecks. It's
+    // too slow for the solver's paths, so puzzles are checked where they come in from outside:
+    // saves, share codes, packs and imported grids.
+    pub fn validate(&self) -> Result<(), PuzzleValidationError> {
+        for row in self.iter_rows() {
+            let puzzle_row = self.row_at(row);
+            let row_len = puzzle_row.cell_selection.len();
+            if puzzle_row.max_column() != self.max_column
+                || puzzle_row.cell_display.len() != row_len
+            {
+                return Err(PuzzleValidationError::RaggedRow(row));
+            }
+            for col in puzzle_row.iter_cols() {
+                let loc = CellLoc { row, col };
+                let selection = self.cell_selection(loc);
+                if selection.width() != row_len {
+                    return Err(PuzzleValidationError::SelectionWidth {
+                        loc,
+                        width: selection.width(),
+                        row_len,
+                    });
                 }
+                match selection {
+                    &PuzzleCellSelection::Solo { index, .. } if index.0 >= row_len => {
+                        return Err(PuzzleValidationError::SoloOutOfRange { loc, index });
+                    }
+                    PuzzleCellSelection::Enabled(enabled, tentative)
+                        if !tentative.is_subset(enabled) =>
+                    {
+                        return Err(PuzzleValidationError::TentativeNotCandidate(loc));
+                    }
+                    _ => (),
+                }
+            }
+            let mut answers = puzzle_row
+                .cell_answers
+                .iter()
+                .map(|a| a.0)
+                .collect::<Vec<_>>();
+            answers.sort();
+            if !answers.into_iter().eq(0..row_len) {
+                return Err(PuzzleValidationError::AnswersNotPermutation(row));
             }
         }
+        Ok(())
+    }
+
+    pub fn has_contradiction(&self) -> bool {
+        self.iter_locs()
+            .any(|loc| self.cell_selection(loc).count_ones() == 0)
+    }
+
+    // Any cell that's lost its answer, whether or not it's been emptied yet.
+    pub fn excludes_answer(&self) -> bool {
+        self.iter_locs().any(|loc| {
+            !self
+                .cell_selection(loc)
+                .is_enabled(self.answer_at(loc).index.decay_to_ind())
+        })
+    }
+
+    // Whether `update` names a candidate that's on the board, in a cell that has candidates. Unless
+    // `allow_empty`, emptying the cell is refused as well.
+    pub fn check_update(
+        &self,
+        &UpdateCellIndex { index, op, .. }: &UpdateCellIndex,
+        allow_empty: bool,
+    ) -> Result<(), PuzzleError> {
+        if !self.contains_loc(index.loc) {
+            return Err(PuzzleError::OutOfBounds(index.loc));
+        }
+        let selection = self.cell_selection(index.loc);
+        if selection.is_void() {
+            return Err(PuzzleError::VoidCell(index.loc));
+        }
+        if index.index.0 >= selection.width() {
+            return Err(PuzzleError::InvalidOperation { index, op });
+        }
+        if !allow_empty && selection.would_empty(index.index, op) {
+            return Err(PuzzleError::ContradictionDetected(index.loc));
+        }
+        Ok(())
+    }
+
+    // Returns the number of directly updated and inferred candidates, or None if nothing changed.
+    pub fn apply_update(
+        &mut self,
+        update: &UpdateCellIndex,
+        to_update: &mut HashSet<CellLoc>,
+    ) -> Result<Option<(usize, usize)>, PuzzleError> {
+        let counted = self.apply_update_counted(update, to_update)?;
+        Ok(counted.map(|(update_count, inference)| (update_count, inference.updates)))
+    }
+
+    // Like `apply_update`, but with everything about the inference that followed.
+    pub fn apply_update_counted(
+        &mut self,
+        update: &UpdateCellIndex,
+        to_update: &mut HashSet<CellLoc>,
+    ) -> Result<Option<(usize, InferenceRun)>, PuzzleError> {
+        let mut updated = HashSet::new();
+        let Some(update_count) = self.apply_update_without_inference(update, &mut updated)? else {
+            return Ok(None);
+        };
+        let inference = self.run_inference_counted(&mut updated);
+        to_update.extend(updated);
+        Ok(Some((update_count, inference)))
+    }
+
+    // Like `apply_update`, but leaves whatever the update implies for `pending_inferences`.
+    pub fn apply_update_without_inference(
+        &mut self,
+        update: &UpdateCellIndex,
+        to_update: &mut HashSet<CellLoc>,
+    ) -> Result<Option<usize>, PuzzleError> {
+        self.check_update(update, true)?;
+        let UpdateCellIndex { index, op, .. } = *update;
+        let update_count = self
+            .try_cell_selection_mut(index.loc)?
+            .apply(index.index, op);
+        if update_count == 0 {
+            return Ok(None);
+        }
+        to_update.insert(index.loc);
+        Ok(Some(update_count))
+    }
+
+    // Candidates that inference over the whole board would eliminate, without eliminating them.
+    pub fn pending_inferences(&self) -> Vec<CellLocIndex> {
+        let mut inferred = self.clone();
+        inferred.run_inference(&mut self.iter_locs().collect());
+        self.candidate_diff(&inferred).removed
+    }
 
+    // The cells other than its own that an update would change through the first step of inference
+    // after it, worked out on a copy of the board.
+    pub fn first_order_consequences(&self, update: &UpdateCellIndex) -> Vec<CellLoc> {
+        let mut after = self.clone();
+        let mut to_update = HashSet::new();
+        if !matches!(
+            after.apply_update_without_inference(update, &mut to_update),
+            Ok(Some(_))
+        ) {
+            return vec![];
+        }
+        let mut considering = to_update.clone();
+        after.one_inference_step(&mut to_update, &mut considering);
+        self.changed_cells(&after)
+            .into_iter()
+            .filter(|&loc| loc != update.index.loc)
+            .collect()
+    }
+
+    fn one_inference_step(
+        &mut self,
+        to_update: &mut HashSet<CellLoc>,
+        considering: &mut HashSet<CellLoc>,
+    ) -> usize {
         let rows = considering.drain().map(|l| l.row).collect::<HashSet<_>>();
         let mut updates = 0;
         let mut did_update = |c: usize| {
             updates += c;
             c > 0
         };
-        let mut solo_ops = HashMap::new();
+        let mut solo_ops: HashMap<LRow, HashSet<CellLocIndex>> = HashMap::new();
         for row in rows {
-            let mut counts = HashMap::new();
-            for col in self.iter_cols() {
-                let loc = CellLoc { row, col };
-                let mut cell_inf = SoloInf::None;
-                for index in self.cell_selection(loc).iter_ones() {
-                    let cell_index = CellLocIndex { loc, index };
-                    counts
-                        .entry(index)
-                        .or_insert(SoloInf::None)
-                        .insert(cell_index);
-                    cell_inf.insert(cell_index);
+            let solos = solo_ops.entry(row).or_default();
+            let puzzle_row = self.row_mut_at(row);
+            // Cells down to one candidate.
+            for col in puzzle_row.iter_cols() {
+                if let Some(index) = puzzle_row.cell_selection[col.0 as usize].is_any_solo() {
+                    solos.insert(CellLocIndex {
+                        loc: CellLoc { row, col },
+                        index,
+                    });
                 }
-                // info!("cell_inf @r{row_nr}xc{cell_nr}: {cell_inf:#?}");
-                cell_inf.drain_into(&mut solo_ops);
             }
-            // info!("counts @r{row_nr}: {counts:#?}");
-            for inf in counts.into_values() {
-                inf.drain_into(&mut solo_ops);
+            // Candidates down to one cell.
+            let single = puzzle_row
+                .candidate_counts()
+                .iter()
+                .enumerate()
+                .filter(|&(_, &n)| n == 1)
+                .map(|(index, _)| LInd(index))
+                .collect::<Vec<_>>();
+            for index in single {
+                let col = puzzle_row
+                    .iter_cols()
+                    .find(|&col| puzzle_row.cell_selection[col.0 as usize].is_enabled(index));
+                if let Some(col) = col {
+                    solos.insert(CellLocIndex {
+                        loc: CellLoc { row, col },
+                        index,
+                    });
+                }
             }
         }
         // info!("solo ops: {solo_ops:#?}");
         for (row, solos) in solo_ops {
             for col in self.iter_cols() {
                 let loc = CellLoc { row, col };
-                let sel = self.cell_selection_mut(loc);
                 for solo_index in &solos {
                     let op = if loc == solo_index.loc {
                         UpdateCellIndexOperation::Solo
                     } else {
                         UpdateCellIndexOperation::Clear
                     };
-                    if did_update(sel.apply(solo_index.index, op)) {
+                    if did_update(
+                        self.row_mut_at(row)
+                            .apply_counted(col, solo_index.index, op),
+                    ) {
                         considering.insert(loc);
                         to_update.insert(loc);
                     }
@@ -612,19 +1212,440 @@ impl Puzzle {
     }
 
     pub fn run_inference(&mut self, to_update: &mut HashSet<CellLoc>) -> usize {
+        self.run_inference_counted(to_update).updates
+    }
+
+    pub fn run_inference_counted(&mut self, to_update: &mut HashSet<CellLoc>) -> InferenceRun {
+        self.run_inference_capped(to_update, usize::MAX)
+    }
+
+    // Inference always settles, since every step but the last removes a candidate; the cap is for
+    // when a bug breaks that.
+    pub fn run_inference_capped(
+        &mut self,
+        to_update: &mut HashSet<CellLoc>,
+        max_steps: usize,
+    ) -> InferenceRun {
         let mut considering = to_update.clone();
-        let mut updates = 0;
-        let mut steps = 0;
+        let mut run = InferenceRun::default();
         while !considering.is_empty() {
+            if run.steps >= max_steps {
+                warn!(
+                    "stopping inference after {} steps with {} cells left to consider",
+                    run.steps,
+                    considering.len()
+                );
+                run.capped = true;
+                break;
+            }
             info!(
                 "running inference to_update hwm {} considering hwm {}",
                 to_update.len(),
                 considering.len()
             );
-            updates += self.one_inference_step(to_update, &mut considering);
-            steps += 1;
-            info!("ran inference step {steps}, {updates} updates");
+            run.updates += self.one_inference_step(to_update, &mut considering);
+            run.steps += 1;
+            info!("ran inference step {}, {} updates", run.steps, run.updates);
         }
-        updates
+        run
+    }
+}
+
+// A puzzle without display assets built from a fixed seed, for tests that need real answers. Rows
+// past the drawn tilesets wide enough for it use the procedural one.
+#[cfg(test)]
+pub fn test_puzzle(n_rows: usize, n_cols: usize) -> Puzzle {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+    let mut puzzle = Puzzle::default();
+    for (nr, tileset) in crate::TILESETS
+        .iter()
+        .filter(|t| t.len() >= n_cols)
+        .chain(std::iter::repeat(&crate::PROCEDURAL_TILESET))
+        .take(n_rows)
+        .enumerate()
+    {
+        puzzle.add_row(PuzzleRow::new_shuffled(
+            &mut rng,
+            n_cols,
+            tileset,
+            crate::row_colors(n_cols, 0, LRow(nr)),
+            Handle::default(),
+            Handle::default(),
+        ));
+    }
+    puzzle
+}
+
+#[cfg(test)]
+pub fn loc(row: usize, col: isize) -> CellLoc {
+    CellLoc {
+        row: LRow(row),
+        col: LCol(col),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_puzzle_is_valid() {
+        assert_eq!(test_puzzle(3, 5).validate(), Ok(()));
+    }
+
+    #[test]
+    fn updates_keep_the_puzzle_valid() {
+        let mut puzzle = test_puzzle(3, 5);
+        let mut to_update = HashSet::new();
+        for loc in puzzle.iter_locs().collect::<Vec<_>>() {
+            let update = puzzle.answer_at(loc).decay_to_ind().as_solo();
+            puzzle.apply_update(&update, &mut to_update).unwrap();
+            assert_eq!(puzzle.validate(), Ok(()));
+        }
+        assert!(puzzle.is_solved());
+    }
+
+    #[test]
+    fn only_changing_operations_are_offered() {
+        use UpdateCellIndexOperation::*;
+        let mut full = FixedBitSet::with_capacity(3);
+        full.insert_range(..);
+        let full = PuzzleCellSelection::new(full);
+        let mut cleared = full.clone();
+        cleared.apply(LInd(1), Clear);
+        let solo = PuzzleCellSelection::Solo {
+            width: 3,
+            index: LInd(1),
+        };
+        let offered = |sel: &PuzzleCellSelection| {
+            [Clear, Set, Toggle, Solo]
+                .into_iter()
+                .filter(|&op| sel.would_change(LInd(1), op))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(offered(&full), vec![Clear, Toggle, Solo]);
+        assert_eq!(offered(&cleared), vec![Set, Toggle, Solo]);
+        assert_eq!(offered(&solo), vec![Clear, Toggle]);
+        assert_eq!(offered(&PuzzleCellSelection::Void), vec![]);
+    }
+
+    #[test]
+    fn tentative_marks_are_confirmed_or_reverted() {
+        use UpdateCellIndexOperation::*;
+        let mut full = FixedBitSet::with_capacity(4);
+        full.insert_range(..);
+        let mut sel = PuzzleCellSelection::new(full);
+        assert_eq!(sel.apply(LInd(1), MarkTentative), 1);
+        assert_eq!(sel.apply(LInd(1), MarkTentative), 0);
+        assert_eq!(sel.apply(LInd(2), MarkTentative), 1);
+        // Marked candidates are still candidates.
+        assert_eq!(sel.count_ones(), 4);
+        assert!(sel.is_tentative(LInd(1)) && sel.is_enabled(LInd(1)));
+
+        let mut reverted = sel.clone();
+        assert_eq!(reverted.apply(LInd(0), RevertTentative), 2);
+        assert_eq!(reverted.count_ones(), 4);
+        assert!(!reverted.is_tentative(LInd(1)));
+        assert!(!reverted.would_change(LInd(0), RevertTentative));
+
+        let mut confirmed = sel.clone();
+        assert_eq!(confirmed.apply(LInd(0), ConfirmAll), 2);
+        assert_eq!(confirmed.iter_ones().collect::<Vec<_>>(), vec![
+            LInd(0),
+            LInd(3)
+        ]);
+        assert!(!confirmed.is_tentative(LInd(1)));
+        assert!(!confirmed.would_change(LInd(1), MarkTentative));
+
+        // Eliminating a marked candidate outright takes its mark with it.
+        sel.apply(LInd(1), Clear);
+        assert!(!sel.is_tentative(LInd(1)));
+        assert_eq!(sel.apply(LInd(0), ConfirmAll), 1);
+        assert_eq!(sel.count_ones(), 2);
+    }
+
+    #[test]
+    fn inference_stops_at_the_step_cap() {
+        let puzzle = test_puzzle(3, 5);
+        let update = puzzle.answer_at(loc(2, 1)).decay_to_ind().as_solo();
+        let capped_at = |max_steps| {
+            let mut puzzle = puzzle.clone();
+            let mut updated = HashSet::new();
+            puzzle
+                .apply_update_without_inference(&update, &mut updated)
+                .unwrap()
+                .unwrap();
+            puzzle.run_inference_capped(&mut updated, max_steps)
+        };
+        let settled = capped_at(usize::MAX);
+        assert!(!settled.capped && settled.steps > 1);
+        assert_eq!(capped_at(settled.steps), settled);
+        let capped = capped_at(1);
+        assert!(capped.capped);
+        assert_eq!(capped.steps, 1);
+    }
+
+    #[test]
+    fn counted_inference_matches_plain_inference() {
+        let mut plain = test_puzzle(3, 5);
+        let mut counted = plain.clone();
+        let update = plain.answer_at(loc(2, 1)).decay_to_ind().as_solo();
+        let (_, inferred_count) = plain
+            .apply_update(&update, &mut HashSet::new())
+            .unwrap()
+            .unwrap();
+        let (_, run) = counted
+            .apply_update_counted(&update, &mut HashSet::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(run.updates, inferred_count);
+        assert!(run.steps > 0);
+        assert!(plain.changed_cells(&counted).is_empty());
+    }
+
+    #[test]
+    fn cached_candidate_counts_survive_inference() {
+        let mut puzzle = test_puzzle(3, 5);
+        for row in puzzle.iter_rows() {
+            puzzle.row_mut_at(row).candidate_counts();
+        }
+        for (row, col) in [(0, 0), (2, 3), (1, 4)] {
+            let update = puzzle.answer_at(loc(row, col)).decay_to_ind().as_solo();
+            let mut to_update = HashSet::new();
+            let (_, inferred) = puzzle
+                .apply_update(&update, &mut to_update)
+                .unwrap()
+                .unwrap();
+            assert!(inferred > 0);
+            let touched = to_update.iter().map(|l| l.row).collect::<HashSet<_>>();
+            for row in puzzle.iter_rows() {
+                let cached = puzzle.row_at(row).candidate_counts.clone();
+                assert!(
+                    cached.is_some() || !touched.contains(&row),
+                    "row {row:?} lost its counts to inference"
+                );
+                let puzzle_row = puzzle.row_mut_at(row);
+                puzzle_row.candidate_counts = None;
+                if let Some(cached) = cached {
+                    assert_eq!(cached, puzzle_row.candidate_counts());
+                }
+            }
+        }
+    }
+
+    // Inference as `apply_update` runs it, optionally dropping every row's counts before each step
+    // the way they were recounted before they were cached.
+    fn replay_with_inference(puzzle: &mut Puzzle, updates: &[UpdateCellIndex], recount: bool) {
+        for update in updates {
+            let mut to_update = HashSet::new();
+            let Ok(Some(_)) = puzzle.apply_update_without_inference(update, &mut to_update) else {
+                continue;
+            };
+            let mut considering = to_update.clone();
+            while !considering.is_empty() {
+                if recount {
+                    for row in puzzle.iter_rows() {
+                        puzzle.row_mut_at(row).candidate_counts = None;
+                    }
+                }
+                puzzle.one_inference_step(&mut to_update, &mut considering);
+            }
+        }
+    }
+
+    #[test]
+    fn cached_counts_solve_like_recounted_counts() {
+        use rand::SeedableRng;
+
+        let start = test_puzzle(5, 5);
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..5 {
+            // Clear wrong candidates at random until inference finishes the board.
+            let mut puzzle = start.clone();
+            let mut updates = vec![];
+            while !puzzle.is_solved() {
+                let wrong = puzzle
+                    .iter_locs()
+                    .flat_map(|loc| {
+                        let answer = puzzle.answer_at(loc).index.decay_to_ind();
+                        puzzle
+                            .cell_selection(loc)
+                            .iter_ones()
+                            .filter(move |&index| index != answer)
+                            .map(move |index| CellLocIndex { loc, index })
+                    })
+                    .collect::<Vec<_>>();
+                let update = wrong[rng.random_range(0..wrong.len())].as_clear();
+                puzzle.apply_update(&update, &mut HashSet::new()).unwrap();
+                updates.push(update);
+            }
+            let mut cached = start.clone();
+            replay_with_inference(&mut cached, &updates, false);
+            let mut recounted = start.clone();
+            replay_with_inference(&mut recounted, &updates, true);
+            assert!(cached.is_solved());
+            assert!(cached.changed_cells(&recounted).is_empty());
+        }
+    }
+
+    #[test]
+    fn bad_updates_are_refused_without_changing_the_board() {
+        let mut puzzle = test_puzzle(2, 3);
+        let before = puzzle.clone();
+        let apply = |puzzle: &mut Puzzle, loc, index| {
+            puzzle.apply_update(&CellLocIndex { loc, index }.as_clear(), &mut HashSet::new())
+        };
+        assert_eq!(
+            apply(&mut puzzle, loc(2, 0), LInd(0)),
+            Err(PuzzleError::OutOfBounds(loc(2, 0)))
+        );
+        assert_eq!(
+            apply(&mut puzzle, loc(0, 3), LInd(0)),
+            Err(PuzzleError::OutOfBounds(loc(0, 3)))
+        );
+        assert_eq!(
+            apply(&mut puzzle, loc(0, 1), LInd(3)),
+            Err(PuzzleError::InvalidOperation {
+                index: CellLocIndex {
+                    loc: loc(0, 1),
+                    index: LInd(3)
+                },
+                op: UpdateCellIndexOperation::Clear,
+            })
+        );
+        *puzzle.cell_selection_mut(loc(1, 1)) = PuzzleCellSelection::Void;
+        assert_eq!(
+            apply(&mut puzzle, loc(1, 1), LInd(0)),
+            Err(PuzzleError::VoidCell(loc(1, 1)))
+        );
+        *puzzle.cell_selection_mut(loc(1, 1)) = before.cell_selection(loc(1, 1)).clone();
+        assert!(puzzle.changed_cells(&before).is_empty());
+
+        // Emptying a cell is only refused when asked.
+        let answer = puzzle.answer_at(loc(0, 0)).index.decay_to_ind();
+        let solo = CellLocIndex {
+            loc: loc(0, 0),
+            index: answer,
+        };
+        puzzle
+            .cell_selection_mut(loc(0, 0))
+            .apply(answer, UpdateCellIndexOperation::Solo);
+        assert_eq!(puzzle.check_update(&solo.as_clear(), true), Ok(()));
+        assert_eq!(
+            puzzle.check_update(&solo.as_clear(), false),
+            Err(PuzzleError::ContradictionDetected(loc(0, 0)))
+        );
+    }
+
+    #[test]
+    fn consequences_stop_after_one_step() {
+        let puzzle = test_puzzle(2, 3);
+        let answer = puzzle.answer_at(loc(0, 0)).index.decay_to_ind();
+        let solo = CellLocIndex {
+            loc: loc(0, 0),
+            index: answer,
+        }
+        .as_solo();
+        assert_eq!(puzzle.first_order_consequences(&solo), vec![
+            loc(0, 1),
+            loc(0, 2)
+        ]);
+        // Nothing follows from an update that changes nothing.
+        let mut solved = puzzle.clone();
+        solved
+            .cell_selection_mut(loc(0, 0))
+            .apply(answer, UpdateCellIndexOperation::Solo);
+        assert_eq!(solved.first_order_consequences(&solo), vec![]);
+    }
+
+    #[test]
+    fn changed_cells_include_pencil_marks() {
+        let before = test_puzzle(2, 4);
+        let mut after = before.clone();
+        assert_eq!(before.changed_cells(&after), vec![]);
+        let index = after.answer_at(loc(1, 2)).decay_to_ind();
+        after
+            .apply_update(&index.as_solo(), &mut HashSet::new())
+            .unwrap();
+        let changed = before.changed_cells(&after);
+        assert!(changed.contains(&loc(1, 2)) && changed.iter().all(|c| c.row == LRow(1)));
+
+        let mut marked = before.clone();
+        marked
+            .cell_selection_mut(loc(0, 3))
+            .apply(LInd(1), UpdateCellIndexOperation::MarkTentative);
+        assert_eq!(before.changed_cells(&marked), vec![loc(0, 3)]);
+    }
+
+    #[test]
+    fn removals_are_only_predicted_for_enabled_candidates() {
+        use UpdateCellIndexOperation::*;
+        let mut full = FixedBitSet::with_capacity(4);
+        full.insert_range(..);
+        let mut sel = PuzzleCellSelection::new(full);
+        assert!(sel.would_remove(LInd(1), Clear, LInd(1)));
+        assert!(!sel.would_remove(LInd(1), Clear, LInd(2)));
+        assert!(sel.would_remove(LInd(1), Solo, LInd(2)));
+        assert!(!sel.would_remove(LInd(2), MarkTentative, LInd(2)));
+
+        sel.apply(LInd(2), MarkTentative);
+        assert!(sel.would_remove(LInd(0), ConfirmAll, LInd(2)));
+        sel.apply(LInd(2), Clear);
+        assert!(!sel.would_remove(LInd(1), Solo, LInd(2)));
+    }
+
+    #[test]
+    fn mismatched_width_is_caught() {
+        let mut puzzle = test_puzzle(2, 5);
+        puzzle.rows[1].cell_selection[3] = PuzzleCellSelection::new(FixedBitSet::with_capacity(4));
+        assert_eq!(
+            puzzle.validate(),
+            Err(PuzzleValidationError::SelectionWidth {
+                loc: loc(1, 3),
+                width: 4,
+                row_len: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn out_of_range_solo_is_caught() {
+        let mut puzzle = test_puzzle(2, 5);
+        puzzle.rows[0].cell_selection[2] = PuzzleCellSelection::Solo {
+            width: 5,
+            index: LInd(5),
+        };
+        assert_eq!(
+            puzzle.validate(),
+            Err(PuzzleValidationError::SoloOutOfRange {
+                loc: loc(0, 2),
+                index: LInd(5),
+            })
+        );
+    }
+
+    #[test]
+    fn tentative_non_candidate_is_caught() {
+        let mut puzzle = test_puzzle(2, 5);
+        let mut tentative = FixedBitSet::with_capacity(5);
+        tentative.insert(2);
+        puzzle.rows[1].cell_selection[4] =
+            PuzzleCellSelection::Enabled(FixedBitSet::with_capacity(5), tentative);
+        assert_eq!(
+            puzzle.validate(),
+            Err(PuzzleValidationError::TentativeNotCandidate(loc(1, 4)))
+        );
+    }
+
+    #[test]
+    fn repeated_answer_is_caught() {
+        let mut puzzle = test_puzzle(2, 5);
+        puzzle.rows[1].cell_answers[0] = puzzle.rows[1].cell_answers[1];
+        assert_eq!(
+            puzzle.validate(),
+            Err(PuzzleValidationError::AnswersNotPermutation(LRow(1)))
+        );
     }
 }
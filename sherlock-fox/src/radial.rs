@@ -0,0 +1,345 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    loading::MENU_BUTTON_COLOR, puzzle::UpdateCellIndexOperation, settings::Settings, GameState,
+    NO_PICK,
+};
+
+// How far out from the cell each operation's label is drawn.
+const LABEL_RADIUS: f32 = 50.;
+// Drags this short are a click that wobbled, and never pick anything.
+const MIN_DRAG: f32 = 10.;
+
+// The radial menu's operations, each taking an equal slice of the circle around the cell. Dragging
+// and the keyboard prompt pick from the slices, and the menu draws each label in the middle of its
+// slice, so all three always agree. Kept in `Settings`, and arranged from the main menu.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RadialLayout {
+    // Going counterclockwise, starting from `first_angle`.
+    pub sectors: Vec<UpdateCellIndexOperation>,
+    // Where the middle of the first sector is, in degrees counterclockwise from the right.
+    pub first_angle: f32,
+    // Letting go within this far of the cell picks nothing; past `MIN_DRAG`, it's marked as such.
+    pub cancel_radius: f32,
+    // Letting go this far away picks nothing either.
+    pub reach: f32,
+}
+
+impl Default for RadialLayout {
+    fn default() -> Self {
+        use UpdateCellIndexOperation as Op;
+        RadialLayout {
+            sectors: vec![Op::Clear, Op::Solo, Op::Toggle, Op::Set],
+            first_angle: 0.,
+            cancel_radius: 0.,
+            reach: 125.,
+        }
+    }
+}
+
+impl RadialLayout {
+    // Every label has to be somewhere it can be picked, and each operation only needs one.
+    pub fn check(&self) -> Result<(), String> {
+        if self.sectors.is_empty() {
+            return Err("the radial menu has no operations".into());
+        }
+        for (nr, op) in self.sectors.iter().enumerate() {
+            if self.sectors[..nr].contains(op) {
+                return Err(format!("{op} is in the radial menu more than once"));
+            }
+        }
+        if self.cancel_radius >= LABEL_RADIUS {
+            return Err(format!(
+                "a cancel radius of {} would cover the labels, which are {LABEL_RADIUS} out",
+                self.cancel_radius
+            ));
+        }
+        if self.reach <= LABEL_RADIUS {
+            return Err(format!(
+                "a reach of {} would fall short of the labels, which are {LABEL_RADIUS} out",
+                self.reach
+            ));
+        }
+        Ok(())
+    }
+
+    // Steps a sector through the operations no other sector has, wrapping around.
+    pub fn step_sector(&mut self, sector: usize, delta: isize) {
+        let Some(&current) = self.sectors.get(sector) else {
+            return;
+        };
+        let choices = UpdateCellIndexOperation::ALL
+            .into_iter()
+            .filter(|&op| op == current || !self.sectors.contains(&op))
+            .collect::<Vec<_>>();
+        let at = choices.iter().position(|&op| op == current).unwrap_or(0) as isize;
+        self.sectors[sector] = choices[(at + delta).rem_euclid(choices.len() as isize) as usize];
+    }
+
+    // Adds the first operation that isn't in the menu yet, if any.
+    pub fn add_sector(&mut self) {
+        if let Some(op) = UpdateCellIndexOperation::ALL
+            .into_iter()
+            .find(|op| !self.sectors.contains(op))
+        {
+            self.sectors.push(op);
+        }
+    }
+
+    // The last sector goes, as long as one is left.
+    pub fn remove_sector(&mut self) {
+        if self.sectors.len() > 1 {
+            self.sectors.pop();
+        }
+    }
+
+    fn sector_width(&self) -> f32 {
+        std::f32::consts::TAU / self.sectors.len() as f32
+    }
+
+    // Each operation and where its label goes, relative to the cell, y up.
+    pub fn labels(&self) -> impl Iterator<Item = (UpdateCellIndexOperation, Vec2)> + '_ {
+        let first = self.first_angle.to_radians();
+        self.sectors.iter().enumerate().map(move |(n, &op)| {
+            let angle = first + n as f32 * self.sector_width();
+            (op, Vec2::from_angle(angle) * LABEL_RADIUS)
+        })
+    }
+
+    pub fn shows_cancel(&self) -> bool {
+        self.cancel_radius > MIN_DRAG
+    }
+
+    // The operation whose sector `direction` points into, however far.
+    pub fn operation_toward(&self, direction: Vec2) -> Option<UpdateCellIndexOperation> {
+        if self.sectors.is_empty() || direction == Vec2::ZERO {
+            return None;
+        }
+        let angle = direction.to_angle() - self.first_angle.to_radians();
+        let n = (angle / self.sector_width()).round() as isize;
+        let n = n.rem_euclid(self.sectors.len() as isize) as usize;
+        Some(self.sectors[n])
+    }
+
+    // The operation a drag by `offset`, y up, would pick if let go.
+    pub fn pick(&self, offset: Vec2) -> Option<UpdateCellIndexOperation> {
+        let distance = offset.length();
+        if distance <= self.cancel_radius.max(MIN_DRAG) || distance >= self.reach {
+            return None;
+        }
+        self.operation_toward(offset)
+    }
+}
+
+// A panel on the main menu for arranging the radial menu, which would otherwise only be
+// adjustable from the inspector.
+const PICKER_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.8);
+
+#[derive(Debug, Component, Reflect)]
+struct RadialPicker;
+
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+enum RadialPickerButton {
+    Step { sector: usize, delta: isize },
+    Add,
+    Remove,
+}
+
+fn spawn_picker_button(parent: &mut ChildBuilder, button: RadialPickerButton, label: &str) {
+    parent
+        .spawn((
+            button,
+            Node {
+                padding: UiRect::axes(Val::Px(10.), Val::Px(4.)),
+                ..Default::default()
+            },
+            BackgroundColor(MENU_BUTTON_COLOR),
+        ))
+        .with_child((Text::new(label), NO_PICK));
+}
+
+// Rebuilt whenever the settings change, since the number of sectors can.
+fn show_radial_picker(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    q_picker: Query<Entity, With<RadialPicker>>,
+) {
+    if !settings.is_changed() && !q_picker.is_empty() {
+        return;
+    }
+    for entity in &q_picker {
+        commands.entity(entity).despawn_recursive();
+    }
+    let small = TextFont {
+        font_size: 14.,
+        ..Default::default()
+    };
+    commands
+        .spawn((
+            RadialPicker,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                right: Val::Px(10.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            BackgroundColor(PICKER_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Drag menu"), NO_PICK));
+            for (sector, op) in settings.radial.sectors.iter().enumerate() {
+                parent
+                    .spawn((
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        NO_PICK,
+                    ))
+                    .with_children(|parent| {
+                        let step = |delta| RadialPickerButton::Step { sector, delta };
+                        spawn_picker_button(parent, step(-1), "<");
+                        parent.spawn((
+                            Text::new(format!("{}: {op}", sector + 1)),
+                            small.clone(),
+                            NO_PICK,
+                        ));
+                        spawn_picker_button(parent, step(1), ">");
+                    });
+            }
+            parent
+                .spawn((
+                    Node {
+                        column_gap: Val::Px(10.),
+                        ..Default::default()
+                    },
+                    NO_PICK,
+                ))
+                .with_children(|parent| {
+                    spawn_picker_button(parent, RadialPickerButton::Remove, "Fewer");
+                    spawn_picker_button(parent, RadialPickerButton::Add, "More");
+                });
+        });
+}
+
+fn despawn_radial_picker(mut commands: Commands, q_picker: Query<Entity, With<RadialPicker>>) {
+    for entity in &q_picker {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn radial_picker_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_button: Query<&RadialPickerButton>,
+    mut settings: ResMut<Settings>,
+) {
+    let Ok(&button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    let layout = &mut settings.radial;
+    match button {
+        RadialPickerButton::Step { sector, delta } => layout.step_sector(sector, delta),
+        RadialPickerButton::Add => layout.add_sector(),
+        RadialPickerButton::Remove => layout.remove_sector(),
+    }
+}
+
+pub struct RadialPlugin;
+
+impl Plugin for RadialPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<RadialLayout>()
+            .register_type::<RadialPicker>()
+            .register_type::<RadialPickerButton>()
+            .add_observer(radial_picker_clicked)
+            .add_systems(
+                Update,
+                show_radial_picker.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(OnExit(GameState::MainMenu), despawn_radial_picker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_sit_where_their_operation_is_picked() {
+        use UpdateCellIndexOperation as Op;
+        let two = RadialLayout {
+            sectors: vec![Op::Set, Op::Clear],
+            first_angle: 90.,
+            cancel_radius: 30.,
+            ..Default::default()
+        };
+        for layout in [RadialLayout::default(), two.clone()] {
+            for (op, offset) in layout.labels() {
+                assert_eq!(layout.pick(offset), Some(op), "{layout:?}");
+            }
+        }
+        // Up is Set and down is Clear, with everything to either side split between them.
+        assert_eq!(two.pick(Vec2::new(40., 20.)), Some(Op::Set));
+        assert_eq!(two.pick(Vec2::new(-40., -20.)), Some(Op::Clear));
+        assert_eq!(two.pick(Vec2::new(0., 20.)), None);
+        assert!(two.shows_cancel() && !RadialLayout::default().shows_cancel());
+    }
+
+    #[test]
+    fn layouts_are_checked_and_stay_valid_when_arranged() {
+        use UpdateCellIndexOperation as Op;
+        assert_eq!(RadialLayout::default().check(), Ok(()));
+        let bad = [
+            RadialLayout {
+                sectors: vec![],
+                ..Default::default()
+            },
+            RadialLayout {
+                sectors: vec![Op::Set, Op::Clear, Op::Set],
+                ..Default::default()
+            },
+            RadialLayout {
+                cancel_radius: LABEL_RADIUS,
+                ..Default::default()
+            },
+            RadialLayout {
+                reach: LABEL_RADIUS,
+                ..Default::default()
+            },
+        ];
+        for layout in bad {
+            assert!(layout.check().is_err(), "{layout:?}");
+        }
+
+        // Stepping skips the other sectors' operations, so no arrangement repeats one.
+        let mut layout = RadialLayout::default();
+        layout.step_sector(0, 1);
+        assert_eq!(layout.sectors, [
+            Op::MarkTentative,
+            Op::Solo,
+            Op::Toggle,
+            Op::Set
+        ]);
+        layout.step_sector(0, -2);
+        assert_eq!(layout.sectors[0], Op::RevertTentative);
+        for _ in 0..10 {
+            layout.add_sector();
+        }
+        assert_eq!(layout.sectors.len(), Op::ALL.len());
+        for _ in 0..10 {
+            layout.remove_sector();
+        }
+        assert_eq!(layout.sectors, [Op::RevertTentative]);
+        assert_eq!(layout.check(), Ok(()));
+    }
+}
@@ -0,0 +1,405 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use petgraph::graph::NodeIndex;
+
+use crate::{
+    fit::FitClickedEvent,
+    puzzle::Puzzle,
+    undo::{move_to_state, UndoTree, UndoTreeLocation},
+    GameState, TopButtonAction, UpdateCellDisplay, NO_PICK,
+};
+
+// Plays the solve back along the history that led to the current state. Every state in the undo
+// tree is a full snapshot, so seeking anywhere is a jump rather than a replay of the moves before
+// it. Undo, redo and history jumps along the timeline move the scrubber with them; leaving the
+// timeline, like making a new move, closes it.
+#[derive(Debug, Resource, Reflect)]
+#[reflect(Resource)]
+struct Replay {
+    timeline: Vec<NodeIndex>,
+    playing: bool,
+    timer: Timer,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Replay {
+            timeline: vec![],
+            playing: false,
+            timer: Timer::new(REPLAY_STEP, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Replay {
+    fn position(&self, node: NodeIndex) -> Option<usize> {
+        self.timeline.iter().position(|&n| n == node)
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ReplayBar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+enum ReplayControl {
+    StepBack,
+    PlayPause,
+    StepForward,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct ReplayTrack;
+
+#[derive(Debug, Component, Reflect)]
+struct ReplayTrackFill;
+
+#[derive(Debug, Component, Reflect)]
+struct ReplayLabel;
+
+const REPLAY_STEP: Duration = Duration::from_millis(600);
+const REPLAY_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const REPLAY_BUTTON_COLOR: Color = Color::hsla(210., 0.6, 0.35, 1.);
+const REPLAY_TRACK_COLOR: Color = Color::hsla(0., 0., 0.3, 1.);
+const REPLAY_FILL_COLOR: Color = Color::hsla(33., 0.8, 0.5, 1.);
+
+// Which move of the timeline a point along the track lands on, with `fraction` running from the
+// left edge to the right.
+fn scrub_position(fraction: f32, len: usize) -> usize {
+    let last = len.saturating_sub(1);
+    ((fraction.clamp(0., 1.) * last as f32).round() as usize).min(last)
+}
+
+fn toggle_replay(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut replay: ResMut<Replay>,
+    q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if action != TopButtonAction::Replay {
+            continue;
+        }
+        if !replay.timeline.is_empty() {
+            *replay = Replay::default();
+        } else if let Ok((tree, tree_loc)) = q_tree.get_single() {
+            replay.timeline = tree.path_to(tree_loc.current);
+        }
+    }
+}
+
+fn seek_replay(
+    replay: &Replay,
+    position: usize,
+    q_puzzle: &mut Query<&mut Puzzle>,
+    q_tree: &mut Query<(&UndoTree, &mut UndoTreeLocation)>,
+    update_display_tx: &mut EventWriter<UpdateCellDisplay>,
+) {
+    let (Some(&node), Ok(mut puzzle), Ok((tree, mut tree_loc))) = (
+        replay.timeline.get(position),
+        q_puzzle.get_single_mut(),
+        q_tree.get_single_mut(),
+    ) else {
+        return;
+    };
+    if tree_loc.current != node {
+        move_to_state(tree, &mut tree_loc, node, &mut puzzle, update_display_tx);
+    }
+}
+
+fn play_replay(
+    time: Res<Time>,
+    mut replay: ResMut<Replay>,
+    mut q_puzzle: Query<&mut Puzzle>,
+    mut q_tree: Query<(&UndoTree, &mut UndoTreeLocation)>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    if !replay.playing {
+        return;
+    }
+    replay.timer.tick(time.delta());
+    if !replay.timer.just_finished() {
+        return;
+    }
+    let Ok((_, tree_loc)) = q_tree.get_single() else {
+        return;
+    };
+    let next = replay.position(tree_loc.current).map_or(0, |p| p + 1);
+    if next + 1 >= replay.timeline.len() {
+        replay.playing = false;
+    }
+    seek_replay(
+        &replay,
+        next,
+        &mut q_puzzle,
+        &mut q_tree,
+        &mut update_display_tx,
+    );
+}
+
+fn replay_control_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_control: Query<&ReplayControl>,
+    mut replay: ResMut<Replay>,
+    mut q_puzzle: Query<&mut Puzzle>,
+    mut q_tree: Query<(&UndoTree, &mut UndoTreeLocation)>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    let Ok(&control) = q_control.get(ev.entity()) else {
+        return;
+    };
+    let Ok((_, tree_loc)) = q_tree.get_single() else {
+        return;
+    };
+    let Some(position) = replay.position(tree_loc.current) else {
+        return;
+    };
+    let last = replay.timeline.len() - 1;
+    let target = match control {
+        ReplayControl::StepBack => position.saturating_sub(1),
+        ReplayControl::StepForward => (position + 1).min(last),
+        ReplayControl::PlayPause => {
+            replay.playing = !replay.playing;
+            replay.timer.reset();
+            // Playing from the end starts over.
+            if replay.playing && position == last {
+                0
+            } else {
+                return;
+            }
+        }
+    };
+    if control != ReplayControl::PlayPause {
+        replay.playing = false;
+    }
+    seek_replay(
+        &replay,
+        target,
+        &mut q_puzzle,
+        &mut q_tree,
+        &mut update_display_tx,
+    );
+}
+
+fn scrub_replay(
+    entity: Entity,
+    q_track: &Query<&RelativeCursorPosition, With<ReplayTrack>>,
+    replay: &mut Replay,
+    q_puzzle: &mut Query<&mut Puzzle>,
+    q_tree: &mut Query<(&UndoTree, &mut UndoTreeLocation)>,
+    update_display_tx: &mut EventWriter<UpdateCellDisplay>,
+) {
+    let Some(cursor) = q_track.get(entity).ok().and_then(|c| c.normalized) else {
+        return;
+    };
+    replay.playing = false;
+    let position = scrub_position(cursor.x, replay.timeline.len());
+    seek_replay(replay, position, q_puzzle, q_tree, update_display_tx);
+}
+
+fn replay_track_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_track: Query<&RelativeCursorPosition, With<ReplayTrack>>,
+    mut replay: ResMut<Replay>,
+    mut q_puzzle: Query<&mut Puzzle>,
+    mut q_tree: Query<(&UndoTree, &mut UndoTreeLocation)>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    scrub_replay(
+        ev.entity(),
+        &q_track,
+        &mut replay,
+        &mut q_puzzle,
+        &mut q_tree,
+        &mut update_display_tx,
+    );
+}
+
+fn replay_track_dragged(
+    ev: Trigger<Pointer<Drag>>,
+    q_track: Query<&RelativeCursorPosition, With<ReplayTrack>>,
+    mut replay: ResMut<Replay>,
+    mut q_puzzle: Query<&mut Puzzle>,
+    mut q_tree: Query<(&UndoTree, &mut UndoTreeLocation)>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    scrub_replay(
+        ev.entity(),
+        &q_track,
+        &mut replay,
+        &mut q_puzzle,
+        &mut q_tree,
+        &mut update_display_tx,
+    );
+}
+
+fn spawn_replay_bar(commands: &mut Commands) {
+    let button = |control, label: &str| {
+        (
+            control,
+            Node {
+                padding: UiRect::axes(Val::Px(8.), Val::Px(2.)),
+                ..Default::default()
+            },
+            BackgroundColor(REPLAY_BUTTON_COLOR),
+            Text::new(label),
+        )
+    };
+    commands
+        .spawn((
+            ReplayBar,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            NO_PICK,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        column_gap: Val::Px(8.),
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(5.)),
+                        ..Default::default()
+                    },
+                    BackgroundColor(REPLAY_BACKGROUND_COLOR),
+                ))
+                .with_children(|bar| {
+                    bar.spawn(button(ReplayControl::StepBack, "<"));
+                    bar.spawn(button(ReplayControl::PlayPause, "Play"));
+                    bar.spawn(button(ReplayControl::StepForward, ">"));
+                    bar.spawn((
+                        ReplayTrack,
+                        Node {
+                            width: Val::Px(300.),
+                            height: Val::Px(16.),
+                            ..Default::default()
+                        },
+                        BackgroundColor(REPLAY_TRACK_COLOR),
+                        RelativeCursorPosition::default(),
+                    ))
+                    .with_child((
+                        ReplayTrackFill,
+                        Node {
+                            height: Val::Percent(100.),
+                            ..Default::default()
+                        },
+                        BackgroundColor(REPLAY_FILL_COLOR),
+                        NO_PICK,
+                    ));
+                    bar.spawn((ReplayLabel, Text::default(), NO_PICK));
+                });
+        });
+}
+
+fn update_replay_bar(
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    q_tree_loc: Query<&UndoTreeLocation>,
+    q_bar: Query<Entity, With<ReplayBar>>,
+    mut q_fill: Query<&mut Node, With<ReplayTrackFill>>,
+    mut q_label: Query<&mut Text, With<ReplayLabel>>,
+    mut q_control: Query<(&ReplayControl, &mut Text), Without<ReplayLabel>>,
+) {
+    let position = q_tree_loc
+        .get_single()
+        .ok()
+        .and_then(|tree_loc| replay.position(tree_loc.current));
+    if position.is_none() && !replay.timeline.is_empty() {
+        *replay = Replay::default();
+    }
+    let Some(position) = position else {
+        for entity in &q_bar {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    if q_bar.is_empty() {
+        spawn_replay_bar(&mut commands);
+        return;
+    }
+
+    let last = replay.timeline.len() - 1;
+    let fraction = if last == 0 {
+        1.
+    } else {
+        position as f32 / last as f32
+    };
+    let width = Val::Percent(fraction * 100.);
+    for mut fill in &mut q_fill {
+        if fill.width != width {
+            fill.width = width;
+        }
+    }
+    let progress = format!("Move {position} of {last}");
+    for mut label in &mut q_label {
+        if label.0 != progress {
+            label.0.clone_from(&progress);
+        }
+    }
+    let play_pause = if replay.playing { "Pause" } else { "Play" };
+    for (&control, mut text) in &mut q_control {
+        if control == ReplayControl::PlayPause && text.0 != play_pause {
+            text.0 = play_pause.to_string();
+        }
+    }
+}
+
+fn close_replay(
+    mut commands: Commands,
+    mut replay: ResMut<Replay>,
+    q_bar: Query<Entity, With<ReplayBar>>,
+) {
+    *replay = Replay::default();
+    for entity in &q_bar {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replay>()
+            .register_type::<Replay>()
+            .register_type::<ReplayBar>()
+            .register_type::<ReplayControl>()
+            .register_type::<ReplayLabel>()
+            .register_type::<ReplayTrack>()
+            .register_type::<ReplayTrackFill>()
+            .add_observer(replay_control_clicked)
+            .add_observer(replay_track_clicked)
+            .add_observer(replay_track_dragged)
+            .add_systems(OnExit(GameState::Playing), close_replay)
+            .add_systems(
+                Update,
+                (toggle_replay, play_replay, update_replay_bar)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrubbing_lands_on_the_nearest_move() {
+        assert_eq!(scrub_position(0., 5), 0);
+        assert_eq!(scrub_position(0.3, 5), 1);
+        assert_eq!(scrub_position(0.4, 5), 2);
+        assert_eq!(scrub_position(1., 5), 4);
+        // The cursor can be dragged past either end of the track.
+        assert_eq!(scrub_position(-0.5, 5), 0);
+        assert_eq!(scrub_position(1.5, 5), 4);
+        assert_eq!(scrub_position(0.5, 1), 0);
+    }
+}
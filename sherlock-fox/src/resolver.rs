@@ -0,0 +1,410 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+// Clues express their logic as if-then rules over the candidates of the cells they mention. The
+// resolver enumerates every placement and ordering of those cells so each rule only has to handle
+// one concrete arrangement.
+
+use bevy::prelude::*;
+
+use crate::{
+    clues::CellDisplay,
+    puzzle::{CellLoc, CellLocIndex, LCol, LColspan, Puzzle},
+    NO_PICK,
+};
+
+// Holds the answer cells a clue talks about, and the if-then rules the clue applies to them.
+pub struct ImplicationResolver<'p, IT> {
+    puzzle: &'p Puzzle,
+    cells: Vec<CellLocIndex>,
+    actions: Vec<IT>,
+}
+
+impl<'p, IT: std::fmt::Debug> std::fmt::Debug for ImplicationResolver<'p, IT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImplicationResolver")
+            .field("puzzle", &(self.puzzle as *const Puzzle as usize))
+            .field("cells", &self.cells)
+            .field("actions", &self.actions)
+            .finish()
+    }
+}
+
+// A snapshot of one candidate's state. Off-board cells are void and never enabled or solo.
+#[derive(Clone, Reflect, Debug)]
+pub struct SelectionProxy {
+    pub index_: CellLocIndex,
+    pub is_enabled: bool,
+    pub is_solo: bool,
+    pub is_void: bool,
+}
+
+impl SelectionProxy {
+    pub fn from_puzzle_and_index(puzzle: &Puzzle, index: CellLocIndex) -> Self {
+        let sel = puzzle.cell_selection(index.loc);
+        let is_enabled = sel.is_enabled(index.index);
+        let is_solo = sel.is_solo(index.index);
+        let is_void = sel.is_void();
+        SelectionProxy {
+            index_: index,
+            is_enabled,
+            is_solo,
+            is_void,
+        }
+    }
+
+    pub fn is_enabled_not_solo(&self) -> bool {
+        self.is_enabled && !self.is_solo
+    }
+}
+
+impl std::ops::Deref for SelectionProxy {
+    type Target = CellLocIndex;
+
+    fn deref(&self) -> &Self::Target {
+        &self.index_
+    }
+}
+
+impl CellDisplay for SelectionProxy {
+    fn as_cell_display_string(&self) -> String {
+        format!(
+            "Cell[{:?} {:?} {:?}]",
+            self.loc.row, self.loc.col, self.index
+        )
+    }
+
+    fn spawn_into(&self, puzzle: &Puzzle, parent: &mut ChildBuilder) {
+        let (mut image_node, color) = puzzle.cell_index_display(self.index_);
+        // let button_size = Vec2::new(32., 32.);
+        // sprite.custom_size = Some(button_size - Vec2::new(5., 5.));
+        image_node.color = Color::hsla(0., 0., 1., 1.);
+        parent
+            .spawn((
+                Node {
+                    width: Val::Px(42.),
+                    height: Val::Px(42.),
+                    margin: UiRect::horizontal(Val::Px(5.)),
+                    padding: UiRect::all(Val::Px(5.)),
+                    ..Default::default()
+                },
+                BackgroundColor(color),
+                NO_PICK,
+            ))
+            .with_child((Node::default(), image_node, NO_PICK));
+    }
+
+    fn loc_index(&self) -> Option<&CellLocIndex> {
+        Some(&self.index_)
+    }
+}
+
+// One of the clue's cells, for clues that rule candidates out on their own.
+#[derive(Debug, Clone)]
+pub struct Loc1 {
+    pub loc1: SelectionProxy,
+}
+
+// An ordered pair of the clue's cells; `iter_perm_2s` offers both orders.
+#[derive(Debug, Clone)]
+pub struct Loc2 {
+    pub loc1: SelectionProxy,
+    pub loc2: SelectionProxy,
+}
+
+// Like Loc2, plus `loc2_p`: `loc2`'s candidate reflected to the other side of `loc1`'s column.
+#[derive(Debug, Clone)]
+pub struct Loc2Mirrored {
+    pub loc1: SelectionProxy,
+    pub loc2: SelectionProxy,
+    pub loc2_p: SelectionProxy,
+}
+
+impl Loc2Mirrored {
+    pub fn colspan(&self) -> usize {
+        self.loc1.loc.columns_between(&self.loc2.loc)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Loc3 {
+    pub loc1: SelectionProxy,
+    pub loc2: SelectionProxy,
+    pub loc3: SelectionProxy,
+}
+
+#[derive(Debug, Clone)]
+pub struct Loc3Mirrored {
+    pub loc1: SelectionProxy,
+    pub loc2: SelectionProxy,
+    pub loc2_p: SelectionProxy,
+    pub loc3: SelectionProxy,
+    pub loc3_p: SelectionProxy,
+}
+
+impl Loc3Mirrored {
+    pub fn as_3s(&self) -> (Loc3, Loc3) {
+        (
+            Loc3 {
+                loc1: self.loc1.clone(),
+                loc2: self.loc2.clone(),
+                loc3: self.loc3.clone(),
+            },
+            (Loc3 {
+                loc1: self.loc1.clone(),
+                loc2: self.loc2_p.clone(),
+                loc3: self.loc3_p.clone(),
+            }),
+        )
+    }
+
+    pub fn both_3s(&self, predicate: fn(&Loc3) -> bool) -> bool {
+        let my_3s = self.as_3s();
+        predicate(&my_3s.0) && predicate(&my_3s.1)
+    }
+}
+
+pub type IfThen<L, R> = fn(&L) -> Option<R>;
+
+impl<'p> ImplicationResolver<'p, ()> {
+    pub fn new_unit(puzzle: &'p Puzzle) -> Self {
+        ImplicationResolver {
+            puzzle,
+            cells: Vec::default(),
+            actions: Vec::default(),
+        }
+    }
+}
+
+impl<'p, IT> ImplicationResolver<'p, IT> {
+    // Adds the answer at `loc`; clues are built from answers, so this is the true arrangement.
+    pub fn add_answer(&mut self, loc: CellLoc) {
+        self.cells.push(self.puzzle.answer_at(loc).decay_to_ind());
+    }
+
+    // Adds the answer at `loc` as though it were in column `col`, for clues about where an item
+    // isn't rather than where it is.
+    pub fn add_answer_in_column(&mut self, loc: CellLoc, col: LCol) {
+        let index = self.puzzle.answer_at(loc).decay_to_ind();
+        self.cells.push(CellLocIndex {
+            loc: CellLoc { col, ..index.loc },
+            ..index
+        });
+    }
+
+    pub fn colspan(&self) -> LColspan {
+        use itertools::Itertools;
+        self.cells.iter().map(|i| i.loc.col).minmax().into()
+    }
+
+    // A clue only fixes the relative columns of its cells, so this slides them across every shift
+    // that leaves at least one cell on the board. Off-board cells show up as void proxies. Each
+    // shift is counted in `shifts_tested` as it's tried.
+    pub fn iter_all_cols<'s, IT2>(
+        &'s self,
+        shifts_tested: &'s mut usize,
+    ) -> impl Iterator<Item = ImplicationResolver<'p, IT2>> + use<'s, 'p, IT, IT2> {
+        let colspan = self.colspan();
+        self.puzzle.iter_col_shift(colspan).map(move |shift| {
+            *shifts_tested += 1;
+            let cells = self.cells.iter().map(|&c| c.shift_column(shift)).collect();
+            ImplicationResolver {
+                cells,
+                actions: Vec::default(),
+                puzzle: self.puzzle,
+            }
+        })
+    }
+
+    pub fn if_then(&mut self, if_then_fn: IT) -> &mut Self {
+        self.actions.push(if_then_fn);
+        self
+    }
+}
+
+impl<'p, R> ImplicationResolver<'p, IfThen<Loc2, R>> {
+    // Evaluates every rule against every ordered pair of cells, yielding each rule that fires.
+    pub fn iter_perm_2s(&self) -> impl Iterator<Item = R> + use<'_, R> {
+        use itertools::Itertools;
+        let proxies = self
+            .cells
+            .iter()
+            .map(|&c| SelectionProxy::from_puzzle_and_index(self.puzzle, c))
+            .collect::<Vec<_>>();
+        let actions_iter = self.actions.iter();
+        proxies
+            .into_iter()
+            .permutations(2)
+            .flat_map(move |mut sels| {
+                let (Some(loc2), Some(loc1)) = (sels.pop(), sels.pop()) else {
+                    unreachable!()
+                };
+                let loc = Loc2 { loc1, loc2 };
+                // info!("incoming perm 2:\n  loc={loc:?}");
+                actions_iter.clone().filter_map(move |a| (a)(&loc))
+            })
+    }
+}
+
+impl<'p, R> ImplicationResolver<'p, IfThen<Loc2Mirrored, R>> {
+    // Evaluates every rule against every ordered pair whose first cell is on the board, along with
+    // the second cell's reflection.
+    pub fn iter_reflected_2s(&self) -> impl Iterator<Item = R> + use<'_, R> {
+        use itertools::Itertools;
+        let proxy = |c| SelectionProxy::from_puzzle_and_index(self.puzzle, c);
+        let proxies = self
+            .cells
+            .iter()
+            .map(|c| proxy(*c))
+            .permutations(2)
+            .map(move |mut locs| {
+                let (Some(loc2), Some(loc1)) = (locs.pop(), locs.pop()) else {
+                    unreachable!()
+                };
+                let loc2_p = proxy(loc2.reflect_loc_about(&loc1));
+                Loc2Mirrored { loc1, loc2, loc2_p }
+            })
+            // .inspect(|l| info!("incoming reflected 2:\n  loc={l:?}",))
+            .filter(|l| !l.loc1.is_void)
+            .collect::<Vec<_>>();
+        let actions_iter = self.actions.iter();
+        proxies
+            .into_iter()
+            .flat_map(move |loc| actions_iter.clone().filter_map(move |a| (a)(&loc)))
+    }
+}
+
+impl<'p, R> ImplicationResolver<'p, IfThen<Loc3Mirrored, R>> {
+    pub fn iter_reflected_3s(&self) -> impl Iterator<Item = R> + use<'_, R> {
+        use itertools::Itertools;
+        let proxy = |c| SelectionProxy::from_puzzle_and_index(self.puzzle, c);
+        let proxies = self
+            .cells
+            .iter()
+            .map(|c| proxy(*c))
+            // TODO: permutations is too many.. we don't need [1, 2, 3] and [1, 3, 2]
+            .permutations(3)
+            .map(move |mut locs| {
+                let (Some(loc3), Some(loc2), Some(loc1)) = (locs.pop(), locs.pop(), locs.pop())
+                else {
+                    unreachable!()
+                };
+                let loc2_p = proxy(loc2.reflect_loc_about(&loc1));
+                let loc3_p = proxy(loc3.reflect_loc_about(&loc1));
+                Loc3Mirrored {
+                    loc1,
+                    loc2,
+                    loc2_p,
+                    loc3,
+                    loc3_p,
+                }
+            })
+            // .inspect(|l| info!("incoming reflected 3:\n  loc={l:#?}",))
+            .filter(|l| !l.loc1.is_void)
+            .collect::<Vec<_>>();
+
+        let actions_iter = self.actions.iter();
+        proxies
+            .into_iter()
+            .flat_map(move |loc| actions_iter.clone().filter_map(move |a| (a)(&loc)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle, LInd, UpdateCellIndexOperation};
+
+    #[test]
+    fn all_cols_keeps_one_cell_on_the_board() {
+        let puzzle = test_puzzle(2, 5);
+        let mut resolver = ImplicationResolver::new_unit(&puzzle);
+        resolver.add_answer(loc(0, 1));
+        resolver.add_answer(loc(1, 2));
+        let answers = resolver.cells.iter().map(|c| c.index).collect::<Vec<_>>();
+        let placements = resolver
+            .iter_all_cols::<()>(&mut 0)
+            .map(|r| {
+                assert_eq!(r.cells.iter().map(|c| c.index).collect::<Vec<_>>(), answers);
+                r.cells.iter().map(|c| c.loc.col.0).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(placements, vec![
+            vec![-1, 0],
+            vec![0, 1],
+            vec![1, 2],
+            vec![2, 3],
+            vec![3, 4],
+            vec![4, 5],
+        ]);
+    }
+
+    #[test]
+    fn perm_2s_offer_both_orders() {
+        let puzzle = test_puzzle(2, 5);
+        let mut resolver = ImplicationResolver::new_unit(&puzzle);
+        resolver.add_answer(loc(0, 1));
+        resolver.add_answer(loc(1, 1));
+        let mut resolver = resolver
+            .iter_all_cols::<IfThen<Loc2, (CellLoc, CellLoc)>>(&mut 0)
+            .nth(1)
+            .unwrap();
+        resolver.if_then(|l: &Loc2| Some((l.loc1.loc, l.loc2.loc)));
+        let pairs = resolver.iter_perm_2s().collect::<Vec<_>>();
+        assert_eq!(pairs, vec![(loc(0, 1), loc(1, 1)), (loc(1, 1), loc(0, 1))]);
+    }
+
+    #[test]
+    fn reflected_2s_mirror_about_the_first_cell() {
+        let puzzle = test_puzzle(2, 5);
+        let mut resolver = ImplicationResolver::new_unit(&puzzle);
+        resolver.add_answer(loc(0, 2));
+        resolver.add_answer(loc(1, 3));
+        let mut resolver = resolver
+            .iter_all_cols::<IfThen<Loc2Mirrored, (CellLoc, CellLoc, CellLoc)>>(&mut 0)
+            .nth(3)
+            .unwrap();
+        resolver.if_then(|l: &Loc2Mirrored| Some((l.loc1.loc, l.loc2.loc, l.loc2_p.loc)));
+        let triples = resolver.iter_reflected_2s().collect::<Vec<_>>();
+        assert_eq!(triples, vec![
+            (loc(0, 2), loc(1, 3), loc(1, 1)),
+            (loc(1, 3), loc(0, 2), loc(0, 4)),
+        ]);
+    }
+
+    #[test]
+    fn reflected_2s_skip_off_board_first_cells() {
+        let puzzle = test_puzzle(2, 5);
+        let mut resolver = ImplicationResolver::new_unit(&puzzle);
+        resolver.add_answer(loc(0, 2));
+        resolver.add_answer(loc(1, 3));
+        let mut resolver = resolver
+            .iter_all_cols::<IfThen<Loc2Mirrored, (CellLoc, CellLoc, CellLoc)>>(&mut 0)
+            .next()
+            .unwrap();
+        resolver.if_then(|l: &Loc2Mirrored| Some((l.loc1.loc, l.loc2.loc, l.loc2_p.loc)));
+        let triples = resolver.iter_reflected_2s().collect::<Vec<_>>();
+        assert_eq!(triples, vec![(loc(1, 0), loc(0, -1), loc(0, 1))]);
+    }
+
+    #[test]
+    fn proxies_track_selection_state() {
+        let mut puzzle = test_puzzle(2, 5);
+        let index = puzzle.answer_at(loc(0, 0)).decay_to_ind();
+        let other = CellLocIndex {
+            index: LInd((index.index.0 + 1) % 5),
+            ..index
+        };
+        puzzle
+            .cell_selection_mut(index.loc)
+            .apply(index.index, UpdateCellIndexOperation::Solo);
+
+        let solo = SelectionProxy::from_puzzle_and_index(&puzzle, index);
+        assert!(solo.is_enabled && solo.is_solo && !solo.is_enabled_not_solo());
+        let cleared = SelectionProxy::from_puzzle_and_index(&puzzle, other);
+        assert!(!cleared.is_enabled && !cleared.is_solo && !cleared.is_void);
+        let off_board = SelectionProxy::from_puzzle_and_index(&puzzle, index.shift_column(-1));
+        assert!(off_board.is_void && !off_board.is_enabled && !off_board.is_solo);
+    }
+}
@@ -0,0 +1,198 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    clues::ClueExplanationChunk,
+    puzzle::{CellLocIndex, Puzzle},
+    settings::Settings,
+    GameState, GestureIds, UpdateCellIndex, NO_PICK,
+};
+
+// With `Settings::pause_inference`, what inference would eliminate is listed here for the
+// player to approve instead of being applied after every action.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct InferenceReview {
+    pending: Vec<CellLocIndex>,
+    expanded: bool,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct InferenceReviewPanel;
+
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+enum InferenceReviewEntry {
+    Expand,
+    ApplyAll,
+    Apply(CellLocIndex),
+}
+
+static INFERRED_CLEAR: &[ClueExplanationChunk] = &[ClueExplanationChunk::Text(
+    "Eliminated by inference: in its row, some item had only one cell left or some cell had only \
+     one item left, so that item was selected and cleared everywhere else.",
+)];
+
+const REVIEW_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const REVIEW_BUTTON_COLOR: Color = Color::hsla(210., 0.6, 0.35, 1.);
+
+fn update_inference_review(
+    settings: Res<Settings>,
+    q_puzzle: Query<Ref<Puzzle>>,
+    mut review: ResMut<InferenceReview>,
+) {
+    let Ok(puzzle) = q_puzzle.get_single() else {
+        return;
+    };
+    if !settings.is_changed() && !puzzle.is_changed() {
+        return;
+    }
+    let pending = if settings.pause_inference {
+        puzzle.pending_inferences()
+    } else {
+        vec![]
+    };
+    if pending != review.pending {
+        review.pending = pending;
+    }
+}
+
+fn rebuild_inference_review(
+    mut commands: Commands,
+    review: Res<InferenceReview>,
+    q_panel: Query<Entity, With<InferenceReviewPanel>>,
+) {
+    if !review.is_changed() {
+        return;
+    }
+    for entity in &q_panel {
+        commands.entity(entity).despawn_recursive();
+    }
+    if review.pending.is_empty() {
+        return;
+    }
+
+    let entry = |entry, label: String, background| {
+        (
+            entry,
+            Node {
+                padding: UiRect::axes(Val::Px(5.), Val::Px(2.)),
+                ..Default::default()
+            },
+            BackgroundColor(background),
+            Text::new(label),
+            TextFont {
+                font_size: 14.,
+                ..Default::default()
+            },
+        )
+    };
+    commands
+        .spawn((
+            InferenceReviewPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                left: Val::Px(10.),
+                max_height: Val::Percent(90.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.),
+                padding: UiRect::all(Val::Px(5.)),
+                overflow: Overflow::clip_y(),
+                ..Default::default()
+            },
+            BackgroundColor(REVIEW_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            let n = review.pending.len();
+            let plural = if n == 1 { "" } else { "s" };
+            parent.spawn((
+                Text::new(format!("{n} automatic elimination{plural} found")),
+                NO_PICK,
+            ));
+            let expand = if review.expanded {
+                "Hide"
+            } else {
+                "Review one by one"
+            };
+            parent.spawn(entry(
+                InferenceReviewEntry::Expand,
+                expand.to_string(),
+                Color::NONE,
+            ));
+            parent.spawn(entry(
+                InferenceReviewEntry::ApplyAll,
+                "Apply all".to_string(),
+                REVIEW_BUTTON_COLOR,
+            ));
+            if !review.expanded {
+                return;
+            }
+            for &index in &review.pending {
+                parent.spawn(entry(
+                    InferenceReviewEntry::Apply(index),
+                    format!("Clear {index}"),
+                    Color::NONE,
+                ));
+            }
+        });
+}
+
+fn inference_review_entry_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_entry: Query<&InferenceReviewEntry>,
+    mut review: ResMut<InferenceReview>,
+    mut gestures: ResMut<GestureIds>,
+    mut update_tx: EventWriter<UpdateCellIndex>,
+) {
+    let Ok(&entry) = q_entry.get(ev.entity()) else {
+        return;
+    };
+    match entry {
+        InferenceReviewEntry::Expand => review.expanded = !review.expanded,
+        InferenceReviewEntry::ApplyAll => {
+            let gesture = gestures.next();
+            update_tx.send_batch(review.pending.iter().map(|index| {
+                index
+                    .as_clear()
+                    .with_explanation(INFERRED_CLEAR)
+                    .in_gesture(gesture)
+            }));
+        }
+        InferenceReviewEntry::Apply(index) => {
+            update_tx.send(index.as_clear().with_explanation(INFERRED_CLEAR));
+        }
+    }
+}
+
+fn close_inference_review(
+    mut commands: Commands,
+    mut review: ResMut<InferenceReview>,
+    q_panel: Query<Entity, With<InferenceReviewPanel>>,
+) {
+    *review = InferenceReview::default();
+    for entity in &q_panel {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct InferenceReviewPlugin;
+
+impl Plugin for InferenceReviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InferenceReview>()
+            .register_type::<InferenceReview>()
+            .register_type::<InferenceReviewEntry>()
+            .register_type::<InferenceReviewPanel>()
+            .add_observer(inference_review_entry_clicked)
+            .add_systems(OnExit(GameState::Playing), close_inference_review)
+            .add_systems(
+                Update,
+                (update_inference_review, rebuild_inference_review)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
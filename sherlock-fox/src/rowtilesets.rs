@@ -0,0 +1,175 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::prelude::*;
+
+use crate::{
+    generate::{self, GeneratorConfig},
+    loading::MENU_BUTTON_COLOR,
+    GameState, Tileset, NO_PICK,
+};
+
+// A panel on the main menu for choosing which tileset each row of the next puzzle gets, instead of
+// leaving them all to chance.
+const PICKER_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.8);
+
+#[derive(Debug, Component, Reflect)]
+struct TilesetPicker;
+
+#[derive(Debug, Clone, Copy, Component, Reflect)]
+struct TilesetPickerStep {
+    row: usize,
+    delta: isize,
+}
+
+fn tileset_name(tileset: Option<&Tileset>) -> &str {
+    tileset.map_or("random", |t| t.asset_path.trim_end_matches(".png"))
+}
+
+// Steps from random through each tileset the row can be given.
+fn step_row_tileset(config: &mut GeneratorConfig, row: usize, delta: isize) {
+    let choices = generate::tileset_choices(config, row);
+    let current = config
+        .tileset_picks()
+        .get(row)
+        .copied()
+        .flatten()
+        .and_then(|pick| choices.iter().position(|t| t.asset_path == pick.asset_path))
+        .map_or(0, |n| n + 1);
+    let next = current.saturating_add_signed(delta).min(choices.len());
+    config.pick_tileset(row, next.checked_sub(1).map(|n| choices[n]));
+}
+
+fn spawn_picker_button(parent: &mut ChildBuilder, step: TilesetPickerStep, label: &str) {
+    parent
+        .spawn((
+            step,
+            Node {
+                padding: UiRect::axes(Val::Px(10.), Val::Px(4.)),
+                ..Default::default()
+            },
+            BackgroundColor(MENU_BUTTON_COLOR),
+        ))
+        .with_child((Text::new(label), NO_PICK));
+}
+
+// Rebuilt whenever the config changes, since the board size decides both how many rows there are
+// and which tilesets are wide enough.
+fn show_tileset_picker(
+    mut commands: Commands,
+    config: Res<GeneratorConfig>,
+    q_picker: Query<Entity, With<TilesetPicker>>,
+) {
+    if !config.is_changed() && !q_picker.is_empty() {
+        return;
+    }
+    for entity in &q_picker {
+        commands.entity(entity).despawn_recursive();
+    }
+    let picks = config.tileset_picks();
+    commands
+        .spawn((
+            TilesetPicker,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.),
+                padding: UiRect::all(Val::Px(10.)),
+                ..Default::default()
+            },
+            BackgroundColor(PICKER_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new("Row tilesets"), NO_PICK));
+            for (row, pick) in picks.into_iter().enumerate() {
+                parent
+                    .spawn((
+                        Node {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.),
+                            ..Default::default()
+                        },
+                        NO_PICK,
+                    ))
+                    .with_children(|parent| {
+                        spawn_picker_button(parent, TilesetPickerStep { row, delta: -1 }, "<");
+                        parent.spawn((
+                            Text::new(format!("Row {}: {}", row + 1, tileset_name(pick))),
+                            TextFont {
+                                font_size: 14.,
+                                ..Default::default()
+                            },
+                            NO_PICK,
+                        ));
+                        spawn_picker_button(parent, TilesetPickerStep { row, delta: 1 }, ">");
+                    });
+            }
+        });
+}
+
+fn despawn_tileset_picker(mut commands: Commands, q_picker: Query<Entity, With<TilesetPicker>>) {
+    for entity in &q_picker {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn tileset_picker_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_step: Query<&TilesetPickerStep>,
+    mut config: ResMut<GeneratorConfig>,
+) {
+    let Ok(&TilesetPickerStep { row, delta }) = q_step.get(ev.entity()) else {
+        return;
+    };
+    step_row_tileset(&mut config, row, delta);
+}
+
+pub struct RowTilesetsPlugin;
+
+impl Plugin for RowTilesetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TilesetPicker>()
+            .register_type::<TilesetPickerStep>()
+            .add_observer(tileset_picker_clicked)
+            .add_systems(
+                Update,
+                show_tileset_picker.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(OnExit(GameState::MainMenu), despawn_tileset_picker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepping_skips_tilesets_other_rows_have() {
+        let mut config = GeneratorConfig {
+            n_rows: 4,
+            n_cols: 6,
+            ..Default::default()
+        };
+        let name = |config: &GeneratorConfig, row: usize| {
+            tileset_name(config.tileset_picks()[row]).to_owned()
+        };
+        step_row_tileset(&mut config, 0, 1);
+        assert_eq!(name(&config, 0), "foods");
+        step_row_tileset(&mut config, 1, 1);
+        assert_eq!(name(&config, 1), "natures");
+        step_row_tileset(&mut config, 0, 1);
+        assert_eq!(name(&config, 0), "tiles");
+        // Stepping back past the first tileset returns the row to chance, and stops there.
+        step_row_tileset(&mut config, 1, -1);
+        step_row_tileset(&mut config, 1, -1);
+        assert_eq!(name(&config, 1), "random");
+        // Stepping forward stops at the last choice.
+        for _ in 0..10 {
+            step_row_tileset(&mut config, 2, 1);
+        }
+        assert_eq!(name(&config, 2), "letters");
+    }
+}
@@ -0,0 +1,760 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use bevy::prelude::*;
+use petgraph::{graph::NodeIndex, visit::EdgeRef, Graph};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::ArchivedClue,
+    clues::{ClueKind, ClueValidationError, DynPuzzleClue, SavedClue},
+    fit::FitClickedEvent,
+    generate::{ClueMix, GeneratedPuzzle, GeneratorConfig, GeneratorVersion},
+    notes::PuzzleNotes,
+    puzzle::{CellLoc, CellLocIndex, Puzzle, PuzzleValidationError, UpdateCellIndexOperation},
+    solver::{DeductionTier, Difficulty},
+    toast::ShowToast,
+    undo::{Action, UndoTree, UndoTreeLocation},
+    DisplayCluebox, GameState, PuzzleClueComponent, PuzzleOrigin, PuzzleReveals, TopButtonAction,
+    UpdateCellDisplay, UpdateCellIndex,
+};
+
+pub const SAVE_EXTENSION: &str = "ron";
+// The game in progress, next to wherever the game was started from.
+pub const SAVE_GAME_PATH: &str = "savegame.ron";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PuzzleMetadata {
+    pub seed: Option<u64>,
+    pub clue_count: usize,
+    pub deduction_depth: usize,
+    pub deduction_tier: DeductionTier,
+    #[serde(default = "GeneratorVersion::unversioned")]
+    pub generator_version: GeneratorVersion,
+    // Only known for puzzles that were solved when they were generated.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    // Puzzles from before the count was configurable always revealed one cell.
+    #[serde(default = "PuzzleMetadata::single_reveal")]
+    pub reveal_count: usize,
+    // How many of the reveals the clues can't do without, for generated puzzles.
+    #[serde(default)]
+    pub needed_reveals: Option<usize>,
+    // The rest of the config that decides which clues a seed rolls. Saves from before these were
+    // kept were generated with the defaults.
+    #[serde(default)]
+    pub clue_mix: ClueMix,
+    // Sorted, so saving the same puzzle twice writes the same text.
+    #[serde(default = "PuzzleMetadata::default_clue_weights")]
+    pub clue_weights: BTreeMap<ClueKind, f32>,
+    #[serde(default = "PuzzleMetadata::default_edge_clue_weight")]
+    pub edge_clue_weight: u8,
+}
+
+impl PuzzleMetadata {
+    fn single_reveal() -> usize {
+        1
+    }
+
+    fn default_clue_weights() -> BTreeMap<ClueKind, f32> {
+        GeneratorConfig::default()
+            .clue_weights
+            .into_iter()
+            .collect()
+    }
+
+    fn default_edge_clue_weight() -> u8 {
+        GeneratorConfig::default().edge_clue_weight
+    }
+
+    pub fn generated(seed: u64, config: &GeneratorConfig, generated: &GeneratedPuzzle) -> Self {
+        PuzzleMetadata {
+            seed: Some(seed),
+            clue_count: generated.clues.len(),
+            deduction_depth: generated.report.rounds,
+            deduction_tier: generated.report.max_tier,
+            generator_version: config.version,
+            difficulty: Some(generated.report.difficulty()),
+            reveal_count: generated.reveals.len(),
+            needed_reveals: Some(generated.needed_reveals),
+            clue_mix: config.clue_mix,
+            clue_weights: config.clue_weights.iter().map(|(&k, &w)| (k, w)).collect(),
+            edge_clue_weight: config.edge_clue_weight,
+        }
+    }
+}
+
+// What a save that left out every optional field would read as.
+impl Default for PuzzleMetadata {
+    fn default() -> Self {
+        PuzzleMetadata {
+            seed: None,
+            clue_count: 0,
+            deduction_depth: 0,
+            deduction_tier: DeductionTier::default(),
+            generator_version: GeneratorVersion::unversioned(),
+            difficulty: None,
+            reveal_count: PuzzleMetadata::single_reveal(),
+            needed_reveals: None,
+            clue_mix: ClueMix::default(),
+            clue_weights: PuzzleMetadata::default_clue_weights(),
+            edge_clue_weight: PuzzleMetadata::default_edge_clue_weight(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPuzzle {
+    pub metadata: PuzzleMetadata,
+    pub puzzle: Puzzle,
+    // Applied when the puzzle is spawned, unless the saved history already starts from them.
+    pub reveals: Vec<CellLocIndex>,
+    pub clues: Vec<SavedClue>,
+    // Positions in `clues` of the ones the player archived.
+    #[serde(default)]
+    pub archived: Vec<usize>,
+    #[serde(default)]
+    pub notes: String,
+    // Only games saved mid-play have one; generated puzzles start their history when spawned.
+    #[serde(default)]
+    pub undo: Option<SavedUndoTree>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SavedPuzzleError {
+    Puzzle(PuzzleValidationError),
+    Reveal(CellLocIndex),
+    Clue(usize, ClueValidationError),
+    ArchivedClue(usize),
+    UndoState(usize, PuzzleValidationError),
+    UndoShape(usize),
+    UndoIndex(usize),
+}
+
+impl std::fmt::Display for SavedPuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SavedPuzzleError::Puzzle(e) => write!(f, "puzzle: {e}"),
+            SavedPuzzleError::Reveal(index) => write!(f, "reveal {index} is off the board"),
+            SavedPuzzleError::Clue(nr, e) => write!(f, "clue #{} {e}", nr + 1),
+            SavedPuzzleError::ArchivedClue(nr) => {
+                write!(f, "archived clue #{} doesn't exist", nr + 1)
+            }
+            SavedPuzzleError::UndoState(nr, e) => write!(f, "undo state {nr}: {e}"),
+            SavedPuzzleError::UndoShape(nr) => {
+                write!(f, "undo state {nr} doesn't match the puzzle's shape")
+            }
+            SavedPuzzleError::UndoIndex(nr) => {
+                write!(f, "undo history refers to missing state {nr}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SavedPuzzleError {}
+
+impl SavedPuzzle {
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let data = self.to_ron().map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    pub fn from_ron(data: &str) -> io::Result<Self> {
+        let saved = ron::from_str::<SavedPuzzle>(data).map_err(io::Error::other)?;
+        saved.validate().map_err(io::Error::other)?;
+        Ok(saved)
+    }
+
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    // Saves can be edited or come from other builds, so everything the game would index with is
+    // checked here instead of panicking partway through spawning.
+    pub fn validate(&self) -> Result<(), SavedPuzzleError> {
+        let puzzle = &self.puzzle;
+        puzzle.validate().map_err(SavedPuzzleError::Puzzle)?;
+        for &reveal in &self.reveals {
+            if !puzzle.contains_loc(reveal.loc)
+                || reveal.index.0 >= puzzle.cell_selection(reveal.loc).width()
+            {
+                return Err(SavedPuzzleError::Reveal(reveal));
+            }
+        }
+        for (nr, clue) in self.clues.iter().enumerate() {
+            DynPuzzleClue::from(clue.clone())
+                .validate(puzzle, std::iter::empty())
+                .map_err(|e| SavedPuzzleError::Clue(nr, e))?;
+        }
+        if let Some(&nr) = self.archived.iter().find(|&&nr| nr >= self.clues.len()) {
+            return Err(SavedPuzzleError::ArchivedClue(nr));
+        }
+        if let Some(undo) = &self.undo {
+            undo.validate(puzzle)?;
+        }
+        Ok(())
+    }
+}
+
+// Explanations point at static text and functions, so actions are saved without them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAction {
+    pub index: CellLocIndex,
+    pub op: UpdateCellIndexOperation,
+    pub update_count: usize,
+    pub inferred_count: usize,
+    // The rest of a gesture's updates, which speedrun certificates replay.
+    #[serde(default)]
+    pub joined: Vec<(CellLocIndex, UpdateCellIndexOperation)>,
+}
+
+// Edges keep the undo tree's direction, pointing from the newer state to the older one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedUndoTree {
+    pub states: Vec<Puzzle>,
+    pub edges: Vec<(usize, usize, SavedAction)>,
+    pub root: usize,
+    pub current: usize,
+    // Saves from before this was kept start at the root.
+    #[serde(default)]
+    pub start: Option<usize>,
+}
+
+impl SavedUndoTree {
+    fn validate(&self, puzzle: &Puzzle) -> Result<(), SavedPuzzleError> {
+        for (nr, state) in self.states.iter().enumerate() {
+            state
+                .validate()
+                .map_err(|e| SavedPuzzleError::UndoState(nr, e))?;
+            if !state.iter_locs().eq(puzzle.iter_locs()) {
+                return Err(SavedPuzzleError::UndoShape(nr));
+            }
+        }
+        let referenced = self
+            .edges
+            .iter()
+            .flat_map(|&(newer, older, _)| [newer, older])
+            .chain([self.root, self.current])
+            .chain(self.start);
+        for nr in referenced {
+            if nr >= self.states.len() {
+                return Err(SavedPuzzleError::UndoIndex(nr));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn capture(tree: &UndoTree, location: &UndoTreeLocation) -> Self {
+        SavedUndoTree {
+            states: tree.tree.node_weights().cloned().collect(),
+            edges: tree
+                .tree
+                .edge_references()
+                .map(|edge| {
+                    let Action {
+                        update: UpdateCellIndex { index, op, .. },
+                        update_count,
+                        inferred_count,
+                        joined,
+                    } = edge.weight().clone();
+                    let action = SavedAction {
+                        index,
+                        op,
+                        update_count,
+                        inferred_count,
+                        joined: joined.iter().map(|u| (u.index, u.op)).collect(),
+                    };
+                    (edge.source().index(), edge.target().index(), action)
+                })
+                .collect(),
+            root: tree.root.index(),
+            current: location.current.index(),
+            start: Some(tree.start.index()),
+        }
+    }
+
+    // Saved states don't carry display assets, so they're borrowed from the live puzzle.
+    pub fn restore(self, live: &Puzzle) -> (UndoTree, UndoTreeLocation) {
+        let mut tree = Graph::new();
+        for mut state in self.states {
+            state.copy_display_assets_from(live);
+            tree.add_node(state);
+        }
+        for (newer, older, action) in self.edges {
+            let action = Action {
+                update: UpdateCellIndex::manual(action.index, action.op),
+                update_count: action.update_count,
+                inferred_count: action.inferred_count,
+                joined: action
+                    .joined
+                    .into_iter()
+                    .map(|(index, op)| UpdateCellIndex::manual(index, op))
+                    .collect(),
+            };
+            tree.add_edge(NodeIndex::new(newer), NodeIndex::new(older), action);
+        }
+        let root = NodeIndex::new(self.root);
+        let current = NodeIndex::new(self.current);
+        let start = self.start.map_or(root, NodeIndex::new);
+        let tree = UndoTree {
+            tree,
+            root,
+            start,
+            reveal_gesture: None,
+        };
+        (tree, UndoTreeLocation { current })
+    }
+}
+
+// The game in progress as it would be saved, or None before a puzzle is on the board. Clues keep
+// the cluebox's order.
+pub fn capture_game(
+    q_puzzle: &Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+    q_tree: &Query<(&UndoTree, &UndoTreeLocation)>,
+    q_cluebox: &Query<&Children, With<DisplayCluebox>>,
+    q_clues: &Query<(&PuzzleClueComponent, Has<ArchivedClue>)>,
+) -> Option<SavedPuzzle> {
+    let (puzzle, notes, reveals, origin) = q_puzzle.get_single().ok()?;
+    let entries = q_cluebox
+        .iter()
+        .flat_map(|children| children.iter())
+        .filter_map(|&entity| q_clues.get(entity).ok())
+        .collect::<Vec<_>>();
+    let clues = entries
+        .iter()
+        .map(|(c, _)| c.clue.to_saved())
+        .collect::<Vec<_>>();
+    let archived = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, &(_, archived))| archived)
+        .map(|(nr, _)| nr)
+        .collect();
+    Some(SavedPuzzle {
+        metadata: PuzzleMetadata {
+            clue_count: clues.len(),
+            ..origin.0.clone()
+        },
+        puzzle: puzzle.clone(),
+        reveals: reveals.0.clone(),
+        clues,
+        archived,
+        notes: notes.text.clone(),
+        undo: q_tree
+            .get_single()
+            .ok()
+            .map(|(tree, location)| SavedUndoTree::capture(tree, location)),
+    })
+}
+
+fn save_game(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    q_puzzle: Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+    q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+    q_cluebox: Query<&Children, With<DisplayCluebox>>,
+    q_clues: Query<(&PuzzleClueComponent, Has<ArchivedClue>)>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        let TopButtonAction::Save = action else {
+            continue;
+        };
+        let Some(saved) = capture_game(&q_puzzle, &q_tree, &q_cluebox, &q_clues) else {
+            continue;
+        };
+        let toast = match saved.write_to(Path::new(SAVE_GAME_PATH)) {
+            Ok(()) => ShowToast::info(format!("Saved to {SAVE_GAME_PATH}.")),
+            Err(e) => ShowToast::error(format!("Couldn't write {SAVE_GAME_PATH}: {e}")),
+        };
+        toast_tx.send(toast);
+    }
+}
+
+// Restored rows arrive with their eliminations already applied, which nothing else displays.
+fn show_restored_puzzle(
+    q_tree_loc: Query<(), Added<UndoTreeLocation>>,
+    puzzle: Single<&Puzzle>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    if q_tree_loc.is_empty() {
+        return;
+    }
+    for row in puzzle.iter_rows() {
+        for col in puzzle.iter_cols() {
+            update_display_tx.send(UpdateCellDisplay {
+                loc: CellLoc { row, col },
+            });
+        }
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (save_game, show_restored_puzzle).run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+// Colors are stored as plain HSLA quadruples rather than pulling in bevy's serialize feature.
+pub mod hsla {
+    use bevy::color::{Color, Hsla};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        let Hsla {
+            hue,
+            saturation,
+            lightness,
+            alpha,
+        } = Hsla::from(*color);
+        [hue, saturation, lightness, alpha].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [hue, saturation, lightness, alpha] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::hsla(hue, saturation, lightness, alpha))
+    }
+}
+
+// FixedBitSet's own deserializer only accepts borrowed bytes, which RON can't hand it, so bitsets
+// are stored as their width and the indices that are set.
+pub mod bitset {
+    use fixedbitset::FixedBitSet;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    // Far wider than any tileset; only here so a corrupt width can't ask for a huge allocation.
+    const MAX_WIDTH: usize = u16::MAX as usize;
+
+    pub fn serialize<S: Serializer>(bits: &FixedBitSet, serializer: S) -> Result<S::Ok, S::Error> {
+        (bits.len(), bits.ones().collect::<Vec<_>>()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FixedBitSet, D::Error> {
+        let (width, ones) = <(usize, Vec<usize>)>::deserialize(deserializer)?;
+        if width > MAX_WIDTH {
+            return Err(D::Error::custom(format!(
+                "bit set width {width} is too wide"
+            )));
+        }
+        let mut bits = FixedBitSet::with_capacity(width);
+        for one in ones {
+            if one >= width {
+                return Err(D::Error::custom(format!(
+                    "bit {one} is past the end of a set of {width}"
+                )));
+            }
+            bits.insert(one);
+        }
+        Ok(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashSet;
+    use fixedbitset::FixedBitSet;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::{
+        clues::{
+            AdjacentColumnClue, BetweenColumnsClue, EdgeColumnClue, LeftOfClue,
+            NotInSameColumnClue, SameColumnClue,
+        },
+        puzzle::{loc, test_puzzle, LInd, PuzzleCellSelection},
+    };
+
+    // Puzzles hold asset handles and bitsets without equality, so "the same" means serializing
+    // back to the same text.
+    fn assert_round_trips<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        let data = ron::to_string(value).unwrap();
+        let parsed = ron::from_str::<T>(&data).unwrap();
+        assert_eq!(ron::to_string(&parsed).unwrap(), data);
+        parsed
+    }
+
+    fn played_puzzle() -> Puzzle {
+        let mut puzzle = test_puzzle(3, 5);
+        let mut to_update = HashSet::new();
+        let solo = puzzle.answer_at(loc(0, 0)).decay_to_ind();
+        puzzle
+            .apply_update(&solo.as_solo(), &mut to_update)
+            .unwrap();
+        let cleared = puzzle.answer_at(loc(1, 2)).decay_to_ind().shift_column(1);
+        puzzle
+            .apply_update(&cleared.as_clear(), &mut to_update)
+            .unwrap();
+        puzzle
+    }
+
+    fn every_clue_kind(puzzle: &Puzzle) -> Vec<SavedClue> {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let clues: [DynPuzzleClue; 6] = [
+            SameColumnClue::new_random(&mut rng, puzzle).unwrap().into(),
+            AdjacentColumnClue::new_random(&mut rng, puzzle)
+                .unwrap()
+                .into(),
+            NotInSameColumnClue::new_random(&mut rng, puzzle)
+                .unwrap()
+                .into(),
+            LeftOfClue::new_random(&mut rng, puzzle).unwrap().into(),
+            BetweenColumnsClue::new_random(&mut rng, puzzle)
+                .unwrap()
+                .into(),
+            EdgeColumnClue::new_random(&mut rng, puzzle).unwrap().into(),
+        ];
+        clues.iter().map(|c| c.to_saved()).collect()
+    }
+
+    fn undo_tree(puzzle: &Puzzle) -> (UndoTree, UndoTreeLocation) {
+        let mut tree = Graph::new();
+        let root = tree.add_node(test_puzzle(3, 5));
+        let index = puzzle.answer_at(loc(0, 0)).decay_to_ind();
+        let newer = tree.add_node(puzzle.clone());
+        tree.add_edge(newer, root, Action {
+            update: index.as_solo(),
+            update_count: 4,
+            inferred_count: 0,
+            joined: vec![puzzle.answer_at(loc(1, 0)).decay_to_ind().as_solo()],
+        });
+        // The first action is taken as the starting reveals.
+        let tree = UndoTree {
+            tree,
+            root,
+            start: newer,
+            reveal_gesture: None,
+        };
+        (tree, UndoTreeLocation { current: newer })
+    }
+
+    fn saved_game() -> SavedPuzzle {
+        let puzzle = played_puzzle();
+        let (tree, location) = undo_tree(&puzzle);
+        SavedPuzzle {
+            metadata: PuzzleMetadata {
+                seed: Some(7),
+                clue_count: 3,
+                deduction_depth: 2,
+                deduction_tier: DeductionTier::MultiClue,
+                generator_version: GeneratorVersion::default(),
+                difficulty: Some(Difficulty::Medium),
+                reveal_count: 1,
+                needed_reveals: Some(1),
+                clue_mix: ClueMix::Hard,
+                clue_weights: ClueMix::Hard.weights().into_iter().collect(),
+                edge_clue_weight: 3,
+            },
+            reveals: vec![puzzle.answer_at(loc(2, 4)).decay_to_ind()],
+            clues: every_clue_kind(&puzzle),
+            archived: vec![2, 4],
+            notes: "fox is\nleft of \"hound\"".to_string(),
+            undo: Some(SavedUndoTree::capture(&tree, &location)),
+            puzzle,
+        }
+    }
+
+    #[test]
+    fn puzzle_round_trips() {
+        let puzzle = played_puzzle();
+        let parsed = assert_round_trips(&puzzle);
+        assert_eq!(parsed.validate(), Ok(()));
+        assert!(parsed.iter_locs().all(|loc| {
+            parsed.cell_selection(loc).count_ones() == puzzle.cell_selection(loc).count_ones()
+        }));
+    }
+
+    #[test]
+    fn every_clue_kind_round_trips() {
+        let puzzle = test_puzzle(3, 5);
+        for clue in assert_round_trips(&every_clue_kind(&puzzle)) {
+            let clue = DynPuzzleClue::from(clue);
+            assert_eq!(clue.validate(&puzzle, std::iter::empty()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn undo_tree_round_trips() {
+        let puzzle = played_puzzle();
+        let (tree, location) = undo_tree(&puzzle);
+        let saved = assert_round_trips(&SavedUndoTree::capture(&tree, &location));
+        let (restored, restored_location) = saved.clone().restore(&puzzle);
+        assert_eq!(restored.tree.node_count(), tree.tree.node_count());
+        assert_eq!(restored.tree.edge_count(), tree.tree.edge_count());
+        assert_eq!(restored.root, tree.root);
+        assert_eq!(restored.start, tree.start);
+        assert_eq!(restored_location.current, location.current);
+        let joined = &restored.tree.edge_weights().next().unwrap().joined;
+        assert_eq!(joined.len(), 1);
+        let recaptured = SavedUndoTree::capture(&restored, &restored_location);
+        assert_eq!(
+            ron::to_string(&recaptured).unwrap(),
+            ron::to_string(&SavedUndoTree::capture(&tree, &location)).unwrap()
+        );
+
+        // Older saves didn't say where play starts, so it's the root.
+        let older = SavedUndoTree {
+            start: None,
+            ..saved
+        };
+        assert_eq!(older.restore(&puzzle).0.start, tree.root);
+    }
+
+    #[test]
+    fn saved_game_round_trips() {
+        let saved = saved_game();
+        let parsed = SavedPuzzle::from_ron(&saved.to_ron().unwrap()).unwrap();
+        assert_eq!(parsed.to_ron().unwrap(), saved.to_ron().unwrap());
+    }
+
+    #[test]
+    fn unversioned_metadata_defaults() {
+        let metadata = ron::from_str::<PuzzleMetadata>(
+            "(seed: None, clue_count: 0, deduction_depth: 0, deduction_tier: SingleClue)",
+        )
+        .unwrap();
+        assert_eq!(metadata.generator_version, GeneratorVersion::unversioned());
+        assert_eq!(metadata.difficulty, None);
+        assert_eq!(metadata.reveal_count, 1);
+        assert_eq!(metadata.needed_reveals, None);
+        assert_eq!(
+            ron::to_string(&metadata).unwrap(),
+            ron::to_string(&PuzzleMetadata::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn captured_games_keep_their_metadata() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let saved = saved_game();
+        let mut world = World::new();
+        world.spawn((
+            saved.puzzle.clone(),
+            PuzzleNotes::default(),
+            PuzzleReveals(saved.reveals.clone()),
+            PuzzleOrigin(saved.metadata.clone()),
+        ));
+        let captured = world
+            .run_system_once(
+                |q_puzzle: Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+                 q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+                 q_cluebox: Query<&Children, With<DisplayCluebox>>,
+                 q_clues: Query<(&PuzzleClueComponent, Has<ArchivedClue>)>| {
+                    capture_game(&q_puzzle, &q_tree, &q_cluebox, &q_clues)
+                },
+            )
+            .unwrap()
+            .unwrap();
+        // The clue count is the cluebox's, which is empty here.
+        let expected = PuzzleMetadata {
+            clue_count: 0,
+            ..saved.metadata
+        };
+        assert_eq!(
+            ron::to_string(&captured.metadata).unwrap(),
+            ron::to_string(&expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_text_is_an_error() {
+        let inputs = [
+            "",
+            "(",
+            "\u{0}\u{1}garbage",
+            "SavedPuzzle(metadata: 5)",
+            "(metadata: (), puzzle: (), reveals: [], clues: [])",
+            "[1, 2, 3]",
+        ];
+        for input in inputs {
+            assert!(SavedPuzzle::from_ron(input).is_err(), "parsed {input:?}");
+        }
+    }
+
+    #[test]
+    fn truncated_saves_are_errors() {
+        let data = saved_game().to_ron().unwrap();
+        for (end, _) in data.char_indices().step_by(7) {
+            assert!(
+                SavedPuzzle::from_ron(&data[..end]).is_err(),
+                "parsed a save cut off at {end}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_clue_kind_is_an_error() {
+        let data = saved_game()
+            .to_ron()
+            .unwrap()
+            .replace("SameColumn(", "DiagonalColumn(");
+        assert!(SavedPuzzle::from_ron(&data).is_err());
+    }
+
+    #[test]
+    fn out_of_range_bits_are_errors() {
+        let data = saved_game().to_ron().unwrap();
+        assert!(data.contains("Enabled((5, ["));
+        for bad in ["Enabled((5, [9, ", "Enabled((99999999999, ["] {
+            let data = data.replacen("Enabled((5, [", bad, 1);
+            assert!(SavedPuzzle::from_ron(&data).is_err(), "parsed {bad:?}");
+        }
+    }
+
+    #[test]
+    fn saves_without_tentative_marks_load() {
+        let data = saved_game().to_ron().unwrap();
+        let marks = ", (5, []))";
+        assert!(data.contains(marks));
+        let saved = SavedPuzzle::from_ron(&data.replace(marks, ")")).unwrap();
+        assert!(saved
+            .puzzle
+            .iter_locs()
+            .all(|loc| (0..5).all(|i| !saved.puzzle.cell_selection(loc).is_tentative(LInd(i)))));
+    }
+
+    #[test]
+    fn inconsistent_saves_are_errors() {
+        let mut saved = saved_game();
+        *saved.puzzle.cell_selection_mut(loc(1, 3)) =
+            PuzzleCellSelection::new(FixedBitSet::with_capacity(4));
+        assert!(matches!(saved.validate(), Err(SavedPuzzleError::Puzzle(_))));
+
+        let mut saved = saved_game();
+        saved.reveals.push(CellLocIndex {
+            loc: loc(0, 9),
+            index: LInd(0),
+        });
+        assert!(matches!(saved.validate(), Err(SavedPuzzleError::Reveal(_))));
+
+        let mut saved = saved_game();
+        saved.archived.push(6);
+        assert_eq!(saved.validate(), Err(SavedPuzzleError::ArchivedClue(6)));
+
+        let mut saved = saved_game();
+        saved.undo.as_mut().unwrap().current = 9;
+        assert_eq!(saved.validate(), Err(SavedPuzzleError::UndoIndex(9)));
+
+        let mut saved = saved_game();
+        saved.undo.as_mut().unwrap().states[0] = test_puzzle(2, 5);
+        assert_eq!(saved.validate(), Err(SavedPuzzleError::UndoShape(0)));
+
+        // Checked on load, so it comes back as an error rather than a panic once it's played.
+        assert!(SavedPuzzle::from_ron(&saved.to_ron().unwrap()).is_err());
+    }
+}
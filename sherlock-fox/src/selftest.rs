@@ -0,0 +1,228 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{app::AppExit, utils::HashSet};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    clues::DynPuzzleClue,
+    generate::{self, GeneratorConfig},
+    puzzle::{CellLocIndex, Puzzle},
+    save::{PuzzleMetadata, SavedPuzzle, SavedUndoTree},
+    solver,
+    undo::{Action, UndoTree},
+};
+
+const GENERATE_ATTEMPTS: usize = 10;
+const UNDO_CYCLES: usize = 3;
+
+// Takes one puzzle through everything a game does short of drawing it, so it can be checked on
+// machines without a window or a GPU.
+pub fn self_test(seed: Option<u64>, config: GeneratorConfig) -> AppExit {
+    match run(seed, &config) {
+        Ok(summary) => {
+            println!("self-test passed: {summary}");
+            AppExit::Success
+        }
+        Err(e) => {
+            eprintln!("self-test failed: {e}");
+            AppExit::error()
+        }
+    }
+}
+
+fn same_candidates(a: &Puzzle, b: &Puzzle) -> bool {
+    let diff = a.candidate_diff(b);
+    diff.added.is_empty() && diff.removed.is_empty()
+}
+
+fn run(seed: Option<u64>, config: &GeneratorConfig) -> Result<String, String> {
+    config.check_size()?;
+    let mut seed_rng = match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_os_rng(),
+    };
+    let (seed, generated) = (0..GENERATE_ATTEMPTS)
+        .find_map(|_| {
+            let seed = seed_rng.random();
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            generate::generate_puzzle(&mut rng, config).map(|generated| (seed, generated))
+        })
+        .ok_or_else(|| format!("couldn't generate a puzzle in {GENERATE_ATTEMPTS} tries"))?;
+    let fail = |what: String| format!("puzzle seed {seed}: {what}");
+
+    let mut puzzle = generated.puzzle.clone();
+    puzzle
+        .validate()
+        .map_err(|e| fail(format!("generated an invalid puzzle: {e}")))?;
+    let report = solver::solve_fresh(
+        &puzzle,
+        &generated.reveals,
+        generated.clues.iter(),
+        config.max_tier,
+    );
+    if !report.solved {
+        return Err(fail(
+            "the solver couldn't finish the puzzle it was generated with".into(),
+        ));
+    }
+
+    // Play it the way someone who knows the answers would, one cell at a time.
+    solver::reveal(&mut puzzle, &generated.reveals).map_err(|e| fail(format!("revealing: {e}")))?;
+    let (mut tree, mut location) = UndoTree::new(puzzle.clone());
+    for loc in puzzle.iter_locs().collect::<Vec<_>>() {
+        let update = puzzle.answer_at(loc).decay_to_ind().as_solo();
+        let Some((update_count, inferred_count)) = puzzle
+            .apply_update(&update, &mut HashSet::new())
+            .map_err(|e| fail(format!("playing {}: {e}", update.index)))?
+        else {
+            continue;
+        };
+        puzzle
+            .validate()
+            .map_err(|e| fail(format!("after playing {}: {e}", update.index)))?;
+        tree.push(&mut location, puzzle.clone(), Action {
+            update,
+            update_count,
+            inferred_count,
+            joined: vec![],
+        });
+    }
+    if !puzzle.is_solved() {
+        return Err(fail("playing every answer didn't solve the puzzle".into()));
+    }
+
+    let moves = tree.tree.edge_count();
+    for _ in 0..UNDO_CYCLES {
+        let mut undone = 0;
+        while let Some(undo) = tree.undo_target(location.current) {
+            location.current = undo;
+            undone += 1;
+        }
+        if location.current != tree.root || undone != moves {
+            return Err(fail(format!(
+                "undo stopped after {undone} of {moves} moves"
+            )));
+        }
+        while let Some(redo) = tree.redo_target(location.current) {
+            location.current = redo;
+        }
+        if !same_candidates(&tree.tree[location.current], &puzzle) {
+            return Err(fail(
+                "redoing every move didn't get back to the solved puzzle".into(),
+            ));
+        }
+    }
+
+    // A different move from the start branches the history, which redo then has to refuse.
+    let branched_from = tree.tree[tree.root].clone();
+    let Some(index) = branched_from
+        .iter_locs()
+        .flat_map(|loc| {
+            let answer = branched_from.answer_at(loc).decay_to_ind();
+            branched_from
+                .cell_selection(loc)
+                .iter_ones()
+                .filter_map(move |index| {
+                    (index != answer.index).then_some(CellLocIndex { index, ..answer })
+                })
+        })
+        .next()
+    else {
+        return Err(fail("the revealed puzzle was already solved".into()));
+    };
+    let mut branch = branched_from.clone();
+    let update = index.as_clear();
+    let (update_count, inferred_count) = branch
+        .apply_update(&update, &mut HashSet::new())
+        .map_err(|e| fail(format!("clearing {index}: {e}")))?
+        .ok_or_else(|| fail(format!("clearing {index} changed nothing")))?;
+    location.current = tree.root;
+    tree.push(&mut location, branch, Action {
+        update,
+        update_count,
+        inferred_count,
+        joined: vec![],
+    });
+    location.current = tree
+        .undo_target(location.current)
+        .ok_or_else(|| fail("couldn't undo the branching move".into()))?;
+    if tree.redo_target(location.current).is_some() {
+        return Err(fail("redo picked a branch by itself".into()));
+    }
+
+    let saved = SavedPuzzle {
+        metadata: PuzzleMetadata::generated(seed, config, &generated),
+        puzzle: tree.tree[location.current].clone(),
+        reveals: generated.reveals.clone(),
+        clues: generated.clues.iter().map(|c| c.to_saved()).collect(),
+        archived: vec![],
+        notes: String::new(),
+        undo: Some(SavedUndoTree::capture(&tree, &location)),
+    };
+    let data = saved
+        .to_ron()
+        .map_err(|e| fail(format!("couldn't save: {e}")))?;
+    let loaded =
+        SavedPuzzle::from_ron(&data).map_err(|e| fail(format!("couldn't load the save: {e}")))?;
+    if !same_candidates(&loaded.puzzle, &saved.puzzle) {
+        return Err(fail("the loaded puzzle doesn't match the saved one".into()));
+    }
+    let clues = loaded
+        .clues
+        .iter()
+        .cloned()
+        .map(DynPuzzleClue::from)
+        .collect::<Vec<_>>();
+    let undo = loaded
+        .undo
+        .ok_or_else(|| fail("the save lost its undo history".into()))?;
+    let (loaded_tree, loaded_location) = undo.restore(&loaded.puzzle);
+    if loaded_tree.tree.node_count() != tree.tree.node_count()
+        || loaded_tree.tree.edge_count() != tree.tree.edge_count()
+        || loaded_location.current != location.current
+        || tree
+            .tree
+            .node_indices()
+            .any(|node| !same_candidates(&tree.tree[node], &loaded_tree.tree[node]))
+    {
+        return Err(fail(
+            "the loaded undo history doesn't match the saved one".into(),
+        ));
+    }
+    let mut replay = loaded_tree.tree[loaded_tree.root].clone();
+    if !solver::solve_up_to(&mut replay, clues.iter(), config.max_tier).solved {
+        return Err(fail("the loaded clues no longer solve the puzzle".into()));
+    }
+
+    Ok(format!(
+        "puzzle seed {seed}, {}x{} with {} clues, {moves} moves undone and redone {UNDO_CYCLES} \
+         times, saved and loaded",
+        config.n_rows,
+        config.n_cols,
+        generated.clues.len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_with_a_fixed_seed() {
+        if let Err(e) = run(Some(0), &GeneratorConfig::default()) {
+            panic!("{e}");
+        }
+    }
+
+    #[test]
+    fn unsupported_sizes_fail() {
+        let config = GeneratorConfig {
+            n_rows: 3,
+            ..Default::default()
+        };
+        assert!(run(Some(0), &config).is_err());
+    }
+}
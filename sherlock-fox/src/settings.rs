@@ -0,0 +1,294 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{palette::Palette, radial::RadialLayout, toast::ShowToast};
+
+// Next to wherever the game was started from, like the saved game.
+pub const SETTINGS_PATH: &str = "settings.ron";
+
+// Player preferences; adjustable from the inspector, and kept between runs. Settings added since a
+// file was written take their defaults.
+#[derive(Debug, Clone, PartialEq, Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct Settings {
+    // Blocks manual clears and toggles that would leave a cell without any candidates.
+    pub prevent_emptying_cells: bool,
+    // Queues what inference finds for the player to review instead of applying it right away.
+    pub pause_inference: bool,
+    // Font size for explanations, toasts, and other panel text.
+    pub text_size: f32,
+    // Smallest width a candidate button gets before a cell wraps its candidates into a grid.
+    pub min_hit_target: f32,
+    // Hovering a cell shows an enlarged, clickable copy of its candidates.
+    pub magnifier: bool,
+    // While a drag points at an operation, the cells it and the inference after it would change
+    // are outlined.
+    pub consequence_preview: bool,
+    // The colors behind items on the board and in clues.
+    pub palette: Palette,
+    // Striped and dotted backgrounds on some items, so telling them apart doesn't rely on color.
+    pub patterns: bool,
+    // What happens when a move rules out a cell's answer.
+    pub mistake_check: MistakeCheck,
+    // A checkpoint is written after this many moves; zero turns autosaving off.
+    pub autosave_interval: usize,
+    // How many checkpoints are kept before the oldest is deleted.
+    pub autosave_checkpoints: usize,
+    // Clues with nothing left to give are dropped from the cluebox instead of only greyed out.
+    pub collapse_spent_clues: bool,
+    // Which operations the drag menu offers, and where.
+    pub radial: RadialLayout,
+    pub debug: DebugSettings,
+}
+
+// For tracking down bugs rather than for play.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugSettings {
+    // Inference after one action stops after this many steps, with a warning and a dump of the
+    // board, instead of hanging.
+    pub max_inference_steps: usize,
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        DebugSettings {
+            max_inference_steps: 1000,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            prevent_emptying_cells: false,
+            pause_inference: false,
+            text_size: 18.,
+            min_hit_target: 24.,
+            magnifier: false,
+            consequence_preview: false,
+            palette: Palette::Generated,
+            patterns: false,
+            mistake_check: MistakeCheck::Off,
+            autosave_interval: 1,
+            autosave_checkpoints: 5,
+            collapse_spent_clues: false,
+            radial: RadialLayout::default(),
+            debug: DebugSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // A radial layout that doesn't check out is swapped for the default, so a bad file can't leave
+    // the drag menu unusable.
+    fn from_ron(data: &str) -> io::Result<Self> {
+        let mut settings = ron::from_str::<Settings>(data).map_err(io::Error::other)?;
+        if let Err(e) = settings.radial.check() {
+            warn!("ignoring the saved radial menu: {e}");
+            settings.radial = RadialLayout::default();
+        }
+        Ok(settings)
+    }
+
+    fn to_ron(&self) -> io::Result<String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)
+    }
+}
+
+// How things move on the board. Kinds of animation that are turned off jump straight to where they
+// would have ended.
+#[derive(Debug, Clone, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AnimationSettings {
+    // Multiplies the speed of every animation, so 2 plays them in half the time.
+    pub speed: f32,
+    pub hover_scale: bool,
+    pub explanation_bounce: bool,
+    // Cells and buttons easing into place when the board is laid out again.
+    pub fit_easing: bool,
+    // Everything else that moves, fades, or flashes.
+    pub other: bool,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        AnimationSettings {
+            speed: 1.,
+            hover_scale: true,
+            explanation_bounce: true,
+            fit_easing: true,
+            other: true,
+        }
+    }
+}
+
+impl AnimationSettings {
+    // For `--reduced-motion`: every change on the board happens at once.
+    pub fn reduced_motion() -> Self {
+        AnimationSettings {
+            speed: 1.,
+            hover_scale: false,
+            explanation_bounce: false,
+            fit_easing: false,
+            other: false,
+        }
+    }
+
+    pub fn plays(&self, motion: MotionKind) -> bool {
+        match motion {
+            MotionKind::HoverScale => self.hover_scale,
+            MotionKind::ExplanationBounce => self.explanation_bounce,
+            MotionKind::FitEasing => self.fit_easing,
+            MotionKind::Other => self.other,
+        }
+    }
+}
+
+// Which of the `AnimationSettings` switches an animation answers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum MotionKind {
+    HoverScale,
+    ExplanationBounce,
+    FitEasing,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+pub enum MistakeCheck {
+    // Mistakes are only counted.
+    Off,
+    // The cell flashes red, but the move still happens.
+    Warn,
+    // The move is refused.
+    Strict,
+}
+
+// Text whose font size follows `Settings::text_size`.
+#[derive(Reflect, Debug, Component, Default)]
+pub struct SizedText;
+
+fn apply_text_size(settings: Res<Settings>, mut q_text: Query<(Ref<SizedText>, &mut TextFont)>) {
+    for (sized, mut font) in &mut q_text {
+        if settings.is_changed() || sized.is_added() {
+            font.font_size = settings.text_size;
+        }
+    }
+}
+
+fn load_settings(mut settings: ResMut<Settings>) {
+    let path = Path::new(SETTINGS_PATH);
+    match fs::read_to_string(path).and_then(|data| Settings::from_ron(&data)) {
+        Ok(loaded) => *settings = loaded,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => warn!("couldn't read {path:?}: {e}"),
+    }
+}
+
+// The inspector can set anything, so a radial layout that doesn't check out is put back the way it
+// was.
+fn keep_radial_layout_valid(
+    mut settings: ResMut<Settings>,
+    mut last_valid: Local<RadialLayout>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    if !settings.is_changed() || settings.radial == *last_valid {
+        return;
+    }
+    match settings.radial.check() {
+        Ok(()) => last_valid.clone_from(&settings.radial),
+        Err(e) => {
+            toast_tx.send(ShowToast::error(format!(
+                "Can't use that radial menu: {e}."
+            )));
+            settings.radial.clone_from(&last_valid);
+        }
+    }
+}
+
+// Only written when something differs from what was loaded or last written.
+fn save_settings(settings: Res<Settings>, mut written: Local<Option<Settings>>) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(written) = written.as_mut() else {
+        *written = Some(settings.clone());
+        return;
+    };
+    if *written == *settings {
+        return;
+    }
+    written.clone_from(&settings);
+    let path = Path::new(SETTINGS_PATH);
+    if let Err(e) = settings.to_ron().and_then(|data| fs::write(path, data)) {
+        warn!("couldn't write {path:?}: {e}");
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnimationSettings>()
+            .init_resource::<Settings>()
+            .register_type::<AnimationSettings>()
+            .register_type::<DebugSettings>()
+            .register_type::<MistakeCheck>()
+            .register_type::<MotionKind>()
+            .register_type::<Palette>()
+            .register_type::<Settings>()
+            .register_type::<SizedText>()
+            .add_systems(Startup, load_settings)
+            .add_systems(
+                Update,
+                (
+                    apply_text_size,
+                    (keep_radial_layout_valid, save_settings).chain(),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_round_trip_and_fill_in_defaults() {
+        let mut settings = Settings {
+            text_size: 24.,
+            palette: Palette::OkabeIto,
+            ..Default::default()
+        };
+        settings.radial.remove_sector();
+        let parsed = Settings::from_ron(&settings.to_ron().unwrap()).unwrap();
+        assert_eq!(parsed, settings);
+        // A file from before any of these were added still loads.
+        let parsed = Settings::from_ron("(text_size: 20.)").unwrap();
+        assert_eq!(parsed, Settings {
+            text_size: 20.,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn a_bad_radial_layout_is_replaced() {
+        let settings = Settings {
+            radial: RadialLayout {
+                cancel_radius: 80.,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let parsed = Settings::from_ron(&settings.to_ron().unwrap()).unwrap();
+        assert_eq!(parsed.radial, RadialLayout::default());
+    }
+}
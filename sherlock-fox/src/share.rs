@@ -0,0 +1,537 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{fs, path::Path};
+
+use bevy::{color::Hsla, prelude::*};
+
+use crate::{
+    archive::ArchivedClue,
+    clues::{ClueKind, SavedClue},
+    fit::FitClickedEvent,
+    generate::{GeneratorConfig, GeneratorVersion, PUZZLE_SIZES},
+    notes::PuzzleNotes,
+    puzzle::{CellLoc, CellLocIndex, LAns, LCol, LInd, LRow, Puzzle, PuzzleRow},
+    save::{self, PuzzleMetadata, SavedPuzzle, SavedPuzzleError, SAVE_GAME_PATH},
+    solver::DeductionTier,
+    toast::ShowToast,
+    undo::{UndoTree, UndoTreeLocation},
+    DisplayCluebox, GameState, PuzzleClueComponent, PuzzleOrigin, PuzzleReveals, PuzzleSpawn,
+    Tileset, TopButtonAction, PROCEDURAL_TILESET, TILESETS,
+};
+
+const USAGE: &str = "usage: sherlock-fox export [save-file]
+
+Prints the share code for a saved game or bank puzzle, which defaults to the game saved with the \
+                     Save button. `sherlock-fox --import CODE` starts that puzzle.";
+
+// There's no clipboard to put the code on, so the Share button writes it here.
+pub const SHARE_CODE_PATH: &str = "puzzle-code.txt";
+
+const CODE_VERSION: u64 = 1;
+const VERSION_BITS: u32 = 4;
+const SIZE_BITS: u32 = 6;
+const CLUE_COUNT_BITS: u32 = 10;
+const CLUE_LOCS_BITS: u32 = 2;
+// Colors are kept to whole degrees and percents; rows are always drawn opaque.
+const HUE_BITS: u32 = 9;
+const PERCENT_BITS: u32 = 7;
+
+// Share codes are URL-safe base64, so they survive being pasted into chat or a link.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareCodeError {
+    Encoding,
+    Truncated,
+    Version(u64),
+    Size(usize, usize),
+    Tileset(LRow),
+    Clue(usize),
+    Puzzle(SavedPuzzleError),
+}
+
+impl std::fmt::Display for ShareCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareCodeError::Encoding => write!(f, "that isn't a share code"),
+            ShareCodeError::Truncated => write!(f, "the code is cut short"),
+            ShareCodeError::Version(v) => write!(f, "the code is from a newer version ({v})"),
+            ShareCodeError::Size(n_rows, n_cols) => {
+                write!(f, "a {n_rows}x{n_cols} board isn't a size this build plays")
+            }
+            ShareCodeError::Tileset(row) => {
+                write!(
+                    f,
+                    "row {} uses a tileset this build doesn't have",
+                    row.0 + 1
+                )
+            }
+            ShareCodeError::Clue(nr) => write!(f, "clue #{} can't be read", nr + 1),
+            ShareCodeError::Puzzle(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ShareCodeError {}
+
+// Bits needed to store any value below `n`.
+pub fn width_for(n: usize) -> u32 {
+    usize::BITS - n.saturating_sub(1).leading_zeros()
+}
+
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    pub bytes: Vec<u8>,
+    n_bits: usize,
+}
+
+impl BitWriter {
+    pub fn write(&mut self, value: u64, width: u32) {
+        debug_assert!(
+            width == 64 || value >> width == 0,
+            "{value} in {width} bits"
+        );
+        for bit in (0..width).rev() {
+            if self.n_bits / 8 == self.bytes.len() {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.n_bits % 8);
+            }
+            self.n_bits += 1;
+        }
+    }
+
+    pub fn write_usize(&mut self, value: usize, width: u32) {
+        self.write(value as u64, width);
+    }
+}
+
+#[derive(Debug)]
+pub struct BitReader<'b> {
+    bytes: &'b [u8],
+    n_bits: usize,
+}
+
+impl<'b> BitReader<'b> {
+    pub fn new(bytes: &'b [u8]) -> Self {
+        BitReader { bytes, n_bits: 0 }
+    }
+
+    pub fn read(&mut self, width: u32) -> Result<u64, ShareCodeError> {
+        let mut value = 0;
+        for _ in 0..width {
+            let byte = self
+                .bytes
+                .get(self.n_bits / 8)
+                .ok_or(ShareCodeError::Truncated)?;
+            value = (value << 1) | u64::from((byte << (self.n_bits % 8)) >> 7);
+            self.n_bits += 1;
+        }
+        Ok(value)
+    }
+
+    pub fn read_usize(&mut self, width: u32) -> Result<usize, ShareCodeError> {
+        self.read(width).map(|v| v as usize)
+    }
+}
+
+pub fn to_base64(bytes: &[u8]) -> String {
+    bytes
+        .chunks(3)
+        .flat_map(|chunk| {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+            (0..=chunk.len()).map(move |i| ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char)
+        })
+        .collect()
+}
+
+pub fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let sextets = text
+        .trim()
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|v| v as u32))
+        .collect::<Option<Vec<_>>>()?;
+    let mut bytes = vec![];
+    for chunk in sextets.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &v)| n | v << (18 - 6 * i));
+        bytes.extend((0..chunk.len() - 1).map(|i| (n >> (16 - 8 * i)) as u8));
+    }
+    Some(bytes)
+}
+
+fn tilesets() -> impl Iterator<Item = &'static Tileset> {
+    TILESETS.iter().chain([&PROCEDURAL_TILESET])
+}
+
+pub fn write_loc(w: &mut BitWriter, loc: CellLoc, n_rows: usize, n_cols: usize) {
+    w.write_usize(loc.row.0, width_for(n_rows));
+    w.write_usize(loc.col.0 as usize, width_for(n_cols));
+}
+
+pub fn read_loc(
+    r: &mut BitReader,
+    n_rows: usize,
+    n_cols: usize,
+) -> Result<CellLoc, ShareCodeError> {
+    Ok(CellLoc {
+        row: LRow(r.read_usize(width_for(n_rows))?),
+        col: LCol(r.read_usize(width_for(n_cols))? as isize),
+    })
+}
+
+// Only what it takes to start the puzzle over: progress, notes and history are left out.
+pub fn export_code(saved: &SavedPuzzle) -> Result<String, ShareCodeError> {
+    let puzzle = &saved.puzzle;
+    let (n_rows, n_cols) = (puzzle.n_rows(), puzzle.iter_cols().count());
+    let n_tilesets = tilesets().count();
+    let mut w = BitWriter::default();
+    w.write(CODE_VERSION, VERSION_BITS);
+    w.write_usize(n_rows, SIZE_BITS);
+    w.write_usize(n_cols, SIZE_BITS);
+    for nr in puzzle.iter_rows() {
+        let row = puzzle.row_at(nr);
+        let tileset = row.tileset();
+        let tileset_nr = tilesets()
+            .position(|t| t.asset_path == tileset.asset_path)
+            .ok_or(ShareCodeError::Tileset(nr))?;
+        w.write_usize(tileset_nr, width_for(n_tilesets));
+        for col in row.iter_cols() {
+            w.write_usize(row.answer_at(col).0, width_for(n_cols));
+        }
+        for index in row.iter_indices() {
+            w.write_usize(row.display_atlas_index(index), width_for(tileset.len()));
+            let color = Hsla::from(row.display_color(index));
+            w.write(color.hue.round() as u64 % 360, HUE_BITS);
+            w.write((color.saturation * 100.).round() as u64, PERCENT_BITS);
+            w.write((color.lightness * 100.).round() as u64, PERCENT_BITS);
+        }
+    }
+    w.write_usize(saved.reveals.len(), width_for(n_rows * n_cols + 1));
+    for reveal in &saved.reveals {
+        write_loc(&mut w, reveal.loc, n_rows, n_cols);
+        w.write_usize(reveal.index.0, width_for(n_cols));
+    }
+    w.write_usize(saved.clues.len(), CLUE_COUNT_BITS);
+    for clue in &saved.clues {
+        let (kind, locs, flipped) = clue.to_parts();
+        let kind_nr = ClueKind::ALL.iter().position(|&k| k == kind).unwrap();
+        w.write_usize(kind_nr, width_for(ClueKind::ALL.len()));
+        w.write_usize(locs.len(), CLUE_LOCS_BITS);
+        for loc in locs {
+            write_loc(&mut w, loc, n_rows, n_cols);
+        }
+        w.write(flipped.into(), 1);
+    }
+    Ok(to_base64(&w.bytes))
+}
+
+pub fn import(code: &str) -> Result<SavedPuzzle, ShareCodeError> {
+    let bytes = from_base64(code).ok_or(ShareCodeError::Encoding)?;
+    let mut r = BitReader::new(&bytes);
+    let version = r.read(VERSION_BITS)?;
+    if version != CODE_VERSION {
+        return Err(ShareCodeError::Version(version));
+    }
+    let n_rows = r.read_usize(SIZE_BITS)?;
+    let n_cols = r.read_usize(SIZE_BITS)?;
+    if !PUZZLE_SIZES.contains(&n_rows) || !PUZZLE_SIZES.contains(&n_cols) {
+        return Err(ShareCodeError::Size(n_rows, n_cols));
+    }
+    let mut rows = vec![];
+    for row in 0..n_rows {
+        let tileset = tilesets()
+            .nth(r.read_usize(width_for(tilesets().count()))?)
+            .ok_or(ShareCodeError::Tileset(LRow(row)))?;
+        let answers = (0..n_cols)
+            .map(|_| r.read_usize(width_for(n_cols)).map(LAns))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut display = vec![];
+        for _ in 0..n_cols {
+            let atlas_index = r.read_usize(width_for(tileset.len()))?;
+            if atlas_index >= tileset.len() {
+                return Err(ShareCodeError::Tileset(LRow(row)));
+            }
+            let hue = r.read(HUE_BITS)? as f32;
+            let saturation = r.read(PERCENT_BITS)? as f32 / 100.;
+            let lightness = r.read(PERCENT_BITS)? as f32 / 100.;
+            display.push((atlas_index, Color::hsl(hue, saturation, lightness)));
+        }
+        rows.push(PuzzleRow::from_parts(tileset, answers, display));
+    }
+    let reveals = (0..r.read_usize(width_for(n_rows * n_cols + 1))?)
+        .map(|_| {
+            Ok(CellLocIndex {
+                loc: read_loc(&mut r, n_rows, n_cols)?,
+                index: LInd(r.read_usize(width_for(n_cols))?),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut clues = vec![];
+    for nr in 0..r.read_usize(CLUE_COUNT_BITS)? {
+        let kind = ClueKind::ALL
+            .get(r.read_usize(width_for(ClueKind::ALL.len()))?)
+            .copied()
+            .ok_or(ShareCodeError::Clue(nr))?;
+        let locs = (0..r.read_usize(CLUE_LOCS_BITS)?)
+            .map(|_| read_loc(&mut r, n_rows, n_cols))
+            .collect::<Result<Vec<_>, _>>()?;
+        let flipped = r.read(1)? == 1;
+        clues.push(SavedClue::from_parts(kind, &locs, flipped).ok_or(ShareCodeError::Clue(nr))?);
+    }
+    // Only added once the whole code has been read; `validate` below checks the rows themselves.
+    let mut puzzle = Puzzle::default();
+    for row in rows {
+        puzzle.add_row(row);
+    }
+    // Codes only carry the puzzle itself, so nothing is known about how it was generated.
+    let config = GeneratorConfig::default();
+    let saved = SavedPuzzle {
+        metadata: PuzzleMetadata {
+            seed: None,
+            clue_count: clues.len(),
+            deduction_depth: 0,
+            deduction_tier: DeductionTier::default(),
+            generator_version: GeneratorVersion::unversioned(),
+            difficulty: None,
+            reveal_count: reveals.len(),
+            needed_reveals: None,
+            clue_mix: config.clue_mix,
+            clue_weights: config.clue_weights.into_iter().collect(),
+            edge_clue_weight: config.edge_clue_weight,
+        },
+        puzzle,
+        reveals,
+        clues,
+        archived: vec![],
+        notes: String::new(),
+        undo: None,
+    };
+    saved.validate().map_err(ShareCodeError::Puzzle)?;
+    Ok(saved)
+}
+
+pub fn export(args: &[String]) -> AppExit {
+    let path = match args {
+        [] => Path::new(SAVE_GAME_PATH),
+        [path] => Path::new(path),
+        _ => {
+            eprintln!("{USAGE}");
+            return AppExit::error();
+        }
+    };
+    let saved = match SavedPuzzle::read_from(path) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("couldn't read {path:?}: {e}");
+            return AppExit::error();
+        }
+    };
+    match export_code(&saved) {
+        Ok(code) => {
+            println!("{code}");
+            AppExit::Success
+        }
+        Err(e) => {
+            eprintln!("couldn't share {path:?}: {e}");
+            AppExit::error()
+        }
+    }
+}
+
+// A puzzle given with `--import`, started in place of generating one.
+#[derive(Resource)]
+pub struct ImportedPuzzle(pub Option<SavedPuzzle>);
+
+fn start_imported_puzzle(
+    mut commands: Commands,
+    mut imported: ResMut<ImportedPuzzle>,
+    mut notes: Single<&mut PuzzleNotes>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(saved) = imported.0.take() else {
+        return;
+    };
+    notes.text.clear();
+    commands.insert_resource(PuzzleSpawn::from_saved(saved));
+    next_state.set(GameState::Playing);
+}
+
+fn share_puzzle(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    q_puzzle: Query<(&Puzzle, &PuzzleNotes, &PuzzleReveals, &PuzzleOrigin)>,
+    q_tree: Query<(&UndoTree, &UndoTreeLocation)>,
+    q_cluebox: Query<&Children, With<DisplayCluebox>>,
+    q_clues: Query<(&PuzzleClueComponent, Has<ArchivedClue>)>,
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        let TopButtonAction::Share = action else {
+            continue;
+        };
+        let Some(saved) = save::capture_game(&q_puzzle, &q_tree, &q_cluebox, &q_clues) else {
+            continue;
+        };
+        let code = match export_code(&saved) {
+            Ok(code) => code,
+            Err(e) => {
+                toast_tx.send(ShowToast::error(format!("Couldn't share this puzzle: {e}")));
+                continue;
+            }
+        };
+        info!("share code: {code}");
+        let toast = match fs::write(SHARE_CODE_PATH, &code) {
+            Ok(()) => ShowToast::info(format!(
+                "Wrote this puzzle's share code to {SHARE_CODE_PATH}."
+            )),
+            Err(e) => ShowToast::error(format!("Couldn't write {SHARE_CODE_PATH}: {e}")),
+        };
+        toast_tx.send(toast);
+    }
+}
+
+pub struct SharePlugin;
+
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Loading), start_imported_puzzle)
+            .add_systems(Update, share_puzzle.run_if(in_state(GameState::Playing)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{loc, test_puzzle, PuzzleValidationError};
+
+    fn shared_puzzle() -> SavedPuzzle {
+        // Codes only hold boards of a size players can pick.
+        let puzzle = test_puzzle(4, 5);
+        let clue =
+            |kind, locs: &[CellLoc], flipped| SavedClue::from_parts(kind, locs, flipped).unwrap();
+        SavedPuzzle {
+            metadata: Default::default(),
+            reveals: vec![puzzle.answer_at(loc(2, 4)).decay_to_ind()],
+            clues: vec![
+                clue(
+                    ClueKind::SameColumn,
+                    &[loc(0, 1), loc(1, 1), loc(2, 1)],
+                    false,
+                ),
+                clue(ClueKind::LeftOf, &[loc(1, 0), loc(2, 3)], false),
+                clue(
+                    ClueKind::BetweenColumns,
+                    &[loc(0, 2), loc(1, 3), loc(2, 4)],
+                    true,
+                ),
+                clue(ClueKind::EdgeColumn, &[loc(1, 4)], false),
+            ],
+            puzzle,
+            archived: vec![],
+            notes: String::new(),
+            undo: None,
+        }
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        for len in 0..8 {
+            let bytes = (0..len).map(|b| b * 37 + 5).collect::<Vec<u8>>();
+            assert_eq!(from_base64(&to_base64(&bytes)), Some(bytes));
+        }
+        assert_eq!(from_base64("not base64!"), None);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let saved = shared_puzzle();
+        let code = export_code(&saved).unwrap();
+        let imported = import(&code).unwrap();
+        assert_eq!(export_code(&imported).unwrap(), code);
+        assert_eq!(imported.reveals, saved.reveals);
+        assert_eq!(imported.clues.len(), saved.clues.len());
+        for (a, b) in imported.clues.iter().zip(&saved.clues) {
+            assert_eq!(a.to_parts(), b.to_parts());
+        }
+        for loc in saved.puzzle.iter_locs() {
+            assert_eq!(imported.puzzle.answer_at(loc), saved.puzzle.answer_at(loc));
+        }
+    }
+
+    #[test]
+    fn damaged_codes_are_errors() {
+        let code = export_code(&shared_puzzle()).unwrap();
+        assert_eq!(
+            import(&code[..code.len() / 8 * 4]).unwrap_err(),
+            ShareCodeError::Truncated
+        );
+        assert_eq!(import("?").unwrap_err(), ShareCodeError::Encoding);
+        assert_eq!(import("_").unwrap_err(), ShareCodeError::Encoding);
+        assert_eq!(import("_w").unwrap_err(), ShareCodeError::Version(15));
+    }
+
+    #[test]
+    fn codes_for_impossible_boards_are_errors() {
+        let mut saved = shared_puzzle();
+        let mut rows = saved.puzzle.into_rows();
+        let row = &rows[0];
+        let display = row
+            .iter_indices()
+            .map(|index| (row.display_atlas_index(index), row.display_color(index)))
+            .collect();
+        rows[0] = PuzzleRow::from_parts(row.tileset(), vec![LAns(0); 5], display);
+        saved.puzzle = Puzzle::default();
+        for row in rows {
+            saved.puzzle.add_row(row);
+        }
+        assert_eq!(
+            import(&export_code(&saved).unwrap()).unwrap_err(),
+            ShareCodeError::Puzzle(SavedPuzzleError::Puzzle(
+                PuzzleValidationError::AnswersNotPermutation(LRow(0))
+            ))
+        );
+
+        let mut w = BitWriter::default();
+        w.write(CODE_VERSION, VERSION_BITS);
+        w.write_usize(0, SIZE_BITS);
+        w.write_usize(0, SIZE_BITS);
+        w.write_usize(0, CLUE_COUNT_BITS);
+        assert_eq!(
+            import(&to_base64(&w.bytes)).unwrap_err(),
+            ShareCodeError::Size(0, 0)
+        );
+    }
+
+    #[test]
+    fn unknown_tilesets_cant_be_shared() {
+        let missing = Tileset {
+            asset_path: "missing.png",
+            ..PROCEDURAL_TILESET.clone()
+        };
+        let mut saved = shared_puzzle();
+        let mut rows = saved.puzzle.into_rows();
+        let row = &rows[2];
+        let display = row
+            .iter_indices()
+            .map(|index| (row.display_atlas_index(index), row.display_color(index)))
+            .collect();
+        let answers = row.iter_cols().map(|col| row.answer_at(col)).collect();
+        rows[2] = PuzzleRow::from_parts(&missing, answers, display);
+        saved.puzzle = Puzzle::default();
+        for row in rows {
+            saved.puzzle.add_row(row);
+        }
+        assert_eq!(export_code(&saved), Err(ShareCodeError::Tileset(LRow(2))));
+    }
+}
@@ -0,0 +1,334 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::str::FromStr;
+
+use bevy::{reflect::Reflect, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clues::{ClueKind, DynPuzzleClue},
+    puzzle::{CellLocIndex, Puzzle, PuzzleError},
+};
+
+// The hardest kind of reasoning a solve needed, in increasing order of difficulty.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+pub enum DeductionTier {
+    // One clue's own implication, as shown by the clue explanation.
+    #[default]
+    SingleClue,
+    // The per-row elimination that runs after every update.
+    InferencePair,
+    // Assuming a candidate and refuting it with a single round of every clue.
+    MultiClue,
+    // Assuming a candidate and following it arbitrarily far to a contradiction.
+    Hypothesis,
+}
+
+impl DeductionTier {
+    pub const ALL: [DeductionTier; 4] = [
+        DeductionTier::SingleClue,
+        DeductionTier::InferencePair,
+        DeductionTier::MultiClue,
+        DeductionTier::Hypothesis,
+    ];
+}
+
+impl FromStr for DeductionTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(DeductionTier::SingleClue),
+            "inference" => Ok(DeductionTier::InferencePair),
+            "multi" => Ok(DeductionTier::MultiClue),
+            "hypothesis" => Ok(DeductionTier::Hypothesis),
+            _ => Err(format!(
+                "unknown tier {s:?}; expected single, inference, multi, or hypothesis"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for DeductionTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DeductionTier::SingleClue => "single",
+            DeductionTier::InferencePair => "inference",
+            DeductionTier::MultiClue => "multi",
+            DeductionTier::Hypothesis => "hypothesis",
+        })
+    }
+}
+
+// A coarser label than the score, for showing players and sorting banks of puzzles.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+    Fiendish,
+}
+
+impl Difficulty {
+    fn from_score(score: usize) -> Self {
+        match score {
+            0..20 => Difficulty::Easy,
+            20..40 => Difficulty::Medium,
+            40..70 => Difficulty::Hard,
+            _ => Difficulty::Fiendish,
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            "fiendish" => Ok(Difficulty::Fiendish),
+            _ => Err(format!(
+                "unknown difficulty {s:?}; expected easy, medium, hard, or fiendish"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SolveReport {
+    // Each round gives every clue one chance to advance the puzzle, so this is the length of the
+    // longest chain of deductions the solve needed.
+    pub rounds: usize,
+    pub clue_deductions: usize,
+    pub inferred: usize,
+    pub max_tier: DeductionTier,
+    // The kinds of clue that made at least one deduction; a player has to understand all of them.
+    pub clue_kinds: HashSet<ClueKind>,
+    pub solved: bool,
+}
+
+impl SolveReport {
+    fn needed(&mut self, tier: DeductionTier) {
+        self.max_tier = self.max_tier.max(tier);
+    }
+
+    // Whether a player would have to try a candidate and backtrack, rather than deduce their way
+    // through; unsolved puzzles count since no amount of deduction finishes them.
+    pub fn needs_guessing(&self) -> bool {
+        !self.solved || self.max_tier == DeductionTier::Hypothesis
+    }
+
+    pub fn difficulty_score(&self) -> usize {
+        let tier_weight = match self.max_tier {
+            DeductionTier::SingleClue => 0,
+            DeductionTier::InferencePair => 10,
+            DeductionTier::MultiClue => 30,
+            DeductionTier::Hypothesis => 60,
+        };
+        tier_weight + self.rounds + 2 * self.clue_kinds.len()
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        if self.needs_guessing() {
+            Difficulty::Fiendish
+        } else {
+            Difficulty::from_score(self.difficulty_score())
+        }
+    }
+}
+
+pub fn reveal(puzzle: &mut Puzzle, reveals: &[CellLocIndex]) -> Result<(), PuzzleError> {
+    let mut to_update = HashSet::new();
+    for index in reveals {
+        puzzle.apply_update(&index.as_solo(), &mut to_update)?;
+    }
+    Ok(())
+}
+
+fn clue_round<'c>(
+    puzzle: &mut Puzzle,
+    clues: impl IntoIterator<Item = &'c DynPuzzleClue>,
+    report: &mut SolveReport,
+) -> bool {
+    let mut to_update = HashSet::new();
+    let mut advanced = false;
+    for clue in clues {
+        let Some(update) = clue.advance_puzzle(puzzle) else {
+            continue;
+        };
+        if let Ok(Some((update_count, inferred_count))) =
+            puzzle.apply_update(&update, &mut to_update)
+        {
+            report.clue_deductions += update_count;
+            report.inferred += inferred_count;
+            report.needed(DeductionTier::SingleClue);
+            report.clue_kinds.insert(clue.kind());
+            if inferred_count > 0 {
+                report.needed(DeductionTier::InferencePair);
+            }
+            advanced = true;
+        }
+    }
+    advanced
+}
+
+// Finds a candidate whose assumption leads to a contradiction within `max_rounds` clue rounds and
+// clears it.
+fn refute_candidate<'c>(
+    puzzle: &mut Puzzle,
+    clues: impl IntoIterator<Item = &'c DynPuzzleClue> + Clone,
+    max_rounds: usize,
+) -> bool {
+    let candidates = puzzle
+        .iter_locs()
+        .filter(|&loc| puzzle.cell_selection(loc).is_any_solo().is_none())
+        .flat_map(|loc| {
+            puzzle
+                .cell_selection(loc)
+                .iter_ones()
+                .map(move |index| CellLocIndex { loc, index })
+        })
+        .collect::<Vec<_>>();
+    for candidate in candidates {
+        let mut scratch = puzzle.clone();
+        if scratch
+            .apply_update(&candidate.as_solo(), &mut HashSet::new())
+            .is_err()
+        {
+            continue;
+        }
+        let mut rounds = 0;
+        while !scratch.has_contradiction()
+            && rounds < max_rounds
+            && clue_round(&mut scratch, clues.clone(), &mut SolveReport::default())
+        {
+            rounds += 1;
+        }
+        if scratch.has_contradiction() {
+            return puzzle
+                .apply_update(&candidate.as_clear(), &mut HashSet::new())
+                .is_ok();
+        }
+    }
+    false
+}
+
+pub fn solve_up_to<'c>(
+    puzzle: &mut Puzzle,
+    clues: impl IntoIterator<Item = &'c DynPuzzleClue> + Clone,
+    max_tier: DeductionTier,
+) -> SolveReport {
+    let mut report = SolveReport::default();
+    while !puzzle.is_solved() {
+        let advanced = if clue_round(puzzle, clues.clone(), &mut report) {
+            true
+        } else if max_tier >= DeductionTier::MultiClue && refute_candidate(puzzle, clues.clone(), 1)
+        {
+            report.needed(DeductionTier::MultiClue);
+            true
+        } else if max_tier >= DeductionTier::Hypothesis
+            && refute_candidate(puzzle, clues.clone(), usize::MAX)
+        {
+            report.needed(DeductionTier::Hypothesis);
+            true
+        } else {
+            false
+        };
+        if !advanced {
+            break;
+        }
+        report.rounds += 1;
+    }
+    report.solved = puzzle.is_solved();
+    report
+}
+
+// Solves a copy of `puzzle` from just its reveals, leaving the original untouched.
+pub fn solve_fresh<'c>(
+    puzzle: &Puzzle,
+    reveals: &[CellLocIndex],
+    clues: impl IntoIterator<Item = &'c DynPuzzleClue> + Clone,
+    max_tier: DeductionTier,
+) -> SolveReport {
+    let mut scratch = puzzle.clone();
+    // Reveals that don't fit the board leave nothing to solve from.
+    if reveal(&mut scratch, reveals).is_err() {
+        return SolveReport::default();
+    }
+    solve_up_to(&mut scratch, clues, max_tier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(max_tier: DeductionTier, rounds: usize, kinds: &[ClueKind]) -> SolveReport {
+        SolveReport {
+            rounds,
+            max_tier,
+            clue_kinds: kinds.iter().copied().collect(),
+            solved: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn harder_tiers_score_higher() {
+        let kinds = [ClueKind::SameColumn, ClueKind::AdjacentColumn];
+        let scores = DeductionTier::ALL.map(|tier| report(tier, 5, &kinds).difficulty_score());
+        assert!(scores.is_sorted());
+        assert_eq!(
+            report(DeductionTier::SingleClue, 3, &[]).difficulty(),
+            Difficulty::Easy
+        );
+        assert_eq!(
+            report(DeductionTier::MultiClue, 8, &kinds).difficulty(),
+            Difficulty::Hard
+        );
+    }
+
+    #[test]
+    fn guessing_is_fiendish() {
+        let hypothesis = report(DeductionTier::Hypothesis, 1, &[]);
+        assert!(hypothesis.needs_guessing());
+        assert_eq!(hypothesis.difficulty(), Difficulty::Fiendish);
+
+        let unsolved = SolveReport {
+            solved: false,
+            ..report(DeductionTier::SingleClue, 1, &[])
+        };
+        assert!(unsolved.needs_guessing());
+        assert_eq!(unsolved.difficulty(), Difficulty::Fiendish);
+    }
+}
@@ -0,0 +1,645 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{fmt, fs, str::FromStr, time::Duration};
+
+use bevy::{prelude::*, utils::HashSet};
+use petgraph::graph::NodeIndex;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    generate::{self, GeneratorConfig, GeneratorVersion},
+    loading::{ConfigScreen, GeneratedFrom, MENU_BUTTON_COLOR},
+    operations::OperationTheme,
+    puzzle::{CellLocIndex, LInd, Puzzle, UpdateCellIndexOperation},
+    share::{self, width_for, BitReader, BitWriter, ShareCodeError},
+    stats::GameStats,
+    toast::ShowToast,
+    undo::{UndoTree, UndoTreeLocation},
+    AppliedCellUpdate, GameState, PuzzleOutcome, UpdateCellIndex, NO_PICK,
+};
+
+pub const CERTIFICATE_PATH: &str = "speedrun-certificate.txt";
+
+const USAGE: &str = "usage: sherlock-fox verify <certificate>
+
+Regenerates the certificate's puzzle from its seed and settings, and replays the moves it records \
+                     on that board, which have to solve it on the last one. The time is as the \
+                     run recorded it.";
+
+// Verifying makes the same attempts the loading screen did, which stopped at the first that
+// worked; a seed that needs more than this many was never really generated.
+const MAX_VERIFY_ATTEMPTS: usize = 100_000;
+
+const CERTIFICATE_PREFIX: &str = "SF1";
+
+const MOVE_COUNT_BITS: u32 = 32;
+const MOVE_OP_BITS: u32 = 3;
+// Operations in the order certificates number them; new ones go on the end.
+const MOVE_OPS: [UpdateCellIndexOperation; 7] = [
+    UpdateCellIndexOperation::Clear,
+    UpdateCellIndexOperation::Set,
+    UpdateCellIndexOperation::Toggle,
+    UpdateCellIndexOperation::Solo,
+    UpdateCellIndexOperation::MarkTentative,
+    UpdateCellIndexOperation::ConfirmAll,
+    UpdateCellIndexOperation::RevertTentative,
+];
+
+// A timed run with hints off, which ends in a certificate that can be checked with `verify`. Only
+// generated puzzles can be runs, since the certificate names the seed and config they came from.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct Speedrun {
+    // Whether the next puzzle is a run.
+    pub enabled: bool,
+    // Whether the puzzle in play is. It's decided when the puzzle starts, so hints can't be turned
+    // back on partway through.
+    active: bool,
+    last_op: Option<UpdateCellIndexOperation>,
+    // Whether the run has been given its certificate, or been refused one.
+    certified: bool,
+}
+
+impl Speedrun {
+    pub fn new(enabled: bool) -> Self {
+        Speedrun {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+}
+
+// FNV-1a, which unlike the std hashers is the same from one build to the next.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Every cell's answer, which for one seed and config is the same for whoever generated it.
+fn board_hash(puzzle: &Puzzle) -> u64 {
+    fnv1a(
+        puzzle
+            .iter_locs()
+            .flat_map(|loc| (puzzle.answer_at(loc).decay_to_ind().index.0 as u64).to_le_bytes()),
+    )
+}
+
+// One update on the way to the solution. Inference ran after it if the action it was part of
+// inferred anything; running it when it found nothing replays the same.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Move {
+    index: CellLocIndex,
+    op: UpdateCellIndexOperation,
+    inferred: bool,
+}
+
+// The updates along the history to `node`, with each gesture's taken one at a time.
+fn moves_to(tree: &UndoTree, node: NodeIndex) -> Vec<Move> {
+    tree.actions_to(node)
+        .into_iter()
+        .flat_map(|action| {
+            let inferred = action.inferred_count > 0;
+            [&action.update]
+                .into_iter()
+                .chain(&action.joined)
+                .map(move |update| Move {
+                    index: update.index,
+                    op: update.op,
+                    inferred,
+                })
+        })
+        .collect()
+}
+
+// How many moves `moves_to` would find, without collecting them.
+fn move_count(tree: &UndoTree, node: NodeIndex) -> usize {
+    tree.actions_to(node)
+        .into_iter()
+        .map(|action| 1 + action.joined.len())
+        .sum()
+}
+
+// Packed the same way as share codes, so the moves fit in one field.
+fn encode_moves(moves: &[Move], n_rows: usize, n_cols: usize) -> String {
+    let mut w = BitWriter::default();
+    w.write_usize(moves.len(), MOVE_COUNT_BITS);
+    for m in moves {
+        share::write_loc(&mut w, m.index.loc, n_rows, n_cols);
+        w.write_usize(m.index.index.0, width_for(n_cols));
+        let op = MOVE_OPS
+            .iter()
+            .position(|&op| op == m.op)
+            .expect("every operation has a number");
+        w.write_usize(op, MOVE_OP_BITS);
+        w.write(m.inferred.into(), 1);
+    }
+    share::to_base64(&w.bytes)
+}
+
+fn decode_moves(code: &str, n_rows: usize, n_cols: usize) -> Result<Vec<Move>, ShareCodeError> {
+    let bytes = share::from_base64(code).ok_or(ShareCodeError::Encoding)?;
+    let mut r = BitReader::new(&bytes);
+    let n_moves = r.read_usize(MOVE_COUNT_BITS)?;
+    (0..n_moves)
+        .map(|_| {
+            let loc = share::read_loc(&mut r, n_rows, n_cols)?;
+            let index = LInd(r.read_usize(width_for(n_cols))?);
+            let op = *MOVE_OPS
+                .get(r.read_usize(MOVE_OP_BITS)?)
+                .ok_or(ShareCodeError::Encoding)?;
+            Ok(Move {
+                index: CellLocIndex { loc, index },
+                op,
+                inferred: r.read(1)? == 1,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Certificate {
+    seed: u64,
+    config: GeneratorConfig,
+    time: Duration,
+    board: u64,
+    moves: Vec<Move>,
+}
+
+impl Certificate {
+    // Configs with clue weights edited away from their mix, or with tilesets chosen for rows, can't
+    // be named in a certificate.
+    fn new(
+        seed: u64,
+        config: &GeneratorConfig,
+        stats: &GameStats,
+        puzzle: &Puzzle,
+        moves: Vec<Move>,
+    ) -> Option<Self> {
+        if config.clue_weights != config.clue_mix.weights()
+            || config.edge_clue_weight != GeneratorConfig::default().edge_clue_weight
+            || config.tileset_picks().iter().any(Option::is_some)
+        {
+            return None;
+        }
+        Some(Certificate {
+            seed,
+            config: config.clone(),
+            time: stats.elapsed,
+            board: board_hash(puzzle),
+            moves,
+        })
+    }
+
+    fn body(&self) -> String {
+        let config = &self.config;
+        format!(
+            "{CERTIFICATE_PREFIX}.{}.{}x{}.{}.{}.{}.{}.{}.{}.{:016x}.{}",
+            self.seed,
+            config.n_rows,
+            config.n_cols,
+            config.max_clues,
+            config.max_tier,
+            config.n_reveals,
+            config.clue_mix,
+            config.version,
+            self.time.as_millis(),
+            self.board,
+            encode_moves(&self.moves, config.n_rows, config.n_cols),
+        )
+    }
+
+    fn summary(&self) -> String {
+        let millis = self.time.as_millis();
+        format!(
+            "seed {} solved in {}:{:02}.{:03} with {} moves",
+            self.seed,
+            millis / 60_000,
+            millis / 1000 % 60,
+            millis % 1000,
+            self.moves.len(),
+        )
+    }
+
+    fn verify(&self) -> Result<(), String> {
+        self.config.check_size()?;
+        self.config.check_reveals()?;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let generated = (0..MAX_VERIFY_ATTEMPTS)
+            .find_map(|_| generate::generate_puzzle(&mut rng, &self.config))
+            .ok_or_else(|| format!("couldn't regenerate seed {}", self.seed))?;
+        if board_hash(&generated.puzzle) != self.board {
+            return Err(format!("the board isn't the one seed {} makes", self.seed));
+        }
+        let mut puzzle = generated.puzzle;
+        for (nr, m) in self.moves.iter().enumerate() {
+            if puzzle.is_solved() {
+                return Err(format!("the puzzle was already solved after {nr} moves"));
+            }
+            let mut to_update = HashSet::new();
+            puzzle
+                .apply_update_without_inference(
+                    &UpdateCellIndex::manual(m.index, m.op),
+                    &mut to_update,
+                )
+                .map_err(|e| format!("move {} can't be made: {e}", nr + 1))?;
+            if m.inferred {
+                puzzle.run_inference(&mut to_update);
+            }
+        }
+        if !puzzle.is_solved() {
+            return Err(format!(
+                "its {} moves don't solve the puzzle",
+                self.moves.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// The check only catches typos and damage in copying. A certificate isn't signed, so nothing but the
+// moves is proven: the time is whatever the run wrote down.
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let body = self.body();
+        let check = fnv1a(body.bytes()) as u32;
+        write!(f, "{body}.{check:08x}")
+    }
+}
+
+impl FromStr for Certificate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (body, check) = s.trim().rsplit_once('.').ok_or("not a certificate")?;
+        if u32::from_str_radix(check, 16).ok() != Some(fnv1a(body.bytes()) as u32) {
+            return Err("the certificate is damaged or mistyped".into());
+        }
+        let fields = body.split('.').collect::<Vec<_>>();
+        let &[CERTIFICATE_PREFIX, seed, size, max_clues, tier, reveals, mix, version, millis, board, moves] =
+            fields.as_slice()
+        else {
+            return Err("not a certificate".into());
+        };
+        let number = |field: &str, what: &str| -> Result<u64, String> {
+            field.parse().map_err(|_| format!("bad {what} {field:?}"))
+        };
+        let (n_rows, n_cols) = size.split_once('x').ok_or("bad board size")?;
+        let mut config = GeneratorConfig {
+            n_rows: number(n_rows, "row count")? as usize,
+            n_cols: number(n_cols, "column count")? as usize,
+            max_clues: number(max_clues, "clue limit")? as usize,
+            max_tier: tier.parse()?,
+            n_reveals: number(reveals, "reveal count")? as usize,
+            version: version.parse::<GeneratorVersion>()?,
+            ..Default::default()
+        };
+        config.set_clue_mix(mix.parse()?);
+        Ok(Certificate {
+            seed: number(seed, "seed")?,
+            time: Duration::from_millis(number(millis, "time")?),
+            board: u64::from_str_radix(board, 16).map_err(|_| "bad board hash")?,
+            moves: decode_moves(moves, config.n_rows, config.n_cols)
+                .map_err(|_| "the moves can't be read")?,
+            config,
+        })
+    }
+}
+
+pub fn verify(args: &[String]) -> AppExit {
+    let [code] = args else {
+        eprintln!("{USAGE}");
+        return AppExit::error();
+    };
+    let certificate = match code.parse::<Certificate>() {
+        Ok(certificate) => certificate,
+        Err(e) => {
+            eprintln!("{e}");
+            return AppExit::error();
+        }
+    };
+    match certificate.verify() {
+        Ok(()) => {
+            println!("verified: {}", certificate.summary());
+            AppExit::Success
+        }
+        Err(e) => {
+            eprintln!("not verified: {e}");
+            AppExit::error()
+        }
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct SpeedrunToggle;
+
+#[derive(Debug, Component, Reflect)]
+struct SpeedrunHud;
+
+fn speedrun_toggle_label(enabled: bool) -> &'static str {
+    if enabled {
+        "Speedrun: on"
+    } else {
+        "Speedrun: off"
+    }
+}
+
+fn spawn_speedrun_toggle(
+    ev: Trigger<OnAdd, ConfigScreen>,
+    speedrun: Res<Speedrun>,
+    mut commands: Commands,
+) {
+    commands.entity(ev.entity()).with_children(|parent| {
+        parent
+            .spawn((
+                SpeedrunToggle,
+                Node {
+                    padding: UiRect::axes(Val::Px(12.), Val::Px(6.)),
+                    ..Default::default()
+                },
+                BackgroundColor(MENU_BUTTON_COLOR),
+            ))
+            .with_child((Text::new(speedrun_toggle_label(speedrun.enabled)), NO_PICK));
+    });
+}
+
+fn speedrun_toggle_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_toggle: Query<&Children, With<SpeedrunToggle>>,
+    mut q_text: Query<&mut Text>,
+    mut speedrun: ResMut<Speedrun>,
+) {
+    let Ok(children) = q_toggle.get(ev.entity()) else {
+        return;
+    };
+    speedrun.enabled = !speedrun.enabled;
+    let mut texts = q_text.iter_many_mut(children);
+    while let Some(mut text) = texts.fetch_next() {
+        text.0 = speedrun_toggle_label(speedrun.enabled).into();
+    }
+}
+
+fn start_speedrun(
+    mut speedrun: ResMut<Speedrun>,
+    generated_from: Res<GeneratedFrom>,
+    mut toast_tx: EventWriter<ShowToast>,
+    mut commands: Commands,
+) {
+    speedrun.active = speedrun.enabled && generated_from.0.is_some();
+    speedrun.last_op = None;
+    speedrun.certified = false;
+    if speedrun.enabled && !speedrun.active {
+        toast_tx.send(ShowToast::info(
+            "Only generated puzzles can be speedruns; this one isn't timed as a run.",
+        ));
+    }
+    if !speedrun.active {
+        return;
+    }
+    commands.spawn((
+        SpeedrunHud,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.),
+            right: Val::Px(10.),
+            ..Default::default()
+        },
+        Text::default(),
+        TextFont {
+            font_size: 18.,
+            ..Default::default()
+        },
+        NO_PICK,
+    ));
+}
+
+fn end_speedrun(
+    mut speedrun: ResMut<Speedrun>,
+    mut commands: Commands,
+    q_hud: Query<Entity, With<SpeedrunHud>>,
+) {
+    speedrun.active = false;
+    for entity in &q_hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Moves are counted the way the certificate counts them, along the history to the current state.
+fn speedrun_hud_text(stats: &GameStats, moves: usize, last: Option<String>) -> String {
+    let centis = stats.elapsed.as_millis() / 10;
+    let mut text = format!(
+        "{}:{:02}.{:02} | {moves} moves",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100,
+    );
+    if let Some(last) = last {
+        text.push_str(&format!(" | {last}"));
+    }
+    text
+}
+
+fn update_speedrun_hud(
+    mut applied_rx: EventReader<AppliedCellUpdate>,
+    mut speedrun: ResMut<Speedrun>,
+    (stats, theme): (Res<GameStats>, Res<OperationTheme>),
+    (tree, location): (Single<&UndoTree>, Single<&UndoTreeLocation>),
+    mut q_hud: Query<&mut Text, With<SpeedrunHud>>,
+) {
+    if let Some(ev) = applied_rx.read().last() {
+        speedrun.last_op = Some(ev.op);
+    }
+    let moves = move_count(&tree, location.current);
+    let text = speedrun_hud_text(&stats, moves, speedrun.last_op.map(|op| theme.label(op)));
+    for mut hud in &mut q_hud {
+        if hud.0 != text {
+            hud.0.clone_from(&text);
+        }
+    }
+}
+
+fn issue_certificate(
+    mut speedrun: ResMut<Speedrun>,
+    stats: Res<GameStats>,
+    generated_from: Res<GeneratedFrom>,
+    (tree, location): (Single<&UndoTree>, Single<&UndoTreeLocation>),
+    mut toast_tx: EventWriter<ShowToast>,
+) {
+    // Undoing out of the solution and back in is still the one run.
+    if !speedrun.active || speedrun.certified {
+        return;
+    }
+    // The board can show the solution a frame before the update that made it is in the history.
+    let solved = &tree.tree[location.current];
+    if !solved.is_solved() {
+        return;
+    }
+    speedrun.certified = true;
+    let Some((seed, config)) = &generated_from.0 else {
+        return;
+    };
+    let moves = moves_to(&tree, location.current);
+    let Some(certificate) = Certificate::new(*seed, config, &stats, solved, moves) else {
+        toast_tx.send(ShowToast::error(
+            "Runs with edited clue weights or chosen tilesets can't be certified.",
+        ));
+        return;
+    };
+    let code = certificate.to_string();
+    info!("speedrun certificate: {code}");
+    let toast = match fs::write(CERTIFICATE_PATH, &code) {
+        Ok(()) => ShowToast::info(format!(
+            "Run over: {}. Wrote the certificate to {CERTIFICATE_PATH}.",
+            certificate.summary()
+        )),
+        Err(e) => ShowToast::error(format!("Couldn't write {CERTIFICATE_PATH}: {e}")),
+    };
+    toast_tx.send(toast);
+}
+
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Speedrun>()
+            .register_type::<Speedrun>()
+            .register_type::<SpeedrunHud>()
+            .register_type::<SpeedrunToggle>()
+            .add_observer(spawn_speedrun_toggle)
+            .add_observer(speedrun_toggle_clicked)
+            .add_systems(OnEnter(GameState::Playing), start_speedrun)
+            .add_systems(OnExit(GameState::Playing), end_speedrun)
+            .add_systems(
+                Update,
+                (
+                    update_speedrun_hud.run_if(in_state(GameState::Playing)),
+                    issue_certificate.run_if(in_state(PuzzleOutcome::Solved)),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate::ClueMix, solver::DeductionTier, undo::Action};
+
+    // Applies an update to the current state with inference, the way the game does, and records it.
+    fn play(tree: &mut UndoTree, location: &mut UndoTreeLocation, update: UpdateCellIndex) {
+        let mut puzzle = tree.tree[location.current].clone();
+        if let Ok(Some((update_count, inferred_count))) =
+            puzzle.apply_update(&update, &mut HashSet::new())
+        {
+            tree.push_or_join(location, puzzle, Action {
+                update,
+                update_count,
+                inferred_count,
+                joined: vec![],
+            });
+        }
+    }
+
+    #[test]
+    fn certificates_round_trip_and_verify() {
+        let config = GeneratorConfig {
+            n_rows: 4,
+            n_cols: 4,
+            n_reveals: 2,
+            ..Default::default()
+        };
+        let seed = 11;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let generated = (0..MAX_VERIFY_ATTEMPTS)
+            .find_map(|_| generate::generate_puzzle(&mut rng, &config))
+            .unwrap();
+        // The reveals go in as one gesture, then cells get their answers until the board is solved.
+        let (mut tree, mut location) = UndoTree::new(generated.puzzle.clone());
+        for index in &generated.reveals {
+            play(&mut tree, &mut location, index.as_solo().in_gesture(1));
+        }
+        for loc in generated.puzzle.iter_locs() {
+            if !tree.tree[location.current].is_solved() {
+                let answer = generated.puzzle.answer_at(loc).decay_to_ind();
+                play(&mut tree, &mut location, answer.as_solo());
+            }
+        }
+        let solved = &tree.tree[location.current];
+        assert!(solved.is_solved());
+        let moves = moves_to(&tree, location.current);
+        assert!(moves.len() > tree.actions_to(location.current).len());
+        assert_eq!(move_count(&tree, location.current), moves.len());
+
+        let stats = GameStats {
+            elapsed: Duration::from_millis(83_250),
+            ..Default::default()
+        };
+        let certificate = Certificate::new(seed, &config, &stats, solved, moves).unwrap();
+        let code = certificate.to_string();
+        let parsed = code.parse::<Certificate>().unwrap();
+        assert_eq!(parsed.to_string(), code);
+        assert_eq!(parsed.moves, certificate.moves);
+        assert_eq!(
+            parsed.summary(),
+            format!(
+                "seed 11 solved in 1:23.250 with {} moves",
+                certificate.moves.len()
+            )
+        );
+        assert_eq!(parsed.verify(), Ok(()));
+
+        // A different board, moves that carry on past the solution, or moves that stop short of it
+        // don't pass.
+        let forged = Certificate {
+            board: certificate.board ^ 1,
+            ..certificate.clone()
+        };
+        assert!(forged.verify().is_err());
+        let mut moves = certificate.moves.clone();
+        moves.push(*moves.last().unwrap());
+        let padded = Certificate {
+            moves,
+            ..certificate.clone()
+        };
+        assert!(padded.verify().is_err());
+        let mut moves = certificate.moves.clone();
+        moves.pop();
+        let short = Certificate {
+            moves,
+            ..certificate.clone()
+        };
+        assert!(short.verify().is_err());
+    }
+
+    #[test]
+    fn config_names_parse_back() {
+        for tier in DeductionTier::ALL {
+            assert_eq!(tier.to_string().parse(), Ok(tier));
+        }
+        for mix in ClueMix::ALL {
+            assert_eq!(mix.to_string().parse(), Ok(mix));
+        }
+        for nr in 1..=9 {
+            let version = nr.to_string().parse::<GeneratorVersion>().unwrap();
+            assert_eq!(version.to_string(), nr.to_string());
+        }
+    }
+
+    #[test]
+    fn the_hud_shows_hundredths() {
+        let stats = GameStats {
+            elapsed: Duration::from_millis(65_432),
+            ..Default::default()
+        };
+        assert_eq!(
+            speedrun_hud_text(&stats, 3, Some("+ Set".into())),
+            "1:05.43 | 3 moves | + Set"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analytics::ClueDeductionApplied,
+    save::{PuzzleMetadata, SAVE_EXTENSION},
+    toast::ToastContainer,
+    AppliedCellUpdate, GameState, LastInferenceRun, PuzzleOrigin, PuzzleOutcome, NO_PICK,
+};
+
+// One file per solved puzzle, named for when it was solved.
+pub const STATS_DIR: &str = "stats";
+
+// How the solve in progress is going. Undone actions still count; a mistake stays a mistake.
+#[derive(Debug, Default, Clone, Resource, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct GameStats {
+    pub elapsed: Duration,
+    pub manual_actions: usize,
+    pub clue_deductions: usize,
+    pub mistakes: usize,
+    // Actions inference ran after, and its steps over all of them. Records from before these were
+    // kept have neither.
+    #[serde(default)]
+    pub inference_runs: usize,
+    #[serde(default)]
+    pub inference_steps: usize,
+}
+
+impl GameStats {
+    fn hud_text(&self) -> String {
+        let secs = self.elapsed.as_secs();
+        let plural = if self.mistakes == 1 { "" } else { "s" };
+        let mut text = format!(
+            "{}:{:02} | {} moves | {} from clues | {} mistake{plural}",
+            secs / 60,
+            secs % 60,
+            self.manual_actions,
+            self.clue_deductions,
+            self.mistakes,
+        );
+        if self.inference_runs > 0 {
+            let per_action = self.inference_steps as f32 / self.inference_runs as f32;
+            text.push_str(&format!(" | {per_action:.1} inference steps/move"));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolveRecord {
+    pub seed: Option<u64>,
+    pub solved_at: u64,
+    pub stats: GameStats,
+}
+
+impl SolveRecord {
+    // The seed is the one the puzzle was made from, however it came to be on the board.
+    fn new(stats: &GameStats, origin: &PuzzleMetadata, solved_at: SystemTime) -> Self {
+        SolveRecord {
+            seed: origin.seed,
+            solved_at: solved_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            stats: stats.clone(),
+        }
+    }
+
+    pub fn write_to(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("solve-{}.{SAVE_EXTENSION}", self.solved_at));
+        let data = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(io::Error::other)?;
+        fs::write(&path, data)?;
+        Ok(path)
+    }
+}
+
+#[derive(Debug, Component, Reflect)]
+struct StatsHud;
+
+// Undoing out of the solution and back in is still the one solve.
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct SolveRecorded(bool);
+
+fn reset_stats(mut stats: ResMut<GameStats>, mut recorded: ResMut<SolveRecorded>) {
+    *stats = GameStats::default();
+    recorded.0 = false;
+}
+
+fn tick_stats(time: Res<Time>, mut stats: ResMut<GameStats>) {
+    stats.elapsed += time.delta();
+}
+
+fn count_actions(
+    mut applied_rx: EventReader<AppliedCellUpdate>,
+    mut clue_rx: EventReader<ClueDeductionApplied>,
+    mut stats: ResMut<GameStats>,
+) {
+    for ev in applied_rx.read() {
+        stats.manual_actions += 1;
+        if ev.mistake {
+            stats.mistakes += 1;
+        }
+    }
+    stats.clue_deductions += clue_rx.read().count();
+}
+
+fn count_inference_steps(last_inference: Res<LastInferenceRun>, mut stats: ResMut<GameStats>) {
+    if last_inference.is_changed() {
+        stats.inference_runs = last_inference.total_runs;
+        stats.inference_steps = last_inference.total_steps;
+    }
+}
+
+fn record_solve(
+    stats: Res<GameStats>,
+    origin: Single<&PuzzleOrigin>,
+    mut recorded: ResMut<SolveRecorded>,
+) {
+    if recorded.0 {
+        return;
+    }
+    recorded.0 = true;
+    let record = SolveRecord::new(&stats, &origin.0, SystemTime::now());
+    let dir = Path::new(STATS_DIR);
+    match record.write_to(dir) {
+        Ok(path) => info!("wrote solve stats to {path:?}"),
+        Err(e) => warn!("couldn't write solve stats to {dir:?}: {e}"),
+    }
+}
+
+// Shares the bottom-left corner with toasts, below any that are showing.
+fn spawn_stats_hud(mut commands: Commands, container: Single<Entity, With<ToastContainer>>) {
+    let hud = commands
+        .spawn((
+            StatsHud,
+            Text::default(),
+            TextFont {
+                font_size: 14.,
+                ..Default::default()
+            },
+            NO_PICK,
+        ))
+        .id();
+    commands.entity(*container).insert_children(0, &[hud]);
+}
+
+fn despawn_stats_hud(mut commands: Commands, q_hud: Query<Entity, With<StatsHud>>) {
+    for entity in &q_hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_stats_hud(stats: Res<GameStats>, mut q_hud: Query<&mut Text, With<StatsHud>>) {
+    let text = stats.hud_text();
+    for mut hud in &mut q_hud {
+        if hud.0 != text {
+            hud.0.clone_from(&text);
+        }
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameStats>()
+            .init_resource::<SolveRecorded>()
+            .register_type::<GameStats>()
+            .register_type::<SolveRecorded>()
+            .register_type::<StatsHud>()
+            .add_systems(OnEnter(GameState::Playing), (reset_stats, spawn_stats_hud))
+            .add_systems(OnExit(GameState::Playing), despawn_stats_hud)
+            .add_systems(
+                Update,
+                (
+                    tick_stats.run_if(
+                        in_state(PuzzleOutcome::InProgress)
+                            .or(in_state(PuzzleOutcome::Contradicted)),
+                    ),
+                    (count_actions, count_inference_steps).run_if(in_state(GameState::Playing)),
+                    update_stats_hud,
+                ),
+            )
+            .add_systems(OnEnter(PuzzleOutcome::Solved), record_solve);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toast::ShowToast;
+
+    #[test]
+    fn hud_shows_minutes_and_seconds() {
+        let stats = GameStats {
+            elapsed: Duration::from_secs(125),
+            manual_actions: 7,
+            clue_deductions: 3,
+            mistakes: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            stats.hud_text(),
+            "2:05 | 7 moves | 3 from clues | 1 mistake"
+        );
+        let stats = GameStats {
+            inference_runs: 4,
+            inference_steps: 10,
+            ..stats
+        };
+        assert!(stats.hud_text().ends_with(" | 2.5 inference steps/move"));
+    }
+
+    #[test]
+    fn solves_are_recorded_with_the_puzzle_seed() {
+        let stats = GameStats {
+            manual_actions: 12,
+            ..Default::default()
+        };
+        let origin = PuzzleMetadata {
+            seed: Some(42),
+            ..Default::default()
+        };
+        let solved_at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let record = SolveRecord::new(&stats, &origin, solved_at);
+        assert_eq!(record.seed, Some(42));
+        assert_eq!(record.solved_at, 1_700_000_000);
+        assert_eq!(record.stats.manual_actions, 12);
+        // Puzzles that weren't generated here, like imported boards, have no seed to record.
+        let record = SolveRecord::new(&stats, &PuzzleMetadata::default(), solved_at);
+        assert_eq!(record.seed, None);
+    }
+
+    #[test]
+    fn toasts_stack_above_the_hud() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(crate::toast::ToastPlugin)
+            .add_systems(Update, spawn_stats_hud.run_if(run_once));
+        app.world_mut()
+            .send_event(ShowToast::info("shown before the HUD"));
+        app.update();
+        app.world_mut().send_event(ShowToast::info("shown after"));
+        app.update();
+        let container = app
+            .world_mut()
+            .query_filtered::<&Children, With<ToastContainer>>()
+            .single(app.world());
+        // The container stacks from the bottom, so its first child is the lowest.
+        assert_eq!(container.len(), 3);
+        assert!(app.world().get::<StatsHud>(container[0]).is_some());
+    }
+}
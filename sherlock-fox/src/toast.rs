@@ -0,0 +1,133 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{settings::SizedText, ThemedPanel, NO_PICK, PANEL_PADDING};
+
+#[derive(Debug, Clone, Copy, Reflect, PartialEq, Eq)]
+pub enum ToastSeverity {
+    Info,
+    Error,
+}
+
+impl ToastSeverity {
+    fn panel(&self) -> ThemedPanel {
+        match self {
+            ToastSeverity::Info => ThemedPanel::default(),
+            ToastSeverity::Error => ThemedPanel::colored(Color::hsla(0., 0.6, 0.35, 1.)),
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Reflect)]
+pub struct ShowToast {
+    pub message: String,
+    pub severity: ToastSeverity,
+}
+
+impl ShowToast {
+    pub fn info(message: impl Into<String>) -> Self {
+        ShowToast {
+            message: message.into(),
+            severity: ToastSeverity::Info,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        ShowToast {
+            message: message.into(),
+            severity: ToastSeverity::Error,
+        }
+    }
+}
+
+// Toasts stack upward from the bottom-left corner. A status line that lives in that corner goes in
+// here as the first child, so toasts stack above it instead of covering it.
+#[derive(Reflect, Debug, Component)]
+pub struct ToastContainer;
+
+#[derive(Reflect, Debug, Component)]
+struct Toast {
+    timer: Timer,
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+const TOAST_FADE: f32 = 0.5;
+
+fn spawn_toast_container(mut commands: Commands) {
+    commands.spawn((
+        ToastContainer,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.),
+            left: Val::Px(10.),
+            flex_direction: FlexDirection::ColumnReverse,
+            row_gap: Val::Px(5.),
+            ..Default::default()
+        },
+        NO_PICK,
+    ));
+}
+
+fn show_toasts(
+    mut ev_rx: EventReader<ShowToast>,
+    q_container: Single<Entity, With<ToastContainer>>,
+    mut commands: Commands,
+) {
+    for ev in ev_rx.read() {
+        match ev.severity {
+            ToastSeverity::Info => info!("toast: {}", ev.message),
+            ToastSeverity::Error => warn!("toast: {}", ev.message),
+        }
+        commands.entity(*q_container).with_child((
+            Toast {
+                timer: Timer::new(TOAST_DURATION, TimerMode::Once),
+            },
+            Text::new(ev.message.clone()),
+            SizedText,
+            Node {
+                padding: UiRect::all(PANEL_PADDING),
+                ..Default::default()
+            },
+            ev.severity.panel(),
+            NO_PICK,
+        ));
+    }
+}
+
+fn expire_toasts(
+    time: Res<Time>,
+    mut q_toast: Query<(Entity, &mut Toast, Option<&mut ImageNode>, &mut TextColor)>,
+    mut commands: Commands,
+) {
+    for (entity, mut toast, panel, mut text_color) in &mut q_toast {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = (toast.timer.remaining_secs() / TOAST_FADE).min(1.);
+        // The panel image is inserted by an observer, so it may not be there on the first frame.
+        if let Some(mut panel) = panel {
+            panel.color.set_alpha(alpha);
+        }
+        text_color.0.set_alpha(alpha);
+    }
+}
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowToast>()
+            .register_type::<ShowToast>()
+            .register_type::<Toast>()
+            .register_type::<ToastContainer>()
+            .add_systems(Startup, spawn_toast_container)
+            .add_systems(Update, (show_toasts, expire_toasts));
+    }
+}
@@ -0,0 +1,210 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use std::time::Duration;
+
+use bevy::{input::touch::Touches, picking::pointer::PointerId, prelude::*};
+
+use crate::{
+    buttons::TopButtonState,
+    fit::{FitClicked, FitClickedEvent},
+    modal::ModalStack,
+    puzzle::{CellLocIndex, UpdateCellIndexOperation},
+    DisplayCellButton, GameState, TopButtonAction, UpdateCellIndex,
+};
+
+// Fingers on candidates, in parallel with the mouse: a tap toggles, a long press opens the radial
+// menu, where dragging picks an operation just as it does with the mouse, and tapping with two
+// fingers undoes.
+const LONG_PRESS: Duration = Duration::from_millis(450);
+// How far, in logical pixels, a finger can wander and still be pressing in place.
+const TOUCH_SLOP: f32 = 12.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Press {
+    // Still down, and could yet be a tap or a long press.
+    Undecided,
+    Long,
+    // Moved too far; someone scrolling or swiping, not pressing.
+    Wandered,
+}
+
+fn classify_press(held: Duration, wandered: f32) -> Press {
+    if wandered > TOUCH_SLOP {
+        Press::Wandered
+    } else if held >= LONG_PRESS {
+        Press::Long
+    } else {
+        Press::Undecided
+    }
+}
+
+#[derive(Debug, Clone, Copy, Reflect)]
+struct CellTouch {
+    id: u64,
+    button: Entity,
+    index: CellLocIndex,
+    start: Vec2,
+    position: Vec2,
+    held: Duration,
+    menu_open: bool,
+}
+
+#[derive(Debug, Clone, Copy, Reflect)]
+struct TwoFingerTap {
+    held: Duration,
+    wandered: f32,
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct TouchGesture {
+    cell: Option<CellTouch>,
+    two_finger: Option<TwoFingerTap>,
+}
+
+impl TouchGesture {
+    // Where the finger that long-pressed `button` is, so its menu opens under the finger rather
+    // than wherever the mouse was left.
+    pub fn menu_position(&self, button: Entity) -> Option<Vec2> {
+        self.cell
+            .filter(|touch| touch.button == button && touch.menu_open)
+            .map(|touch| touch.position)
+    }
+}
+
+fn cell_touched(
+    ev: Trigger<Pointer<Down>>,
+    q_button: Query<&DisplayCellButton>,
+    mut gesture: ResMut<TouchGesture>,
+    modals: Res<ModalStack>,
+) {
+    let PointerId::Touch(id) = ev.pointer_id else {
+        return;
+    };
+    if !modals.is_empty() {
+        return;
+    }
+    let Ok(button) = q_button.get(ev.entity()) else {
+        return;
+    };
+    if gesture.cell.is_some() || gesture.two_finger.is_some() {
+        return;
+    }
+    let position = ev.pointer_location.position;
+    gesture.cell = Some(CellTouch {
+        id,
+        button: ev.entity(),
+        index: button.index,
+        start: position,
+        position,
+        held: Duration::ZERO,
+        menu_open: false,
+    });
+}
+
+fn track_touch_gesture(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut gesture: ResMut<TouchGesture>,
+    button_state: Res<TopButtonState>,
+    mut clicked_tx: EventWriter<FitClickedEvent<TopButtonAction>>,
+    mut writer: EventWriter<UpdateCellIndex>,
+    mut commands: Commands,
+) {
+    let menu_open = gesture.cell.is_some_and(|touch| touch.menu_open);
+    if touches.iter().count() >= 2 && gesture.two_finger.is_none() && !menu_open {
+        // A second finger turns whatever the first was doing into a two-finger tap.
+        gesture.cell = None;
+        gesture.two_finger = Some(TwoFingerTap {
+            held: Duration::ZERO,
+            wandered: 0.,
+        });
+    }
+
+    if let Some(tap) = &mut gesture.two_finger {
+        tap.held += time.delta();
+        for finger in touches.iter() {
+            tap.wandered = tap.wandered.max(finger.distance().length());
+        }
+        if touches.iter().next().is_none() {
+            let undo = TopButtonAction::Undo;
+            if classify_press(tap.held, tap.wandered) == Press::Undecided
+                && button_state.is_enabled(undo)
+            {
+                clicked_tx.send(FitClickedEvent(undo));
+            }
+            gesture.two_finger = None;
+        }
+        return;
+    }
+
+    let Some(touch) = &mut gesture.cell else {
+        return;
+    };
+    if let Some(finger) = touches.get_pressed(touch.id) {
+        touch.position = finger.position();
+        touch.held += time.delta();
+        if touch.menu_open {
+            // The drag itself is followed through the pointer's moves, as the mouse's is.
+            return;
+        }
+        match classify_press(touch.held, touch.start.distance(touch.position)) {
+            Press::Undecided => (),
+            Press::Long => {
+                touch.menu_open = true;
+                commands.entity(touch.button).insert(FitClicked);
+            }
+            Press::Wandered => gesture.cell = None,
+        }
+        return;
+    }
+
+    // The finger lifted, or the touch was canceled.
+    let Some(touch) = gesture.cell.take() else {
+        return;
+    };
+    if touch.menu_open {
+        // Releasing the drag applies whichever operation it was over.
+        commands.entity(touch.button).remove::<FitClicked>();
+    } else if touches.just_released(touch.id) {
+        writer.send(UpdateCellIndex::manual(
+            touch.index,
+            UpdateCellIndexOperation::Toggle,
+        ));
+    }
+}
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchGesture>()
+            .register_type::<TouchGesture>()
+            .add_observer(cell_touched)
+            .add_systems(
+                Update,
+                track_touch_gesture.run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presses_are_told_apart_by_time_and_distance() {
+        let short = LONG_PRESS / 3;
+        assert_eq!(classify_press(short, 0.), Press::Undecided);
+        assert_eq!(classify_press(short, TOUCH_SLOP / 2.), Press::Undecided);
+        assert_eq!(classify_press(LONG_PRESS, 0.), Press::Long);
+        // Wandering off wins even after the finger has been down long enough.
+        assert_eq!(classify_press(short, TOUCH_SLOP * 2.), Press::Wandered);
+        assert_eq!(
+            classify_press(LONG_PRESS * 2, TOUCH_SLOP * 2.),
+            Press::Wandered
+        );
+    }
+}
@@ -2,13 +2,15 @@
 //
 // SPDX-License-Identifier: EUPL-1.2
 
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashMap};
 use petgraph::{graph::NodeIndex, visit::EdgeRef, Direction, Graph};
 
 use crate::{
-    fit::FitClickedEvent,
-    puzzle::{CellLoc, Puzzle},
-    TopButtonAction, UpdateCellDisplay, UpdateCellIndex,
+    buttons::TopButtonState, fit::FitClickedEvent, modal::no_modal, notes::editing_notes,
+    operations::OperationTheme, puzzle::Puzzle, DisplayCellButton, GameState, TopButtonAction,
+    UpdateCellDisplay, UpdateCellIndex, NO_PICK,
 };
 
 #[derive(Debug, Event, Reflect)]
@@ -22,6 +24,8 @@ pub struct Action {
     pub update: UpdateCellIndex,
     pub update_count: usize,
     pub inferred_count: usize,
+    // The rest of a gesture's updates after `update`, in the order they were applied.
+    pub joined: Vec<UpdateCellIndex>,
 }
 
 #[derive(Debug, Component, Reflect)]
@@ -29,6 +33,10 @@ pub struct UndoTree {
     #[reflect(ignore)]
     pub tree: Graph<Puzzle, Action>,
     pub root: NodeIndex,
+    // Where play starts, which restart goes back to and undo stops at. It's the root until the
+    // starting reveals, sent as `reveal_gesture`, come in as an action of their own.
+    pub start: NodeIndex,
+    pub reveal_gesture: Option<u32>,
 }
 
 #[derive(Debug, Component, Reflect)]
@@ -36,6 +44,99 @@ pub struct UndoTreeLocation {
     pub current: NodeIndex,
 }
 
+impl UndoTree {
+    pub fn new(root_state: Puzzle) -> (UndoTree, UndoTreeLocation) {
+        let mut tree = Graph::new();
+        let root = tree.add_node(root_state);
+        let tree = UndoTree {
+            tree,
+            root,
+            start: root,
+            reveal_gesture: None,
+        };
+        (tree, UndoTreeLocation { current: root })
+    }
+
+    pub fn push(&mut self, location: &mut UndoTreeLocation, new_state: Puzzle, action: Action) {
+        let new_node = self.tree.add_node(new_state);
+        self.tree.add_edge(new_node, location.current, action);
+        location.current = new_node;
+    }
+
+    // Folds an update into the action that led to the current state, as long as both came from the
+    // same gesture; the first update of a gesture to get through is pushed as usual.
+    pub fn push_or_join(
+        &mut self,
+        location: &mut UndoTreeLocation,
+        new_state: Puzzle,
+        action: Action,
+    ) {
+        let joined = action.update.gesture.and_then(|_| {
+            self.tree
+                .edges_directed(location.current, Direction::Outgoing)
+                .next()
+                .filter(|edge| edge.weight().update.gesture == action.update.gesture)
+                .map(|edge| edge.id())
+        });
+        let gesture = action.update.gesture;
+        if let Some(edge) = joined {
+            let folded = &mut self.tree[edge];
+            folded.update_count += action.update_count;
+            folded.inferred_count += action.inferred_count;
+            folded.joined.push(action.update);
+            folded.joined.extend(action.joined);
+            self.tree[location.current] = new_state;
+        } else {
+            self.push(location, new_state, action);
+        }
+        if gesture.is_some() && gesture == self.reveal_gesture {
+            self.start = location.current;
+        }
+    }
+
+    pub fn undo_target(&self, current: NodeIndex) -> Option<NodeIndex> {
+        self.tree
+            .edges_directed(current, Direction::Outgoing)
+            .next()
+            .map(|undo| undo.target())
+    }
+
+    // Every state from the root up to and including `node`, oldest first.
+    pub fn path_to(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let mut path = vec![node];
+        while let Some(undo) = self.undo_target(*path.last().unwrap()) {
+            path.push(undo);
+        }
+        path.reverse();
+        path
+    }
+
+    // The actions that led from the root to `node`, oldest first.
+    pub fn actions_to(&self, node: NodeIndex) -> Vec<&Action> {
+        let mut actions = vec![];
+        let mut current = node;
+        while let Some(undo) = self
+            .tree
+            .edges_directed(current, Direction::Outgoing)
+            .next()
+        {
+            actions.push(undo.weight());
+            current = undo.target();
+        }
+        actions.reverse();
+        actions
+    }
+
+    // Redo only follows the history when it doesn't branch.
+    pub fn redo_target(&self, current: NodeIndex) -> Option<NodeIndex> {
+        let mut redos = self.tree.edges_directed(current, Direction::Incoming);
+        match (redos.next(), redos.next()) {
+            (Some(redo), None) => Some(redo.source()),
+            _ => None,
+        }
+    }
+}
+
 fn add_undo_state(
     mut ev_rx: EventReader<PushNewAction>,
     mut q_tree: Query<&mut UndoTree>,
@@ -53,10 +154,7 @@ fn add_undo_state(
             tree.tree.node_count(),
             tree.tree.edge_count()
         );
-        let new_node = tree.tree.add_node(ev.new_state.clone());
-        tree.tree
-            .add_edge(new_node, tree_loc.current, ev.action.clone());
-        tree_loc.current = new_node;
+        tree.push_or_join(&mut tree_loc, ev.new_state.clone(), ev.action.clone());
         info!(
             "tree out: {tree_loc:?} nodes={} edges={}",
             tree.tree.node_count(),
@@ -85,51 +183,610 @@ fn adjust_undo_state(
         use TopButtonAction as B;
         let new_node = match action {
             B::Undo => {
-                let Some(undo) = tree
-                    .tree
-                    .edges_directed(tree_loc.current, Direction::Outgoing)
-                    .next()
-                else {
+                let undo = (tree_loc.current != tree.start)
+                    .then(|| tree.undo_target(tree_loc.current))
+                    .flatten();
+                let Some(undo) = undo else {
                     warn!("nothing to undo");
                     continue;
                 };
-                info!("on undo: {undo:#?}");
-                undo.target()
+                info!("on undo: {:?} -> {undo:?}", tree_loc.current);
+                undo
             }
             B::Redo => {
-                let redos = tree
-                    .tree
-                    .edges_directed(tree_loc.current, Direction::Incoming)
-                    .take(2)
-                    .collect::<Vec<_>>();
-                if redos.len() != 1 {
-                    warn!("couldn't redo from {redos:#?}");
+                let Some(redo) = tree.redo_target(tree_loc.current) else {
+                    warn!("couldn't redo from {:?}", tree_loc.current);
                     continue;
-                }
-                info!("on redo: {redos:#?}");
-                redos[0].source()
+                };
+                info!("on redo: {:?} -> {redo:?}", tree_loc.current);
+                redo
             }
+            B::Restart => tree.start,
             _ => continue,
         };
-        let Some(new_state) = tree.tree.node_weight(new_node) else {
-            unreachable!()
+        move_to_state(
+            tree,
+            &mut tree_loc,
+            new_node,
+            &mut puzzle,
+            &mut update_display_tx,
+        );
+    }
+}
+
+// Only the cells that differ are redrawn, so stepping through history doesn't restart every
+// cell's fade.
+pub fn move_to_state(
+    tree: &UndoTree,
+    tree_loc: &mut UndoTreeLocation,
+    node: NodeIndex,
+    puzzle: &mut Puzzle,
+    update_display_tx: &mut EventWriter<UpdateCellDisplay>,
+) {
+    let Some(new_state) = tree.tree.node_weight(node) else {
+        unreachable!()
+    };
+    tree_loc.current = node;
+    let changed = puzzle.changed_cells(new_state);
+    puzzle.clone_from(new_state);
+    update_display_tx.send_batch(changed.into_iter().map(|loc| UpdateCellDisplay { loc }));
+}
+
+// Holding a shortcut fires it once, then again at a steady rate after a pause, like key repeat in
+// a text field, so holding undo steps back through history slowly enough to watch.
+const SHORTCUT_REPEAT_DELAY: Duration = Duration::from_millis(400);
+const SHORTCUT_REPEAT_INTERVAL: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Default)]
+struct ShortcutRepeat {
+    held: Option<TopButtonAction>,
+    timer: Timer,
+}
+
+impl ShortcutRepeat {
+    // Which action to fire this frame, given the one whose shortcut is down, if any.
+    fn update(
+        &mut self,
+        pressed: Option<TopButtonAction>,
+        delta: Duration,
+    ) -> Option<TopButtonAction> {
+        if pressed != self.held {
+            self.held = pressed;
+            self.timer = Timer::new(SHORTCUT_REPEAT_DELAY, TimerMode::Once);
+            return pressed;
+        }
+        self.held?;
+        self.timer.tick(delta);
+        if !self.timer.finished() {
+            return None;
+        }
+        self.timer = Timer::new(SHORTCUT_REPEAT_INTERVAL, TimerMode::Once);
+        self.held
+    }
+}
+
+fn read_undo_shortcuts(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut repeat: Local<ShortcutRepeat>,
+    button_state: Res<TopButtonState>,
+    mut clicked_tx: EventWriter<FitClickedEvent<TopButtonAction>>,
+) {
+    // Command on macOS, Control everywhere else.
+    let modifier = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let pressed = (modifier && keys.pressed(KeyCode::KeyZ)).then_some(if shift {
+        TopButtonAction::Redo
+    } else {
+        TopButtonAction::Undo
+    });
+    if let Some(action) = repeat.update(pressed, time.delta()) {
+        if button_state.is_enabled(action) {
+            clicked_tx.send(FitClickedEvent(action));
+        }
+    }
+}
+
+#[derive(Debug, Default, Resource, Reflect)]
+#[reflect(Resource)]
+struct UndoHistory {
+    shown: bool,
+    // At most two nodes; the second is diffed against the first.
+    selected: Vec<NodeIndex>,
+}
+
+#[derive(Debug, Component, Reflect)]
+struct DisplayUndoTree;
+
+#[derive(Debug, Component, Reflect)]
+struct UndoHistoryNode(NodeIndex);
+
+#[derive(Debug, Component, Reflect)]
+struct UndoDiffMarker;
+
+const HISTORY_BACKGROUND_COLOR: Color = Color::hsla(0., 0., 0.1, 0.85);
+const HISTORY_CURRENT_COLOR: Color = Color::hsla(0., 0., 0.3, 1.);
+const HISTORY_SELECTED_COLORS: [Color; 2] = [
+    Color::hsla(210., 0.6, 0.35, 1.),
+    Color::hsla(33., 0.8, 0.35, 1.),
+];
+const DIFF_ADDED_COLOR: Color = Color::hsla(120., 0.8, 0.5, 0.5);
+const DIFF_REMOVED_COLOR: Color = Color::hsla(0., 0.8, 0.5, 0.5);
+
+fn toggle_undo_history(
+    mut ev_rx: EventReader<FitClickedEvent<TopButtonAction>>,
+    mut history: ResMut<UndoHistory>,
+) {
+    for &FitClickedEvent(action) in ev_rx.read() {
+        if let TopButtonAction::History = action {
+            history.shown = !history.shown;
+            history.selected.clear();
+        }
+    }
+}
+
+fn action_label(action: &Action, theme: &OperationTheme) -> String {
+    let UpdateCellIndex { index, op, .. } = &action.update;
+    format!("{} {index}", theme.label(*op))
+}
+
+// One line per state, depth first with the oldest branch first. A run of states without branches
+// stays in one column; where the history branches, each branch is drawn hanging off the state it
+// branched from. Each line is colored after the operation that led to its state, if any.
+fn undo_tree_rows(tree: &UndoTree, theme: &OperationTheme) -> Vec<(NodeIndex, String, Color)> {
+    let mut rows = vec![];
+    // Each state's line starts with `lead`, and the lines under it with `indent`.
+    let mut stack = vec![(tree.root, String::new(), String::new())];
+    while let Some((node, lead, indent)) = stack.pop() {
+        // Edges point from newer states to older ones, so a node's children are the sources of
+        // its incoming edges.
+        let mut children = tree
+            .tree
+            .edges_directed(node, Direction::Incoming)
+            .map(|edge| edge.source())
+            .collect::<Vec<_>>();
+        children.sort();
+        let (label, color) = match tree.tree.edges_directed(node, Direction::Outgoing).next() {
+            Some(edge) => (
+                action_label(edge.weight(), theme),
+                theme.color(edge.weight().update.op),
+            ),
+            None => ("Start".to_string(), Color::WHITE),
+        };
+        let label = match children.len() {
+            0 | 1 => label,
+            n => format!("{label} ({n} branches)"),
         };
-        tree_loc.current = new_node;
-        puzzle.clone_from(new_state);
-        for row in puzzle.iter_rows() {
-            for col in puzzle.iter_cols() {
-                update_display_tx.send(UpdateCellDisplay {
-                    loc: CellLoc { row, col },
-                });
+        rows.push((node, format!("{lead}{label}"), color));
+        if let [child] = children[..] {
+            stack.push((child, indent.clone(), indent));
+            continue;
+        }
+        let last = children.len().saturating_sub(1);
+        for (nr, child) in children.into_iter().enumerate().rev() {
+            let (edge, under) = if nr == last {
+                ("`- ", "   ")
+            } else {
+                ("|- ", "|  ")
+            };
+            stack.push((child, format!("{indent}{edge}"), format!("{indent}{under}")));
+        }
+    }
+    rows
+}
+
+fn rebuild_undo_history(
+    mut commands: Commands,
+    history: Res<UndoHistory>,
+    theme: Res<OperationTheme>,
+    q_tree: Query<Ref<UndoTree>>,
+    q_tree_loc: Query<Ref<UndoTreeLocation>>,
+    q_panel: Query<Entity, With<DisplayUndoTree>>,
+) {
+    let tree = q_tree.get_single().ok();
+    let tree_loc = q_tree_loc.get_single().ok();
+    let tree_changed = tree.as_ref().is_some_and(|t| t.is_changed())
+        || tree_loc.as_ref().is_some_and(|l| l.is_changed());
+    if !history.is_changed() && !theme.is_changed() && !tree_changed {
+        return;
+    }
+    for entity in &q_panel {
+        commands.entity(entity).despawn_recursive();
+    }
+    let (true, Some(tree), Some(tree_loc)) = (history.shown, tree, tree_loc) else {
+        return;
+    };
+
+    commands
+        .spawn((
+            DisplayUndoTree,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                max_height: Val::Percent(90.),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(2.),
+                padding: UiRect::all(Val::Px(5.)),
+                overflow: Overflow::clip_y(),
+                ..Default::default()
+            },
+            BackgroundColor(HISTORY_BACKGROUND_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Click a state to go back to it, or right-click two to compare"),
+                NO_PICK,
+            ));
+            for (node, label, color) in undo_tree_rows(&tree, &theme) {
+                let background = match history.selected.iter().position(|&s| s == node) {
+                    Some(n) => HISTORY_SELECTED_COLORS[n],
+                    None if node == tree_loc.current => HISTORY_CURRENT_COLOR,
+                    None => Color::NONE,
+                };
+                parent
+                    .spawn((
+                        UndoHistoryNode(node),
+                        Node {
+                            padding: UiRect::axes(Val::Px(5.), Val::Px(2.)),
+                            ..Default::default()
+                        },
+                        BackgroundColor(background),
+                    ))
+                    .with_child((
+                        Text::new(label),
+                        TextFont {
+                            font_size: 14.,
+                            ..Default::default()
+                        },
+                        TextColor(color),
+                        NO_PICK,
+                    ));
             }
+        });
+}
+
+fn undo_history_node_clicked(
+    ev: Trigger<Pointer<Click>>,
+    q_node: Query<&UndoHistoryNode>,
+    mut history: ResMut<UndoHistory>,
+    mut q_puzzle: Query<&mut Puzzle>,
+    q_tree: Query<&UndoTree>,
+    mut q_tree_loc: Query<&mut UndoTreeLocation>,
+    mut update_display_tx: EventWriter<UpdateCellDisplay>,
+) {
+    let Ok(&UndoHistoryNode(node)) = q_node.get(ev.entity()) else {
+        return;
+    };
+    if ev.button == PointerButton::Primary {
+        let (Ok(mut puzzle), Ok(tree), Ok(mut tree_loc)) = (
+            q_puzzle.get_single_mut(),
+            q_tree.get_single(),
+            q_tree_loc.get_single_mut(),
+        ) else {
+            return;
+        };
+        if tree_loc.current != node {
+            info!("on jump: {:?} -> {node:?}", tree_loc.current);
+            move_to_state(
+                tree,
+                &mut tree_loc,
+                node,
+                &mut puzzle,
+                &mut update_display_tx,
+            );
         }
+        return;
+    }
+    if let Some(n) = history.selected.iter().position(|&s| s == node) {
+        history.selected.remove(n);
+    } else {
+        if history.selected.len() == 2 {
+            history.selected.remove(0);
+        }
+        history.selected.push(node);
+    }
+}
+
+fn show_undo_diff(
+    mut commands: Commands,
+    history: Res<UndoHistory>,
+    q_tree: Query<&UndoTree>,
+    q_marker: Query<Entity, With<UndoDiffMarker>>,
+    q_button: Query<(Entity, &DisplayCellButton)>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+    for entity in &q_marker {
+        commands.entity(entity).despawn_recursive();
+    }
+    let &[base, compared] = history.selected.as_slice() else {
+        return;
+    };
+    let Ok(tree) = q_tree.get_single() else {
+        return;
+    };
+    let (Some(base), Some(compared)) =
+        (tree.tree.node_weight(base), tree.tree.node_weight(compared))
+    else {
+        return;
+    };
+    let diff = base.candidate_diff(compared);
+    let colors = diff
+        .added
+        .into_iter()
+        .map(|index| (index, DIFF_ADDED_COLOR))
+        .chain(
+            diff.removed
+                .into_iter()
+                .map(|index| (index, DIFF_REMOVED_COLOR)),
+        )
+        .collect::<HashMap<_, _>>();
+    for (entity, button) in &q_button {
+        let Some(&color) = colors.get(&button.index) else {
+            continue;
+        };
+        commands.entity(entity).with_child((
+            UndoDiffMarker,
+            Sprite::from_color(color, Vec2::new(32., 32.)),
+            Transform::from_xyz(0., 0., 2.),
+            NO_PICK,
+        ));
     }
 }
 
+fn close_undo_history(mut history: ResMut<UndoHistory>) {
+    *history = UndoHistory::default();
+}
+
 pub struct UndoPlugin;
 
 impl Plugin for UndoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (add_undo_state, adjust_undo_state));
+        app.init_resource::<UndoHistory>()
+            .register_type::<UndoDiffMarker>()
+            .register_type::<UndoHistory>()
+            .register_type::<UndoHistoryNode>()
+            .register_type::<DisplayUndoTree>()
+            .add_observer(undo_history_node_clicked)
+            .add_systems(OnExit(GameState::Playing), close_undo_history)
+            .add_systems(
+                Update,
+                (
+                    read_undo_shortcuts
+                        .run_if(not(editing_notes))
+                        .run_if(no_modal)
+                        .run_if(in_state(GameState::Playing))
+                        .before(adjust_undo_state),
+                    add_undo_state,
+                    adjust_undo_state,
+                    toggle_undo_history,
+                    (rebuild_undo_history, show_undo_diff).after(toggle_undo_history),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{test_puzzle, CellLoc, CellLocIndex, LCol, LInd, LRow};
+
+    fn push(tree: &mut UndoTree, location: &mut UndoTreeLocation, col: isize) {
+        let index = CellLocIndex {
+            loc: CellLoc {
+                row: LRow(0),
+                col: LCol(col),
+            },
+            index: LInd(0),
+        };
+        tree.push(location, test_puzzle(2, 4), Action {
+            update: index.as_clear(),
+            update_count: 1,
+            inferred_count: 0,
+            joined: vec![],
+        });
+    }
+
+    #[test]
+    fn held_shortcuts_repeat_after_a_pause() {
+        let mut repeat = ShortcutRepeat::default();
+        let frame = Duration::from_millis(50);
+        let undo = Some(TopButtonAction::Undo);
+        assert_eq!(repeat.update(None, frame), None);
+        assert_eq!(repeat.update(undo, frame), undo);
+        // Nothing more until the delay is up, then once per interval.
+        let fired = (0..20)
+            .map(|_| repeat.update(undo, frame))
+            .collect::<Vec<_>>();
+        assert_eq!(fired.iter().flatten().count(), 5);
+        assert_eq!(fired[..7], [None; 7]);
+        assert_eq!(fired[7], undo);
+        // Switching to redo fires it straight away.
+        let redo = Some(TopButtonAction::Redo);
+        assert_eq!(repeat.update(redo, frame), redo);
+        assert_eq!(repeat.update(redo, frame), None);
+        assert_eq!(repeat.update(None, frame), None);
+    }
+
+    #[test]
+    fn branches_are_drawn_off_the_state_they_left() {
+        let (mut tree, mut location) = UndoTree::new(test_puzzle(2, 4));
+        push(&mut tree, &mut location, 0);
+        let branch_point = location.current;
+        push(&mut tree, &mut location, 1);
+        push(&mut tree, &mut location, 2);
+        location.current = branch_point;
+        push(&mut tree, &mut location, 3);
+        assert_eq!(tree.redo_target(branch_point), None);
+
+        let theme = OperationTheme::default();
+        let rows = undo_tree_rows(&tree, &theme);
+        let labels = rows.iter().map(|(_, label, _)| label).collect::<Vec<_>>();
+        assert_eq!(labels, vec![
+            "Start",
+            "x Clear item 0 at row 0 column 0 (2 branches)",
+            "|- x Clear item 0 at row 0 column 1",
+            "|  x Clear item 0 at row 0 column 2",
+            "`- x Clear item 0 at row 0 column 3",
+        ]);
+        assert!(rows[1..]
+            .iter()
+            .all(|&(_, _, color)| color == theme.clear.color));
+    }
+
+    #[test]
+    fn paths_run_from_the_root() {
+        let (mut tree, mut location) = UndoTree::new(test_puzzle(2, 4));
+        assert_eq!(tree.path_to(tree.root), vec![tree.root]);
+        push(&mut tree, &mut location, 0);
+        let branch_point = location.current;
+        push(&mut tree, &mut location, 1);
+        let first_branch = location.current;
+        location.current = branch_point;
+        push(&mut tree, &mut location, 2);
+        push(&mut tree, &mut location, 3);
+        let second_branch = location.current;
+        assert_eq!(tree.path_to(first_branch), vec![
+            tree.root,
+            branch_point,
+            first_branch
+        ]);
+        let path = tree.path_to(second_branch);
+        assert_eq!(path.len(), 4);
+        assert_eq!(path[..2], [tree.root, branch_point]);
+        assert_eq!(path[3], second_branch);
+        assert!(!path.contains(&first_branch));
+    }
+
+    #[test]
+    fn a_gesture_undoes_as_one_action() {
+        let (mut tree, mut location) = UndoTree::new(test_puzzle(2, 4));
+        push(&mut tree, &mut location, 0);
+        let before = location.current;
+        let sweep = |col| Action {
+            update: CellLocIndex {
+                loc: CellLoc {
+                    row: LRow(1),
+                    col: LCol(col),
+                },
+                index: LInd(0),
+            }
+            .as_clear()
+            .in_gesture(7),
+            update_count: 1,
+            inferred_count: col as usize,
+            joined: vec![],
+        };
+        for col in 0..3 {
+            tree.push_or_join(&mut location, test_puzzle(2, 4), sweep(col));
+        }
+        assert_eq!(tree.tree.node_count(), 3);
+        assert_eq!(tree.undo_target(location.current), Some(before));
+        let edge = tree.tree.edges(location.current).next().unwrap();
+        assert_eq!(edge.weight().update_count, 3);
+        assert_eq!(edge.weight().inferred_count, 3);
+        // The action keeps every update, so the gesture can be replayed.
+        let cols = [&edge.weight().update]
+            .into_iter()
+            .chain(&edge.weight().joined)
+            .map(|update| update.index.loc.col.0)
+            .collect::<Vec<_>>();
+        assert_eq!(cols, [0, 1, 2]);
+
+        // Another gesture starts its own action.
+        let mut next = sweep(3);
+        next.update.gesture = Some(8);
+        tree.push_or_join(&mut location, test_puzzle(2, 4), next);
+        assert_eq!(tree.tree.node_count(), 4);
+    }
+
+    #[test]
+    fn restart_and_undo_keep_the_starting_reveals() {
+        use TopButtonAction as B;
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<PushNewAction>()
+            .add_event::<FitClickedEvent<TopButtonAction>>()
+            .add_event::<UpdateCellDisplay>()
+            .add_systems(Update, (add_undo_state, adjust_undo_state).chain());
+        let puzzle = test_puzzle(2, 4);
+        let (mut tree, location) = UndoTree::new(puzzle.clone());
+        tree.reveal_gesture = Some(1);
+        app.world_mut().spawn(tree);
+        app.world_mut().spawn(location);
+
+        // The reveals come in as one gesture after the tree is spawned, then the player moves.
+        let mut revealed = puzzle.clone();
+        let mut to_update = bevy::utils::HashSet::new();
+        let mut play = |puzzle: &mut Puzzle, update: UpdateCellIndex| {
+            let (update_count, inferred_count) = puzzle
+                .apply_update(&update, &mut to_update)
+                .unwrap()
+                .unwrap();
+            PushNewAction {
+                new_state: puzzle.clone(),
+                action: Action {
+                    update,
+                    update_count,
+                    inferred_count,
+                    joined: vec![],
+                },
+            }
+        };
+        let reveals = [0, 1].map(|col| {
+            let loc = CellLoc {
+                row: LRow(0),
+                col: LCol(col),
+            };
+            let reveal = revealed.answer_at(loc).decay_to_ind();
+            play(&mut revealed, reveal.as_solo().in_gesture(1))
+        });
+        let mut played = revealed.clone();
+        let answer = played
+            .answer_at(CellLoc {
+                row: LRow(1),
+                col: LCol(3),
+            })
+            .decay_to_ind();
+        let moved = play(&mut played, answer.as_solo());
+        // Updates are applied to the board before they're pushed.
+        app.world_mut().spawn(played.clone());
+        app.world_mut().send_event_batch(reveals);
+        app.world_mut().send_event(moved);
+        app.update();
+
+        let tree = app.world_mut().query::<&UndoTree>().single(app.world());
+        assert_ne!(tree.start, tree.root);
+        assert_eq!(tree.undo_target(tree.start), Some(tree.root));
+        let start = tree.start;
+        let current = |app: &mut App| {
+            app.world_mut()
+                .query::<&UndoTreeLocation>()
+                .single(app.world())
+                .current
+        };
+        let board = |app: &mut App| {
+            app.world_mut()
+                .query::<&Puzzle>()
+                .single(app.world())
+                .clone()
+        };
+        assert_ne!(current(&mut app), start);
+        assert!(board(&mut app).changed_cells(&played).is_empty());
+
+        // Restart goes back to just after the reveals, and undo doesn't go any further.
+        for action in [B::Restart, B::Undo] {
+            app.world_mut().send_event(FitClickedEvent(action));
+            app.update();
+            assert_eq!(current(&mut app), start);
+            assert!(board(&mut app).changed_cells(&revealed).is_empty());
+        }
     }
 }
@@ -0,0 +1,206 @@
+// © 2025 <_@habnab.it>
+//
+// SPDX-License-Identifier: EUPL-1.2
+
+use bevy::{
+    animation::{animated_field, RepeatAnimation},
+    input::mouse::MouseWheel,
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    animation::AnimatorPlugin,
+    camera::{BoardCamera, BOARD_SIZE},
+    cursor_to_world,
+    fit::{wheel_steps, FitTransformAnimationBundle, FitTransformEdge},
+    modal::no_modal,
+    notes::editing_notes,
+    DisplayPuzzle, GameState,
+};
+
+// Holding either of these turns the wheel from scrolling the cluebox into zooming the board.
+pub const ZOOM_MODIFIERS: [KeyCode; 2] = [KeyCode::ControlLeft, KeyCode::ControlRight];
+
+const ZOOM_IN_KEYS: [KeyCode; 2] = [KeyCode::Equal, KeyCode::NumpadAdd];
+const ZOOM_OUT_KEYS: [KeyCode; 2] = [KeyCode::Minus, KeyCode::NumpadSubtract];
+
+const MIN_ZOOM: f32 = 1.;
+const MAX_ZOOM: f32 = 3.;
+const ZOOM_STEP: f32 = 1.25;
+
+// How far the whole layout is scaled up, and where to. The layout itself is still fit to the
+// board; zooming scales its root, so everything in it, text and borders included, grows together.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct BoardZoom {
+    level: f32,
+    offset: Vec2,
+}
+
+impl Default for BoardZoom {
+    fn default() -> Self {
+        BoardZoom {
+            level: MIN_ZOOM,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl BoardZoom {
+    // Zooms by `factor`, keeping whatever's at `focus`, in world space, where it is.
+    fn zoom_about(&mut self, factor: f32, focus: Vec2) {
+        let level = (self.level * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let local = (focus - self.offset) / self.level;
+        self.offset = focus - local * level;
+        self.level = level;
+        // The zoomed layout always covers the whole board, so there's no empty space to pan into.
+        let slack = BOARD_SIZE / 2. * (level - 1.);
+        self.offset = self.offset.clamp(-slack, slack);
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::from_translation(self.offset.extend(0.))
+            .with_scale(Vec2::splat(self.level).extend(1.))
+    }
+}
+
+fn make_puzzle_zoomable(
+    ev: Trigger<OnAdd, DisplayPuzzle>,
+    mut animation_graphs: ResMut<Assets<AnimationGraph>>,
+    mut commands: Commands,
+) {
+    commands.entity(ev.entity()).insert((
+        AnimationPlayer::default(),
+        AnimationGraphHandle(animation_graphs.add(AnimationGraph::new())),
+        FitTransformAnimationBundle::new(ev.entity()),
+    ));
+}
+
+fn zoom_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel_rx: EventReader<MouseWheel>,
+    mut pixels: Local<f32>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    q_camera: Single<(&Camera, &GlobalTransform), With<BoardCamera>>,
+    mut zoom: ResMut<BoardZoom>,
+) {
+    let (camera, camera_transform) = *q_camera;
+    // Keys zoom about the middle of the view, and the wheel about the cursor.
+    let view_center = camera_transform.translation().truncate();
+    if keys.any_just_pressed(ZOOM_IN_KEYS) {
+        zoom.zoom_about(ZOOM_STEP, view_center);
+    }
+    if keys.any_just_pressed(ZOOM_OUT_KEYS) {
+        zoom.zoom_about(ZOOM_STEP.recip(), view_center);
+    }
+    if !keys.any_pressed(ZOOM_MODIFIERS) {
+        wheel_rx.clear();
+        return;
+    }
+    let focus = window
+        .cursor_position()
+        .and_then(|cursor| cursor_to_world(camera, camera_transform, cursor))
+        .unwrap_or(view_center);
+    for wheel in wheel_rx.read() {
+        let steps = wheel_steps(wheel, &mut pixels);
+        if steps != 0 {
+            zoom.zoom_about(ZOOM_STEP.powi(steps as i32), focus);
+        }
+    }
+}
+
+fn apply_board_zoom(
+    zoom: Res<BoardZoom>,
+    q_puzzle: Query<Entity, (With<DisplayPuzzle>, With<FitTransformEdge>)>,
+    mut commands: Commands,
+) {
+    if !zoom.is_changed() {
+        return;
+    }
+    let target = zoom.transform();
+    for entity in &q_puzzle {
+        AnimatorPlugin::<FitTransformEdge>::start_animation(
+            &mut commands,
+            entity,
+            RepeatAnimation::Never,
+            move |transform, animation_target| {
+                let mut clip = AnimationClip::default();
+                let duration = interval(0., 0.25).unwrap();
+                clip.add_curve_to_target(
+                    animation_target,
+                    AnimatableCurve::new(
+                        animated_field!(Transform::translation),
+                        EasingCurve::new(
+                            transform.translation,
+                            target.translation,
+                            EaseFunction::CubicOut,
+                        )
+                        .reparametrize_linear(duration)
+                        .unwrap(),
+                    ),
+                );
+                clip.add_curve_to_target(
+                    animation_target,
+                    AnimatableCurve::new(
+                        animated_field!(Transform::scale),
+                        EasingCurve::new(transform.scale, target.scale, EaseFunction::CubicOut)
+                            .reparametrize_linear(duration)
+                            .unwrap(),
+                    ),
+                );
+                clip
+            },
+        );
+    }
+}
+
+pub struct ZoomPlugin;
+
+impl Plugin for ZoomPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoardZoom>()
+            .register_type::<BoardZoom>()
+            .add_observer(make_puzzle_zoomable)
+            .add_systems(
+                Update,
+                (
+                    zoom_input
+                        .run_if(in_state(GameState::Playing))
+                        .run_if(not(editing_notes))
+                        .run_if(no_modal),
+                    apply_board_zoom,
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zooming_keeps_the_focus_still_and_the_board_covered() {
+        let mut zoom = BoardZoom::default();
+        let focus = Vec2::new(200., -100.);
+        zoom.zoom_about(ZOOM_STEP, focus);
+        assert_eq!(zoom.level, ZOOM_STEP);
+        let moved = zoom.transform().transform_point(focus.extend(0.));
+        assert!(moved.truncate().abs_diff_eq(focus, 1e-3));
+
+        // Zooming about a corner can't pull the layout's edge into view.
+        zoom.zoom_about(ZOOM_STEP, BOARD_SIZE);
+        let slack = BOARD_SIZE / 2. * (zoom.level - 1.);
+        assert!(zoom.offset.abs().cmple(slack + 1e-3).all());
+
+        for _ in 0..20 {
+            zoom.zoom_about(ZOOM_STEP, focus);
+        }
+        assert_eq!(zoom.level, MAX_ZOOM);
+        for _ in 0..20 {
+            zoom.zoom_about(ZOOM_STEP.recip(), focus);
+        }
+        assert_eq!(zoom, BoardZoom::default());
+    }
+}